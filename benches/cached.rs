@@ -0,0 +1,92 @@
+//! Cross-core SPSC throughput through the ring's shared indices
+//! against the split handles, which cache the other side's index
+//! and only reload it when the ring looks full or empty
+//!
+//! cargo bench --bench cached -- <filter>
+
+use std::hint::black_box;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use brainstorm::ephemeral::wait::Backoff;
+use brainstorm::spsc::SPSCEphemeral;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// Tokens over the shared ring, both indices loaded every time
+fn uncached<const N: usize>(c: &mut Criterion) {
+    let mut group = c.benchmark_group("indices");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function(BenchmarkId::new("uncached", N), |b| {
+        b.iter_custom(|iters| {
+            let ring = SPSCEphemeral::<u64, N>::new();
+            let (tx, rx) = (ring.producer_token(), ring.consumer_token());
+            thread::scope(|s| {
+                let start = Instant::now();
+                s.spawn(move || {
+                    let mut backoff = Backoff::new();
+                    for i in 0..iters {
+                        while tx.push(i).is_err() {
+                            backoff.snooze();
+                        }
+                    }
+                });
+                let mut backoff = Backoff::new();
+                for _ in 0..iters {
+                    while rx.pop().map(black_box).is_none() {
+                        backoff.snooze();
+                    }
+                }
+                start.elapsed()
+            })
+        })
+    });
+    group.finish();
+}
+
+/// The split handles, the other side's index mostly comes from the cache
+fn cached<const N: usize>(c: &mut Criterion) {
+    let mut group = c.benchmark_group("indices");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function(BenchmarkId::new("cached", N), |b| {
+        b.iter_custom(|iters| {
+            let (mut tx, mut rx) = SPSCEphemeral::<u64, N>::new().split();
+            let start = Instant::now();
+            let produce = thread::spawn(move || {
+                let mut backoff = Backoff::new();
+                for i in 0..iters {
+                    while tx.push(i).is_err() {
+                        backoff.snooze();
+                    }
+                }
+            });
+            let mut backoff = Backoff::new();
+            for _ in 0..iters {
+                while rx.pop().map(black_box).is_err() {
+                    backoff.snooze();
+                }
+            }
+            let elapsed = start.elapsed();
+            produce.join().unwrap();
+            elapsed
+        })
+    });
+    group.finish();
+}
+
+fn benches(c: &mut Criterion) {
+    uncached::<64>(c);
+    cached::<64>(c);
+    uncached::<1024>(c);
+    cached::<1024>(c);
+}
+
+criterion_group! {
+    name = indices;
+    config = Criterion::default()
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2));
+    targets = benches
+}
+criterion_main!(indices);
@@ -0,0 +1,97 @@
+//! Short critical sections under contention, the crate's locks
+//! next to `std::sync::Mutex` as threads pile on
+//!
+//! cargo bench --bench lock -- <filter>
+
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use brainstorm::sync::{SpinLock, TicketLock};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// Just enough of a lock to bump a counter behind it
+trait Lock: Sync {
+    const NAME: &'static str;
+
+    fn new() -> Self;
+    fn bump(&self);
+}
+
+impl Lock for SpinLock<u64> {
+    const NAME: &'static str = "spin";
+
+    fn new() -> Self {
+        SpinLock::new(0)
+    }
+
+    fn bump(&self) {
+        *self.lock() += 1;
+    }
+}
+
+impl Lock for TicketLock<u64> {
+    const NAME: &'static str = "ticket";
+
+    fn new() -> Self {
+        TicketLock::new(0)
+    }
+
+    fn bump(&self) {
+        *self.lock() += 1;
+    }
+}
+
+impl Lock for Mutex<u64> {
+    const NAME: &'static str = "mutex";
+
+    fn new() -> Self {
+        Mutex::new(0)
+    }
+
+    fn bump(&self) {
+        *self.lock().unwrap() += 1;
+    }
+}
+
+/// `threads` threads bumping one counter, lock acquisitions per second
+fn contended<L: Lock>(c: &mut Criterion, threads: u64) {
+    let mut group = c.benchmark_group("contended");
+    group.throughput(Throughput::Elements(1));
+
+    let lock = L::new();
+    group.bench_function(BenchmarkId::new(L::NAME, threads), |b| {
+        b.iter_custom(|iters| {
+            let per_thread = iters.div_ceil(threads);
+            let start = Instant::now();
+            thread::scope(|s| {
+                for _ in 0..threads {
+                    s.spawn(|| {
+                        for _ in 0..per_thread {
+                            lock.bump();
+                        }
+                    });
+                }
+            });
+            start.elapsed()
+        })
+    });
+    group.finish();
+}
+
+fn benches(c: &mut Criterion) {
+    for threads in [1, 2, 4, 8] {
+        contended::<SpinLock<u64>>(c, threads);
+        contended::<TicketLock<u64>>(c, threads);
+        contended::<Mutex<u64>>(c, threads);
+    }
+}
+
+criterion_group! {
+    name = lock;
+    config = Criterion::default()
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2));
+    targets = benches
+}
+criterion_main!(lock);
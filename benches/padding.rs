@@ -0,0 +1,156 @@
+//! Cross-core throughput of a bare SPSC ring with its head and tail
+//! on one cache line against each on its own, as `CachePadded` lays
+//! out the indices of the crate's rings
+//!
+//! cargo bench --bench padding -- <filter>
+
+use std::cell::UnsafeCell;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use brainstorm::ephemeral::wait::Backoff;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// Where the ring keeps its two indices
+trait Indices: Default + Send + Sync + 'static {
+    const NAME: &'static str;
+
+    fn head(&self) -> &AtomicUsize;
+    fn tail(&self) -> &AtomicUsize;
+}
+
+/// Side by side, every push invalidates the consumer's line and back
+#[derive(Default)]
+struct Adjacent {
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+impl Indices for Adjacent {
+    const NAME: &'static str = "adjacent";
+
+    fn head(&self) -> &AtomicUsize {
+        &self.head
+    }
+
+    fn tail(&self) -> &AtomicUsize {
+        &self.tail
+    }
+}
+
+/// `CachePadded`'s alignment, the crate's wrapper isn't public
+#[cfg_attr(any(target_arch = "x86_64", target_arch = "aarch64"), repr(align(128)))]
+#[cfg_attr(
+    not(any(target_arch = "x86_64", target_arch = "aarch64")),
+    repr(align(64))
+)]
+#[derive(Default)]
+struct Line<T>(T);
+
+#[derive(Default)]
+struct Padded {
+    head: Line<AtomicUsize>,
+    tail: Line<AtomicUsize>,
+}
+
+impl Indices for Padded {
+    const NAME: &'static str = "padded";
+
+    fn head(&self) -> &AtomicUsize {
+        &self.head.0
+    }
+
+    fn tail(&self) -> &AtomicUsize {
+        &self.tail.0
+    }
+}
+
+/// Just the indices and the slots, no cached indices or anything
+/// else that would hide how the indices are laid out
+struct Ring<I, const N: usize> {
+    indices: I,
+    slots: [UnsafeCell<u64>; N],
+}
+
+// one producer and one consumer, the indices hand the slots over
+unsafe impl<I: Sync, const N: usize> Sync for Ring<I, N> {}
+
+impl<I: Indices, const N: usize> Ring<I, N> {
+    fn new() -> Self {
+        Self {
+            indices: I::default(),
+            slots: [const { UnsafeCell::new(0) }; N],
+        }
+    }
+
+    fn push(&self, val: u64) -> bool {
+        let tail = self.indices.tail().load(Ordering::Relaxed);
+        if tail - self.indices.head().load(Ordering::Acquire) == N {
+            return false;
+        }
+        unsafe { *self.slots[tail % N].get() = val };
+        self.indices.tail().store(tail + 1, Ordering::Release);
+        true
+    }
+
+    fn pop(&self) -> Option<u64> {
+        let head = self.indices.head().load(Ordering::Relaxed);
+        if head == self.indices.tail().load(Ordering::Acquire) {
+            return None;
+        }
+        let val = unsafe { *self.slots[head % N].get() };
+        self.indices.head().store(head + 1, Ordering::Release);
+        Some(val)
+    }
+}
+
+/// One producer thread streaming into the consumer on the bench thread
+fn cross_core<I: Indices, const N: usize>(c: &mut Criterion) {
+    let mut group = c.benchmark_group("indices");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function(BenchmarkId::new(I::NAME, N), |b| {
+        b.iter_custom(|iters| {
+            let ring = Arc::new(Ring::<I, N>::new());
+            let tx = ring.clone();
+            let start = Instant::now();
+            let produce = thread::spawn(move || {
+                let mut backoff = Backoff::new();
+                for i in 0..iters {
+                    while !tx.push(i) {
+                        backoff.snooze();
+                    }
+                }
+            });
+            let mut backoff = Backoff::new();
+            for _ in 0..iters {
+                while ring.pop().map(black_box).is_none() {
+                    backoff.snooze();
+                }
+            }
+            let elapsed = start.elapsed();
+            produce.join().unwrap();
+            elapsed
+        })
+    });
+    group.finish();
+}
+
+fn benches(c: &mut Criterion) {
+    cross_core::<Adjacent, 64>(c);
+    cross_core::<Padded, 64>(c);
+    cross_core::<Adjacent, 1024>(c);
+    cross_core::<Padded, 1024>(c);
+}
+
+criterion_group! {
+    name = padding;
+    config = Criterion::default()
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2));
+    targets = benches
+}
+criterion_main!(padding);
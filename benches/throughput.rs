@@ -0,0 +1,328 @@
+//! Throughput and round-trip latency of the crate's buffers next to
+//! `std::sync::mpsc`, over a few capacities and payload sizes
+//!
+//! cargo bench --bench throughput -- <filter>
+
+use std::hint::black_box;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use brainstorm::ephemeral::{
+    mpmc::{BoundedMpmc, MPMCEphemeral},
+    mpsc::MPSCEphemeral,
+    scq::SCQEphemeral,
+    spsc::SPSCEphemeral,
+    wait::Backoff,
+};
+use brainstorm::{spsc, EphemeralSlot};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// `W` words, so 8, 64 and 512 byte payloads
+type Payload<const W: usize> = [u64; W];
+
+/// Common face of every variant, just enough to move items across
+trait Queue<T: Send + 'static> {
+    const NAME: &'static str;
+    const CAPACITY: usize;
+    type Tx: Send + 'static;
+    type Rx: Send + 'static;
+
+    fn channel() -> (Self::Tx, Self::Rx);
+    /// Hands `val` back when there's no room
+    fn send(tx: &mut Self::Tx, val: T) -> Result<(), T>;
+    fn recv(rx: &mut Self::Rx) -> Option<T>;
+}
+
+/// One value at a time, whatever `N` the others run at
+struct Slot;
+
+impl<T: Send + 'static> Queue<T> for Slot {
+    const NAME: &'static str = "slot";
+    const CAPACITY: usize = 1;
+    type Tx = Arc<EphemeralSlot<T>>;
+    type Rx = Arc<EphemeralSlot<T>>;
+
+    fn channel() -> (Self::Tx, Self::Rx) {
+        let slot = Arc::new(EphemeralSlot::new());
+        (slot.clone(), slot)
+    }
+
+    fn send(tx: &mut Self::Tx, val: T) -> Result<(), T> {
+        tx.set(val);
+        Ok(())
+    }
+
+    fn recv(rx: &mut Self::Rx) -> Option<T> {
+        rx.get()
+    }
+}
+
+struct Spsc<const N: usize>;
+
+impl<T: Send + 'static, const N: usize> Queue<T> for Spsc<N> {
+    const NAME: &'static str = "spsc";
+    const CAPACITY: usize = N;
+    type Tx = spsc::Producer<SPSCEphemeral<T, N>>;
+    type Rx = spsc::Consumer<SPSCEphemeral<T, N>>;
+
+    fn channel() -> (Self::Tx, Self::Rx) {
+        SPSCEphemeral::new().split()
+    }
+
+    fn send(tx: &mut Self::Tx, val: T) -> Result<(), T> {
+        tx.push(val)
+    }
+
+    fn recv(rx: &mut Self::Rx) -> Option<T> {
+        rx.pop().ok()
+    }
+}
+
+struct Mpsc<const N: usize>;
+
+impl<T: Send + 'static, const N: usize> Queue<T> for Mpsc<N> {
+    const NAME: &'static str = "mpsc";
+    const CAPACITY: usize = N;
+    type Tx = brainstorm::ephemeral::mpsc::Producer<T, N>;
+    type Rx = brainstorm::ephemeral::mpsc::Consumer<T, N>;
+
+    fn channel() -> (Self::Tx, Self::Rx) {
+        MPSCEphemeral::new().split()
+    }
+
+    fn send(tx: &mut Self::Tx, val: T) -> Result<(), T> {
+        tx.push(val)
+    }
+
+    fn recv(rx: &mut Self::Rx) -> Option<T> {
+        rx.pop()
+    }
+}
+
+/// The baseline, `sync_channel` being the bounded flavour
+struct Std<const N: usize>;
+
+impl<T: Send + 'static, const N: usize> Queue<T> for Std<N> {
+    const NAME: &'static str = "std";
+    const CAPACITY: usize = N;
+    type Tx = mpsc::SyncSender<T>;
+    type Rx = mpsc::Receiver<T>;
+
+    fn channel() -> (Self::Tx, Self::Rx) {
+        mpsc::sync_channel(N)
+    }
+
+    fn send(tx: &mut Self::Tx, val: T) -> Result<(), T> {
+        tx.try_send(val).map_err(|err| match err {
+            mpsc::TrySendError::Full(val) | mpsc::TrySendError::Disconnected(val) => val,
+        })
+    }
+
+    fn recv(rx: &mut Self::Rx) -> Option<T> {
+        rx.try_recv().ok()
+    }
+}
+
+/// Backs off rather than spinning flat out, so the
+/// other side still gets to run on a single core
+fn send_spinning<T: Send + 'static, Q: Queue<T>>(tx: &mut Q::Tx, mut val: T) {
+    let mut backoff = Backoff::new();
+    while let Err(back) = Q::send(tx, val) {
+        val = back;
+        backoff.snooze();
+    }
+}
+
+fn recv_spinning<T: Send + 'static, Q: Queue<T>>(rx: &mut Q::Rx) -> T {
+    let mut backoff = Backoff::new();
+    loop {
+        if let Some(val) = Q::recv(rx) {
+            return val;
+        }
+        backoff.snooze();
+    }
+}
+
+fn id<T: Send + 'static, Q: Queue<T>>() -> BenchmarkId {
+    let bytes = std::mem::size_of::<T>();
+    BenchmarkId::new(Q::NAME, format!("cap{}/{bytes}B", Q::CAPACITY))
+}
+
+/// Fills the queue and empties it again on the one thread,
+/// the cost of the operations themselves without any contention
+fn single_thread<const W: usize, Q: Queue<Payload<W>>>(c: &mut Criterion) {
+    let mut group = c.benchmark_group("single_thread");
+    group.throughput(Throughput::Elements(Q::CAPACITY as u64));
+
+    let (mut tx, mut rx) = Q::channel();
+    group.bench_function(id::<Payload<W>, Q>(), |b| {
+        b.iter(|| {
+            for i in 0..Q::CAPACITY {
+                let pushed = Q::send(&mut tx, black_box([i as u64; W]));
+                debug_assert!(pushed.is_ok());
+            }
+            for _ in 0..Q::CAPACITY {
+                black_box(Q::recv(&mut rx));
+            }
+        })
+    });
+    group.finish();
+}
+
+/// One producer thread streaming into the consumer on the bench
+/// thread, items per second once both sides are running flat out
+fn cross_core<const W: usize, Q: Queue<Payload<W>>>(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cross_core");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function(id::<Payload<W>, Q>(), |b| {
+        b.iter_custom(|iters| {
+            let (mut tx, mut rx) = Q::channel();
+            let start = Instant::now();
+            let produce = thread::spawn(move || {
+                for i in 0..iters {
+                    send_spinning::<_, Q>(&mut tx, [i; W]);
+                }
+            });
+            for _ in 0..iters {
+                black_box(recv_spinning::<_, Q>(&mut rx));
+            }
+            let elapsed = start.elapsed();
+            produce.join().unwrap();
+            elapsed
+        })
+    });
+    group.finish();
+}
+
+/// Ping-pong through a pair of queues, one iteration being
+/// a full round trip to the echo thread and back
+fn round_trip<const W: usize, Q: Queue<Payload<W>>>(c: &mut Criterion) {
+    let mut group = c.benchmark_group("round_trip");
+
+    group.bench_function(id::<Payload<W>, Q>(), |b| {
+        b.iter_custom(|iters| {
+            let (mut ping_tx, mut ping_rx) = Q::channel();
+            let (mut pong_tx, mut pong_rx) = Q::channel();
+            let echo = thread::spawn(move || {
+                for _ in 0..iters {
+                    let val = recv_spinning::<_, Q>(&mut ping_rx);
+                    send_spinning::<_, Q>(&mut pong_tx, val);
+                }
+            });
+
+            let start = Instant::now();
+            for i in 0..iters {
+                send_spinning::<_, Q>(&mut ping_tx, [i; W]);
+                black_box(recv_spinning::<_, Q>(&mut pong_rx));
+            }
+            let elapsed = start.elapsed();
+            echo.join().unwrap();
+            elapsed
+        })
+    });
+    group.finish();
+}
+
+/// `threads` producers and as many consumers on one shared ring, items
+/// per second with every thread fighting over the same indices
+fn contended<Q>(c: &mut Criterion, name: &str, threads: u64)
+where
+    Q: BoundedMpmc<Item = u64> + Default + Send + 'static,
+{
+    let mut group = c.benchmark_group("contended");
+    group.throughput(Throughput::Elements(1));
+
+    let queue = Arc::new(Q::default());
+    let id = BenchmarkId::new(name, format!("cap{}/{threads}x{threads}", queue.capacity()));
+    group.bench_function(id, |b| {
+        b.iter_custom(|iters| {
+            let per_thread = iters.div_ceil(threads);
+            let start = Instant::now();
+            let workers: Vec<_> = (0..threads)
+                .flat_map(|_| {
+                    let (tx, rx) = (queue.clone(), queue.clone());
+                    let produce = thread::spawn(move || {
+                        let mut backoff = Backoff::new();
+                        for i in 0..per_thread {
+                            let mut val = i;
+                            while let Err(back) = tx.push(val) {
+                                val = back;
+                                backoff.snooze();
+                            }
+                        }
+                    });
+                    let consume = thread::spawn(move || {
+                        let mut backoff = Backoff::new();
+                        for _ in 0..per_thread {
+                            while rx.pop().map(black_box).is_none() {
+                                backoff.snooze();
+                            }
+                        }
+                    });
+                    [produce, consume]
+                })
+                .collect();
+            for worker in workers {
+                worker.join().unwrap();
+            }
+            start.elapsed()
+        })
+    });
+    group.finish();
+}
+
+/// The CAS ring against the fetch-add one as threads pile on
+fn mpmc(c: &mut Criterion) {
+    for threads in [1, 2, 4, 8] {
+        contended::<MPMCEphemeral<u64, 1024>>(c, "mpmc", threads);
+        contended::<SCQEphemeral<u64, 1024>>(c, "scq", threads);
+    }
+}
+
+/// Every variant at capacity `N` with `W`-word payloads
+fn variants<const N: usize, const W: usize>(c: &mut Criterion) {
+    single_thread::<W, Spsc<N>>(c);
+    single_thread::<W, Mpsc<N>>(c);
+    single_thread::<W, Std<N>>(c);
+
+    cross_core::<W, Spsc<N>>(c);
+    cross_core::<W, Mpsc<N>>(c);
+    cross_core::<W, Std<N>>(c);
+
+    round_trip::<W, Spsc<N>>(c);
+    round_trip::<W, Mpsc<N>>(c);
+    round_trip::<W, Std<N>>(c);
+}
+
+/// The slot holds a single value, so it only varies in payload
+fn slot<const W: usize>(c: &mut Criterion) {
+    single_thread::<W, Slot>(c);
+    cross_core::<W, Slot>(c);
+    round_trip::<W, Slot>(c);
+}
+
+fn benches(c: &mut Criterion) {
+    slot::<1>(c);
+    slot::<8>(c);
+    slot::<64>(c);
+
+    variants::<64, 1>(c);
+    variants::<64, 8>(c);
+    variants::<64, 64>(c);
+    variants::<1024, 1>(c);
+    variants::<1024, 8>(c);
+    variants::<1024, 64>(c);
+
+    mpmc(c);
+}
+
+criterion_group! {
+    name = throughput;
+    config = Criterion::default()
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2));
+    targets = benches
+}
+criterion_main!(throughput);
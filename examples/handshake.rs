@@ -0,0 +1,34 @@
+use std::sync::Arc;
+use std::thread;
+
+use brainstorm::ephemeral::wait::Backoff;
+use brainstorm::EphemeralSlot;
+
+fn main() {
+    let slot = Arc::new(EphemeralSlot::<i32>::new());
+
+    let producer = Arc::clone(&slot);
+    let produce = thread::spawn(move || {
+        for i in 0..1000 {
+            producer.set(i);
+        }
+    });
+
+    let consumer = Arc::clone(&slot);
+    let consume = thread::spawn(move || {
+        for _ in 0..1000 {
+            let mut backoff = Backoff::new();
+            loop {
+                if let Some(value) = consumer.get() {
+                    println!("just consumed value: {value}");
+                    break;
+                }
+                // value not ready -> spin, then yield
+                backoff.snooze();
+            }
+        }
+    });
+
+    produce.join().unwrap();
+    consume.join().unwrap();
+}
@@ -0,0 +1,48 @@
+use std::env;
+use std::process::Command;
+use std::thread;
+
+use brainstorm::ephemeral::ipc::ShmRing;
+
+type Ring = ShmRing<u64, 64>;
+
+const ITEMS: u64 = 1000;
+
+fn main() {
+    let path = env::temp_dir().join("brainstorm-shm-example");
+
+    // the child process is the consumer
+    if env::args().nth(1).as_deref() == Some("consume") {
+        let mut ring = Ring::open(&path).unwrap();
+        let mut sum = 0;
+        for _ in 0..ITEMS {
+            loop {
+                if let Some(value) = ring.pop() {
+                    sum += value;
+                    break;
+                }
+                thread::yield_now();
+            }
+        }
+        println!("consumer got {ITEMS} values, sum {sum}");
+        return;
+    }
+
+    // a ring left behind by an earlier run, unlinking it is safe
+    // even if some process still has it mapped
+    let _ = std::fs::remove_file(&path);
+    let mut ring = Ring::create(&path).unwrap();
+    let mut consumer = Command::new(env::current_exe().unwrap())
+        .arg("consume")
+        .spawn()
+        .unwrap();
+
+    for i in 0..ITEMS {
+        while ring.push(i).is_err() {
+            thread::yield_now();
+        }
+    }
+
+    assert!(consumer.wait().unwrap().success());
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,256 @@
+use core::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use std::{
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+
+use super::{
+    channel::{self, Receiver, Sender, TrySendError},
+    mpsc::MPSCEphemeral,
+    oneshot,
+};
+
+/// messages an actor's mailbox holds before `Full` kicks in
+pub const MAILBOX: usize = 64;
+
+/// What `Addr::send` does while the mailbox is full
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Full {
+    /// Waits for the actor to make room
+    #[default]
+    Block,
+    /// Drops the message and reports success
+    Drop,
+    /// Hands the message back as `TrySendError::Full`
+    Reject,
+}
+
+enum Envelope<M> {
+    Msg(M),
+    Stop,
+}
+
+type Mailbox<M> = MPSCEphemeral<Envelope<M>, MAILBOX>;
+
+struct Shared {
+    full: Full,
+    stopping: AtomicBool,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// Runs `handler` on a dedicated thread for every message sent to
+/// the returned address, in order, with exclusive access to `state`.
+/// Blocks senders while the mailbox is full, see `spawn_actor_with`
+pub fn spawn_actor<S, M, F>(state: S, handler: F) -> Addr<M>
+where
+    S: Send + 'static,
+    M: Send + 'static,
+    F: FnMut(&mut S, M) + Send + 'static,
+{
+    spawn_actor_with(Full::Block, state, handler)
+}
+
+/// `spawn_actor` handling a full mailbox as `full` says
+pub fn spawn_actor_with<S, M, F>(full: Full, mut state: S, mut handler: F) -> Addr<M>
+where
+    S: Send + 'static,
+    M: Send + 'static,
+    F: FnMut(&mut S, M) + Send + 'static,
+{
+    let (tx, rx): (_, Receiver<Mailbox<M>>) = channel::bounded();
+    let thread = thread::spawn(move || {
+        // ends on `Stop` or once every address is gone
+        while let Ok(Envelope::Msg(msg)) = rx.recv() {
+            handler(&mut state, msg);
+        }
+    });
+
+    Addr {
+        tx,
+        shared: Arc::new(Shared {
+            full,
+            stopping: AtomicBool::new(false),
+            thread: Mutex::new(Some(thread)),
+        }),
+    }
+}
+
+/// Cloneable handle to a running actor
+pub struct Addr<M> {
+    tx: Sender<Mailbox<M>>,
+    shared: Arc<Shared>,
+}
+
+impl<M> Addr<M> {
+    /// Queues `msg` for the actor, fails with `Disconnected` once it
+    /// stopped or its handler panicked, the mailbox policy decides the rest
+    pub fn send(&self, msg: M) -> Result<(), TrySendError<M>> {
+        if self.is_stopped() {
+            return Err(TrySendError::Disconnected(msg));
+        }
+
+        let sent = match self.shared.full {
+            Full::Block => self
+                .tx
+                .send(Envelope::Msg(msg))
+                .map_err(|err| TrySendError::Disconnected(err.into_inner())),
+            Full::Drop => match self.tx.try_send(Envelope::Msg(msg)) {
+                Err(TrySendError::Full(_)) => Ok(()),
+                sent => sent,
+            },
+            Full::Reject => self.tx.try_send(Envelope::Msg(msg)),
+        };
+        sent.map_err(|err| match err {
+            TrySendError::Full(envelope) => TrySendError::Full(open(envelope)),
+            TrySendError::Disconnected(envelope) => TrySendError::Disconnected(open(envelope)),
+        })
+    }
+
+    /// Sends the message `make` builds around a reply sender, the
+    /// returned receiver gets whatever the handler sends back, or
+    /// reports a disconnect if it drops the sender instead
+    pub fn ask<R>(
+        &self,
+        make: impl FnOnce(oneshot::Sender<R>) -> M,
+    ) -> Result<oneshot::Receiver<R>, TrySendError<M>> {
+        let (reply, answer) = oneshot::channel();
+        self.send(make(reply))?;
+        Ok(answer)
+    }
+
+    /// Stops taking messages, lets the actor handle everything queued
+    /// so far and waits for its thread. Messages racing the stop may
+    /// be dropped unhandled, `Err` with the panic if the handler hit one
+    pub fn stop(&self) -> thread::Result<()> {
+        self.shared.stopping.store(true, Ordering::Release);
+        // behind what's queued, fails if the actor is gone already
+        let _ = self.tx.send(Envelope::Stop);
+
+        let thread = self
+            .shared
+            .thread
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        thread.map_or(Ok(()), JoinHandle::join)
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.shared.stopping.load(Ordering::Acquire)
+    }
+}
+
+/// Unwraps a message that never reached the actor
+fn open<M>(envelope: Envelope<M>) -> M {
+    match envelope {
+        Envelope::Msg(msg) => msg,
+        Envelope::Stop => unreachable!("only `stop` sends `Stop`"),
+    }
+}
+
+impl<M> Clone for Addr<M> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<M> fmt::Debug for Addr<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Addr")
+            .field("full", &self.shared.full)
+            .field("stopped", &self.is_stopped())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use std::sync::Barrier;
+
+    enum Counter {
+        Add(u64),
+        Get(oneshot::Sender<u64>),
+    }
+
+    fn counter(total: &mut u64, msg: Counter) {
+        match msg {
+            Counter::Add(n) => *total += n,
+            Counter::Get(reply) => {
+                let _ = reply.send(*total);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ask_actor() {
+        const ITEMS: u64 = if cfg!(miri) { 50 } else { 1000 };
+        let addr = spawn_actor(0, counter);
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                let addr = addr.clone();
+                s.spawn(move || {
+                    for i in 0..ITEMS {
+                        addr.send(Counter::Add(i)).unwrap();
+                    }
+                });
+            }
+        });
+
+        let mut total = addr.ask(Counter::Get).unwrap();
+        assert_eq!(total.recv(), Ok(4 * (0..ITEMS).sum::<u64>()));
+        addr.stop().unwrap();
+    }
+
+    #[test]
+    fn test_full_actor() {
+        let gate = Arc::new(Barrier::new(2));
+        let held = gate.clone();
+        let addr = spawn_actor_with(Full::Reject, (), move |_, first: bool| {
+            if first {
+                held.wait();
+                held.wait();
+            }
+        });
+
+        // the actor is stuck on the first message, the rest queue up
+        addr.send(true).unwrap();
+        gate.wait();
+        for _ in 0..MAILBOX {
+            addr.send(false).unwrap();
+        }
+        assert_eq!(addr.send(false), Err(TrySendError::Full(false)));
+
+        gate.wait();
+        addr.stop().unwrap();
+        assert_eq!(addr.send(false), Err(TrySendError::Disconnected(false)));
+    }
+
+    #[test]
+    fn test_stop_actor() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let addr = spawn_actor((), move |_, msg: u32| {
+            assert_ne!(msg, 0, "zero");
+            log.lock().unwrap().push(msg);
+        });
+
+        // everything queued before the stop is still handled
+        for i in 1..=10 {
+            addr.send(i).unwrap();
+        }
+        addr.stop().unwrap();
+        assert_eq!(*seen.lock().unwrap(), (1..=10).collect::<Vec<_>>());
+
+        let addr = spawn_actor((), |_, msg: u32| assert_ne!(msg, 0, "zero"));
+        addr.send(0).unwrap();
+        assert!(addr.stop().is_err());
+    }
+}
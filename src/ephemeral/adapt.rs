@@ -0,0 +1,309 @@
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "std")]
+use super::handle::BlockingConsume;
+use super::handle::Consume;
+
+/// Consumer handing out `f` of every item
+///
+/// Adapters are built with `Consume::map_items` and friends, or
+/// `new`. Each is a `Consume` again, so they stack
+pub struct Map<T, C, F> {
+    inner: C,
+    f: F,
+    _item: PhantomData<fn(T)>,
+}
+
+impl<T, C, F> Map<T, C, F> {
+    pub fn new(inner: C, f: F) -> Self {
+        Self {
+            inner,
+            f,
+            _item: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+impl<T, U, C: Consume<T>, F: FnMut(T) -> U> Consume<U> for Map<T, C, F> {
+    fn try_pop(&mut self) -> Option<U> {
+        self.inner.try_pop().map(&mut self.f)
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.inner.is_disconnected()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, U, C: BlockingConsume<T>, F: FnMut(T) -> U> BlockingConsume<U> for Map<T, C, F> {
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<U> {
+        self.inner.pop_timeout(timeout).map(&mut self.f)
+    }
+}
+
+/// Consumer skipping the items `f` turns down
+pub struct Filter<T, C, F> {
+    inner: C,
+    f: F,
+    _item: PhantomData<fn(T)>,
+}
+
+impl<T, C, F> Filter<T, C, F> {
+    pub fn new(inner: C, f: F) -> Self {
+        Self {
+            inner,
+            f,
+            _item: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+impl<T, C: Consume<T>, F: FnMut(&T) -> bool> Consume<T> for Filter<T, C, F> {
+    /// Drops rejected items until one passes or the queue runs dry
+    fn try_pop(&mut self) -> Option<T> {
+        while let Some(val) = self.inner.try_pop() {
+            if (self.f)(&val) {
+                return Some(val);
+            }
+        }
+        None
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.inner.is_disconnected()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, C: BlockingConsume<T>, F: FnMut(&T) -> bool> BlockingConsume<T> for Filter<T, C, F> {
+    /// `timeout` covers the rejected items too
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let val = self.inner.pop_timeout(left)?;
+            if (self.f)(&val) {
+                return Some(val);
+            }
+        }
+    }
+}
+
+/// Consumer handing out whatever is queued as one `Vec`, up to `max`
+/// items
+pub struct Batch<T, C> {
+    inner: C,
+    max: usize,
+    _item: PhantomData<fn(T)>,
+}
+
+impl<T, C> Batch<T, C> {
+    pub fn new(inner: C, max: usize) -> Self {
+        assert!(max > 0, "a batch holds at least one item");
+        Self {
+            inner,
+            max,
+            _item: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    fn fill(&mut self, first: T) -> Vec<T>
+    where
+        C: Consume<T>,
+    {
+        let mut batch = Vec::with_capacity(self.max);
+        batch.push(first);
+        while batch.len() < self.max {
+            match self.inner.try_pop() {
+                Some(val) => batch.push(val),
+                None => break,
+            }
+        }
+        batch
+    }
+}
+
+impl<T, C: Consume<T>> Consume<Vec<T>> for Batch<T, C> {
+    /// `None` only if nothing at all is queued, a batch is never empty
+    fn try_pop(&mut self) -> Option<Vec<T>> {
+        let first = self.inner.try_pop()?;
+        Some(self.fill(first))
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.inner.is_disconnected()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, C: BlockingConsume<T>> BlockingConsume<Vec<T>> for Batch<T, C> {
+    /// Waits for the first item only, then takes what else is queued
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<Vec<T>> {
+        let first = self.inner.pop_timeout(timeout)?;
+        Some(self.fill(first))
+    }
+}
+
+/// Consumer handing out chunks of `max` items, or fewer once `linger`
+/// passed since the first item of the chunk came in
+///
+/// The chunk is kept in here in between, dropping the adapter drops
+/// a partial chunk along with it
+#[cfg(feature = "std")]
+pub struct TimeoutChunks<T, C> {
+    inner: C,
+    max: usize,
+    linger: Duration,
+    chunk: Vec<T>,
+    started: Option<Instant>, // first item of `chunk` came in
+}
+
+#[cfg(feature = "std")]
+impl<T, C> TimeoutChunks<T, C> {
+    pub fn new(inner: C, max: usize, linger: Duration) -> Self {
+        assert!(max > 0, "a chunk holds at least one item");
+        Self {
+            inner,
+            max,
+            linger,
+            chunk: Vec::with_capacity(max),
+            started: None,
+        }
+    }
+
+    /// Items of the chunk not handed out yet
+    pub fn pending(&self) -> usize {
+        self.chunk.len()
+    }
+
+    fn add(&mut self, val: T) {
+        self.started.get_or_insert_with(Instant::now);
+        self.chunk.push(val);
+    }
+
+    /// The chunk if it's full or lingered long enough
+    fn ready(&mut self, now: Instant) -> Option<Vec<T>> {
+        let due = self.due()?;
+        if self.chunk.len() < self.max && now < due {
+            return None;
+        }
+        self.started = None;
+        let next = Vec::with_capacity(self.max);
+        Some(core::mem::replace(&mut self.chunk, next))
+    }
+
+    fn due(&self) -> Option<Instant> {
+        Some(self.started? + self.linger)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, C: Consume<T>> Consume<Vec<T>> for TimeoutChunks<T, C> {
+    fn try_pop(&mut self) -> Option<Vec<T>> {
+        while self.chunk.len() < self.max {
+            match self.inner.try_pop() {
+                Some(val) => self.add(val),
+                None => break,
+            }
+        }
+        self.ready(Instant::now())
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.inner.is_disconnected()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, C: BlockingConsume<T>> BlockingConsume<Vec<T>> for TimeoutChunks<T, C> {
+    /// A partial chunk stays put if `timeout` runs out before `linger`
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<Vec<T>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let now = Instant::now();
+            if let Some(chunk) = self.ready(now) {
+                return Some(chunk);
+            }
+            // guard: out of time, the chunk lingers on
+            if now >= deadline {
+                return None;
+            }
+            let wake = self.due().map_or(deadline, |due| due.min(deadline));
+            if let Some(val) = self.inner.pop_timeout(wake.saturating_duration_since(now)) {
+                self.add(val);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use crate::ephemeral::handle::Consume;
+    use crate::ephemeral::spsc::SPSCEphemeral;
+
+    #[test]
+    fn test_map_filter_adapt() {
+        let (mut producer, consumer) = SPSCEphemeral::<u32, 8>::new().split();
+        let mut evens = consumer
+            .filter_items(|val| val % 2 == 0)
+            .map_items(|val| val * 10);
+        assert_eq!(evens.try_pop(), None);
+
+        assert_eq!(producer.push_slice(&[1, 2, 3, 4, 5]), 5);
+        assert_eq!(evens.try_pop(), Some(20));
+        assert_eq!(evens.try_pop(), Some(40));
+        // 5 was turned down on the way
+        assert_eq!(evens.try_pop(), None);
+        assert!(evens.into_inner().into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_batch_adapt() {
+        let (mut producer, consumer) = SPSCEphemeral::<u32, 8>::new().split();
+        let mut batches = consumer.batch(3);
+        assert_eq!(batches.try_pop(), None);
+
+        assert_eq!(producer.push_slice(&[1, 2, 3, 4]), 4);
+        assert_eq!(batches.try_pop(), Some(vec![1, 2, 3]));
+        assert_eq!(batches.try_pop(), Some(vec![4]));
+        assert_eq!(batches.try_pop(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_timeout_chunks_adapt() {
+        use crate::ephemeral::handle::BlockingConsume;
+        use std::time::{Duration, Instant};
+
+        let linger = Duration::from_millis(20);
+        let (mut producer, consumer) = SPSCEphemeral::<u32, 8>::new().split();
+        let mut chunks = consumer.timeout_chunks(2, linger);
+
+        assert_eq!(producer.push_slice(&[1, 2, 3]), 3);
+        assert_eq!(chunks.try_pop(), Some(vec![1, 2]));
+        // 3 waits for a partner, then goes alone
+        assert_eq!(chunks.try_pop(), None);
+        assert_eq!(chunks.pending(), 1);
+        let start = Instant::now();
+        assert_eq!(chunks.pop_timeout(Duration::from_secs(5)), Some(vec![3]));
+        assert!(start.elapsed() >= linger / 2);
+
+        // nothing at all, the caller's timeout wins
+        assert_eq!(chunks.pop_timeout(Duration::from_millis(5)), None);
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    io, panic,
+    sync::mpsc,
+    thread::{self, JoinHandle},
+};
+
+/// Cores the calling thread may run on, in id order. Where the
+/// producer and consumer of a ring run dominates its latency, pin
+/// them to two of these, sharing a cache if the numbers are to hold
+pub fn cores() -> io::Result<Vec<usize>> {
+    #[cfg(target_os = "linux")]
+    {
+        let mut set = unsafe { core::mem::zeroed::<libc::cpu_set_t>() };
+        let size = core::mem::size_of::<libc::cpu_set_t>();
+        if unsafe { libc::sched_getaffinity(0, size, &mut set) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let cores =
+            (0..libc::CPU_SETSIZE as usize).filter(|&id| unsafe { libc::CPU_ISSET(id, &set) });
+        Ok(cores.collect())
+    }
+    #[cfg(not(target_os = "linux"))]
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Keeps the calling thread on core `id` from here on, Linux only
+pub fn pin_to_core(id: usize) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        // guard: past what a cpu set holds
+        if id >= libc::CPU_SETSIZE as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "no such core"));
+        }
+        let mut set = unsafe { core::mem::zeroed::<libc::cpu_set_t>() };
+        unsafe { libc::CPU_SET(id, &mut set) };
+        let size = core::mem::size_of::<libc::cpu_set_t>();
+        if unsafe { libc::sched_setaffinity(0, size, &set) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = id;
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// Spawns a thread pinned to core `id` before `f` starts, see
+/// `spawn_pinned_with`
+pub fn spawn_pinned<F, T>(id: usize, f: F) -> io::Result<JoinHandle<T>>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    spawn_pinned_with(thread::Builder::new(), id, f)
+}
+
+/// Spawns a thread from `builder` pinned to core `id`. Returns once
+/// the pin took, if it didn't the thread exits without running `f`
+/// and the error comes back here
+pub fn spawn_pinned_with<F, T>(
+    builder: thread::Builder,
+    id: usize,
+    f: F,
+) -> io::Result<JoinHandle<T>>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (pinned_tx, pinned) = mpsc::sync_channel(1);
+    let handle = builder.spawn(move || {
+        let res = pin_to_core(id);
+        let failed = res.is_err();
+        let _ = pinned_tx.send(res);
+        if failed {
+            // unwinds without the panic hook, nobody joins this one
+            panic::resume_unwind(Box::new("thread was never pinned"));
+        }
+        f()
+    })?;
+
+    pinned
+        .recv()
+        .map_err(|_| io::Error::other("pinned thread exited before reporting"))??;
+    Ok(handle)
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_spawn_affinity() {
+        let allowed = cores().unwrap();
+        let last = *allowed.last().unwrap();
+
+        let on = spawn_pinned(last, cores).unwrap().join().unwrap();
+        assert_eq!(on.unwrap(), [last]);
+        // the spawning thread keeps its own set
+        assert_eq!(cores().unwrap(), allowed);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_errors_affinity() {
+        let err = pin_to_core(usize::MAX).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        // nothing ran, and the error came back to the spawner
+        let builder = thread::Builder::new().name("never".into());
+        let res = spawn_pinned_with(builder, usize::MAX, || unreachable!());
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+}
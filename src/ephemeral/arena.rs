@@ -0,0 +1,314 @@
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::util::CachePadded;
+
+// the free list head packs a tag above the slot index, bumped on
+// every change so a stale head can't win its CAS (ABA)
+const HALF: u32 = usize::BITS / 2;
+const INDEX: usize = (1 << HALF) - 1;
+// end of the free list, hence N stays below it
+const NIL: usize = INDEX;
+
+/// Handle to an allocated slot, small enough to pass through the
+/// queues in place of the payload. The generation tells the slot's
+/// lifetimes apart, a handle outliving its `free` is simply refused
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct SlotIdx {
+    index: usize,
+    generation: usize,
+}
+
+impl SlotIdx {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+}
+
+/// Lock-free fixed-size slab of N slots handing out `SlotIdx`s,
+/// free slots sit on a Treiber stack, ones never used yet are
+/// bumped off a counter so nothing needs linking up front
+/// N:: slot count, below 2^(usize::BITS / 2) - 1
+pub struct Arena<const N: usize> {
+    head: CachePadded<AtomicUsize>, // tag | first free slot
+    fresh: AtomicUsize,             // slots from here on were never used
+    next: [AtomicUsize; N],         // free list links
+    gens: [AtomicUsize; N],         // odd while allocated
+}
+
+impl<const N: usize> Arena<N> {
+    pub const fn new() -> Self {
+        const { assert!(N < NIL, "arena size must fit half a usize") };
+
+        Self {
+            head: CachePadded::new(AtomicUsize::new(NIL)),
+            fresh: AtomicUsize::new(0),
+            next: [const { AtomicUsize::new(NIL) }; N],
+            gens: [const { AtomicUsize::new(0) }; N],
+        }
+    }
+
+    /// `None` while every slot is taken
+    pub fn alloc(&self) -> Option<SlotIdx> {
+        self.pop_free().map(|i| self.publish(i))
+    }
+
+    /// Hands the slot back, `false` if `idx` was freed already
+    pub fn free(&self, idx: SlotIdx) -> bool {
+        let freed = self.retire(idx);
+        if freed {
+            self.push_free(idx.index);
+        }
+        freed
+    }
+
+    /// Whether `idx` wasn't freed yet, stale as soon as it's returned
+    pub fn is_live(&self, idx: SlotIdx) -> bool {
+        self.gens
+            .get(idx.index)
+            .is_some_and(|gen| gen.load(Ordering::Acquire) == idx.generation)
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    fn pop_free(&self) -> Option<usize> {
+        let mut head = self.head.load(Ordering::Acquire);
+        loop {
+            let i = head & INDEX;
+            if i == NIL {
+                return self
+                    .fresh
+                    .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                        (n < N).then_some(n + 1)
+                    })
+                    .ok();
+            }
+
+            // may be stale if `i` was popped meanwhile, the tag then fails the CAS
+            let next = self.next[i].load(Ordering::Relaxed);
+            let popped = (head & !INDEX).wrapping_add(1 << HALF) | next;
+            match self.head.compare_exchange_weak(
+                head,
+                popped,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return Some(i),
+                Err(now) => head = now,
+            }
+        }
+    }
+
+    fn push_free(&self, i: usize) {
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            self.next[i].store(head & INDEX, Ordering::Relaxed);
+            let pushed = (head & !INDEX).wrapping_add(1 << HALF) | i;
+            match self.head.compare_exchange_weak(
+                head,
+                pushed,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return,
+                Err(now) => head = now,
+            }
+        }
+    }
+
+    // odd generation, the slot is live from here on
+    fn publish(&self, i: usize) -> SlotIdx {
+        let generation = self.gens[i].fetch_add(1, Ordering::Release).wrapping_add(1);
+        SlotIdx {
+            index: i,
+            generation,
+        }
+    }
+
+    // even generation, the one caller winning this owns the slot until `push_free`
+    fn retire(&self, idx: SlotIdx) -> bool {
+        idx.generation & 1 == 1
+            && self.gens.get(idx.index).is_some_and(|gen| {
+                gen.compare_exchange(
+                    idx.generation,
+                    idx.generation.wrapping_add(1),
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+            })
+    }
+}
+
+impl<const N: usize> Default for Arena<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Debug for Arena<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Arena").field("capacity", &N).finish()
+    }
+}
+
+/// `Arena` storing a T per slot, producers `insert` the payload and
+/// pass the `SlotIdx` on, whoever receives it `take`s the payload out
+/// N:: slot count, below 2^(usize::BITS / 2) - 1
+pub struct Slab<T, const N: usize> {
+    arena: Arena<N>,
+    values: [UnsafeCell<MaybeUninit<T>>; N],
+}
+
+impl<T, const N: usize> Slab<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            arena: Arena::new(),
+            values: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+        }
+    }
+
+    /// Hands `val` back while every slot is taken
+    pub fn insert(&self, val: T) -> Result<SlotIdx, T> {
+        let Some(i) = self.arena.pop_free() else {
+            return Err(val);
+        };
+        // SAFETY: popped slots are ours alone until published
+        unsafe { (*self.values[i].get()).write(val) };
+        Ok(self.arena.publish(i))
+    }
+
+    /// Frees the slot and moves its value out, `None` for a stale `idx`
+    pub fn take(&self, idx: SlotIdx) -> Option<T> {
+        if !self.arena.retire(idx) {
+            return None;
+        }
+        // SAFETY: retiring won the slot, its value was written before publishing
+        let val = unsafe { (*self.values[idx.index].get()).assume_init_read() };
+        self.arena.push_free(idx.index);
+        Some(val)
+    }
+
+    /// `None` for a stale `idx`
+    pub fn get_mut(&mut self, idx: SlotIdx) -> Option<&mut T> {
+        if !self.arena.is_live(idx) {
+            return None;
+        }
+        // SAFETY: live slots hold a value, `&mut self` rules out a `take`
+        Some(unsafe { self.values[idx.index].get_mut().assume_init_mut() })
+    }
+
+    /// Whether `idx` wasn't taken yet, stale as soon as it's returned
+    pub fn contains(&self, idx: SlotIdx) -> bool {
+        self.arena.is_live(idx)
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+}
+
+impl<T, const N: usize> Default for Slab<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for Slab<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Slab").field("capacity", &N).finish()
+    }
+}
+
+impl<T, const N: usize> Drop for Slab<T, N> {
+    fn drop(&mut self) {
+        for (gen, value) in self.arena.gens.iter_mut().zip(&mut self.values) {
+            if *gen.get_mut() & 1 == 1 {
+                // SAFETY: odd generations hold a value nobody took
+                unsafe { value.get_mut().assume_init_drop() };
+            }
+        }
+    }
+}
+
+unsafe impl<T: Send, const N: usize> Sync for Slab<T, N> {}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_alloc_arena() {
+        let arena = Arena::<2>::new();
+
+        let a = arena.alloc().unwrap();
+        let b = arena.alloc().unwrap();
+        assert!(arena.alloc().is_none());
+
+        assert!(arena.free(a));
+        assert!(!arena.free(a) && !arena.is_live(a));
+
+        // same slot, next lifetime
+        let c = arena.alloc().unwrap();
+        assert_eq!(c.index(), a.index());
+        assert_ne!(c, a);
+        assert!(arena.is_live(b) && arena.is_live(c));
+    }
+
+    #[test]
+    fn test_stale_arena() {
+        let mut slab = Slab::<alloc::string::String, 4>::new();
+
+        let idx = slab.insert("abc".into()).unwrap();
+        slab.get_mut(idx).unwrap().push('d');
+        assert_eq!(slab.take(idx).as_deref(), Some("abcd"));
+        assert_eq!(slab.take(idx), None);
+
+        // the rest are dropped along with the slab
+        let _ = slab.insert("left".into()).unwrap();
+        let reused = slab.insert("over".into()).unwrap();
+        assert!(slab.get_mut(idx).is_none() && slab.contains(reused));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_threaded_arena() {
+        use crate::ephemeral::spsc::SPSCEphemeral;
+        use std::thread;
+
+        const ITEMS: usize = if cfg!(miri) { 100 } else { 10000 };
+        let slab = Slab::<[usize; 32], 8>::new();
+        let (mut producer, mut consumer) = SPSCEphemeral::<SlotIdx, 8>::new().split();
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                for i in 0..ITEMS {
+                    let mut val = [i; 32];
+                    let idx = loop {
+                        match slab.insert(val) {
+                            Ok(idx) => break idx,
+                            Err(back) => val = back,
+                        }
+                        thread::yield_now();
+                    };
+                    producer.push_blocking(idx).unwrap();
+                }
+            });
+
+            for i in 0..ITEMS {
+                let idx = consumer.pop_blocking().unwrap();
+                assert_eq!(slab.take(idx), Some([i; 32]));
+            }
+        });
+    }
+}
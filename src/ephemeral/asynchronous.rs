@@ -0,0 +1,320 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "futures")]
+use futures_core::Stream;
+#[cfg(feature = "futures")]
+use futures_sink::Sink;
+
+use super::spsc::{Consumer, Disconnected, PopError, Producer, PushError, Ring};
+
+/// Async face of a split `Producer`, a full ring parks
+/// the task on the ring's waker instead of spinning
+pub struct AsyncProducer<R: Ring> {
+    inner: Producer<R>,
+}
+
+impl<R: Ring> AsyncProducer<R> {
+    pub fn new(inner: Producer<R>) -> Self {
+        Self { inner }
+    }
+
+    pub fn into_inner(self) -> Producer<R> {
+        self.inner
+    }
+
+    /// Resolves once `val` made it into the ring,
+    /// or hands it back if the consumer is gone
+    pub fn push(&mut self, val: R::Item) -> Push<'_, R> {
+        Push {
+            producer: &mut self.inner,
+            val: Some(val),
+        }
+    }
+
+    /// Ready once there is a free slot, which stays free
+    /// until this handle pushes into it
+    pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Disconnected>> {
+        poll_ready(&mut self.inner, cx)
+    }
+
+    pub fn try_push(&mut self, val: R::Item) -> Result<(), PushError<R::Item>> {
+        self.inner.try_push(val)
+    }
+
+    pub fn close(&mut self) {
+        self.inner.close();
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.inner.is_disconnected()
+    }
+}
+
+impl<R: Ring> From<Producer<R>> for AsyncProducer<R> {
+    fn from(inner: Producer<R>) -> Self {
+        Self::new(inner)
+    }
+}
+
+/// Async face of a split `Consumer`, an empty ring parks
+/// the task on the ring's waker instead of spinning
+pub struct AsyncConsumer<R: Ring> {
+    inner: Consumer<R>,
+}
+
+impl<R: Ring> AsyncConsumer<R> {
+    pub fn new(inner: Consumer<R>) -> Self {
+        Self { inner }
+    }
+
+    pub fn into_inner(self) -> Consumer<R> {
+        self.inner
+    }
+
+    /// Resolves with the next item, `None` once
+    /// the producer is gone and the ring drained
+    pub fn pop(&mut self) -> Pop<'_, R> {
+        Pop {
+            consumer: &mut self.inner,
+        }
+    }
+
+    pub fn poll_pop(&mut self, cx: &mut Context<'_>) -> Poll<Option<R::Item>> {
+        poll_pop(&mut self.inner, cx)
+    }
+
+    pub fn try_pop(&mut self) -> Result<R::Item, PopError> {
+        self.inner.pop()
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.inner.is_disconnected()
+    }
+}
+
+impl<R: Ring> From<Consumer<R>> for AsyncConsumer<R> {
+    fn from(inner: Consumer<R>) -> Self {
+        Self::new(inner)
+    }
+}
+
+/// Ends once the producer is gone and the ring drained
+#[cfg(feature = "futures")]
+impl<R: Ring> Stream for AsyncConsumer<R> {
+    type Item = R::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<R::Item>> {
+        self.get_mut().poll_pop(cx)
+    }
+}
+
+/// Items are visible to the consumer as soon as they are sent,
+/// so flushing has nothing left to do and closing closes the ring
+#[cfg(feature = "futures")]
+impl<R: Ring> Sink<R::Item> for AsyncProducer<R> {
+    type Error = Disconnected;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Disconnected>> {
+        AsyncProducer::poll_ready(self.get_mut(), cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, val: R::Item) -> Result<(), Disconnected> {
+        match self.get_mut().try_push(val) {
+            Ok(()) => Ok(()),
+            Err(PushError::Disconnected(_)) => Err(Disconnected),
+            Err(PushError::Full(_)) => panic!("`start_send` without a successful `poll_ready`"),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Disconnected>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Disconnected>> {
+        self.get_mut().close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Future returned by `AsyncProducer::push`
+pub struct Push<'a, R: Ring> {
+    producer: &'a mut Producer<R>,
+    val: Option<R::Item>,
+}
+
+// the pending value is moved in and out, never pinned
+impl<R: Ring> Unpin for Push<'_, R> {}
+
+impl<R: Ring> Future for Push<'_, R> {
+    type Output = Result<(), R::Item>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if poll_ready(this.producer, cx).is_pending() {
+            return Poll::Pending;
+        }
+
+        // a free slot only goes away when the consumer disconnects
+        let val = this.val.take().expect("`Push` polled after completion");
+        Poll::Ready(this.producer.push(val))
+    }
+}
+
+/// Future returned by `AsyncConsumer::pop`
+pub struct Pop<'a, R: Ring> {
+    consumer: &'a mut Consumer<R>,
+}
+
+impl<R: Ring> Future for Pop<'_, R> {
+    type Output = Option<R::Item>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<R::Item>> {
+        poll_pop(self.consumer, cx)
+    }
+}
+
+pub(crate) fn poll_ready<R: Ring>(
+    producer: &mut Producer<R>,
+    cx: &mut Context<'_>,
+) -> Poll<Result<(), Disconnected>> {
+    if let Poll::Ready(res) = ready(producer) {
+        return Poll::Ready(res);
+    }
+
+    // register first, then recheck so a pop in between isn't missed
+    producer.bufr.state().producer_waker.register(cx.waker());
+    ready(producer)
+}
+
+fn ready<R: Ring>(producer: &mut Producer<R>) -> Poll<Result<(), Disconnected>> {
+    match producer.has_room() {
+        true => Poll::Ready(Ok(())),
+        false if producer.is_disconnected() => Poll::Ready(Err(Disconnected)),
+        false => Poll::Pending,
+    }
+}
+
+pub(crate) fn poll_pop<R: Ring>(
+    consumer: &mut Consumer<R>,
+    cx: &mut Context<'_>,
+) -> Poll<Option<R::Item>> {
+    if let Poll::Ready(val) = pop(consumer) {
+        return Poll::Ready(val);
+    }
+
+    // register first, then retry so a push in between isn't missed
+    consumer.bufr.state().consumer_waker.register(cx.waker());
+    pop(consumer)
+}
+
+fn pop<R: Ring>(consumer: &mut Consumer<R>) -> Poll<Option<R::Item>> {
+    match consumer.pop() {
+        Ok(val) => Poll::Ready(Some(val)),
+        Err(PopError::Disconnected) => Poll::Ready(None),
+        Err(PopError::Empty) => Poll::Pending,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ephemeral::spsc::SPSCEphemeral;
+    use std::sync::Arc;
+    use std::task::{Wake, Waker};
+    use std::thread::{self, Thread};
+
+    struct Unparker(Thread);
+
+    impl Wake for Unparker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Minimal executor, parks the thread until the waker fires
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = std::pin::pin!(fut);
+        let waker = Waker::from(Arc::new(Unparker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+
+        loop {
+            if let Poll::Ready(val) = fut.as_mut().poll(&mut cx) {
+                return val;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn test_async_roundtrip() {
+        let (producer, consumer) = SPSCEphemeral::<i32, 4>::new().split();
+        let mut producer = AsyncProducer::from(producer);
+        let mut consumer = AsyncConsumer::from(consumer);
+
+        let produce_t = thread::spawn(move || {
+            block_on(async {
+                for i in 0..10000 {
+                    producer.push(i).await.unwrap();
+                }
+            })
+        });
+
+        block_on(async {
+            for i in 0..10000 {
+                assert_eq!(consumer.pop().await, Some(i));
+            }
+        });
+        produce_t.join().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_async_wakes_from_sync_side() {
+        let (mut producer, consumer) = SPSCEphemeral::<i32, 2>::new().split();
+        let mut consumer = AsyncConsumer::from(consumer);
+
+        let produce_t = thread::spawn(move || {
+            for i in 0..1000 {
+                producer.push_blocking(i).unwrap();
+            }
+        });
+
+        block_on(async {
+            for i in 0..1000 {
+                assert_eq!(consumer.pop().await, Some(i));
+            }
+            // the producer thread dropping its handle ends the ring
+            assert_eq!(consumer.pop().await, None);
+        });
+        produce_t.join().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "futures")]
+    fn test_stream_sink() {
+        use futures::{executor, stream, SinkExt, StreamExt};
+
+        let (producer, consumer) = SPSCEphemeral::<i32, 4>::new().split();
+        let mut sink = AsyncProducer::from(producer);
+        let stream = AsyncConsumer::from(consumer);
+
+        let produce_t = thread::spawn(move || {
+            executor::block_on(async {
+                let mut items = stream::iter(0..1000).map(Ok);
+                sink.send_all(&mut items).await.unwrap();
+            })
+        });
+
+        let evens: Vec<_> = executor::block_on(
+            stream
+                .filter(|val| std::future::ready(val % 2 == 0))
+                .collect(), // ends once the producer thread drops the sink
+        );
+        assert_eq!(evens, (0..1000).step_by(2).collect::<Vec<_>>());
+        produce_t.join().unwrap();
+    }
+}
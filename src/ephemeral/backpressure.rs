@@ -0,0 +1,249 @@
+use core::{fmt, marker::PhantomData};
+
+use super::handle::Produce;
+use super::spsc::PushError;
+use super::wait::{retry, Backoff, WaitStrategy};
+
+/// What `Backpressure::push` does about a full queue
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FullPolicy {
+    /// waits with the wait strategy until there's room
+    #[default]
+    Block,
+    /// drops the value being pushed and carries on
+    DropNewest,
+    /// drops the oldest pending item to make room, only handles
+    /// that can pop from the pushing end (`Produce::CAN_EVICT`)
+    DropOldest,
+    /// hands the value back as `PushError::Full`
+    Error,
+}
+
+/// Items `Backpressure` dropped so far, by policy
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Drops {
+    /// pushed values dropped under `DropNewest`
+    pub newest: u64,
+    /// pending items dropped under `DropOldest`
+    pub oldest: u64,
+}
+
+/// Producer handle with its behaviour on a full queue spelled out,
+/// and swappable at runtime with `set_policy`. Other handles of the
+/// same queue keep their own
+///
+/// Every policy hands the value back as `Disconnected` once the
+/// wrapped handle reports nobody left to pop
+pub struct Backpressure<T, P, W = Backoff> {
+    inner: P,
+    policy: FullPolicy,
+    wait: W, // copied fresh for every blocking push
+    drops: Drops,
+    _item: PhantomData<fn(T)>,
+}
+
+impl<T, P: Produce<T>> Backpressure<T, P> {
+    /// Blocking waits `Backoff`
+    ///
+    /// Panics on `DropOldest` for a handle that can't evict
+    pub fn new(inner: P, policy: FullPolicy) -> Self {
+        Self::with_wait(inner, policy, Backoff::new())
+    }
+}
+
+impl<T, P: Produce<T>, W: WaitStrategy + Clone> Backpressure<T, P, W> {
+    /// `new` with blocking waits passed with `wait`
+    pub fn with_wait(inner: P, policy: FullPolicy, wait: W) -> Self {
+        assert_policy::<T, P>(policy);
+        Self {
+            inner,
+            policy,
+            wait,
+            drops: Drops::default(),
+            _item: PhantomData,
+        }
+    }
+
+    pub fn push(&mut self, val: T) -> Result<(), PushError<T>> {
+        let val = match self.inner.try_push(val) {
+            Ok(()) => return Ok(()),
+            Err(val) => val,
+        };
+        // guard: full for good
+        if self.inner.is_disconnected() {
+            return Err(PushError::Disconnected(val));
+        }
+
+        match self.policy {
+            FullPolicy::Block => {
+                let (inner, mut pending) = (&mut self.inner, Some(val));
+                retry(&mut self.wait.clone(), || {
+                    match inner.try_push(pending.take()?) {
+                        Ok(()) => Some(Ok(())),
+                        Err(val) if inner.is_disconnected() => {
+                            Some(Err(PushError::Disconnected(val)))
+                        }
+                        Err(val) => {
+                            pending = Some(val);
+                            None
+                        }
+                    }
+                })
+            }
+            FullPolicy::DropNewest => {
+                self.drops.newest += 1;
+                Ok(())
+            }
+            FullPolicy::DropOldest => {
+                let mut val = val;
+                loop {
+                    // a consumer may have taken it first, room either way
+                    if self.inner.evict().is_some() {
+                        self.drops.oldest += 1;
+                    }
+                    match self.inner.try_push(val) {
+                        Ok(()) => return Ok(()),
+                        Err(back) => val = back,
+                    }
+                }
+            }
+            FullPolicy::Error => Err(PushError::Full(val)),
+        }
+    }
+
+    pub fn policy(&self) -> FullPolicy {
+        self.policy
+    }
+
+    /// Takes effect from the next push on
+    ///
+    /// Panics on `DropOldest` for a handle that can't evict
+    pub fn set_policy(&mut self, policy: FullPolicy) {
+        assert_policy::<T, P>(policy);
+        self.policy = policy;
+    }
+}
+
+impl<T, P, W> Backpressure<T, P, W> {
+    pub fn drops(&self) -> Drops {
+        self.drops
+    }
+
+    pub fn get_ref(&self) -> &P {
+        &self.inner
+    }
+
+    /// Pushing through this skips the policy
+    pub fn get_mut(&mut self) -> &mut P {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+}
+
+impl<T, P: Produce<T>, W: WaitStrategy + Clone> Produce<T> for Backpressure<T, P, W> {
+    fn try_push(&mut self, val: T) -> Result<(), T> {
+        self.push(val).map_err(PushError::into_inner)
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.inner.is_disconnected()
+    }
+}
+
+impl<T, P, W> fmt::Debug for Backpressure<T, P, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Backpressure")
+            .field("policy", &self.policy)
+            .field("drops", &self.drops)
+            .finish_non_exhaustive()
+    }
+}
+
+fn assert_policy<T, P: Produce<T>>(policy: FullPolicy) {
+    assert!(
+        policy != FullPolicy::DropOldest || P::CAN_EVICT,
+        "this handle can't pop, so it can't drop the oldest item"
+    );
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use crate::ephemeral::{mpmc::MPMCEphemeral, spmc::SPMCEphemeral, spsc::SPSCEphemeral};
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_block_backpressure() {
+        use std::thread;
+
+        let (producer, mut consumer) = SPSCEphemeral::<u32, 2>::new().split();
+        let mut producer = Backpressure::new(producer, FullPolicy::Block);
+        let produce_t = thread::spawn(move || {
+            for i in 0..1000 {
+                producer.push(i).unwrap();
+            }
+            producer
+        });
+        for i in 0..1000 {
+            assert_eq!(consumer.pop_blocking(), Ok(i));
+        }
+        let mut producer = produce_t.join().unwrap();
+        assert_eq!(producer.drops(), Drops::default());
+
+        // the consumer going away ends the wait
+        producer.push(0).unwrap();
+        producer.push(1).unwrap();
+        drop(consumer);
+        assert_eq!(producer.push(2), Err(PushError::Disconnected(2)));
+    }
+
+    #[test]
+    fn test_drop_backpressure() {
+        let (producer, consumer) = SPMCEphemeral::<u32, 2>::new().split();
+        let mut producer = Backpressure::new(producer, FullPolicy::DropNewest);
+        for i in 0..4 {
+            producer.push(i).unwrap();
+        }
+        assert_eq!((consumer.pop(), consumer.pop()), (Some(0), Some(1)));
+
+        // swapped at runtime, same handle
+        producer.set_policy(FullPolicy::DropOldest);
+        for i in 4..8 {
+            producer.push(i).unwrap();
+        }
+        assert_eq!((consumer.pop(), consumer.pop()), (Some(6), Some(7)));
+        assert_eq!(
+            producer.drops(),
+            Drops {
+                newest: 2,
+                oldest: 2
+            }
+        );
+
+        let queue = MPMCEphemeral::<u32, 2>::new();
+        let mut shared = Backpressure::new(&queue, FullPolicy::DropOldest);
+        (0..3).for_each(|i| shared.push(i).unwrap());
+        assert_eq!(queue.into_inner(), [1, 2]);
+    }
+
+    #[test]
+    fn test_error_backpressure() {
+        let (producer, _consumer) = SPSCEphemeral::<u32, 2>::new().split();
+        let mut producer = Backpressure::new(producer, FullPolicy::Error);
+        producer.push(0).unwrap();
+        producer.push(1).unwrap();
+        assert_eq!(producer.push(2), Err(PushError::Full(2)));
+        assert_eq!(producer.drops(), Drops::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "can't drop the oldest item")]
+    fn test_evict_backpressure() {
+        let (producer, _consumer) = SPSCEphemeral::<u32, 2>::new().split();
+        let mut producer = Backpressure::new(producer, FullPolicy::Error);
+        producer.set_policy(FullPolicy::DropOldest);
+    }
+}
@@ -0,0 +1,330 @@
+use alloc::{boxed::Box, sync::Arc};
+use core::{
+    cell::UnsafeCell,
+    slice,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::util::CachePadded;
+
+#[cfg(feature = "stats")]
+use super::stats::{Counters, Stats};
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+
+/// SPSC byte ring handing out contiguous regions, a grant that
+/// doesn't fit before the end wraps to the front and the bytes
+/// skipped at the end are fenced off by the `last` watermark
+pub struct BipBuffer {
+    bufr: Box<[UnsafeCell<u8>]>,
+    read: CachePadded<AtomicUsize>,  // start of the committed region
+    write: CachePadded<AtomicUsize>, // end of the committed region
+    last: CachePadded<AtomicUsize>,  // end of readable bytes before a wrap
+    #[cfg(feature = "stats")]
+    stats: Counters,
+    #[cfg(feature = "tracing")]
+    trace: Label,
+}
+
+impl BipBuffer {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            bufr: (0..capacity).map(|_| UnsafeCell::new(0)).collect(),
+            read: CachePadded::new(AtomicUsize::new(0)),
+            write: CachePadded::new(AtomicUsize::new(0)),
+            last: CachePadded::new(AtomicUsize::new(0)),
+            #[cfg(feature = "stats")]
+            stats: Counters::new(),
+            #[cfg(feature = "tracing")]
+            trace: Label::new("bip"),
+        }
+    }
+
+    /// Names the buffer in its `tracing` events, `bip` otherwise.
+    /// Counts are in bytes, a commit is a push and a release a pop
+    #[cfg(feature = "tracing")]
+    pub fn with_name(mut self, name: &'static str) -> Self {
+        self.trace = Label::new(name);
+        self
+    }
+
+    pub fn split(self) -> (Writer, Reader) {
+        let bufr = Arc::new(self);
+        let writer = Writer {
+            bufr: bufr.clone(),
+            grant: None,
+        };
+        (writer, Reader { bufr, grant: None })
+    }
+
+    fn capacity(&self) -> usize {
+        self.bufr.len()
+    }
+
+    /// Committed bytes, counting both runs while wrapped
+    fn len(&self) -> usize {
+        let read = self.read.load(Ordering::Acquire);
+        let write = self.write.load(Ordering::Acquire);
+        if write >= read {
+            write - read
+        } else {
+            self.last.load(Ordering::Acquire) - read + write
+        }
+    }
+
+    /// Region only ever touched by the side holding the matching grant
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn region(&self, start: usize, len: usize) -> &mut [u8] {
+        slice::from_raw_parts_mut(UnsafeCell::raw_get(self.bufr.as_ptr().add(start)), len)
+    }
+}
+
+unsafe impl Sync for BipBuffer {}
+
+/// Write half of a split `BipBuffer`
+pub struct Writer {
+    bufr: Arc<BipBuffer>,
+    grant: Option<(usize, usize)>, // start and length of the open grant
+}
+
+impl Writer {
+    /// Contiguous room for exactly `len` bytes, nothing is visible
+    /// to the reader until `commit`. A new grant replaces an open one
+    pub fn grant(&mut self, len: usize) -> Option<&mut [u8]> {
+        let b = &*self.bufr;
+        let write = b.write.load(Ordering::Acquire);
+        let read = b.read.load(Ordering::Acquire);
+
+        let start = if write < read {
+            // already wrapped, only the gap up to `read` is free
+            (write + len < read).then_some(write)
+        } else if write + len <= b.capacity() {
+            Some(write)
+        } else {
+            // wrap around, keeping one byte so `write == read` stays empty
+            (len < read).then_some(0)
+        };
+        // guard: no room
+        let Some(start) = start else {
+            #[cfg(feature = "stats")]
+            b.stats.full();
+            #[cfg(feature = "tracing")]
+            b.trace.full();
+            return None;
+        };
+
+        self.grant = Some((start, len));
+        Some(unsafe { b.region(start, len) })
+    }
+
+    /// Publishes the first `used` bytes of the open grant
+    pub fn commit(&mut self, used: usize) {
+        let (start, len) = self.grant.take().expect("`commit` without a grant");
+        let b = &*self.bufr;
+
+        let write = b.write.load(Ordering::Acquire);
+        let last = b.last.load(Ordering::Acquire);
+        let new_write = start + used.min(len);
+
+        if new_write < write && write != b.capacity() {
+            // wrapped, fence off the bytes skipped at the end
+            b.last.store(write, Ordering::Release);
+        } else if new_write > last {
+            // moved past the old fence, the whole arena is usable again
+            b.last.store(b.capacity(), Ordering::Release);
+        }
+        b.write.store(new_write, Ordering::Release);
+        #[cfg(feature = "stats")]
+        b.stats.pushed(used.min(len), b.len());
+        #[cfg(feature = "tracing")]
+        b.trace.pushed(used.min(len), b.len());
+    }
+
+    /// Committed bytes not yet released, approximate while the other side is busy
+    pub fn len(&self) -> usize {
+        self.bufr.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.bufr.capacity()
+    }
+
+    occupancy!();
+
+    /// In bytes, `pushes` are committed and `pops` released
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.bufr.stats.snapshot()
+    }
+}
+
+/// Read half of a split `BipBuffer`
+pub struct Reader {
+    bufr: Arc<BipBuffer>,
+    grant: Option<(usize, usize)>, // start and length of the open read
+}
+
+impl Reader {
+    /// Longest committed run of bytes, wrapped data shows
+    /// up once everything before the fence was released
+    pub fn read(&mut self) -> Option<&[u8]> {
+        let b = &*self.bufr;
+        let write = b.write.load(Ordering::Acquire);
+        let last = b.last.load(Ordering::Acquire);
+        let mut read = b.read.load(Ordering::Relaxed);
+
+        // drained up to the fence, follow the writer to the front
+        if read == last && write < read {
+            read = 0;
+            b.read.store(0, Ordering::Release);
+        }
+
+        let end = if write < read { last } else { write };
+        // guard: nothing committed
+        if end == read {
+            #[cfg(feature = "stats")]
+            b.stats.empty();
+            #[cfg(feature = "tracing")]
+            b.trace.empty();
+            return None;
+        }
+
+        self.grant = Some((read, end - read));
+        Some(unsafe { b.region(read, end - read) })
+    }
+
+    /// Hands the first `used` bytes of the last `read` back to the writer
+    pub fn release(&mut self, used: usize) {
+        let (start, len) = self.grant.take().expect("`release` without a read");
+        self.bufr
+            .read
+            .store(start + used.min(len), Ordering::Release);
+        #[cfg(feature = "stats")]
+        self.bufr.stats.popped(used.min(len));
+        #[cfg(feature = "tracing")]
+        self.bufr.trace.popped(used.min(len));
+    }
+
+    /// Committed bytes not yet released, approximate while the other side is busy
+    pub fn len(&self) -> usize {
+        self.bufr.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.bufr.capacity()
+    }
+
+    occupancy!();
+
+    /// In bytes, `pushes` are committed and `pops` released
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.bufr.stats.snapshot()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_seq_bip() {
+        let (mut writer, mut reader) = BipBuffer::with_capacity(8).split();
+
+        writer.grant(6).unwrap().copy_from_slice(b"hello!");
+        assert!(reader.read().is_none());
+        writer.commit(6);
+        assert!(writer.grant(3).is_none());
+
+        assert_eq!(reader.read(), Some(&b"hello!"[..]));
+        reader.release(4);
+
+        // doesn't fit after "o!", wraps to the front
+        writer.grant(3).unwrap().copy_from_slice(b"abc");
+        writer.commit(3);
+        assert_eq!(reader.read(), Some(&b"o!"[..]));
+        reader.release(2);
+        assert_eq!(reader.read(), Some(&b"abc"[..]));
+        reader.release(3);
+        assert!(reader.read().is_none());
+    }
+
+    #[test]
+    fn test_partial_commit_bip() {
+        let (mut writer, mut reader) = BipBuffer::with_capacity(8).split();
+
+        writer.grant(6).unwrap()[..2].copy_from_slice(b"ab");
+        writer.commit(2);
+        assert_eq!(reader.read(), Some(&b"ab"[..]));
+
+        // an uncommitted grant is simply dropped
+        writer.grant(3).unwrap().fill(b'x');
+        writer.grant(1).unwrap()[0] = b'c';
+        writer.commit(1);
+        reader.release(2);
+        assert_eq!(reader.read(), Some(&b"c"[..]));
+    }
+
+    #[test]
+    fn test_threaded_bip() {
+        let (mut writer, mut reader) = BipBuffer::with_capacity(64).split();
+
+        // length-prefixed frames of 1..=20 bytes
+        let produce_t = thread::spawn(move || {
+            for i in 0..2000usize {
+                let len = i % 20 + 1;
+                let frame = loop {
+                    if let Some(frame) = writer.grant(len + 1) {
+                        break frame;
+                    }
+                    thread::yield_now();
+                };
+                frame[0] = len as u8;
+                frame[1..].fill(i as u8);
+                writer.commit(len + 1);
+            }
+        });
+
+        for i in 0..2000usize {
+            let frame = loop {
+                if let Some(frame) = reader.read() {
+                    break frame;
+                }
+                thread::yield_now();
+            };
+            let len = frame[0] as usize;
+            assert_eq!(len, i % 20 + 1);
+            assert!(frame[1..=len].iter().all(|&byte| byte == i as u8));
+            reader.release(len + 1);
+        }
+        produce_t.join().unwrap();
+    }
+
+    #[test]
+    fn test_len_bip() {
+        let (mut writer, mut reader) = BipBuffer::with_capacity(8).split();
+        assert!(writer.is_empty());
+        assert_eq!((writer.capacity(), reader.free_space()), (8, 8));
+
+        writer.grant(6).unwrap();
+        // granted bytes don't count before `commit`
+        assert_eq!(reader.len(), 0);
+        writer.commit(6);
+        assert_eq!((writer.len(), reader.free_space()), (6, 2));
+
+        reader.read().unwrap();
+        reader.release(4);
+        writer.grant(3).unwrap();
+        writer.commit(3);
+        // wrapped, two bytes before the fence and three at the front
+        assert_eq!((reader.len(), writer.free_space()), (5, 3));
+
+        reader.read().unwrap();
+        reader.release(2);
+        assert_eq!(reader.len(), 3);
+        reader.read().unwrap();
+        reader.release(3);
+        assert!(reader.is_empty());
+    }
+}
@@ -0,0 +1,212 @@
+use alloc::vec::Vec;
+use core::{iter, mem::MaybeUninit, slice};
+
+use crate::sync::UnsafeCell;
+
+#[cfg(feature = "latency")]
+use super::latency::LatencyStats;
+use super::spsc::{
+    drop_pending, len, pop, push, sealed, split, Consumer, Producer, Ring, RingState,
+};
+#[cfg(feature = "stats")]
+use super::stats::Stats;
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+
+type Slot<T> = UnsafeCell<MaybeUninit<T>>;
+
+/// SPSC ring over storage the caller owns, a `static` buffer, a
+/// region of shared memory or a linker section, instead of an arena
+/// of its own. The ring and its split handles borrow the storage,
+/// so it outlives them. Items still pending when the ring goes are
+/// dropped, the storage is left uninitialized
+pub struct RingRef<'a, T> {
+    bufr: &'a [Slot<T>],
+    state: RingState,
+}
+
+impl<'a, T> RingRef<'a, T> {
+    /// Ring over the largest power of two of `storage`'s slots,
+    /// any past it go unused
+    ///
+    /// # Panics
+    /// On empty `storage`
+    pub fn from_slice(storage: &'a mut [MaybeUninit<T>]) -> Self {
+        assert!(!storage.is_empty(), "ring over empty storage");
+
+        let cap = 1 << storage.len().ilog2();
+        unsafe { Self::from_raw_parts(storage.as_mut_ptr(), cap) }
+    }
+
+    /// Ring over the `cap` slots starting at `ptr`
+    ///
+    /// # Safety
+    /// `ptr` must be valid for reads and writes of `cap` slots and
+    /// suitably aligned, and nothing else may touch them for `'a`
+    ///
+    /// # Panics
+    /// If `cap` isn't a power of two
+    pub unsafe fn from_raw_parts(ptr: *mut MaybeUninit<T>, cap: usize) -> Self {
+        assert!(cap.is_power_of_two(), "arena size must be a power of two");
+
+        // `UnsafeCell` is transparent, and the caller handed us the slots
+        let bufr = unsafe { slice::from_raw_parts(ptr.cast::<Slot<T>>(), cap) };
+        Self {
+            bufr,
+            state: RingState::new(),
+        }
+    }
+
+    /// Names the ring in its `tracing` events, `spsc` otherwise
+    #[cfg(feature = "tracing")]
+    pub fn with_name(mut self, name: &'static str) -> Self {
+        self.state.trace = Label::new(name);
+        self
+    }
+
+    /// Moves the ring behind a producer/consumer pair, both
+    /// borrowing the storage for as long as the ring did
+    pub fn split(self) -> (Producer<Self>, Consumer<Self>) {
+        split(self)
+    }
+
+    /// Pushes while nothing else holds the ring, `split` pushes from
+    /// another thread
+    pub fn push(&mut self, val: T) -> Result<(), T> {
+        push(self, val)
+    }
+
+    /// Pops while nothing else holds the ring, `split` pops from
+    /// another thread
+    pub fn pop(&mut self) -> Option<T> {
+        pop(self)
+    }
+
+    /// Pending items, approximate while the other side is busy
+    pub fn len(&self) -> usize {
+        len(self)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.bufr.len()
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.state.stats.snapshot()
+    }
+
+    /// Queueing delay histogram of what was popped so far
+    #[cfg(feature = "latency")]
+    pub fn latency_stats(&self) -> LatencyStats {
+        self.state.latency.snapshot()
+    }
+
+    /// Drops every pending item
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Remaining items in pop order, handing the storage back empty
+    pub fn into_inner(mut self) -> Vec<T> {
+        iter::from_fn(|| self.pop()).collect()
+    }
+}
+
+impl<T> sealed::Sealed for RingRef<'_, T> {}
+
+unsafe impl<T> Ring for RingRef<'_, T> {
+    type Item = T;
+
+    fn arena_size(&self) -> usize {
+        self.bufr.len()
+    }
+
+    fn state(&self) -> &RingState {
+        &self.state
+    }
+
+    fn slot(&self, idx: usize) -> &UnsafeCell<MaybeUninit<T>> {
+        &self.bufr[idx]
+    }
+}
+
+impl<T> Drop for RingRef<'_, T> {
+    fn drop(&mut self) {
+        drop_pending(self);
+    }
+}
+
+unsafe impl<T: Send> Send for RingRef<'_, T> {}
+unsafe impl<T: Send> Sync for RingRef<'_, T> {}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use std::{rc::Rc, thread};
+
+    #[test]
+    fn test_slice_borrowed() {
+        let mut storage = [const { MaybeUninit::uninit() }; 6];
+        let mut ring = RingRef::from_slice(&mut storage);
+        // rounded down to the slots a power of two covers
+        assert_eq!(ring.capacity(), 4);
+
+        for i in 0..4 {
+            ring.push(i).unwrap();
+        }
+        assert_eq!(ring.push(4), Err(4));
+        assert_eq!(ring.pop(), Some(0));
+        assert_eq!(ring.into_inner(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_threaded_borrowed() {
+        let mut storage = [const { MaybeUninit::uninit() }; 8];
+        let (mut producer, mut consumer) = RingRef::from_slice(&mut storage).split();
+
+        // scoped threads, the handles can't outlive `storage`
+        thread::scope(|s| {
+            s.spawn(move || {
+                for i in 0..1000 {
+                    while producer.push(i).is_err() {
+                        thread::yield_now();
+                    }
+                }
+            });
+            for i in 0..1000 {
+                loop {
+                    if let Ok(val) = consumer.pop() {
+                        assert_eq!(val, i);
+                        break;
+                    }
+                    thread::yield_now();
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_raw_parts_borrowed() {
+        let mut storage = Vec::<MaybeUninit<Rc<u8>>>::with_capacity(4);
+        let item = Rc::new(0);
+
+        let mut ring = unsafe { RingRef::from_raw_parts(storage.as_mut_ptr(), 4) };
+        ring.push(item.clone()).unwrap();
+        ring.push(item.clone()).unwrap();
+        assert_eq!(Rc::strong_count(&item), 3);
+
+        // what's left is dropped with the ring, not the storage
+        drop(ring);
+        assert_eq!(Rc::strong_count(&item), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "power of two")]
+    fn test_raw_parts_capacity_borrowed() {
+        let mut storage = [const { MaybeUninit::<u8>::uninit() }; 3];
+        let _ring = unsafe { RingRef::from_raw_parts(storage.as_mut_ptr(), 3) };
+    }
+}
@@ -0,0 +1,741 @@
+use std::{
+    cell::UnsafeCell,
+    error::Error,
+    fmt,
+    mem::MaybeUninit,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+#[cfg(feature = "async")]
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::fence,
+    task::{Context, Poll, Waker},
+};
+
+use crate::util::CachePadded;
+
+#[cfg(feature = "stats")]
+use super::stats::{Counters, Stats};
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+use super::wait::{retry, Backoff, WaitStrategy};
+
+/// What the producer does about a subscriber a whole lap behind
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Policy {
+    /// pushing fails until the slowest subscriber caught up
+    Block,
+    /// the oldest items are overwritten, slow subscribers skip ahead
+    Lag,
+}
+
+/// Why `Subscriber::pop` came back empty handed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PopError {
+    Empty,
+    /// this many items were overwritten before they were read,
+    /// the subscriber was moved on to the oldest one still queued
+    Lagged(u64),
+}
+
+impl fmt::Display for PopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.pad("popping from an empty ring"),
+            Self::Lagged(n) => write!(f, "lagged {n} items behind the producer"),
+        }
+    }
+}
+
+impl Error for PopError {}
+
+/// Why `AsyncSubscriber::recv` came back empty handed
+#[cfg(feature = "async")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecvError {
+    /// the producer is gone and this subscriber saw everything it pushed
+    Closed,
+    /// as `PopError::Lagged`, the next `recv` carries on from the
+    /// oldest item still queued
+    Lagged(u64),
+}
+
+#[cfg(feature = "async")]
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Closed => f.pad("receiving from a closed ring"),
+            Self::Lagged(n) => write!(f, "lagged {n} items behind the producer"),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Error for RecvError {}
+
+/// Ring slot stamped with the position it holds, `2 * pos + 2` once
+/// written, `2 * pos + 1` while being written and 0 before the first
+/// lap. Readers pin it while cloning, the producer waits them out
+/// before overwriting, so a clone never sees a value being replaced
+struct Slot<T> {
+    stamp: AtomicU64,
+    readers: AtomicU32, // clones in progress
+    val: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T: Clone> Slot<T> {
+    const fn new() -> Self {
+        Self {
+            stamp: AtomicU64::new(0),
+            readers: AtomicU32::new(0),
+            val: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Replaces the value with `val` for position `pos`, producer only
+    fn write(&self, pos: u64, val: T) {
+        let old = self.stamp.load(Ordering::Relaxed);
+        // SeqCst against `read`, either a reader sees the odd stamp
+        // and backs off or the count of its pin is seen here
+        self.stamp.store(2 * pos + 1, Ordering::SeqCst);
+        let mut backoff = Backoff::new();
+        while self.readers.load(Ordering::SeqCst) != 0 {
+            backoff.snooze();
+        }
+
+        unsafe {
+            let slot = &mut *self.val.get();
+            // guard: first lap, nothing to drop
+            if old != 0 {
+                slot.assume_init_drop();
+            }
+            slot.write(val);
+        }
+        self.stamp.store(2 * pos + 2, Ordering::SeqCst);
+    }
+
+    /// A clone of the value at `pos`, or the position the slot
+    /// holds or is being written for instead
+    fn read(&self, pos: u64) -> Result<T, u64> {
+        self.readers.fetch_add(1, Ordering::SeqCst);
+        let stamp = self.stamp.load(Ordering::SeqCst);
+        let res = if stamp == 2 * pos + 2 {
+            Ok(unsafe { (*self.val.get()).assume_init_ref().clone() })
+        } else {
+            // a slot never written counts as holding position 0
+            Err(stamp.saturating_sub(1) / 2)
+        };
+        self.readers.fetch_sub(1, Ordering::SeqCst);
+        res
+    }
+}
+
+/// Bounded fan-out ring, one producer and any number of
+/// subscribers that each see every item from their own cursor.
+/// A subscriber pins the one slot it clones from, so a lagging
+/// subscriber never clones a value that is being overwritten
+/// N:: arena size, a power of two
+pub struct BroadcastEphemeral<T, const N: usize> {
+    bufr: [Slot<T>; N],
+    tail: CachePadded<AtomicU64>, // write position
+    policy: Policy,
+    // read positions of live subscribers, only consulted by `Policy::Block`
+    cursors: Mutex<Vec<Arc<CachePadded<AtomicU64>>>>,
+    closed: AtomicBool, // the producer is gone
+    // async subscribers waiting for the next push, all woken by it
+    #[cfg(feature = "async")]
+    wakers: Mutex<Vec<Waker>>,
+    #[cfg(feature = "async")]
+    parked: AtomicBool, // `wakers` isn't empty, spares pushes the lock
+    #[cfg(feature = "stats")]
+    stats: Counters,
+    #[cfg(feature = "tracing")]
+    trace: Label,
+}
+
+impl<T: Clone, const N: usize> BroadcastEphemeral<T, N> {
+    pub const fn new(policy: Policy) -> Self {
+        const { assert!(N.is_power_of_two(), "arena size must be a power of two") };
+
+        Self {
+            bufr: [const { Slot::new() }; N],
+            tail: CachePadded::new(AtomicU64::new(0)),
+            policy,
+            cursors: Mutex::new(Vec::new()),
+            closed: AtomicBool::new(false),
+            #[cfg(feature = "async")]
+            wakers: Mutex::new(Vec::new()),
+            #[cfg(feature = "async")]
+            parked: AtomicBool::new(false),
+            #[cfg(feature = "stats")]
+            stats: Counters::new(),
+            #[cfg(feature = "tracing")]
+            trace: Label::new("broadcast"),
+        }
+    }
+
+    /// Names the ring in its `tracing` events, `broadcast` otherwise.
+    /// Every subscriber's pops show up under it
+    #[cfg(feature = "tracing")]
+    pub const fn with_name(mut self, name: &'static str) -> Self {
+        self.trace = Label::new(name);
+        self
+    }
+
+    /// Moves the ring behind its producer and a first subscriber,
+    /// further ones come from `Producer::subscribe` or cloning
+    pub fn split(self) -> (Producer<T, N>, Subscriber<T, N>) {
+        let bufr = Arc::new(self);
+        let subscriber = Subscriber::register(bufr.clone(), 0);
+        (Producer { bufr, min: 0 }, subscriber)
+    }
+
+    /// Every subscriber's pops count, `high_water` is the backlog
+    /// of the slowest one
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.stats.snapshot()
+    }
+
+    /// Slowest live read position, `tail` when nobody subscribed
+    fn min_cursor(&self, tail: u64) -> u64 {
+        let cursors = self.cursors.lock().unwrap_or_else(PoisonError::into_inner);
+        cursors
+            .iter()
+            .map(|cursor| cursor.load(Ordering::Acquire))
+            .fold(tail, u64::min)
+    }
+
+    /// Wakes every parked async subscriber
+    #[cfg(feature = "async")]
+    fn wake_all(&self) {
+        // pairs with the fence in `AsyncSubscriber::poll_recv`, either
+        // the push is seen there or the parked flag here
+        fence(Ordering::SeqCst);
+        if !self.parked.load(Ordering::Relaxed) {
+            return;
+        }
+        let wakers = {
+            let mut wakers = self.wakers.lock().unwrap_or_else(PoisonError::into_inner);
+            self.parked.store(false, Ordering::Relaxed);
+            std::mem::take(&mut *wakers)
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl<T, const N: usize> Drop for BroadcastEphemeral<T, N> {
+    fn drop(&mut self) {
+        for slot in &mut self.bufr {
+            if *slot.stamp.get_mut() != 0 {
+                unsafe { slot.val.get_mut().assume_init_drop() };
+            }
+        }
+    }
+}
+
+// subscribers on several threads clone the same items
+unsafe impl<T: Send + Sync, const N: usize> Sync for BroadcastEphemeral<T, N> {}
+
+/// Write half of a split `BroadcastEphemeral`
+pub struct Producer<T: Clone, const N: usize> {
+    bufr: Arc<BroadcastEphemeral<T, N>>,
+    min: u64, // last seen slowest cursor, only refreshed on apparent full
+}
+
+impl<T: Clone, const N: usize> Producer<T, N> {
+    /// Only fails under `Policy::Block`, while a subscriber is a lap behind
+    pub fn push(&mut self, val: T) -> Result<(), T> {
+        let b = &*self.bufr;
+        let tail = b.tail.load(Ordering::Relaxed);
+
+        if b.policy == Policy::Block && tail - self.min == N as u64 {
+            self.min = b.min_cursor(tail);
+            // guard: slowest subscriber still a lap behind
+            if tail - self.min == N as u64 {
+                #[cfg(feature = "stats")]
+                b.stats.full();
+                #[cfg(feature = "tracing")]
+                b.trace.full();
+                return Err(val);
+            }
+        }
+
+        b.bufr[tail as usize & (N - 1)].write(tail, val);
+        b.tail.store(tail + 1, Ordering::Release);
+        #[cfg(feature = "stats")]
+        b.stats.pushed(1, self.depth(tail + 1));
+        #[cfg(feature = "tracing")]
+        b.trace.pushed(1, self.depth(tail + 1));
+        #[cfg(feature = "async")]
+        b.wake_all();
+        Ok(())
+    }
+
+    /// Waits with `Backoff` until the slowest subscriber made room
+    pub fn push_blocking(&mut self, val: T) {
+        self.push_blocking_with(val, &mut Backoff::new())
+    }
+
+    pub fn push_blocking_with<W: WaitStrategy>(&mut self, val: T, wait: &mut W) {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.trace.blocking("push_blocking");
+        let mut pending = Some(val);
+        retry(wait, || match self.push(pending.take()?) {
+            Ok(()) => Some(()),
+            Err(val) => {
+                pending = Some(val);
+                None
+            }
+        })
+    }
+
+    /// Subscriber that sees everything pushed from here on
+    pub fn subscribe(&self) -> Subscriber<T, N> {
+        let tail = self.bufr.tail.load(Ordering::Relaxed);
+        Subscriber::register(self.bufr.clone(), tail)
+    }
+
+    /// `len` as of the last cursor scan, without taking the lock.
+    /// Runs high between scans, which only `Policy::Block` does
+    #[cfg(any(feature = "stats", feature = "tracing"))]
+    fn depth(&self, tail: u64) -> usize {
+        (tail - self.min).min(N as u64) as usize
+    }
+
+    /// Backlog of the slowest subscriber, approximate while they pop
+    pub fn len(&self) -> usize {
+        let tail = self.bufr.tail.load(Ordering::Relaxed);
+        (tail - self.bufr.min_cursor(tail)).min(N as u64) as usize
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.bufr.stats.snapshot()
+    }
+}
+
+impl<T: Clone, const N: usize> Drop for Producer<T, N> {
+    fn drop(&mut self) {
+        self.bufr.closed.store(true, Ordering::Release);
+        #[cfg(feature = "async")]
+        self.bufr.wake_all();
+    }
+}
+
+/// Read half of a split `BroadcastEphemeral`, a clone
+/// starts out at the same position as the original
+pub struct Subscriber<T: Clone, const N: usize> {
+    bufr: Arc<BroadcastEphemeral<T, N>>,
+    pos: u64,
+    cursor: Arc<CachePadded<AtomicU64>>, // `pos` as the producer sees it
+}
+
+impl<T: Clone, const N: usize> Subscriber<T, N> {
+    fn register(bufr: Arc<BroadcastEphemeral<T, N>>, pos: u64) -> Self {
+        let cursor = Arc::new(CachePadded::new(AtomicU64::new(pos)));
+        bufr.cursors
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(cursor.clone());
+        Self { bufr, pos, cursor }
+    }
+
+    /// Next item for this subscriber, the others still get their copy
+    pub fn pop(&mut self) -> Result<T, PopError> {
+        let val = match self.bufr.bufr[self.pos as usize & (N - 1)].read(self.pos) {
+            Ok(val) => val,
+            // lapped, the item at `pos` is gone
+            Err(held) if held > self.pos => return Err(self.skip(held)),
+            // guard: not written yet
+            Err(_) => {
+                #[cfg(feature = "stats")]
+                self.bufr.stats.empty();
+                #[cfg(feature = "tracing")]
+                self.bufr.trace.empty();
+                return Err(PopError::Empty);
+            }
+        };
+
+        #[cfg(feature = "stats")]
+        self.bufr.stats.popped(1);
+        #[cfg(feature = "tracing")]
+        self.bufr.trace.popped(1);
+        self.pos += 1;
+        self.cursor.store(self.pos, Ordering::Release);
+        Ok(val)
+    }
+
+    /// Moves a lapped subscriber on to the oldest item still queued.
+    /// `held` is what the slot at `pos` holds or is being written for,
+    /// a lap past `pos` at least, and may run ahead of a `tail` the
+    /// producer hasn't bumped yet, so at least one item counts as missed
+    fn skip(&mut self, held: u64) -> PopError {
+        let tail = self.bufr.tail.load(Ordering::Acquire);
+        let oldest = tail.max(held + 1).saturating_sub(N as u64);
+        let missed = oldest - self.pos;
+        debug_assert!(missed > 0, "lagged without missing an item");
+
+        self.pos = oldest;
+        self.cursor.store(oldest, Ordering::Release);
+        PopError::Lagged(missed)
+    }
+
+    /// The producer is gone, once caught up nothing more comes
+    pub fn is_disconnected(&self) -> bool {
+        self.bufr.closed.load(Ordering::Acquire)
+    }
+
+    /// Items this subscriber hasn't seen yet, approximate while the
+    /// producer pushes; a lapped one counts a full ring
+    pub fn len(&self) -> usize {
+        let tail = self.bufr.tail.load(Ordering::Acquire);
+        (tail - self.pos).min(N as u64) as usize
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.bufr.stats.snapshot()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for Subscriber<T, N> {
+    fn clone(&self) -> Self {
+        Self::register(self.bufr.clone(), self.pos)
+    }
+}
+
+impl<T: Clone, const N: usize> Drop for Subscriber<T, N> {
+    fn drop(&mut self) {
+        self.bufr
+            .cursors
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|cursor| !Arc::ptr_eq(cursor, &self.cursor));
+    }
+}
+
+/// Async face of a `Subscriber`, parks the task until the next push
+/// and reports a lap it missed as `RecvError::Lagged` instead of
+/// skipping over it quietly
+#[cfg(feature = "async")]
+pub struct AsyncSubscriber<T: Clone, const N: usize> {
+    inner: Subscriber<T, N>,
+}
+
+#[cfg(feature = "async")]
+impl<T: Clone, const N: usize> AsyncSubscriber<T, N> {
+    pub fn new(inner: Subscriber<T, N>) -> Self {
+        Self { inner }
+    }
+
+    pub fn into_inner(self) -> Subscriber<T, N> {
+        self.inner
+    }
+
+    /// Resolves with the next item, `Lagged` once for a lap missed,
+    /// `Closed` once the producer is gone and everything it pushed seen
+    pub fn recv(&mut self) -> Recv<'_, T, N> {
+        Recv { subscriber: self }
+    }
+
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Result<T, RecvError>> {
+        if let Poll::Ready(res) = self.ready() {
+            return Poll::Ready(res);
+        }
+
+        // register first, then retry so a push in between isn't missed
+        let b = &*self.inner.bufr;
+        let mut wakers = b.wakers.lock().unwrap_or_else(PoisonError::into_inner);
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        b.parked.store(true, Ordering::Relaxed);
+        drop(wakers);
+        fence(Ordering::SeqCst);
+        self.ready()
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, PopError> {
+        self.inner.pop()
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.inner.is_disconnected()
+    }
+
+    fn ready(&mut self) -> Poll<Result<T, RecvError>> {
+        // checked first, a push right before the close is popped below
+        let closed = self.inner.is_disconnected();
+        match self.inner.pop() {
+            Ok(val) => Poll::Ready(Ok(val)),
+            Err(PopError::Lagged(n)) => Poll::Ready(Err(RecvError::Lagged(n))),
+            Err(PopError::Empty) if closed => Poll::Ready(Err(RecvError::Closed)),
+            Err(PopError::Empty) => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: Clone, const N: usize> From<Subscriber<T, N>> for AsyncSubscriber<T, N> {
+    fn from(inner: Subscriber<T, N>) -> Self {
+        Self::new(inner)
+    }
+}
+
+/// Future returned by `AsyncSubscriber::recv`
+#[cfg(feature = "async")]
+pub struct Recv<'a, T: Clone, const N: usize> {
+    subscriber: &'a mut AsyncSubscriber<T, N>,
+}
+
+#[cfg(feature = "async")]
+impl<T: Clone, const N: usize> Future for Recv<'_, T, N> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.subscriber.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_block_broadcast() {
+        let (mut producer, mut fast) = BroadcastEphemeral::<i32, 4>::new(Policy::Block).split();
+        let mut slow = fast.clone();
+
+        for i in 0..4 {
+            assert!(producer.push(i).is_ok());
+        }
+        for i in 0..4 {
+            assert_eq!(fast.pop(), Ok(i));
+        }
+        assert_eq!(fast.pop(), Err(PopError::Empty));
+
+        // `slow` hasn't read anything yet
+        assert_eq!(producer.push(4), Err(4));
+        assert_eq!(slow.pop(), Ok(0));
+        assert!(producer.push(4).is_ok());
+
+        // dropped subscribers no longer hold the producer back
+        drop(slow);
+        assert!(producer.push(5).is_ok());
+        assert_eq!(fast.pop(), Ok(4));
+        assert_eq!(fast.pop(), Ok(5));
+
+        let mut late = producer.subscribe();
+        assert_eq!(late.pop(), Err(PopError::Empty));
+        assert!(producer.push(6).is_ok());
+        assert_eq!(late.pop(), Ok(6));
+    }
+
+    #[test]
+    fn test_lag_broadcast() {
+        let (mut producer, mut fast) = BroadcastEphemeral::<String, 4>::new(Policy::Lag).split();
+        let mut slow = fast.clone();
+
+        for i in 0..10 {
+            assert!(producer.push(i.to_string()).is_ok());
+            assert_eq!(fast.pop(), Ok(i.to_string()));
+        }
+
+        // items 0..6 were overwritten
+        assert_eq!(slow.pop(), Err(PopError::Lagged(6)));
+        for i in 6..10 {
+            assert_eq!(slow.pop(), Ok(i.to_string()));
+        }
+        assert_eq!(slow.pop(), Err(PopError::Empty));
+    }
+
+    #[test]
+    fn test_lag_mid_write_broadcast() {
+        let (mut producer, mut slow) = BroadcastEphemeral::<i32, 2>::new(Policy::Lag).split();
+        producer.push(0).unwrap();
+        producer.push(1).unwrap();
+
+        // the producer is halfway through writing position 2 over
+        // item 0, `tail` isn't bumped yet
+        slow.bufr.bufr[0].stamp.store(2 * 2 + 1, Ordering::SeqCst);
+        assert_eq!(slow.pop(), Err(PopError::Lagged(1)));
+        assert_eq!(slow.pop(), Ok(1));
+        assert_eq!(slow.pop(), Err(PopError::Empty));
+
+        producer.push(2).unwrap();
+        assert_eq!(slow.pop(), Ok(2));
+    }
+
+    #[test]
+    fn test_threaded_broadcast() {
+        let (mut producer, subscriber) = BroadcastEphemeral::<usize, 8>::new(Policy::Block).split();
+
+        let consume_ts: Vec<_> = (0..3)
+            .map(|_| {
+                let mut subscriber = subscriber.clone();
+                thread::spawn(move || {
+                    for i in 0..1000 {
+                        loop {
+                            if let Ok(val) = subscriber.pop() {
+                                assert_eq!(val, i);
+                                break;
+                            }
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+        drop(subscriber);
+
+        for i in 0..1000 {
+            producer.push_blocking(i);
+        }
+        for consume_t in consume_ts {
+            consume_t.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_threaded_lag_broadcast() {
+        const ITEMS: usize = if cfg!(miri) { 200 } else { 10000 };
+        let (mut producer, subscriber) = BroadcastEphemeral::<String, 4>::new(Policy::Lag).split();
+
+        // the producer laps the subscribers mid-clone all the time
+        let consume_ts: Vec<_> = (0..2)
+            .map(|_| {
+                let mut subscriber = subscriber.clone();
+                thread::spawn(move || {
+                    let mut last = None;
+                    while last != Some(ITEMS - 1) {
+                        match subscriber.pop() {
+                            Ok(val) => {
+                                let val = val.parse().unwrap();
+                                assert!(last < Some(val));
+                                last = Some(val);
+                            }
+                            Err(_) => thread::yield_now(),
+                        }
+                    }
+                })
+            })
+            .collect();
+        drop(subscriber);
+
+        for i in 0..ITEMS {
+            producer.push(i.to_string()).unwrap();
+        }
+        for consume_t in consume_ts {
+            consume_t.join().unwrap();
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_async_lag_broadcast() {
+        use futures::executor::block_on;
+
+        let (mut producer, subscriber) = BroadcastEphemeral::<u32, 4>::new(Policy::Lag).split();
+        let mut slow = AsyncSubscriber::from(subscriber.clone());
+        let mut fast = AsyncSubscriber::from(subscriber);
+
+        block_on(async {
+            for i in 0..6 {
+                producer.push(i).unwrap();
+                assert_eq!(fast.recv().await, Ok(i));
+            }
+        });
+
+        // the loss is reported once, then it carries on
+        assert_eq!(block_on(slow.recv()), Err(RecvError::Lagged(2)));
+        assert_eq!(block_on(slow.recv()), Ok(2));
+
+        producer.push(6).unwrap();
+        drop(producer);
+        block_on(async {
+            for i in 3..7 {
+                assert_eq!(slow.recv().await, Ok(i));
+            }
+            assert_eq!(slow.recv().await, Err(RecvError::Closed));
+            assert_eq!(fast.recv().await, Ok(6));
+            assert_eq!(fast.recv().await, Err(RecvError::Closed));
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_async_wake_broadcast() {
+        use futures::executor::block_on;
+
+        let (mut producer, subscriber) = BroadcastEphemeral::<usize, 8>::new(Policy::Block).split();
+        let consume_ts: Vec<_> = (0..3)
+            .map(|_| {
+                let mut subscriber = AsyncSubscriber::from(subscriber.clone());
+                thread::spawn(move || {
+                    block_on(async {
+                        let mut seen = 0;
+                        while let Ok(val) = subscriber.recv().await {
+                            assert_eq!(val, seen);
+                            seen += 1;
+                        }
+                        seen
+                    })
+                })
+            })
+            .collect();
+        drop(subscriber);
+
+        for i in 0..1000 {
+            producer.push_blocking(i);
+        }
+        drop(producer);
+        for consume_t in consume_ts {
+            assert_eq!(consume_t.join().unwrap(), 1000);
+        }
+    }
+
+    #[test]
+    fn test_len_broadcast() {
+        let (mut producer, mut fast) = BroadcastEphemeral::<i32, 4>::new(Policy::Lag).split();
+        let mut slow = fast.clone();
+        assert!(producer.is_empty());
+        assert_eq!((producer.capacity(), fast.free_space()), (4, 4));
+
+        for i in 0..3 {
+            producer.push(i).unwrap();
+        }
+        fast.pop().unwrap();
+        fast.pop().unwrap();
+        // the producer counts the slowest subscriber
+        assert_eq!((producer.len(), fast.len(), slow.len()), (3, 1, 3));
+
+        for i in 3..6 {
+            producer.push(i).unwrap();
+        }
+        assert!(producer.is_full() && slow.is_full());
+        assert_eq!(fast.len(), 4);
+
+        assert_eq!(slow.pop(), Err(PopError::Lagged(2)));
+        assert_eq!((slow.len(), slow.free_space()), (4, 0));
+        slow.pop().unwrap();
+        assert_eq!(slow.len(), 3);
+    }
+}
@@ -0,0 +1,286 @@
+#[cfg(feature = "async")]
+use alloc::vec::Vec;
+use core::{error::Error, fmt, sync::atomic::Ordering};
+#[cfg(feature = "async")]
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+#[cfg(feature = "async")]
+use crate::sync::SpinLock;
+use crate::sync::{Arc, AtomicBool};
+
+use super::{
+    spsc::Disconnected,
+    wait::{retry, WaitStrategy},
+};
+
+/// The token was cancelled before the wait finished
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+/// Why a cancellable wait came back empty handed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interrupted {
+    Cancelled,
+    /// the other side is gone and nothing is left queued
+    Disconnected,
+}
+
+impl From<Cancelled> for Interrupted {
+    fn from(_: Cancelled) -> Self {
+        Self::Cancelled
+    }
+}
+
+impl From<Disconnected> for Interrupted {
+    fn from(_: Disconnected) -> Self {
+        Self::Disconnected
+    }
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("the wait was cancelled")
+    }
+}
+
+impl fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cancelled => f.pad("the wait was cancelled"),
+            Self::Disconnected => f.pad("the other side disconnected"),
+        }
+    }
+}
+
+impl Error for Cancelled {}
+impl Error for Interrupted {}
+
+struct Shared {
+    cancelled: AtomicBool,
+    // every task waiting in `cancelled`/`run`, all woken on cancel
+    #[cfg(feature = "async")]
+    wakers: SpinLock<Vec<Waker>>,
+}
+
+/// Shutdown flag shared by every clone, once `cancel` is called the
+/// cancellable waits watching it give up with `Cancelled` instead of
+/// hanging on a ring nobody feeds anymore
+///
+/// Blocking waits notice within one nap of their wait strategy,
+/// async ones are woken right away
+#[derive(Clone)]
+pub struct CancellationToken {
+    shared: Arc<Shared>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                cancelled: AtomicBool::new(false),
+                #[cfg(feature = "async")]
+                wakers: SpinLock::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Cancels every clone, calling it again does nothing
+    pub fn cancel(&self) {
+        self.shared.cancelled.store(true, Ordering::Release);
+
+        #[cfg(feature = "async")]
+        for waker in self.shared.wakers.lock().drain(..) {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.shared.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Calls `attempt` until it succeeds, passing time with `wait` in
+    /// between, turns any `try_*` call into a blocking one shutdown
+    /// can interrupt
+    pub fn retry<T, W: WaitStrategy>(
+        &self,
+        wait: &mut W,
+        mut attempt: impl FnMut() -> Option<T>,
+    ) -> Result<T, Cancelled> {
+        retry(wait, || match attempt() {
+            Some(val) => Some(Ok(val)),
+            None if self.is_cancelled() => Some(Err(Cancelled)),
+            None => None,
+        })
+    }
+
+    #[cfg(feature = "async")]
+    /// Resolves once the token is cancelled
+    pub fn cancelled(&self) -> WhenCancelled {
+        WhenCancelled {
+            token: self.clone(),
+        }
+    }
+
+    #[cfg(feature = "async")]
+    /// Drives `fut` until it finishes or the token is cancelled,
+    /// whichever comes first, works with any of the async waits
+    pub fn run<F: Future>(&self, fut: F) -> UntilCancelled<F> {
+        UntilCancelled {
+            fut,
+            token: self.clone(),
+        }
+    }
+
+    #[cfg(feature = "async")]
+    fn poll_cancelled(&self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.is_cancelled() {
+            return Poll::Ready(());
+        }
+
+        // register first, then recheck so a cancel in between isn't missed
+        let mut wakers = self.shared.wakers.lock();
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        drop(wakers);
+
+        match self.is_cancelled() {
+            true => Poll::Ready(()),
+            false => Poll::Pending,
+        }
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Future returned by `CancellationToken::cancelled`
+#[cfg(feature = "async")]
+pub struct WhenCancelled {
+    token: CancellationToken,
+}
+
+#[cfg(feature = "async")]
+impl Future for WhenCancelled {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.token.poll_cancelled(cx)
+    }
+}
+
+/// Future returned by `CancellationToken::run`
+#[cfg(feature = "async")]
+pub struct UntilCancelled<F> {
+    fut: F,
+    token: CancellationToken,
+}
+
+#[cfg(feature = "async")]
+impl<F: Future> Future for UntilCancelled<F> {
+    type Output = Result<F::Output, Cancelled>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // the token is never pinned, `fut` never moves out
+        let this = unsafe { self.get_unchecked_mut() };
+        if let Poll::Ready(()) = this.token.poll_cancelled(cx) {
+            return Poll::Ready(Err(Cancelled));
+        }
+        unsafe { Pin::new_unchecked(&mut this.fut) }
+            .poll(cx)
+            .map(Ok)
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use crate::ephemeral::wait::Backoff;
+
+    #[test]
+    fn test_retry_cancel() {
+        let token = CancellationToken::new();
+        let mut rounds = 0;
+        let res = token.retry(&mut Backoff::new(), || {
+            rounds += 1;
+            (rounds == 3).then_some(rounds)
+        });
+        assert_eq!(res, Ok(3));
+
+        // a clone shares the flag, the next retry gives up
+        token.clone().cancel();
+        assert!(token.is_cancelled());
+        assert_eq!(
+            token.retry(&mut Backoff::new(), || None::<()>),
+            Err(Cancelled)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_blocking_cancel() {
+        use crate::ephemeral::spsc::SPSCEphemeral;
+        use std::{thread, time::Duration};
+
+        let token = CancellationToken::new();
+        let (mut producer, mut consumer) = SPSCEphemeral::<i32, 2>::new().split();
+
+        let shutdown_t = {
+            let token = token.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                token.cancel();
+            })
+        };
+        // nothing ever arrives, only the token ends the wait
+        assert_eq!(
+            consumer.pop_cancellable(&token),
+            Err(Interrupted::Cancelled)
+        );
+        shutdown_t.join().unwrap();
+
+        producer.push(1).unwrap();
+        producer.push(2).unwrap();
+        assert_eq!(producer.push_cancellable(3, &token), Err(3));
+        // what's queued still comes out, cancelled or not
+        assert_eq!(consumer.pop_cancellable(&token), Ok(1));
+    }
+
+    #[test]
+    #[cfg(all(feature = "async", feature = "std"))]
+    fn test_async_cancel() {
+        use crate::ephemeral::{asynchronous::AsyncConsumer, spsc::SPSCEphemeral};
+        use futures::executor::block_on;
+        use std::{thread, time::Duration};
+
+        let token = CancellationToken::new();
+        let (_producer, consumer) = SPSCEphemeral::<i32, 4>::new().split();
+        let mut consumer = AsyncConsumer::new(consumer);
+
+        let shutdown_t = {
+            let token = token.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                token.cancel();
+            })
+        };
+        assert_eq!(block_on(token.run(consumer.pop())), Err(Cancelled));
+        block_on(token.cancelled());
+        shutdown_t.join().unwrap();
+    }
+}
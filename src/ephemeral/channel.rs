@@ -0,0 +1,673 @@
+use core::{
+    cell::RefCell,
+    fmt,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+use std::{error::Error, sync::Arc, time::Duration};
+
+use super::{
+    mpsc::{self, MPSCEphemeral},
+    spsc::{self, SPSCEphemeral},
+    wait::{retry, retry_until, Backoff},
+};
+
+/// Nap of a blocked `send`/`recv` once spinning and yielding didn't help
+const PARK: Duration = Duration::from_micros(50);
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Queue a channel pair runs over, `MPSCEphemeral` for senders
+/// that clone or `SPSCEphemeral` when there's only ever one
+pub trait Flavor: sealed::Sealed {
+    type Item;
+    #[doc(hidden)]
+    type Tx;
+    #[doc(hidden)]
+    type Rx;
+    #[doc(hidden)]
+    const CAPACITY: usize;
+
+    #[doc(hidden)]
+    fn split(self) -> (Self::Tx, Self::Rx);
+    #[doc(hidden)]
+    fn push(tx: &Self::Tx, val: Self::Item) -> Result<(), Self::Item>;
+    #[doc(hidden)]
+    fn pop(rx: &mut Self::Rx) -> Option<Self::Item>;
+    #[doc(hidden)]
+    fn tx_len(tx: &Self::Tx) -> usize;
+    #[doc(hidden)]
+    fn rx_len(rx: &Self::Rx) -> usize;
+}
+
+impl<T, const N: usize> sealed::Sealed for MPSCEphemeral<T, N> {}
+
+impl<T, const N: usize> Flavor for MPSCEphemeral<T, N> {
+    type Item = T;
+    type Tx = mpsc::Producer<T, N>;
+    type Rx = mpsc::Consumer<T, N>;
+    const CAPACITY: usize = N;
+
+    fn split(self) -> (Self::Tx, Self::Rx) {
+        MPSCEphemeral::split(self)
+    }
+
+    fn push(tx: &Self::Tx, val: T) -> Result<(), T> {
+        tx.push(val)
+    }
+
+    fn pop(rx: &mut Self::Rx) -> Option<T> {
+        rx.pop()
+    }
+
+    fn tx_len(tx: &Self::Tx) -> usize {
+        tx.len()
+    }
+
+    fn rx_len(rx: &Self::Rx) -> usize {
+        rx.len()
+    }
+}
+
+impl<T, const N: usize> sealed::Sealed for SPSCEphemeral<T, N> {}
+
+impl<T, const N: usize> Flavor for SPSCEphemeral<T, N> {
+    type Item = T;
+    // pushes need `&mut`, the sender hands out `&self`
+    type Tx = RefCell<spsc::Producer<Self>>;
+    type Rx = spsc::Consumer<Self>;
+    const CAPACITY: usize = N;
+
+    fn split(self) -> (Self::Tx, Self::Rx) {
+        let (producer, consumer) = SPSCEphemeral::split(self);
+        (RefCell::new(producer), consumer)
+    }
+
+    fn push(tx: &Self::Tx, val: T) -> Result<(), T> {
+        tx.borrow_mut().push(val)
+    }
+
+    fn pop(rx: &mut Self::Rx) -> Option<T> {
+        rx.pop().ok()
+    }
+
+    fn tx_len(tx: &Self::Tx) -> usize {
+        tx.borrow().len()
+    }
+
+    fn rx_len(rx: &Self::Rx) -> usize {
+        rx.len()
+    }
+}
+
+/// Who is still around, so either side can report a disconnect
+struct Shared {
+    senders: AtomicUsize,
+    receiver: AtomicBool,
+}
+
+/// Bounded channel over an `MPSCEphemeral` of `N` slots, a stand-in
+/// for `crossbeam_channel::bounded(N)` with a cloneable sender
+pub fn bounded<T, const N: usize>() -> (Sender<MPSCEphemeral<T, N>>, Receiver<MPSCEphemeral<T, N>>)
+{
+    MPSCEphemeral::new().into()
+}
+
+/// Bounded channel over an `SPSCEphemeral` of `N` slots,
+/// cheaper than `bounded` but the sender can't be cloned
+pub fn bounded_spsc<T, const N: usize>(
+) -> (Sender<SPSCEphemeral<T, N>>, Receiver<SPSCEphemeral<T, N>>) {
+    SPSCEphemeral::new().into()
+}
+
+fn channel<Q: Flavor>(queue: Q) -> (Sender<Q>, Receiver<Q>) {
+    let (tx, rx) = queue.split();
+    let shared = Arc::new(Shared {
+        senders: AtomicUsize::new(1),
+        receiver: AtomicBool::new(true),
+    });
+
+    let sender = Sender {
+        tx,
+        shared: shared.clone(),
+    };
+    let receiver = Receiver {
+        rx: RefCell::new(rx),
+        shared,
+    };
+    (sender, receiver)
+}
+
+impl<T, const N: usize> From<MPSCEphemeral<T, N>>
+    for (Sender<MPSCEphemeral<T, N>>, Receiver<MPSCEphemeral<T, N>>)
+{
+    fn from(queue: MPSCEphemeral<T, N>) -> Self {
+        channel(queue)
+    }
+}
+
+impl<T, const N: usize> From<SPSCEphemeral<T, N>>
+    for (Sender<SPSCEphemeral<T, N>>, Receiver<SPSCEphemeral<T, N>>)
+{
+    fn from(queue: SPSCEphemeral<T, N>) -> Self {
+        channel(queue)
+    }
+}
+
+/// Sending half, mirrors `crossbeam_channel::Sender`
+pub struct Sender<Q: Flavor> {
+    tx: Q::Tx,
+    shared: Arc<Shared>,
+}
+
+impl<Q: Flavor> Sender<Q> {
+    /// Waits for room, fails only once the receiver is gone
+    pub fn send(&self, msg: Q::Item) -> Result<(), SendError<Q::Item>> {
+        let mut pending = Some(msg);
+        let mut wait = Backoff::with_park(PARK);
+        retry(&mut wait, || match self.try_send(pending.take()?) {
+            Ok(()) => Some(Ok(())),
+            Err(TrySendError::Disconnected(msg)) => Some(Err(SendError(msg))),
+            Err(TrySendError::Full(msg)) => {
+                pending = Some(msg);
+                None
+            }
+        })
+    }
+
+    pub fn try_send(&self, msg: Q::Item) -> Result<(), TrySendError<Q::Item>> {
+        if !self.shared.receiver.load(Ordering::Acquire) {
+            return Err(TrySendError::Disconnected(msg));
+        }
+        Q::push(&self.tx, msg).map_err(TrySendError::Full)
+    }
+
+    /// `send` giving up once `timeout` passed
+    pub fn send_timeout(
+        &self,
+        msg: Q::Item,
+        timeout: Duration,
+    ) -> Result<(), SendTimeoutError<Q::Item>> {
+        let mut pending = Some(msg);
+        let sent = retry_until(timeout, || match self.try_send(pending.take()?) {
+            Ok(()) => Some(Ok(())),
+            Err(TrySendError::Disconnected(msg)) => Some(Err(msg)),
+            Err(TrySendError::Full(msg)) => {
+                pending = Some(msg);
+                None
+            }
+        });
+
+        match (sent, pending) {
+            (Some(Err(msg)), _) => Err(SendTimeoutError::Disconnected(msg)),
+            (None, Some(msg)) => Err(SendTimeoutError::Timeout(msg)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Pending messages, approximate while the receiver is busy
+    pub fn len(&self) -> usize {
+        Q::tx_len(&self.tx)
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        Some(Q::CAPACITY)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() >= Q::CAPACITY
+    }
+}
+
+impl<T, const N: usize> Clone for Sender<MPSCEphemeral<T, N>> {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::Relaxed);
+        Self {
+            tx: self.tx.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<Q: Flavor> Drop for Sender<Q> {
+    fn drop(&mut self) {
+        // pairs with the receiver's acquire, every push so far is visible
+        self.shared.senders.fetch_sub(1, Ordering::Release);
+    }
+}
+
+impl<Q: Flavor> fmt::Debug for Sender<Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Sender { .. }")
+    }
+}
+
+/// Receiving half, mirrors `crossbeam_channel::Receiver`
+/// though it can't be cloned, there's one consumer per queue
+pub struct Receiver<Q: Flavor> {
+    rx: RefCell<Q::Rx>,
+    shared: Arc<Shared>,
+}
+
+impl<Q: Flavor> Receiver<Q> {
+    /// Waits for a message, fails only once every sender
+    /// is gone and nothing is left queued
+    pub fn recv(&self) -> Result<Q::Item, RecvError> {
+        let mut wait = Backoff::with_park(PARK);
+        retry(&mut wait, || match self.try_recv() {
+            Ok(msg) => Some(Ok(msg)),
+            Err(TryRecvError::Disconnected) => Some(Err(RecvError)),
+            Err(TryRecvError::Empty) => None,
+        })
+    }
+
+    pub fn try_recv(&self) -> Result<Q::Item, TryRecvError> {
+        let mut rx = self.rx.borrow_mut();
+        if let Some(msg) = Q::pop(&mut rx) {
+            return Ok(msg);
+        }
+
+        if self.shared.senders.load(Ordering::Acquire) != 0 {
+            return Err(TryRecvError::Empty);
+        }
+        // the last sender may have pushed right before leaving
+        Q::pop(&mut rx).ok_or(TryRecvError::Disconnected)
+    }
+
+    /// `recv` giving up once `timeout` passed
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Q::Item, RecvTimeoutError> {
+        retry_until(timeout, || match self.try_recv() {
+            Ok(msg) => Some(Ok(msg)),
+            Err(TryRecvError::Disconnected) => Some(Err(RecvTimeoutError::Disconnected)),
+            Err(TryRecvError::Empty) => None,
+        })
+        .unwrap_or(Err(RecvTimeoutError::Timeout))
+    }
+
+    /// Blocks for every message until the senders are gone
+    pub fn iter(&self) -> Iter<'_, Q> {
+        Iter { receiver: self }
+    }
+
+    /// Messages already queued, without waiting for more
+    pub fn try_iter(&self) -> TryIter<'_, Q> {
+        TryIter { receiver: self }
+    }
+
+    /// Pending messages, approximate while senders are busy
+    pub fn len(&self) -> usize {
+        Q::rx_len(&self.rx.borrow())
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        Some(Q::CAPACITY)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() >= Q::CAPACITY
+    }
+}
+
+impl<Q: Flavor> Drop for Receiver<Q> {
+    fn drop(&mut self) {
+        self.shared.receiver.store(false, Ordering::Release);
+    }
+}
+
+impl<Q: Flavor> fmt::Debug for Receiver<Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Receiver { .. }")
+    }
+}
+
+impl<'a, Q: Flavor> IntoIterator for &'a Receiver<Q> {
+    type Item = Q::Item;
+    type IntoIter = Iter<'a, Q>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<Q: Flavor> IntoIterator for Receiver<Q> {
+    type Item = Q::Item;
+    type IntoIter = IntoIter<Q>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { receiver: self }
+    }
+}
+
+/// Iterator returned by `Receiver::iter`
+pub struct Iter<'a, Q: Flavor> {
+    receiver: &'a Receiver<Q>,
+}
+
+impl<Q: Flavor> Iterator for Iter<'_, Q> {
+    type Item = Q::Item;
+
+    fn next(&mut self) -> Option<Q::Item> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// Iterator returned by `Receiver::try_iter`
+pub struct TryIter<'a, Q: Flavor> {
+    receiver: &'a Receiver<Q>,
+}
+
+impl<Q: Flavor> Iterator for TryIter<'_, Q> {
+    type Item = Q::Item;
+
+    fn next(&mut self) -> Option<Q::Item> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+/// Iterator returned by `Receiver::into_iter`
+pub struct IntoIter<Q: Flavor> {
+    receiver: Receiver<Q>,
+}
+
+impl<Q: Flavor> Iterator for IntoIter<Q> {
+    type Item = Q::Item;
+
+    fn next(&mut self) -> Option<Q::Item> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// The receiver is gone, hands the message back
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl<T> SendError<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    Full(T),
+    Disconnected(T),
+}
+
+impl<T> TrySendError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            Self::Full(msg) | Self::Disconnected(msg) => msg,
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        matches!(self, Self::Full(_))
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        matches!(self, Self::Disconnected(_))
+    }
+}
+
+impl<T> From<SendError<T>> for TrySendError<T> {
+    fn from(SendError(msg): SendError<T>) -> Self {
+        Self::Disconnected(msg)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SendTimeoutError<T> {
+    Timeout(T),
+    Disconnected(T),
+}
+
+impl<T> SendTimeoutError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            Self::Timeout(msg) | Self::Disconnected(msg) => msg,
+        }
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Self::Timeout(_))
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        matches!(self, Self::Disconnected(_))
+    }
+}
+
+impl<T> From<SendError<T>> for SendTimeoutError<T> {
+    fn from(SendError(msg): SendError<T>) -> Self {
+        Self::Disconnected(msg)
+    }
+}
+
+/// Every sender is gone and nothing is left queued
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecvError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Disconnected,
+}
+
+impl TryRecvError {
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Self::Empty)
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        matches!(self, Self::Disconnected)
+    }
+}
+
+impl From<RecvError> for TryRecvError {
+    fn from(_: RecvError) -> Self {
+        Self::Disconnected
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecvTimeoutError {
+    Timeout,
+    Disconnected,
+}
+
+impl RecvTimeoutError {
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Self::Timeout)
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        matches!(self, Self::Disconnected)
+    }
+}
+
+impl From<RecvError> for RecvTimeoutError {
+    fn from(_: RecvError) -> Self {
+        Self::Disconnected
+    }
+}
+
+// like crossbeam's, these don't ask for a `Debug` message
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("SendError(..)")
+    }
+}
+
+impl<T> fmt::Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(_) => f.pad("Full(..)"),
+            Self::Disconnected(_) => f.pad("Disconnected(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Debug for SendTimeoutError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout(_) => f.pad("Timeout(..)"),
+            Self::Disconnected(_) => f.pad("Disconnected(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("sending on a disconnected channel")
+    }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(_) => f.pad("sending on a full channel"),
+            Self::Disconnected(_) => f.pad("sending on a disconnected channel"),
+        }
+    }
+}
+
+impl<T> fmt::Display for SendTimeoutError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout(_) => f.pad("timed out waiting on send operation"),
+            Self::Disconnected(_) => f.pad("sending on a disconnected channel"),
+        }
+    }
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("receiving on a disconnected channel")
+    }
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.pad("receiving on an empty channel"),
+            Self::Disconnected => f.pad("receiving on a disconnected channel"),
+        }
+    }
+}
+
+impl fmt::Display for RecvTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout => f.pad("timed out waiting on receive operation"),
+            Self::Disconnected => f.pad("receiving on a disconnected channel"),
+        }
+    }
+}
+
+impl<T> Error for SendError<T> {}
+impl<T> Error for TrySendError<T> {}
+impl<T> Error for SendTimeoutError<T> {}
+impl Error for RecvError {}
+impl Error for TryRecvError {}
+impl Error for RecvTimeoutError {}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use std::thread;
+
+    const ITEMS: usize = if cfg!(miri) { 100 } else { 10000 };
+
+    #[test]
+    fn test_seq_channel() {
+        let (tx, rx) = bounded::<i32, 2>();
+
+        tx.send(1).unwrap();
+        assert!(tx.try_send(2).is_ok());
+        assert!(tx.try_send(3).unwrap_err().is_full());
+        assert_eq!(
+            tx.send_timeout(3, Duration::from_millis(1)),
+            Err(SendTimeoutError::Timeout(3))
+        );
+        assert!(tx.is_full() && rx.len() == 2);
+
+        assert_eq!(rx.recv(), Ok(1));
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), [2]);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(1)),
+            Err(RecvTimeoutError::Timeout)
+        );
+    }
+
+    #[test]
+    fn test_disconnect_channel() {
+        let (tx, rx) = bounded::<i32, 4>();
+        let other = tx.clone();
+        tx.send(1).unwrap();
+        drop(tx);
+        other.send(2).unwrap();
+        drop(other);
+
+        // whatever was queued still comes out first
+        assert_eq!(rx.iter().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(rx.recv(), Err(RecvError));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+
+        let (tx, rx) = bounded_spsc::<i32, 4>();
+        drop(rx);
+        assert_eq!(tx.send(1), Err(SendError(1)));
+        assert!(tx.try_send(2).unwrap_err().is_disconnected());
+    }
+
+    #[test]
+    fn test_threaded_channel() {
+        const SENDERS: usize = 4;
+        let (tx, rx) = bounded::<usize, 16>();
+
+        let senders: Vec<_> = (0..SENDERS)
+            .map(|s| {
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    for i in 0..ITEMS {
+                        tx.send(s * ITEMS + i).unwrap();
+                    }
+                })
+            })
+            .collect();
+        drop(tx);
+
+        // ends once the last sender hung up
+        let mut seen: Vec<_> = rx.into_iter().collect();
+        for sender in senders {
+            sender.join().unwrap();
+        }
+        seen.sort_unstable();
+        assert_eq!(seen, (0..SENDERS * ITEMS).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_spsc_channel() {
+        let (tx, rx) = bounded_spsc::<usize, 8>();
+
+        let send_t = thread::spawn(move || {
+            for i in 0..ITEMS {
+                tx.send(i).unwrap();
+            }
+        });
+        for i in 0..ITEMS {
+            assert_eq!(rx.recv_timeout(Duration::from_secs(10)), Ok(i));
+        }
+        send_t.join().unwrap();
+        assert_eq!(rx.recv(), Err(RecvError));
+    }
+}
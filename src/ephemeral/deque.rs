@@ -0,0 +1,381 @@
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use core::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::atomic::{self, AtomicIsize, AtomicPtr, Ordering},
+};
+
+use crate::util::CachePadded;
+
+#[cfg(feature = "stats")]
+use super::stats::{Counters, Stats};
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+
+/// Circular arena, positions index it modulo its power-of-two size
+struct Buffer<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+}
+
+impl<T> Buffer<T> {
+    fn alloc(capacity: usize) -> *mut Self {
+        let slots = (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect();
+        Box::into_raw(Box::new(Self { slots }))
+    }
+
+    fn capacity(&self) -> isize {
+        self.slots.len() as isize
+    }
+
+    fn slot(&self, pos: isize) -> *mut MaybeUninit<T> {
+        self.slots[pos as usize & (self.slots.len() - 1)].get()
+    }
+}
+
+struct Inner<T> {
+    top: CachePadded<AtomicIsize>,    // steal end
+    bottom: CachePadded<AtomicIsize>, // owner end
+    buffer: AtomicPtr<Buffer<T>>,
+    // outgrown arenas, a stealer may still be reading one,
+    // so they live until the deque goes; only the worker pushes
+    retired: UnsafeCell<Vec<*mut Buffer<T>>>,
+    #[cfg(feature = "stats")]
+    stats: Counters,
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        let top = *self.top.get_mut();
+        let bottom = *self.bottom.get_mut();
+        unsafe {
+            let buffer = Box::from_raw(*self.buffer.get_mut());
+            for pos in top..bottom {
+                (*buffer.slot(pos)).assume_init_drop();
+            }
+            for old in self.retired.get_mut().drain(..) {
+                drop(Box::from_raw(old));
+            }
+        }
+    }
+}
+
+unsafe impl<T: Send> Send for Inner<T> {}
+unsafe impl<T: Send> Sync for Inner<T> {}
+
+/// Owner half of a Chase–Lev work-stealing deque, pushes and pops
+/// at the bottom while `Stealer`s take the oldest items off the top.
+/// The arena doubles whenever it runs full
+pub struct Worker<T> {
+    inner: Arc<Inner<T>>,
+    #[cfg(feature = "tracing")]
+    trace: Label, // handed on to the stealers
+}
+
+impl<T> Worker<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(32)
+    }
+
+    /// Starting arena size, rounded up to the next power of two
+    pub fn with_capacity(capacity: usize) -> Self {
+        let inner = Inner {
+            top: CachePadded::new(AtomicIsize::new(0)),
+            bottom: CachePadded::new(AtomicIsize::new(0)),
+            buffer: AtomicPtr::new(Buffer::alloc(capacity.max(1).next_power_of_two())),
+            retired: UnsafeCell::new(Vec::new()),
+            #[cfg(feature = "stats")]
+            stats: Counters::new(),
+        };
+        Self {
+            inner: Arc::new(inner),
+            #[cfg(feature = "tracing")]
+            trace: Label::new("deque"),
+        }
+    }
+
+    /// Names the deque in its `tracing` events, `deque` otherwise,
+    /// stealers taken after this carry the name too
+    #[cfg(feature = "tracing")]
+    pub fn with_name(mut self, name: &'static str) -> Self {
+        self.trace = Label::new(name);
+        self
+    }
+
+    pub fn stealer(&self) -> Stealer<T> {
+        Stealer {
+            inner: self.inner.clone(),
+            #[cfg(feature = "tracing")]
+            trace: self.trace,
+        }
+    }
+
+    pub fn push(&mut self, val: T) {
+        let inner = &*self.inner;
+        let bottom = inner.bottom.load(Ordering::Relaxed);
+        let top = inner.top.load(Ordering::Acquire);
+        let mut buffer = inner.buffer.load(Ordering::Relaxed);
+
+        if bottom - top >= unsafe { (*buffer).capacity() } {
+            buffer = self.grow(top, bottom);
+        }
+
+        unsafe { (*(*buffer).slot(bottom)).write(val) };
+        atomic::fence(Ordering::Release);
+        inner.bottom.store(bottom + 1, Ordering::Relaxed);
+        // as of the `top` seen above, steals since make it an overcount
+        #[cfg(feature = "stats")]
+        inner.stats.pushed(1, (bottom + 1 - top) as usize);
+        #[cfg(feature = "tracing")]
+        self.trace.pushed(1, (bottom + 1 - top) as usize);
+    }
+
+    /// Newest item, racing stealers only over the very last one
+    pub fn pop(&mut self) -> Option<T> {
+        let inner = &*self.inner;
+        let bottom = inner.bottom.load(Ordering::Relaxed) - 1;
+        let buffer = inner.buffer.load(Ordering::Relaxed);
+
+        // claim the bottom slot before looking at what stealers took
+        inner.bottom.store(bottom, Ordering::Relaxed);
+        atomic::fence(Ordering::SeqCst);
+        let top = inner.top.load(Ordering::Relaxed);
+
+        // guard: empty
+        if top > bottom {
+            inner.bottom.store(bottom + 1, Ordering::Relaxed);
+            #[cfg(feature = "stats")]
+            inner.stats.empty();
+            #[cfg(feature = "tracing")]
+            self.trace.empty();
+            return None;
+        }
+
+        // more than one left, no stealer can reach this one
+        if top < bottom {
+            #[cfg(feature = "stats")]
+            inner.stats.popped(1);
+            #[cfg(feature = "tracing")]
+            self.trace.popped(1);
+            return Some(unsafe { (*(*buffer).slot(bottom)).assume_init_read() });
+        }
+
+        // last item, whoever moves `top` first gets it
+        let won = inner
+            .top
+            .compare_exchange(top, top + 1, Ordering::SeqCst, Ordering::Relaxed)
+            .is_ok();
+        inner.bottom.store(bottom + 1, Ordering::Relaxed);
+        #[cfg(feature = "stats")]
+        match won {
+            true => inner.stats.popped(1),
+            false => inner.stats.empty(),
+        }
+        #[cfg(feature = "tracing")]
+        match won {
+            true => self.trace.popped(1),
+            false => self.trace.empty(),
+        }
+        won.then(|| unsafe { (*(*buffer).slot(bottom)).assume_init_read() })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let bottom = self.inner.bottom.load(Ordering::Relaxed);
+        bottom <= self.inner.top.load(Ordering::Relaxed)
+    }
+
+    /// Pops and steals both count as pops
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.inner.stats.snapshot()
+    }
+
+    /// Moves the live items into an arena twice the size
+    fn grow(&self, top: isize, bottom: isize) -> *mut Buffer<T> {
+        let inner = &*self.inner;
+        let old = inner.buffer.load(Ordering::Relaxed);
+        let new = Buffer::alloc(unsafe { (*old).capacity() } as usize * 2);
+
+        for pos in top..bottom {
+            unsafe {
+                (*new)
+                    .slot(pos)
+                    .copy_from_nonoverlapping((*old).slot(pos), 1)
+            };
+        }
+
+        inner.buffer.store(new, Ordering::Release);
+        unsafe { (*inner.retired.get()).push(old) };
+        new
+    }
+}
+
+impl<T> Default for Worker<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Thief half of a work-stealing deque, clone it per stealing thread
+pub struct Stealer<T> {
+    inner: Arc<Inner<T>>,
+    #[cfg(feature = "tracing")]
+    trace: Label,
+}
+
+impl<T> Stealer<T> {
+    /// Oldest item, `None` only once the deque looked empty
+    pub fn steal(&self) -> Option<T> {
+        let inner = &*self.inner;
+
+        loop {
+            let top = inner.top.load(Ordering::Acquire);
+            atomic::fence(Ordering::SeqCst);
+            let bottom = inner.bottom.load(Ordering::Acquire);
+
+            // guard: empty
+            if top >= bottom {
+                #[cfg(feature = "stats")]
+                inner.stats.empty();
+                #[cfg(feature = "tracing")]
+                self.trace.empty();
+                return None;
+            }
+
+            // read before claiming, the copy only counts if the CAS wins
+            let buffer = inner.buffer.load(Ordering::Acquire);
+            let val = unsafe { (*buffer).slot(top).read() };
+
+            if inner
+                .top
+                .compare_exchange(top, top + 1, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+            {
+                #[cfg(feature = "stats")]
+                inner.stats.popped(1);
+                #[cfg(feature = "tracing")]
+                self.trace.popped(1);
+                return Some(unsafe { val.assume_init() });
+            }
+        }
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.inner.stats.snapshot()
+    }
+}
+
+impl<T> Clone for Stealer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            #[cfg(feature = "tracing")]
+            trace: self.trace,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::thread;
+
+    #[test]
+    fn test_seq_deque() {
+        let mut worker = Worker::with_capacity(4);
+        let stealer = worker.stealer();
+
+        // outgrows the arena a few times
+        for i in 0..100 {
+            worker.push(i);
+        }
+        assert_eq!(stealer.steal(), Some(0));
+        assert_eq!(stealer.steal(), Some(1));
+        assert_eq!(worker.pop(), Some(99));
+        assert_eq!(worker.pop(), Some(98));
+
+        for i in 2..98 {
+            assert_eq!(stealer.steal(), Some(i));
+        }
+        assert!(worker.is_empty());
+        assert_eq!(worker.pop(), None);
+        assert_eq!(stealer.steal(), None);
+    }
+
+    #[test]
+    fn test_drop_deque() {
+        struct DropCount(Arc<AtomicUsize>);
+
+        impl Drop for DropCount {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let drops = Arc::new(AtomicUsize::new(0));
+        let mut worker = Worker::with_capacity(2);
+        let stealer = worker.stealer();
+
+        for _ in 0..10 {
+            worker.push(DropCount(drops.clone()));
+        }
+        drop(worker.pop());
+        drop(stealer.steal());
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+        drop((worker, stealer));
+        assert_eq!(drops.load(Ordering::Relaxed), 10);
+    }
+
+    #[test]
+    fn test_stress_deque() {
+        const THREADS: usize = 4;
+        const ITEMS: usize = if cfg!(miri) { 100 } else { 5000 };
+
+        let taken: Vec<_> = (0..THREADS * ITEMS).map(|_| AtomicUsize::new(0)).collect();
+        let done = AtomicUsize::new(0);
+        let workers: Vec<_> = (0..THREADS).map(|_| Worker::with_capacity(4)).collect();
+        let stealers: Vec<_> = workers.iter().map(Worker::stealer).collect();
+
+        thread::scope(|s| {
+            for (t, mut worker) in workers.into_iter().enumerate() {
+                let (taken, done, stealers) = (&taken, &done, &stealers);
+                s.spawn(move || {
+                    let run = |item: usize| {
+                        taken[item].fetch_add(1, Ordering::Relaxed);
+                        done.fetch_add(1, Ordering::Relaxed);
+                    };
+
+                    // keep a backlog around for thieves
+                    for i in 0..ITEMS {
+                        worker.push(t * ITEMS + i);
+                        if i % 3 == 0 {
+                            if let Some(item) = worker.pop() {
+                                run(item);
+                            }
+                        }
+                    }
+                    while let Some(item) = worker.pop() {
+                        run(item);
+                    }
+
+                    // then steal from random victims until everything ran
+                    let mut seed = t as u32 + 1;
+                    while done.load(Ordering::Relaxed) < THREADS * ITEMS {
+                        seed ^= seed << 13;
+                        seed ^= seed >> 17;
+                        seed ^= seed << 5;
+                        match stealers[seed as usize % THREADS].steal() {
+                            Some(item) => run(item),
+                            None => thread::yield_now(),
+                        }
+                    }
+                });
+            }
+        });
+
+        assert!(taken.iter().all(|count| count.load(Ordering::Relaxed) == 1));
+    }
+}
@@ -0,0 +1,270 @@
+#[cfg(feature = "async")]
+use core::{future::poll_fn, task::Poll};
+use std::collections::HashMap;
+
+#[cfg(feature = "async")]
+use super::asynchronous::{poll_pop, poll_ready};
+use super::spsc::{Consumer, Disconnected, PopError, Producer, PushError, SPSCEphemeral};
+
+type Ring<T, const N: usize> = SPSCEphemeral<(u64, T), N>;
+
+/// Request/response pair over two `SPSCEphemeral` rings of `N` slots,
+/// requests one way and responses the other, each tagged with the
+/// `Ticket` of the call it belongs to
+pub fn channel<Req, Resp, const N: usize>() -> (Caller<Req, Resp, N>, Callee<Req, Resp, N>) {
+    let (req_tx, req_rx) = Ring::new().split();
+    let (resp_tx, resp_rx) = Ring::new().split();
+    let caller = Caller {
+        tx: req_tx,
+        rx: resp_rx,
+        next: 0,
+        early: HashMap::new(),
+    };
+    let callee = Callee {
+        rx: req_rx,
+        tx: resp_tx,
+    };
+    (caller, callee)
+}
+
+/// Which call a response answers, handed out by `Caller::send`
+/// and passed back with `Callee::reply`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Ticket(u64);
+
+/// Calling end of a `duplex::channel`. Calls can be sent ahead and
+/// waited for in any order, responses that arrive before their turn
+/// are kept until asked for
+pub struct Caller<Req, Resp, const N: usize> {
+    tx: Producer<Ring<Req, N>>,
+    rx: Consumer<Ring<Resp, N>>,
+    next: u64,
+    early: HashMap<u64, Resp>, // answers that overtook the one waited for
+}
+
+impl<Req, Resp, const N: usize> Caller<Req, Resp, N> {
+    /// Sends `req` and waits for its response
+    pub fn call(&mut self, req: Req) -> Result<Resp, Disconnected> {
+        let ticket = self.send(req).map_err(|_| Disconnected)?;
+        self.wait(ticket)
+    }
+
+    /// Waits for room to send `req`, hands it back if the callee is gone
+    pub fn send(&mut self, req: Req) -> Result<Ticket, Req> {
+        let id = self.next;
+        self.tx.push_blocking((id, req)).map_err(|(_, req)| req)?;
+        Ok(self.issue())
+    }
+
+    pub fn try_send(&mut self, req: Req) -> Result<Ticket, PushError<Req>> {
+        let id = self.next;
+        self.tx.try_push((id, req)).map_err(|err| match err {
+            PushError::Full((_, req)) => PushError::Full(req),
+            PushError::Disconnected((_, req)) => PushError::Disconnected(req),
+        })?;
+        Ok(self.issue())
+    }
+
+    /// Waits for the response to `ticket`, setting aside the ones for
+    /// other tickets. A ticket already answered, or from another
+    /// caller, waits until the callee is gone
+    pub fn wait(&mut self, ticket: Ticket) -> Result<Resp, Disconnected> {
+        loop {
+            if let Some(resp) = self.early.remove(&ticket.0) {
+                return Ok(resp);
+            }
+            let (id, resp) = self.rx.pop_blocking()?;
+            self.early.insert(id, resp);
+        }
+    }
+
+    /// The response to `ticket` if it came in, `Disconnected` once the
+    /// callee is gone without answering
+    pub fn try_wait(&mut self, ticket: Ticket) -> Result<Resp, PopError> {
+        loop {
+            if let Some(resp) = self.early.remove(&ticket.0) {
+                return Ok(resp);
+            }
+            let (id, resp) = self.rx.pop()?;
+            self.early.insert(id, resp);
+        }
+    }
+
+    /// `call` that parks the task instead of the thread
+    #[cfg(feature = "async")]
+    pub async fn call_async(&mut self, req: Req) -> Result<Resp, Disconnected> {
+        let ticket = self.send_async(req).await.map_err(|_| Disconnected)?;
+        self.wait_async(ticket).await
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn send_async(&mut self, req: Req) -> Result<Ticket, Req> {
+        if poll_fn(|cx| poll_ready(&mut self.tx, cx)).await.is_err() {
+            return Err(req);
+        }
+        // a free slot only goes away when the callee disconnects
+        self.try_send(req).map_err(PushError::into_inner)
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn wait_async(&mut self, ticket: Ticket) -> Result<Resp, Disconnected> {
+        poll_fn(|cx| loop {
+            if let Some(resp) = self.early.remove(&ticket.0) {
+                return Poll::Ready(Ok(resp));
+            }
+            match poll_pop(&mut self.rx, cx) {
+                Poll::Ready(Some((id, resp))) => drop(self.early.insert(id, resp)),
+                Poll::Ready(None) => return Poll::Ready(Err(Disconnected)),
+                Poll::Pending => return Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    fn issue(&mut self) -> Ticket {
+        let ticket = Ticket(self.next);
+        self.next += 1;
+        ticket
+    }
+}
+
+/// Answering end of a `duplex::channel`, free to reply in any order
+pub struct Callee<Req, Resp, const N: usize> {
+    rx: Consumer<Ring<Req, N>>,
+    tx: Producer<Ring<Resp, N>>,
+}
+
+impl<Req, Resp, const N: usize> Callee<Req, Resp, N> {
+    /// Waits for the next call, `Disconnected` once the caller is
+    /// gone and every call sent before was taken
+    pub fn recv(&mut self) -> Result<(Ticket, Req), Disconnected> {
+        let (id, req) = self.rx.pop_blocking()?;
+        Ok((Ticket(id), req))
+    }
+
+    pub fn try_recv(&mut self) -> Result<(Ticket, Req), PopError> {
+        let (id, req) = self.rx.pop()?;
+        Ok((Ticket(id), req))
+    }
+
+    /// Waits for room to answer the call `ticket` came with, hands
+    /// `resp` back if the caller is gone
+    pub fn reply(&mut self, ticket: Ticket, resp: Resp) -> Result<(), Resp> {
+        self.tx
+            .push_blocking((ticket.0, resp))
+            .map_err(|(_, resp)| resp)
+    }
+
+    /// Answers every call in order with `f` until the caller is gone
+    pub fn serve(&mut self, mut f: impl FnMut(Req) -> Resp) {
+        while let Ok((ticket, req)) = self.recv() {
+            if self.reply(ticket, f(req)).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// `recv` that parks the task instead of the thread
+    #[cfg(feature = "async")]
+    pub async fn recv_async(&mut self) -> Result<(Ticket, Req), Disconnected> {
+        let (id, req) = poll_fn(|cx| poll_pop(&mut self.rx, cx))
+            .await
+            .ok_or(Disconnected)?;
+        Ok((Ticket(id), req))
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn reply_async(&mut self, ticket: Ticket, resp: Resp) -> Result<(), Resp> {
+        if poll_fn(|cx| poll_ready(&mut self.tx, cx)).await.is_err() {
+            return Err(resp);
+        }
+        self.tx.push((ticket.0, resp)).map_err(|(_, resp)| resp)
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_call_duplex() {
+        let (mut caller, mut callee) = channel::<u32, String, 4>();
+        let serve_t = thread::spawn(move || callee.serve(|n| n.to_string()));
+
+        for n in 0..100 {
+            assert_eq!(caller.call(n).unwrap(), n.to_string());
+        }
+        drop(caller);
+        serve_t.join().unwrap();
+    }
+
+    #[test]
+    fn test_out_of_order_duplex() {
+        let (mut caller, mut callee) = channel::<u32, u32, 4>();
+        let tickets: Vec<_> = (0..3).map(|n| caller.try_send(n).unwrap()).collect();
+
+        // answered last call first
+        let calls: Vec<_> = (0..3).map(|_| callee.try_recv().unwrap()).collect();
+        for &(ticket, n) in calls.iter().rev() {
+            callee.reply(ticket, n * 10).unwrap();
+        }
+        assert_eq!(caller.try_wait(tickets[1]), Ok(10));
+        assert_eq!(caller.wait(tickets[0]), Ok(0));
+        assert_eq!(caller.try_wait(tickets[2]), Ok(20));
+
+        // hung up without answering
+        let ticket = caller.try_send(3).unwrap();
+        drop(callee);
+        assert_eq!(caller.wait(ticket), Err(Disconnected));
+        assert_eq!(caller.try_send(4), Err(PushError::Disconnected(4)));
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_async_duplex() {
+        use core::{future::Future, task::Context};
+        use std::{
+            sync::Arc,
+            task::{Wake, Waker},
+            thread::Thread,
+        };
+
+        struct Unparker(Thread);
+
+        impl Wake for Unparker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        fn block_on<F: Future>(fut: F) -> F::Output {
+            let mut fut = core::pin::pin!(fut);
+            let waker = Waker::from(Arc::new(Unparker(thread::current())));
+            let mut cx = Context::from_waker(&waker);
+            loop {
+                if let Poll::Ready(val) = fut.as_mut().poll(&mut cx) {
+                    return val;
+                }
+                thread::park();
+            }
+        }
+
+        let (mut caller, mut callee) = channel::<u32, u32, 2>();
+        let serve_t = thread::spawn(move || {
+            block_on(async {
+                while let Ok((ticket, n)) = callee.recv_async().await {
+                    callee.reply_async(ticket, n + 1).await.unwrap();
+                }
+            })
+        });
+
+        block_on(async {
+            for n in 0..100 {
+                assert_eq!(caller.call_async(n).await, Ok(n + 1));
+            }
+        });
+        drop(caller);
+        serve_t.join().unwrap();
+    }
+}
@@ -0,0 +1,485 @@
+use alloc::vec::Vec;
+use core::{iter, mem::MaybeUninit};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer};
+#[cfg(feature = "numa")]
+use std::io;
+
+use crate::sync::UnsafeCell;
+
+#[cfg(feature = "serde")]
+use super::snapshot::{self, Snapshot};
+#[cfg(feature = "serde")]
+use super::spsc::pending;
+use super::spsc::{
+    drop_pending, len, pop, push, sealed, split, Consumer, Producer, Ring, RingState,
+};
+#[cfg(feature = "stats")]
+use super::stats::Stats;
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+
+/// SPSC ring whose arena is allocated on the heap,
+/// for when the capacity is only known at runtime
+pub struct DynBuffer<T> {
+    bufr: Arena<T>,
+    state: RingState,
+    #[cfg(feature = "numa")]
+    node: Option<usize>, // the arena was placed on
+    #[cfg(feature = "huge-pages")]
+    policy: AllocPolicy, // the arena ended up with
+}
+
+type Slot<T> = UnsafeCell<MaybeUninit<T>>;
+
+#[cfg(not(all(any(feature = "numa", feature = "huge-pages"), target_os = "linux")))]
+type Arena<T> = alloc::boxed::Box<[Slot<T>]>;
+#[cfg(all(any(feature = "numa", feature = "huge-pages"), target_os = "linux"))]
+type Arena<T> = mapped::Arena<T>;
+
+/// Where `DynBuffer::with_policy` takes the arena's memory from
+#[cfg(feature = "huge-pages")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AllocPolicy {
+    /// the global allocator, like `with_capacity`
+    #[default]
+    Heap,
+    /// huge pages, cutting the TLB misses of a ring spanning
+    /// megabytes. Reserved ones if the hugetlb pool has room,
+    /// otherwise transparent ones, and the heap for arenas smaller
+    /// than a huge page or wherever neither is to be had
+    HugePages,
+}
+
+impl<T> DynBuffer<T> {
+    /// Allocates room for at least `capacity` pending items,
+    /// rounded up to the next power of two
+    pub fn with_capacity(capacity: usize) -> Self {
+        let bufr = (0..capacity.max(1).next_power_of_two())
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect();
+
+        Self {
+            bufr,
+            state: RingState::new(),
+            #[cfg(feature = "numa")]
+            node: None,
+            #[cfg(feature = "huge-pages")]
+            policy: AllocPolicy::Heap,
+        }
+    }
+
+    /// Like `with_capacity`, with the arena's memory taken as `policy`
+    /// says, falling back to the heap rather than failing
+    #[cfg(feature = "huge-pages")]
+    pub fn with_policy(capacity: usize, policy: AllocPolicy) -> Self {
+        #[cfg(target_os = "linux")]
+        if policy == AllocPolicy::HugePages {
+            let len = capacity.max(1).next_power_of_two();
+            // guard: a huge page for a small ring only wastes memory
+            if len.saturating_mul(core::mem::size_of::<T>()) >= crate::util::pages::HUGE {
+                if let Ok(bufr) = Arena::mapped(len, crate::util::pages::Pages::huge) {
+                    return Self {
+                        bufr,
+                        state: RingState::new(),
+                        #[cfg(feature = "numa")]
+                        node: None,
+                        policy,
+                    };
+                }
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = policy;
+        Self::with_capacity(capacity)
+    }
+
+    /// The policy the arena was allocated by, `Heap` after a fallback.
+    /// Transparent huge pages are up to the kernel, which may still
+    /// back a `HugePages` arena with small ones
+    #[cfg(feature = "huge-pages")]
+    pub fn alloc_policy(&self) -> AllocPolicy {
+        self.policy
+    }
+
+    /// Like `with_capacity`, with the arena's pages on NUMA node
+    /// `node`, for a ring whose producer and consumer both run there
+    /// rather than on the node that happened to allocate it
+    ///
+    /// Fails if the process may not allocate on `node`, it doesn't
+    /// exist, and always off Linux
+    #[cfg(feature = "numa")]
+    pub fn with_capacity_on_node(capacity: usize, node: usize) -> io::Result<Self> {
+        #[cfg(target_os = "linux")]
+        {
+            let bufr = Arena::mapped(capacity.max(1).next_power_of_two(), |size| {
+                let pages = crate::util::pages::Pages::new(size)?;
+                pages.bind(node)?;
+                Ok(pages)
+            })?;
+            Ok(Self {
+                bufr,
+                state: RingState::new(),
+                node: Some(node),
+                #[cfg(feature = "huge-pages")]
+                policy: AllocPolicy::Heap,
+            })
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = (capacity, node);
+            Err(io::ErrorKind::Unsupported.into())
+        }
+    }
+
+    /// NUMA node the arena sits on, if it was placed on one
+    #[cfg(feature = "numa")]
+    pub fn node(&self) -> Option<usize> {
+        self.node
+    }
+
+    /// Names the ring in its `tracing` events, `spsc` otherwise
+    #[cfg(feature = "tracing")]
+    pub fn with_name(mut self, name: &'static str) -> Self {
+        self.state.trace = Label::new(name);
+        self
+    }
+
+    /// Moves the buffer behind a producer/consumer pair,
+    /// so only one thread can ever write and one can read
+    pub fn split(self) -> (Producer<Self>, Consumer<Self>) {
+        split(self)
+    }
+
+    /// Pushes while nothing else holds the ring, `split` pushes from
+    /// another thread
+    pub fn push(&mut self, val: T) -> Result<(), T> {
+        push(self, val)
+    }
+
+    /// Pops while nothing else holds the ring, `split` pops from
+    /// another thread
+    pub fn pop(&mut self) -> Option<T> {
+        pop(self)
+    }
+
+    pub fn try_push(&mut self, val: T) -> Result<(), T> {
+        self.push(val)
+    }
+
+    pub fn try_pop(&mut self) -> Option<T> {
+        self.pop()
+    }
+
+    /// Pending items, approximate while the other side is busy
+    pub fn len(&self) -> usize {
+        len(self)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.bufr.len()
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.state.stats.snapshot()
+    }
+
+    /// Drops every pending item
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Remaining items in pop order
+    pub fn into_inner(mut self) -> Vec<T> {
+        iter::from_fn(|| self.pop()).collect()
+    }
+
+    /// Borrows what's queued for serializing, see `Snapshot`
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&mut self) -> Snapshot<'_, T> {
+        Snapshot::new(pending(self))
+    }
+
+    /// A ring of `capacity`, rounded like `with_capacity`, holding what
+    /// a `snapshot` serialized in the same order. Fails on more items
+    /// than fit
+    #[cfg(feature = "serde")]
+    pub fn restore<'de, D: Deserializer<'de>>(
+        capacity: usize,
+        deserializer: D,
+    ) -> Result<Self, D::Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let mut ring = Self::with_capacity(capacity);
+        snapshot::restore(deserializer, ring.capacity(), |val| ring.push(val))?;
+        Ok(ring)
+    }
+}
+
+impl<T> sealed::Sealed for DynBuffer<T> {}
+
+unsafe impl<T> Ring for DynBuffer<T> {
+    type Item = T;
+
+    fn arena_size(&self) -> usize {
+        self.bufr.len()
+    }
+
+    fn state(&self) -> &RingState {
+        &self.state
+    }
+
+    fn slot(&self, idx: usize) -> &UnsafeCell<MaybeUninit<T>> {
+        &self.bufr[idx]
+    }
+
+    #[cfg(feature = "numa")]
+    fn node(&self) -> Option<usize> {
+        self.node
+    }
+}
+
+impl<T> Drop for DynBuffer<T> {
+    fn drop(&mut self) {
+        drop_pending(self);
+    }
+}
+
+unsafe impl<T: Send> Sync for DynBuffer<T> {}
+
+/// Slots either off the heap or in pages mapped for them, the
+/// slice is rebuilt from the pointer so `slot` doesn't branch
+#[cfg(all(any(feature = "numa", feature = "huge-pages"), target_os = "linux"))]
+mod mapped {
+    use alloc::boxed::Box;
+    use core::{alloc::Layout, mem::MaybeUninit, ops::Deref, ptr::NonNull, slice};
+    use std::io;
+
+    use super::Slot;
+    use crate::sync::UnsafeCell;
+    use crate::util::pages::Pages;
+
+    /// smallest page size of the supported targets
+    const PAGE: usize = 4096;
+
+    pub struct Arena<T> {
+        ptr: NonNull<Slot<T>>,
+        len: usize,
+        pages: Option<Pages>, // None if `ptr` came from a `Box`
+    }
+
+    impl<T> Arena<T> {
+        /// `len` slots in the pages `map` hands out for their size
+        pub fn mapped(
+            len: usize,
+            map: impl FnOnce(usize) -> io::Result<Pages>,
+        ) -> io::Result<Self> {
+            let layout = Layout::array::<Slot<T>>(len)
+                .map_err(|_| io::Error::from(io::ErrorKind::OutOfMemory))?;
+            assert!(layout.align() <= PAGE, "an item aligned past a page");
+
+            let pages = map(layout.size())?;
+            let ptr = pages.as_ptr().cast::<Slot<T>>();
+            for idx in 0..len {
+                unsafe { ptr.add(idx).write(UnsafeCell::new(MaybeUninit::uninit())) };
+            }
+            Ok(Self {
+                ptr: unsafe { NonNull::new_unchecked(ptr) },
+                len,
+                pages: Some(pages),
+            })
+        }
+    }
+
+    impl<T> FromIterator<Slot<T>> for Arena<T> {
+        fn from_iter<I: IntoIterator<Item = Slot<T>>>(iter: I) -> Self {
+            let slots: Box<[Slot<T>]> = iter.into_iter().collect();
+            let len = slots.len();
+            let ptr = Box::into_raw(slots).cast::<Slot<T>>();
+            Self {
+                ptr: unsafe { NonNull::new_unchecked(ptr) },
+                len,
+                pages: None,
+            }
+        }
+    }
+
+    impl<T> Deref for Arena<T> {
+        type Target = [Slot<T>];
+
+        fn deref(&self) -> &[Slot<T>] {
+            unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+        }
+    }
+
+    impl<T> Drop for Arena<T> {
+        fn drop(&mut self) {
+            let slots = core::ptr::slice_from_raw_parts_mut(self.ptr.as_ptr(), self.len);
+            match self.pages {
+                None => drop(unsafe { Box::from_raw(slots) }),
+                // the pages themselves go with the field
+                Some(_) => unsafe { slots.drop_in_place() },
+            }
+        }
+    }
+
+    unsafe impl<T: Send> Send for Arena<T> {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ephemeral::spsc::PopError;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_seq_dynamic() {
+        // rounded up to 8 slots
+        let (mut producer, mut consumer) = DynBuffer::with_capacity(5).split();
+
+        for lap in 0..100 {
+            for i in 0..8 {
+                assert!(producer.push(lap * 8 + i).is_ok());
+            }
+            assert_eq!(producer.push(0), Err(0));
+
+            for i in 0..8 {
+                assert_eq!(consumer.pop(), Ok(lap * 8 + i));
+            }
+            assert_eq!(consumer.pop(), Err(PopError::Empty));
+        }
+    }
+
+    #[test]
+    fn test_unsplit_dynamic() {
+        let mut src = DynBuffer::with_capacity(1);
+
+        assert!(src.try_push(1).is_ok());
+        assert_eq!(src.push(2), Err(2));
+        assert_eq!(src.try_pop(), Some(1));
+        assert_eq!(src.pop(), None);
+    }
+
+    #[test]
+    fn test_threaded_dynamic() {
+        let (mut producer, mut consumer) = DynBuffer::<i32>::with_capacity(100).split();
+
+        let produce_t = thread::spawn(move || {
+            for i in 0..10000 {
+                while producer.push(i).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let consume_t = thread::spawn(move || {
+            for i in 0..10000 {
+                loop {
+                    if let Ok(result) = consumer.pop() {
+                        assert_eq!(result, i);
+                        break;
+                    }
+                    thread::yield_now();
+                }
+            }
+        });
+
+        produce_t.join().unwrap();
+        consume_t.join().unwrap();
+    }
+
+    #[test]
+    fn test_drop_dynamic() {
+        struct DropCount(Arc<AtomicUsize>);
+
+        impl Drop for DropCount {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let drops = Arc::new(AtomicUsize::new(0));
+        let (mut producer, mut consumer) = DynBuffer::with_capacity(3).split();
+
+        for _ in 0..2 {
+            assert!(producer.push(DropCount(drops.clone())).is_ok());
+            drop(consumer.pop());
+        }
+        for _ in 0..3 {
+            assert!(producer.push(DropCount(drops.clone())).is_ok());
+        }
+        drop((producer, consumer));
+        assert_eq!(drops.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    #[cfg(all(feature = "numa", target_os = "linux"))]
+    // Miri doesn't emulate mbind
+    #[cfg_attr(miri, ignore)]
+    fn test_node_dynamic() {
+        // node 0 exists on every Linux machine, NUMA or not
+        let (mut producer, mut consumer) = DynBuffer::with_capacity_on_node(5, 0).unwrap().split();
+        assert_eq!(producer.preferred_node(), Some(0));
+        assert_eq!(consumer.preferred_node(), Some(0));
+
+        for lap in 0..4 {
+            for i in 0..8 {
+                assert!(producer.push(lap * 8 + i).is_ok());
+            }
+            assert_eq!(producer.push(0), Err(0));
+            for i in 0..8 {
+                assert_eq!(consumer.pop(), Ok(lap * 8 + i));
+            }
+        }
+
+        assert!(DynBuffer::<u8>::with_capacity_on_node(1, 1 << 20).is_err());
+        assert_eq!(DynBuffer::<u8>::with_capacity(1).node(), None);
+    }
+
+    #[test]
+    #[cfg(all(feature = "huge-pages", target_os = "linux"))]
+    // Miri doesn't emulate mmap
+    #[cfg_attr(miri, ignore)]
+    fn test_huge_dynamic() {
+        // 4 MiB of slots, mapped whether or not hugetlb has a pool
+        let src = DynBuffer::<u32>::with_policy(1 << 20, AllocPolicy::HugePages);
+        assert_eq!(src.alloc_policy(), AllocPolicy::HugePages);
+        let (mut producer, mut consumer) = src.split();
+
+        for lap in 0..2 {
+            for i in 0..1 << 20 {
+                assert!(producer.push(lap + i).is_ok());
+            }
+            assert_eq!(producer.push(0), Err(0));
+            for i in 0..1 << 20 {
+                assert_eq!(consumer.pop(), Ok(lap + i));
+            }
+        }
+
+        // too small to spend a huge page on
+        let small = DynBuffer::<u32>::with_policy(8, AllocPolicy::HugePages);
+        assert_eq!(small.alloc_policy(), AllocPolicy::Heap);
+    }
+
+    #[test]
+    fn test_len_dynamic() {
+        let mut src = DynBuffer::with_capacity(3);
+        assert_eq!((src.len(), src.capacity()), (0, 4));
+        src.push(0).unwrap();
+        src.push(1).unwrap();
+        assert_eq!((src.len(), src.free_space()), (2, 2));
+
+        let (mut producer, mut consumer) = src.split();
+        producer.push(2).unwrap();
+        producer.push(3).unwrap();
+        assert!(consumer.is_full());
+        consumer.pop().unwrap();
+        assert_eq!((producer.len(), producer.free_space()), (3, 1));
+    }
+}
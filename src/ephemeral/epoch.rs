@@ -0,0 +1,328 @@
+use alloc::boxed::Box;
+use core::{
+    ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering},
+};
+
+use crate::util::CachePadded;
+
+/// defers between attempts to advance the epoch and free garbage
+const COLLECT_EVERY: usize = 64;
+/// bit 0 of a local's word marks it pinned, so epochs step by 2
+const PINNED: usize = 1;
+const STEP: usize = 2;
+
+/// One participant's announcement, claimed by a `Guard` while it lives
+struct Local {
+    epoch: AtomicUsize, // epoch | PINNED while pinned, 0 otherwise
+    in_use: AtomicBool,
+    next: *mut Local, // set once before linking
+}
+
+/// Retired allocation plus the epoch it was retired in
+struct Deferred {
+    ptr: *mut (),
+    free: unsafe fn(*mut ()),
+    epoch: usize,
+    next: *mut Deferred,
+}
+
+/// Epoch-based reclamation for the unbounded linked structures.
+/// Operations `pin` first, nodes they unlink are deferred and only
+/// freed two epochs later, and the epoch only advances once every
+/// pinned participant has seen the current one, so nothing a pinned
+/// thread could still reach is freed under it
+///
+/// Each structure owns its collector, so dropping the structure
+/// frees whatever garbage is left without any global state
+pub(crate) struct Collector {
+    epoch: CachePadded<AtomicUsize>,
+    locals: AtomicPtr<Local>,     // every record, unlinked only on drop
+    garbage: AtomicPtr<Deferred>, // retired, waiting to be freed
+    deferred: AtomicUsize,        // defers so far, paces `collect`
+}
+
+impl Collector {
+    pub(crate) const fn new() -> Self {
+        Self {
+            epoch: CachePadded::new(AtomicUsize::new(0)),
+            locals: AtomicPtr::new(ptr::null_mut()),
+            garbage: AtomicPtr::new(ptr::null_mut()),
+            deferred: AtomicUsize::new(0),
+        }
+    }
+
+    /// Announces the caller in the current epoch until the guard drops
+    pub(crate) fn pin(&self) -> Guard<'_> {
+        let local = self.claim();
+        // SeqCst with the unlinking CAS and `try_advance`, a stale
+        // epoch only holds the next advance back
+        let epoch = self.epoch.load(Ordering::SeqCst);
+        local.epoch.store(epoch | PINNED, Ordering::SeqCst);
+        Guard {
+            collector: self,
+            local,
+        }
+    }
+
+    /// Advances the epoch if it can and frees what no pinned
+    /// thread can reach anymore
+    pub(crate) fn collect(&self) {
+        let epoch = self.try_advance();
+
+        // whoever swaps the list out owns it, unripe entries go back
+        let mut node = self.garbage.swap(ptr::null_mut(), Ordering::Acquire);
+        let (mut kept, mut last) = (ptr::null_mut::<Deferred>(), ptr::null_mut());
+        while !node.is_null() {
+            let deferred = unsafe { &mut *node };
+            let next = deferred.next;
+            if epoch.wrapping_sub(deferred.epoch) >= 2 * STEP {
+                unsafe { (deferred.free)(deferred.ptr) };
+                drop(unsafe { Box::from_raw(node) });
+            } else {
+                deferred.next = kept;
+                if kept.is_null() {
+                    last = node;
+                }
+                kept = node;
+            }
+            node = next;
+        }
+
+        if !kept.is_null() {
+            self.push_garbage(kept, last);
+        }
+    }
+
+    /// A free record, or a fresh one linked in when all are busy
+    fn claim(&self) -> &Local {
+        let mut node = self.locals.load(Ordering::Acquire);
+        while !node.is_null() {
+            let local = unsafe { &*node };
+            if !local.in_use.load(Ordering::Relaxed)
+                && local
+                    .in_use
+                    .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                return local;
+            }
+            node = local.next;
+        }
+
+        let local = Box::into_raw(Box::new(Local {
+            epoch: AtomicUsize::new(0),
+            in_use: AtomicBool::new(true),
+            next: ptr::null_mut(),
+        }));
+        let mut head = self.locals.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*local).next = head };
+            match self.locals.compare_exchange_weak(
+                head,
+                local,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return unsafe { &*local },
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// Moves the epoch on once every pinned record is in it,
+    /// returns the epoch as of now
+    fn try_advance(&self) -> usize {
+        let epoch = self.epoch.load(Ordering::SeqCst);
+
+        let mut node = self.locals.load(Ordering::Acquire);
+        while !node.is_null() {
+            let local = unsafe { &*node };
+            let seen = local.epoch.load(Ordering::SeqCst);
+            // guard: someone pinned in an older epoch
+            if seen & PINNED != 0 && seen != epoch | PINNED {
+                return epoch;
+            }
+            node = local.next;
+        }
+
+        match self.epoch.compare_exchange(
+            epoch,
+            epoch.wrapping_add(STEP),
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        ) {
+            Ok(_) => epoch.wrapping_add(STEP),
+            Err(current) => current,
+        }
+    }
+
+    /// Links the chain `first..=last` into the garbage list
+    fn push_garbage(&self, first: *mut Deferred, last: *mut Deferred) {
+        let mut garbage = self.garbage.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*last).next = garbage };
+            match self.garbage.compare_exchange_weak(
+                garbage,
+                first,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return,
+                Err(current) => garbage = current,
+            }
+        }
+    }
+}
+
+impl Drop for Collector {
+    fn drop(&mut self) {
+        // no guard outlives the borrow, everything is unreachable
+        let mut node = *self.garbage.get_mut();
+        while !node.is_null() {
+            let deferred = unsafe { Box::from_raw(node) };
+            unsafe { (deferred.free)(deferred.ptr) };
+            node = deferred.next;
+        }
+
+        let mut node = *self.locals.get_mut();
+        while !node.is_null() {
+            let local = unsafe { Box::from_raw(node) };
+            node = local.next;
+        }
+    }
+}
+
+unsafe impl Send for Collector {}
+unsafe impl Sync for Collector {}
+
+/// Keeps the caller pinned, nodes loaded meanwhile stay allocated
+pub struct Guard<'a> {
+    collector: &'a Collector,
+    local: &'a Local,
+}
+
+impl Guard<'_> {
+    /// Frees `ptr` once no thread pinned right now can reach it,
+    /// every so often collecting the garbage that became ripe
+    ///
+    /// # Safety
+    /// `ptr` comes from `Box::into_raw` and was unlinked with a SeqCst
+    /// operation, so threads pinning from here on can't reach it
+    pub(crate) unsafe fn defer_free<T>(&self, ptr: *mut T) {
+        unsafe fn free<T>(ptr: *mut ()) {
+            drop(unsafe { Box::from_raw(ptr.cast::<T>()) });
+        }
+
+        let collector = self.collector;
+        let deferred = Box::into_raw(Box::new(Deferred {
+            ptr: ptr.cast(),
+            free: free::<T>,
+            epoch: collector.epoch.load(Ordering::SeqCst),
+            next: ptr::null_mut(),
+        }));
+        collector.push_garbage(deferred, deferred);
+
+        if collector.deferred.fetch_add(1, Ordering::Relaxed) % COLLECT_EVERY == COLLECT_EVERY - 1 {
+            collector.collect();
+        }
+    }
+}
+
+impl Drop for Guard<'_> {
+    fn drop(&mut self) {
+        self.local.epoch.store(0, Ordering::Release);
+        self.local.in_use.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use alloc::sync::Arc;
+
+    struct DropCount(Arc<AtomicUsize>);
+
+    impl Drop for DropCount {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn retire(guard: &Guard<'_>, drops: &Arc<AtomicUsize>) {
+        let ptr = Box::into_raw(Box::new(DropCount(drops.clone())));
+        unsafe { guard.defer_free(ptr) };
+    }
+
+    #[test]
+    fn test_pinned_epoch() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let collector = Collector::new();
+
+        let reader = collector.pin();
+        {
+            let guard = collector.pin();
+            retire(&guard, &drops);
+        }
+        // the reader pinned before the retire holds it back
+        for _ in 0..4 {
+            collector.collect();
+        }
+        assert_eq!(drops.load(Ordering::Relaxed), 0);
+
+        drop(reader);
+        for _ in 0..3 {
+            collector.collect();
+        }
+        assert_eq!(drops.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_drop_epoch() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let collector = Collector::new();
+
+        let guard = collector.pin();
+        for _ in 0..COLLECT_EVERY * 2 {
+            retire(&guard, &drops);
+        }
+        drop(guard);
+        // whatever wasn't ripe yet goes along with the collector
+        drop(collector);
+        assert_eq!(drops.load(Ordering::Relaxed), COLLECT_EVERY * 2);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stress_epoch() {
+        use std::thread;
+
+        const THREADS: usize = 4;
+        const ITEMS: usize = if cfg!(miri) { 200 } else { 20000 };
+        let collector = Collector::new();
+        let shared = AtomicPtr::new(Box::into_raw(Box::new(0usize)));
+
+        // readers dereference whatever is current while writers swap
+        // and retire it, a node freed too early is a use-after-free
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let (collector, shared) = (&collector, &shared);
+                s.spawn(move || {
+                    for i in 0..ITEMS {
+                        let guard = collector.pin();
+                        let current = shared.load(Ordering::SeqCst);
+                        assert!(unsafe { *current } < THREADS * ITEMS);
+
+                        if i % 2 == t % 2 {
+                            let fresh = Box::into_raw(Box::new(t * ITEMS + i));
+                            let old = shared.swap(fresh, Ordering::SeqCst);
+                            unsafe { guard.defer_free(old) };
+                        }
+                    }
+                });
+            }
+        });
+
+        drop(unsafe { Box::from_raw(shared.into_inner()) });
+    }
+}
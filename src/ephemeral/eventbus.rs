@@ -0,0 +1,245 @@
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    fmt,
+    hash::Hash,
+    sync::{Arc, Mutex, PoisonError, RwLock},
+    time::Duration,
+};
+
+use super::{
+    broadcast::{BroadcastEphemeral, Policy, PopError, Producer, Subscriber},
+    wait::{retry, retry_until, Backoff},
+};
+
+/// Nap of a blocked publish or receive once spinning and yielding didn't help
+const PARK: Duration = Duration::from_micros(50);
+
+/// What happens once a subscriber falls a whole ring behind its topic
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lag {
+    /// publishers to the topic wait for it, nothing is missed
+    Block,
+    /// the events it fell behind on are skipped silently
+    Skip,
+    /// skipped as well, but the next receive reports how many
+    Report,
+}
+
+/// One ring per kind of subscriber, publishing goes to both so
+/// `Lag::Block` subscribers only hold back each other
+struct Topic<E: Clone, const N: usize> {
+    lossless: Producer<E, N>,
+    lossy: Producer<E, N>,
+}
+
+impl<E: Clone, const N: usize> Topic<E, N> {
+    fn new() -> Self {
+        // the rings' first subscribers go right away
+        let (lossless, _) = BroadcastEphemeral::new(Policy::Block).split();
+        let (lossy, _) = BroadcastEphemeral::new(Policy::Lag).split();
+        Self { lossless, lossy }
+    }
+}
+
+/// Topic-keyed pub/sub over the broadcast ring, any number of
+/// publishers post events under a topic K (a string, an enum..),
+/// every subscriber of that topic gets its own copy from its own
+/// handle and picks what happens when it falls behind
+/// N:: events a topic holds per subscriber, a power of two
+pub struct Bus<K, E: Clone, const N: usize> {
+    topics: RwLock<HashMap<K, Arc<Mutex<Topic<E, N>>>>>,
+}
+
+impl<K: Hash + Eq, E: Clone, const N: usize> Bus<K, E, N> {
+    pub fn new() -> Self {
+        Self {
+            topics: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Posts `event` to everyone subscribed to `topic`, waiting while
+    /// a `Lag::Block` subscriber is a ring behind. Dropped if nobody
+    /// ever subscribed to `topic`
+    pub fn publish<Q>(&self, topic: &Q, event: E)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let topics = self.topics.read().unwrap_or_else(PoisonError::into_inner);
+        let Some(topic) = topics.get(topic).cloned() else {
+            return;
+        };
+        drop(topics);
+
+        let mut topic = topic.lock().unwrap_or_else(PoisonError::into_inner);
+        // never fails, laps its slow subscribers instead
+        let _ = topic.lossy.push(event.clone());
+        topic
+            .lossless
+            .push_blocking_with(event, &mut Backoff::with_park(PARK));
+    }
+
+    /// Subscription to every event posted to `topic` from here on
+    pub fn subscribe(&self, topic: K, lag: Lag) -> Subscription<E, N> {
+        let mut topics = self.topics.write().unwrap_or_else(PoisonError::into_inner);
+        let topic = topics
+            .entry(topic)
+            .or_insert_with(|| Arc::new(Mutex::new(Topic::new())))
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let sub = match lag {
+            Lag::Block => topic.lossless.subscribe(),
+            Lag::Skip | Lag::Report => topic.lossy.subscribe(),
+        };
+        Subscription { sub, lag }
+    }
+
+    /// Topics anyone ever subscribed to
+    pub fn topics(&self) -> usize {
+        self.topics
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+}
+
+impl<K: Hash + Eq, E: Clone, const N: usize> Default for Bus<K, E, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, E: Clone, const N: usize> fmt::Debug for Bus<K, E, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Bus { .. }")
+    }
+}
+
+/// One subscriber's handle on a topic of a `Bus`
+pub struct Subscription<E: Clone, const N: usize> {
+    sub: Subscriber<E, N>,
+    lag: Lag,
+}
+
+impl<E: Clone, const N: usize> Subscription<E, N> {
+    /// Next event, `Lagged` comes up under `Lag::Report` only
+    pub fn try_recv(&mut self) -> Result<E, PopError> {
+        match self.sub.pop() {
+            Err(PopError::Lagged(_)) if self.lag == Lag::Skip => self.sub.pop(),
+            popped => popped,
+        }
+    }
+
+    /// Waits for the next event, `Err` only with `Lagged` under `Lag::Report`
+    pub fn recv(&mut self) -> Result<E, PopError> {
+        let mut wait = Backoff::with_park(PARK);
+        retry(&mut wait, || self.ready())
+    }
+
+    /// `recv` giving up with `Empty` once `timeout` passed
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<E, PopError> {
+        retry_until(timeout, || self.ready()).unwrap_or(Err(PopError::Empty))
+    }
+
+    fn ready(&mut self) -> Option<Result<E, PopError>> {
+        match self.try_recv() {
+            Err(PopError::Empty) => None,
+            popped => Some(popped),
+        }
+    }
+
+    pub fn lag(&self) -> Lag {
+        self.lag
+    }
+
+    /// Events not received yet, approximate while publishers post
+    pub fn len(&self) -> usize {
+        self.sub.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<E: Clone, const N: usize> fmt::Debug for Subscription<E, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscription")
+            .field("lag", &self.lag)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+    enum Topic {
+        Orders,
+        Prices,
+    }
+
+    #[test]
+    fn test_topics_eventbus() {
+        let bus = Bus::<Topic, u32, 4>::new();
+
+        // nobody listens yet
+        bus.publish(&Topic::Orders, 0);
+        let mut orders = bus.subscribe(Topic::Orders, Lag::Skip);
+        let mut prices = bus.subscribe(Topic::Prices, Lag::Skip);
+
+        bus.publish(&Topic::Orders, 1);
+        bus.publish(&Topic::Prices, 2);
+        assert_eq!(orders.try_recv(), Ok(1));
+        assert_eq!(prices.try_recv(), Ok(2));
+        assert_eq!(orders.try_recv(), Err(PopError::Empty));
+        assert_eq!(bus.topics(), 2);
+    }
+
+    #[test]
+    fn test_lag_eventbus() {
+        let bus = Bus::<String, u32, 4>::new();
+        let mut skip = bus.subscribe("ticks".into(), Lag::Skip);
+        let mut report = bus.subscribe("ticks".into(), Lag::Report);
+
+        for i in 0..6 {
+            bus.publish("ticks", i);
+        }
+        // two events were overwritten before either read them
+        assert_eq!(skip.try_recv(), Ok(2));
+        assert_eq!(report.try_recv(), Err(PopError::Lagged(2)));
+        assert_eq!(report.try_recv(), Ok(2));
+    }
+
+    #[test]
+    fn test_block_eventbus() {
+        const ITEMS: u32 = if cfg!(miri) { 50 } else { 2000 };
+        let bus = Bus::<&str, u32, 4>::new();
+        let mut slow = bus.subscribe("jobs", Lag::Block);
+
+        // publishers wait for the lossless subscriber, it sees everything
+        thread::scope(|s| {
+            for p in 0..2 {
+                let bus = &bus;
+                s.spawn(move || {
+                    for i in 0..ITEMS {
+                        bus.publish("jobs", p * ITEMS + i);
+                    }
+                });
+            }
+
+            let mut seen: Vec<_> = (0..2 * ITEMS).map(|_| slow.recv().unwrap()).collect();
+            seen.sort_unstable();
+            assert_eq!(seen, (0..2 * ITEMS).collect::<Vec<_>>());
+        });
+        assert_eq!(
+            slow.recv_timeout(Duration::from_millis(1)),
+            Err(PopError::Empty)
+        );
+    }
+}
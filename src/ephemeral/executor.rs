@@ -0,0 +1,421 @@
+use core::{
+    cell::RefCell,
+    fmt, ptr,
+    sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering},
+};
+use std::{
+    io,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Condvar, Mutex, PoisonError},
+    thread,
+};
+
+#[cfg(feature = "affinity")]
+use super::affinity::spawn_pinned_with;
+use super::{
+    deque::{Stealer, Worker},
+    mpmc::MPMCEphemeral,
+    oneshot,
+    spsc::{Disconnected, PopError},
+};
+use crate::sync::long_wait;
+
+/// jobs the injection queue holds before `spawn` waits for room
+pub const INJECTOR: usize = 256;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+struct Shared {
+    injector: MPMCEphemeral<Job, INJECTOR>,
+    stealers: Vec<Stealer<Job>>,
+    shutdown: AtomicBool,
+    idle: AtomicUsize, // workers between announcing a sleep and waking
+    sleep: Mutex<()>,
+    wake: Condvar,
+}
+
+impl Shared {
+    /// Called after queueing a job, rouses one sleeping worker
+    fn notify(&self) {
+        // pairs with the fence in `park`, either the sleeper's recheck
+        // finds the job or this sees it announced
+        fence(Ordering::SeqCst);
+        if self.idle.load(Ordering::Relaxed) > 0 {
+            // taken so the notify can't slip in between the sleeper's
+            // recheck and its wait
+            drop(self.lock());
+            self.wake.notify_one();
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ()> {
+        self.sleep.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// the deque of the pool worker running on this thread, if any
+thread_local! {
+    static LOCAL: RefCell<Option<(*const Shared, Worker<Job>)>> = const { RefCell::new(None) };
+}
+
+/// Fixed pool of worker threads running spawned closures. Jobs from
+/// outside go through an MPMC injection queue, jobs spawned by a
+/// running job onto that worker's own work-stealing deque, and idle
+/// workers steal the oldest jobs off the others before sleeping
+///
+/// Dropping the pool, or `shutdown`, runs everything queued so far,
+/// including what those jobs spawn, then joins the workers
+pub struct ThreadPool {
+    spawner: Spawner,
+    threads: Vec<thread::JoinHandle<()>>,
+}
+
+impl ThreadPool {
+    /// Starts `threads` workers
+    ///
+    /// Panics if `threads` is 0
+    pub fn new(threads: usize) -> Self {
+        assert!(threads > 0, "a pool needs at least one thread");
+        Self::start(threads, |builder, _, run| builder.spawn(run)).expect("spawning a pool worker")
+    }
+
+    /// Starts one worker per core in `cores`, each pinned to its own,
+    /// what a benchmark wants so the cores it measures stay put
+    ///
+    /// Panics if `cores` is empty
+    #[cfg(feature = "affinity")]
+    pub fn pinned(cores: &[usize]) -> io::Result<Self> {
+        assert!(!cores.is_empty(), "a pool needs at least one thread");
+        Self::start(cores.len(), |builder, idx, run| {
+            spawn_pinned_with(builder, cores[idx], run)
+        })
+    }
+
+    /// Spawns the workers through `spawn`, a failed one shuts down
+    /// those already running
+    fn start(
+        threads: usize,
+        mut spawn: impl FnMut(thread::Builder, usize, Job) -> io::Result<thread::JoinHandle<()>>,
+    ) -> io::Result<Self> {
+        let workers: Vec<Worker<Job>> = (0..threads).map(|_| Worker::new()).collect();
+        let shared = Arc::new(Shared {
+            injector: MPMCEphemeral::new(),
+            stealers: workers.iter().map(Worker::stealer).collect(),
+            shutdown: AtomicBool::new(false),
+            idle: AtomicUsize::new(0),
+            sleep: Mutex::new(()),
+            wake: Condvar::new(),
+        });
+
+        let mut pool = Self {
+            spawner: Spawner {
+                shared: shared.clone(),
+            },
+            threads: Vec::with_capacity(threads),
+        };
+        for (idx, worker) in workers.into_iter().enumerate() {
+            let shared = shared.clone();
+            let builder = thread::Builder::new().name(format!("pool-worker-{idx}"));
+            // dropping `pool` on the way out joins the workers so far
+            let thread = spawn(builder, idx, Box::new(move || run(&shared, idx, worker)))?;
+            pool.threads.push(thread);
+        }
+        Ok(pool)
+    }
+
+    /// See `Spawner::spawn`
+    pub fn spawn<F, T>(&self, job: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.spawner.spawn(job)
+    }
+
+    /// Handle for spawning from elsewhere, jobs included
+    pub fn spawner(&self) -> Spawner {
+        self.spawner.clone()
+    }
+
+    pub fn threads(&self) -> usize {
+        self.spawner.shared.stealers.len()
+    }
+
+    /// Runs everything queued, then waits for the workers to exit.
+    /// Same as dropping the pool
+    pub fn shutdown(self) {}
+}
+
+impl Default for ThreadPool {
+    /// One worker per available core
+    fn default() -> Self {
+        Self::new(thread::available_parallelism().map_or(1, usize::from))
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        let shared = &self.spawner.shared;
+        shared.shutdown.store(true, Ordering::SeqCst);
+        drop(shared.lock());
+        shared.wake.notify_all();
+        for thread in self.threads.drain(..) {
+            // jobs run under `catch_unwind`, workers don't panic
+            let _ = thread.join();
+        }
+    }
+}
+
+impl fmt::Debug for ThreadPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThreadPool")
+            .field("threads", &self.threads())
+            .field("queued", &self.spawner.shared.injector.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Cloneable handle spawning onto a `ThreadPool`, for jobs that spawn
+/// more jobs without keeping the pool itself alive
+#[derive(Clone)]
+pub struct Spawner {
+    shared: Arc<Shared>,
+}
+
+impl Spawner {
+    /// Queues `job`, the handle gets its result. From one of the
+    /// pool's workers it lands on that worker's deque, otherwise on
+    /// the injection queue, waiting while that's full
+    ///
+    /// From outside a pool that's shutting down the job runs on
+    /// the calling thread instead
+    pub fn spawn<F, T>(&self, job: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let job: Job = Box::new(move || {
+            // the receiver may be gone, nobody wants the result then
+            let _ = tx.send(panic::catch_unwind(AssertUnwindSafe(job)));
+        });
+
+        let shared = &*self.shared;
+        let job = LOCAL.with(|local| match &mut *local.borrow_mut() {
+            Some((owner, worker)) if ptr::eq(*owner, shared) => {
+                worker.push(job);
+                None
+            }
+            _ => Some(job),
+        });
+        if let Some(mut job) = job {
+            let mut backoff = long_wait();
+            loop {
+                // guard: the workers may be gone, run it here instead
+                if shared.shutdown.load(Ordering::SeqCst) {
+                    job();
+                    return JoinHandle { rx };
+                }
+                match shared.injector.push(job) {
+                    Ok(()) => break,
+                    Err(back) => job = back,
+                }
+                backoff.snooze();
+            }
+        }
+        shared.notify();
+
+        // pushed as the workers made their last check, see `park`,
+        // help drain what nobody may be left to run
+        if shared.shutdown.load(Ordering::SeqCst) {
+            while let Some(job) = shared.injector.pop() {
+                job();
+            }
+        }
+
+        JoinHandle { rx }
+    }
+}
+
+impl fmt::Debug for Spawner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Spawner").finish_non_exhaustive()
+    }
+}
+
+/// Worker loop, exits once shut down with nothing left to run
+fn run(shared: &Shared, idx: usize, worker: Worker<Job>) {
+    LOCAL.with(|local| *local.borrow_mut() = Some((shared, worker)));
+
+    loop {
+        // the deque stays borrowed only for the pop, jobs may spawn
+        let own = LOCAL.with(|local| local.borrow_mut().as_mut().and_then(|(_, w)| w.pop()));
+        if let Some(job) = own.or_else(|| find(shared, idx)) {
+            job();
+            continue;
+        }
+        if !park(shared, idx) {
+            break;
+        }
+    }
+
+    LOCAL.with(|local| local.borrow_mut().take());
+}
+
+/// A job off the injection queue, or the oldest one off another worker
+fn find(shared: &Shared, idx: usize) -> Option<Job> {
+    let others = shared.stealers.len();
+    shared.injector.pop().or_else(|| {
+        (1..others)
+            .map(|step| &shared.stealers[(idx + step) % others])
+            .find_map(Stealer::steal)
+    })
+}
+
+/// Sleeps until there may be work, runs a job found on the recheck
+/// right away. False once shut down with nothing left anywhere
+fn park(shared: &Shared, idx: usize) -> bool {
+    let guard = shared.lock();
+    shared.idle.fetch_add(1, Ordering::Relaxed);
+    // read before the recheck, a spawn the recheck misses then sees
+    // the shutdown itself and runs the job, see `Spawner::spawn`
+    let shutdown = shared.shutdown.load(Ordering::SeqCst);
+    fence(Ordering::SeqCst);
+
+    let found = find(shared, idx);
+    let guard = if found.is_none() && !shutdown {
+        shared
+            .wake
+            .wait(guard)
+            .unwrap_or_else(PoisonError::into_inner)
+    } else {
+        guard
+    };
+    shared.idle.fetch_sub(1, Ordering::Relaxed);
+    drop(guard);
+
+    match found {
+        Some(job) => {
+            job();
+            true
+        }
+        None => !shutdown,
+    }
+}
+
+/// Result of a spawned job
+pub struct JoinHandle<T> {
+    rx: oneshot::Receiver<thread::Result<T>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Waits for the job, `Err` with the panic if it hit one
+    pub fn join(mut self) -> thread::Result<T> {
+        self.rx.recv().unwrap_or_else(|Disconnected| lost())
+    }
+
+    /// The result if the job finished, hands the handle back otherwise
+    pub fn try_join(mut self) -> Result<thread::Result<T>, Self> {
+        match self.rx.try_recv() {
+            Ok(res) => Ok(res),
+            Err(PopError::Empty) => Err(self),
+            Err(PopError::Disconnected) => Ok(lost()),
+        }
+    }
+}
+
+/// A job dropped without running, which only a worker thread
+/// dying outside `catch_unwind` could cause
+fn lost<T>() -> thread::Result<T> {
+    Err(Box::new("the job was dropped before it ran"))
+}
+
+impl<T> fmt::Debug for JoinHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinHandle").finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_join_executor() {
+        let pool = ThreadPool::new(2);
+
+        let sum = pool.spawn(|| (1..=10).sum::<u32>());
+        let failed = pool.spawn(|| panic!("boom"));
+        assert_eq!(sum.join().unwrap(), 55);
+        assert_eq!(
+            failed.join().unwrap_err().downcast_ref::<&str>(),
+            Some(&"boom")
+        );
+        // a panicking job doesn't take its worker down
+        assert_eq!(pool.spawn(|| 7).join().unwrap(), 7);
+    }
+
+    #[test]
+    #[cfg(feature = "affinity")]
+    #[cfg_attr(miri, ignore)]
+    fn test_pinned_executor() {
+        use crate::ephemeral::affinity::cores;
+
+        let allowed = cores().unwrap();
+        let pool = ThreadPool::pinned(&allowed[..1]).unwrap();
+        assert_eq!(pool.threads(), 1);
+        // jobs run on the pinned worker
+        let on = pool.spawn(|| cores().unwrap()).join().unwrap();
+        assert_eq!(on, allowed[..1]);
+
+        // the worker that can't be pinned takes the pool down
+        assert!(ThreadPool::pinned(&[allowed[0], usize::MAX]).is_err());
+    }
+
+    #[test]
+    fn test_nested_executor() {
+        const JOBS: usize = if cfg!(miri) { 8 } else { 200 };
+        let pool = ThreadPool::new(3);
+        let done = Arc::new(AtomicUsize::new(0));
+
+        // jobs spawning jobs onto their worker's deque, which the
+        // others steal from while the injection queue stays empty
+        let (spawner, counter) = (pool.spawner(), done.clone());
+        pool.spawn(move || {
+            for _ in 0..JOBS {
+                let counter = counter.clone();
+                spawner.spawn(move || counter.fetch_add(1, Ordering::Relaxed));
+            }
+        })
+        .join()
+        .unwrap();
+
+        pool.shutdown();
+        assert_eq!(done.load(Ordering::Relaxed), JOBS);
+    }
+
+    #[test]
+    fn test_shutdown_executor() {
+        const JOBS: usize = if cfg!(miri) { 20 } else { 2 * INJECTOR };
+        let pool = ThreadPool::new(2);
+        let done = Arc::new(AtomicUsize::new(0));
+
+        // more than the injection queue holds, spawn waits for room
+        let handles: Vec<_> = (0..JOBS)
+            .map(|i| {
+                let done = done.clone();
+                pool.spawn(move || {
+                    done.fetch_add(1, Ordering::Relaxed);
+                    i
+                })
+            })
+            .collect();
+
+        // everything queued runs before the workers exit
+        pool.shutdown();
+        assert_eq!(done.load(Ordering::Relaxed), JOBS);
+        for (i, handle) in handles.into_iter().enumerate() {
+            assert_eq!(handle.try_join().ok().unwrap().unwrap(), i);
+        }
+    }
+}
@@ -0,0 +1,841 @@
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use core::time::Duration;
+#[cfg(feature = "async")]
+use core::{
+    future::{poll_fn, Future},
+    task::{Context, Poll},
+};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+#[cfg(feature = "std")]
+use super::adapt::TimeoutChunks;
+use super::adapt::{Batch, Filter, Map};
+#[cfg(feature = "async")]
+use super::asynchronous::{AsyncConsumer, AsyncProducer};
+use super::reclaim::Reclaim;
+use super::segment::SegQueue;
+#[cfg(feature = "std")]
+use super::stack::EphemeralStack;
+#[cfg(feature = "std")]
+use super::timed::DelayQueue;
+#[cfg(feature = "std")]
+use super::wait::{push_until, retry_until, Timeout};
+#[cfg(feature = "std")]
+use super::{broadcast, channel, std_mpsc};
+use super::{
+    linked, mpmc::BoundedMpmc, mpsc, multi, overwrite, priority, rendezvous, sharded, small, spmc,
+    spsc,
+};
+
+/// Pushing end of any queue in the crate, for code written once
+/// against all of them. Shared queues push through `&Q` or `Arc<Q>`
+///
+/// Two are left out. A `rendezvous::Sender` holds nothing, so a push
+/// can't land without waiting on the receiver, and `IsrQueue` pushes
+/// and pops through `unsafe` calls only its owner can vouch for
+pub trait Produce<T> {
+    /// Whether `evict` can make room, see `FullPolicy::DropOldest`
+    const CAN_EVICT: bool = false;
+
+    /// Hands `val` back when there's no room, or nobody left to pop it
+    fn try_push(&mut self, val: T) -> Result<(), T>;
+
+    /// Nobody left to pop, waiting for room is pointless. Handles that
+    /// can't tell always say `false`
+    fn is_disconnected(&self) -> bool {
+        false
+    }
+
+    /// Pops the oldest pending item from the pushing end, only handles
+    /// whose ring lets a producer pop do so
+    fn evict(&mut self) -> Option<T> {
+        None
+    }
+}
+
+/// Popping end of any queue in the crate, see `Produce` for the
+/// ones left out
+pub trait Consume<T> {
+    /// `None` when nothing is queued right now
+    fn try_pop(&mut self) -> Option<T>;
+
+    /// Nobody left to push, once drained nothing more comes. Handles
+    /// that can't tell always say `false`
+    fn is_disconnected(&self) -> bool {
+        false
+    }
+
+    /// Hands out `f` of every item, in place of a pipeline stage.
+    /// Not `map`, which consumers that are iterators already have
+    fn map_items<U, F: FnMut(T) -> U>(self, f: F) -> Map<T, Self, F>
+    where
+        Self: Sized,
+    {
+        Map::new(self, f)
+    }
+
+    /// Drops the items `f` turns down on the way out
+    fn filter_items<F: FnMut(&T) -> bool>(self, f: F) -> Filter<T, Self, F>
+    where
+        Self: Sized,
+    {
+        Filter::new(self, f)
+    }
+
+    /// Hands out whatever is queued as one `Vec`, up to `max` items
+    fn batch(self, max: usize) -> Batch<T, Self>
+    where
+        Self: Sized,
+    {
+        Batch::new(self, max)
+    }
+
+    /// Hands out chunks of `max` items, or fewer once `linger` passed
+    /// since the first of them came in
+    #[cfg(feature = "std")]
+    fn timeout_chunks(self, max: usize, linger: Duration) -> TimeoutChunks<T, Self>
+    where
+        Self: Sized,
+    {
+        TimeoutChunks::new(self, max, linger)
+    }
+}
+
+/// `Produce` that can wait for room. The default retries `try_push`
+/// until the deadline, napping in between, handles with a wait of
+/// their own (notify, disconnect) use that instead
+#[cfg(feature = "std")]
+pub trait BlockingProduce<T>: Produce<T> {
+    /// Gives up once `timeout` passed, handing the value back
+    fn push_timeout(&mut self, val: T, timeout: Duration) -> Result<(), Timeout<T>> {
+        push_until(val, timeout, |val| self.try_push(val))
+    }
+}
+
+/// `Consume` that can wait for an item, see `BlockingProduce`
+#[cfg(feature = "std")]
+pub trait BlockingConsume<T>: Consume<T> {
+    /// Gives up with `None` once `timeout` passed
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+        retry_until(timeout, || self.try_pop())
+    }
+}
+
+/// `Produce` that parks the task instead of the thread
+#[cfg(feature = "async")]
+pub trait AsyncProduce<T>: Produce<T> {
+    /// Ready once `try_push` has room, `Err` with nobody left to pop
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), spsc::Disconnected>>;
+
+    /// Resolves once `val` went in, or hands it back with nobody
+    /// left to pop it
+    fn push_async(&mut self, val: T) -> impl Future<Output = Result<(), T>> {
+        let mut pending = Some(val);
+        poll_fn(move |cx| loop {
+            let Poll::Ready(ready) = self.poll_ready(cx) else {
+                return Poll::Pending;
+            };
+            let val = pending.take().expect("polled after completion");
+            if ready.is_err() {
+                return Poll::Ready(Err(val));
+            }
+            match self.try_push(val) {
+                Ok(()) => return Poll::Ready(Ok(())),
+                // lost the room to another handle, wait again
+                Err(val) => pending = Some(val),
+            }
+        })
+    }
+}
+
+/// `Consume` that parks the task instead of the thread
+#[cfg(feature = "async")]
+pub trait AsyncConsume<T>: Consume<T> {
+    /// `Ready(None)` once the producer is gone and the queue drained
+    fn poll_pop(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>>;
+
+    fn pop_async(&mut self) -> impl Future<Output = Option<T>> {
+        poll_fn(|cx| self.poll_pop(cx))
+    }
+}
+
+impl<R: spsc::Ring> Produce<R::Item> for spsc::Producer<R> {
+    fn try_push(&mut self, val: R::Item) -> Result<(), R::Item> {
+        self.push(val)
+    }
+
+    fn is_disconnected(&self) -> bool {
+        spsc::Producer::is_disconnected(self)
+    }
+}
+
+impl<R: spsc::Ring> Consume<R::Item> for spsc::Consumer<R> {
+    fn try_pop(&mut self) -> Option<R::Item> {
+        self.pop().ok()
+    }
+
+    fn is_disconnected(&self) -> bool {
+        spsc::Consumer::is_disconnected(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: spsc::Ring> BlockingProduce<R::Item> for spsc::Producer<R> {
+    fn push_timeout(&mut self, val: R::Item, timeout: Duration) -> Result<(), Timeout<R::Item>> {
+        spsc::Producer::push_timeout(self, val, timeout)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: spsc::Ring> BlockingConsume<R::Item> for spsc::Consumer<R> {
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<R::Item> {
+        spsc::Consumer::pop_timeout(self, timeout).ok()
+    }
+}
+
+impl<T, const N: usize> Produce<T> for mpsc::Producer<T, N> {
+    fn try_push(&mut self, val: T) -> Result<(), T> {
+        self.push(val)
+    }
+}
+
+impl<T, const N: usize> Consume<T> for mpsc::Consumer<T, N> {
+    fn try_pop(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, const N: usize> BlockingProduce<T> for mpsc::Producer<T, N> {
+    fn push_timeout(&mut self, val: T, timeout: Duration) -> Result<(), Timeout<T>> {
+        mpsc::Producer::push_timeout(self, val, timeout)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, const N: usize> BlockingConsume<T> for mpsc::Consumer<T, N> {
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+        mpsc::Consumer::pop_timeout(self, timeout)
+    }
+}
+
+impl<T, const N: usize> Produce<T> for spmc::Producer<T, N> {
+    const CAN_EVICT: bool = true;
+
+    fn try_push(&mut self, val: T) -> Result<(), T> {
+        self.push(val)
+    }
+
+    fn evict(&mut self) -> Option<T> {
+        spmc::Producer::evict(self)
+    }
+}
+
+impl<T, const N: usize> Consume<T> for spmc::Consumer<T, N> {
+    fn try_pop(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, const N: usize> BlockingProduce<T> for spmc::Producer<T, N> {
+    fn push_timeout(&mut self, val: T, timeout: Duration) -> Result<(), Timeout<T>> {
+        spmc::Producer::push_timeout(self, val, timeout)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, const N: usize> BlockingConsume<T> for spmc::Consumer<T, N> {
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+        spmc::Consumer::pop_timeout(self, timeout)
+    }
+}
+
+/// Never refuses, the oldest pending item goes instead
+impl<T, const N: usize> Produce<T> for overwrite::Producer<T, N> {
+    fn try_push(&mut self, val: T) -> Result<(), T> {
+        self.push_overwrite(val);
+        Ok(())
+    }
+}
+
+impl<T, const N: usize> Consume<T> for overwrite::Consumer<T, N> {
+    fn try_pop(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, const N: usize> BlockingProduce<T> for overwrite::Producer<T, N> {}
+
+#[cfg(feature = "std")]
+impl<T, const N: usize> BlockingConsume<T> for overwrite::Consumer<T, N> {
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+        overwrite::Consumer::pop_timeout(self, timeout)
+    }
+}
+
+impl<Q: BoundedMpmc> Produce<Q::Item> for &Q {
+    const CAN_EVICT: bool = true;
+
+    fn try_push(&mut self, val: Q::Item) -> Result<(), Q::Item> {
+        Q::push(self, val)
+    }
+
+    fn evict(&mut self) -> Option<Q::Item> {
+        Q::pop(self)
+    }
+}
+
+impl<Q: BoundedMpmc> Consume<Q::Item> for &Q {
+    fn try_pop(&mut self) -> Option<Q::Item> {
+        Q::pop(self)
+    }
+}
+
+impl<Q: BoundedMpmc> Produce<Q::Item> for Arc<Q> {
+    const CAN_EVICT: bool = true;
+
+    fn try_push(&mut self, val: Q::Item) -> Result<(), Q::Item> {
+        Q::push(self, val)
+    }
+
+    fn evict(&mut self) -> Option<Q::Item> {
+        Q::pop(self)
+    }
+}
+
+impl<Q: BoundedMpmc> Consume<Q::Item> for Arc<Q> {
+    fn try_pop(&mut self) -> Option<Q::Item> {
+        Q::pop(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Q: BoundedMpmc> BlockingProduce<Q::Item> for &Q {}
+
+#[cfg(feature = "std")]
+impl<Q: BoundedMpmc> BlockingConsume<Q::Item> for &Q {}
+
+#[cfg(feature = "std")]
+impl<Q: BoundedMpmc> BlockingProduce<Q::Item> for Arc<Q> {}
+
+#[cfg(feature = "std")]
+impl<Q: BoundedMpmc> BlockingConsume<Q::Item> for Arc<Q> {}
+
+impl<T> Produce<T> for sharded::ProducerToken<T> {
+    fn try_push(&mut self, val: T) -> Result<(), T> {
+        self.push(val)
+    }
+}
+
+impl<T> Consume<T> for sharded::Consumer<T> {
+    fn try_pop(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> BlockingProduce<T> for sharded::ProducerToken<T> {}
+
+#[cfg(feature = "std")]
+impl<T> BlockingConsume<T> for sharded::Consumer<T> {}
+
+impl<T> Produce<T> for linked::Producer<T> {
+    fn try_push(&mut self, val: T) -> Result<(), T> {
+        self.push(val);
+        Ok(())
+    }
+}
+
+impl<T> Consume<T> for linked::Consumer<T> {
+    fn try_pop(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> BlockingProduce<T> for linked::Producer<T> {}
+
+#[cfg(feature = "std")]
+impl<T> BlockingConsume<T> for linked::Consumer<T> {
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+        linked::Consumer::pop_timeout(self, timeout)
+    }
+}
+
+/// Items carry their lane, `(priority, val)` as `push` takes them
+impl<T, const L: usize, const N: usize> Produce<(usize, T)> for priority::Producer<T, L, N> {
+    fn try_push(&mut self, (priority, val): (usize, T)) -> Result<(), (usize, T)> {
+        self.push(priority, val).map_err(|val| (priority, val))
+    }
+}
+
+impl<T, const L: usize, const N: usize> Consume<T> for priority::Consumer<T, L, N> {
+    fn try_pop(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, const L: usize, const N: usize> BlockingProduce<(usize, T)>
+    for priority::Producer<T, L, N>
+{
+}
+
+#[cfg(feature = "std")]
+impl<T, const L: usize, const N: usize> BlockingConsume<T> for priority::Consumer<T, L, N> {
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+        priority::Consumer::pop_timeout(self, timeout)
+    }
+}
+
+impl<T, const N: usize, A: small::Index> Produce<T> for small::Producer<'_, T, N, A> {
+    fn try_push(&mut self, val: T) -> Result<(), T> {
+        self.push(val)
+    }
+}
+
+impl<T, const N: usize, A: small::Index> Consume<T> for small::Consumer<'_, T, N, A> {
+    fn try_pop(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, const N: usize, A: small::Index> BlockingProduce<T> for small::Producer<'_, T, N, A> {}
+
+#[cfg(feature = "std")]
+impl<T, const N: usize, A: small::Index> BlockingConsume<T> for small::Consumer<'_, T, N, A> {}
+
+impl<T> Consume<T> for rendezvous::Receiver<T> {
+    fn try_pop(&mut self) -> Option<T> {
+        self.try_recv().ok()
+    }
+
+    fn is_disconnected(&self) -> bool {
+        rendezvous::Receiver::is_disconnected(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> BlockingConsume<T> for rendezvous::Receiver<T> {
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+        self.recv_timeout(timeout).ok()
+    }
+}
+
+/// Unbounded, never refuses
+impl<T, R: Reclaim> Produce<T> for &SegQueue<T, R> {
+    fn try_push(&mut self, val: T) -> Result<(), T> {
+        self.push(val);
+        Ok(())
+    }
+}
+
+impl<T, R: Reclaim> Consume<T> for &SegQueue<T, R> {
+    fn try_pop(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
+
+impl<T, R: Reclaim> Produce<T> for Arc<SegQueue<T, R>> {
+    fn try_push(&mut self, val: T) -> Result<(), T> {
+        self.push(val);
+        Ok(())
+    }
+}
+
+impl<T, R: Reclaim> Consume<T> for Arc<SegQueue<T, R>> {
+    fn try_pop(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, R: Reclaim> BlockingProduce<T> for &SegQueue<T, R> {}
+
+#[cfg(feature = "std")]
+impl<T, R: Reclaim> BlockingConsume<T> for &SegQueue<T, R> {}
+
+#[cfg(feature = "std")]
+impl<T, R: Reclaim> BlockingProduce<T> for Arc<SegQueue<T, R>> {}
+
+#[cfg(feature = "std")]
+impl<T, R: Reclaim> BlockingConsume<T> for Arc<SegQueue<T, R>> {}
+
+/// Unbounded, never refuses. Pops come out newest first
+#[cfg(feature = "std")]
+impl<T, R: Reclaim> Produce<T> for &EphemeralStack<T, R> {
+    fn try_push(&mut self, val: T) -> Result<(), T> {
+        self.push(val);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, R: Reclaim> Consume<T> for &EphemeralStack<T, R> {
+    fn try_pop(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, R: Reclaim> Produce<T> for Arc<EphemeralStack<T, R>> {
+    fn try_push(&mut self, val: T) -> Result<(), T> {
+        self.push(val);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, R: Reclaim> Consume<T> for Arc<EphemeralStack<T, R>> {
+    fn try_pop(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, R: Reclaim> BlockingProduce<T> for &EphemeralStack<T, R> {}
+
+#[cfg(feature = "std")]
+impl<T, R: Reclaim> BlockingConsume<T> for &EphemeralStack<T, R> {}
+
+#[cfg(feature = "std")]
+impl<T, R: Reclaim> BlockingProduce<T> for Arc<EphemeralStack<T, R>> {}
+
+#[cfg(feature = "std")]
+impl<T, R: Reclaim> BlockingConsume<T> for Arc<EphemeralStack<T, R>> {}
+
+/// Items carry their deadline, `(val, at)` as `push_at` takes them.
+/// Never refuses
+#[cfg(feature = "std")]
+impl<T> Produce<(T, Instant)> for &DelayQueue<T> {
+    fn try_push(&mut self, (val, at): (T, Instant)) -> Result<(), (T, Instant)> {
+        self.push_at(val, at);
+        Ok(())
+    }
+}
+
+/// Only items whose deadline passed
+#[cfg(feature = "std")]
+impl<T> Consume<T> for &DelayQueue<T> {
+    fn try_pop(&mut self) -> Option<T> {
+        self.pop_expired()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Produce<(T, Instant)> for Arc<DelayQueue<T>> {
+    fn try_push(&mut self, (val, at): (T, Instant)) -> Result<(), (T, Instant)> {
+        self.push_at(val, at);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Consume<T> for Arc<DelayQueue<T>> {
+    fn try_pop(&mut self) -> Option<T> {
+        self.pop_expired()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> BlockingProduce<(T, Instant)> for &DelayQueue<T> {}
+
+#[cfg(feature = "std")]
+impl<T> BlockingConsume<T> for &DelayQueue<T> {
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+        self.pop_next_timeout(timeout)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> BlockingProduce<(T, Instant)> for Arc<DelayQueue<T>> {}
+
+#[cfg(feature = "std")]
+impl<T> BlockingConsume<T> for Arc<DelayQueue<T>> {
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+        self.pop_next_timeout(timeout)
+    }
+}
+
+impl<R: spsc::Ring> Produce<R::Item> for multi::MultiProducer<R>
+where
+    R::Item: Clone,
+{
+    fn try_push(&mut self, val: R::Item) -> Result<(), R::Item> {
+        self.push(val).map_err(spsc::PushError::into_inner)
+    }
+
+    fn is_disconnected(&self) -> bool {
+        multi::MultiProducer::is_disconnected(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: spsc::Ring> BlockingProduce<R::Item> for multi::MultiProducer<R> where R::Item: Clone {}
+
+#[cfg(feature = "std")]
+impl<T: Clone, const N: usize> Produce<T> for broadcast::Producer<T, N> {
+    fn try_push(&mut self, val: T) -> Result<(), T> {
+        self.push(val)
+    }
+}
+
+/// A lapped subscriber skips ahead to the oldest item still queued
+#[cfg(feature = "std")]
+impl<T: Clone, const N: usize> Consume<T> for broadcast::Subscriber<T, N> {
+    fn try_pop(&mut self) -> Option<T> {
+        match self.pop() {
+            Ok(val) => Some(val),
+            Err(broadcast::PopError::Lagged(_)) => self.pop().ok(),
+            Err(broadcast::PopError::Empty) => None,
+        }
+    }
+
+    fn is_disconnected(&self) -> bool {
+        broadcast::Subscriber::is_disconnected(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone, const N: usize> BlockingProduce<T> for broadcast::Producer<T, N> {}
+
+#[cfg(feature = "std")]
+impl<T: Clone, const N: usize> BlockingConsume<T> for broadcast::Subscriber<T, N> {}
+
+#[cfg(feature = "std")]
+impl<Q: channel::Flavor> Produce<Q::Item> for channel::Sender<Q> {
+    fn try_push(&mut self, val: Q::Item) -> Result<(), Q::Item> {
+        self.try_send(val)
+            .map_err(channel::TrySendError::into_inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Q: channel::Flavor> Consume<Q::Item> for channel::Receiver<Q> {
+    fn try_pop(&mut self) -> Option<Q::Item> {
+        self.try_recv().ok()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Q: channel::Flavor> BlockingProduce<Q::Item> for channel::Sender<Q> {
+    fn push_timeout(&mut self, val: Q::Item, timeout: Duration) -> Result<(), Timeout<Q::Item>> {
+        self.send_timeout(val, timeout)
+            .map_err(|err| Timeout(err.into_inner()))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Q: channel::Flavor> BlockingConsume<Q::Item> for channel::Receiver<Q> {
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<Q::Item> {
+        self.recv_timeout(timeout).ok()
+    }
+}
+
+/// Unbounded, only refuses once the receiver is gone
+#[cfg(feature = "std")]
+impl<T> Produce<T> for std_mpsc::Sender<T> {
+    fn try_push(&mut self, val: T) -> Result<(), T> {
+        self.send(val).map_err(channel::SendError::into_inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Produce<T> for std_mpsc::SyncSender<T> {
+    fn try_push(&mut self, val: T) -> Result<(), T> {
+        self.try_send(val)
+            .map_err(channel::TrySendError::into_inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Consume<T> for std_mpsc::Receiver<T> {
+    fn try_pop(&mut self) -> Option<T> {
+        self.try_recv().ok()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> BlockingProduce<T> for std_mpsc::Sender<T> {}
+
+#[cfg(feature = "std")]
+impl<T> BlockingProduce<T> for std_mpsc::SyncSender<T> {}
+
+#[cfg(feature = "std")]
+impl<T> BlockingConsume<T> for std_mpsc::Receiver<T> {
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+        self.recv_timeout(timeout).ok()
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: spsc::Ring> Produce<R::Item> for AsyncProducer<R> {
+    fn try_push(&mut self, val: R::Item) -> Result<(), R::Item> {
+        AsyncProducer::try_push(self, val).map_err(spsc::PushError::into_inner)
+    }
+
+    fn is_disconnected(&self) -> bool {
+        AsyncProducer::is_disconnected(self)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: spsc::Ring> Consume<R::Item> for AsyncConsumer<R> {
+    fn try_pop(&mut self) -> Option<R::Item> {
+        AsyncConsumer::try_pop(self).ok()
+    }
+
+    fn is_disconnected(&self) -> bool {
+        AsyncConsumer::is_disconnected(self)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: spsc::Ring> AsyncProduce<R::Item> for AsyncProducer<R> {
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), spsc::Disconnected>> {
+        AsyncProducer::poll_ready(self, cx)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: spsc::Ring> AsyncConsume<R::Item> for AsyncConsumer<R> {
+    fn poll_pop(&mut self, cx: &mut Context<'_>) -> Poll<Option<R::Item>> {
+        AsyncConsumer::poll_pop(self, cx)
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use crate::ephemeral::{
+        linked::LinkedMPSC, mpmc::MPMCEphemeral, mpsc::MPSCEphemeral, overwrite::OverwriteBuffer,
+        priority::PriorityBuffer, scq::SCQEphemeral, small::SmallRing, spmc::SPMCEphemeral,
+        spsc::SPSCEphemeral,
+    };
+    use alloc::vec::Vec;
+
+    /// Written once, run against every queue below
+    fn fill_drain(mut tx: impl Produce<u32>, mut rx: impl Consume<u32>) -> Vec<u32> {
+        let mut pushed = 0;
+        while pushed < 8 && tx.try_push(pushed).is_ok() {
+            pushed += 1;
+        }
+        core::iter::from_fn(|| rx.try_pop()).collect()
+    }
+
+    #[test]
+    fn test_generic_handle() {
+        let (tx, rx) = SPSCEphemeral::<u32, 4>::new().split();
+        assert_eq!(fill_drain(tx, rx), [0, 1, 2, 3]);
+        let (tx, rx) = MPSCEphemeral::<u32, 4>::new().split();
+        assert_eq!(fill_drain(tx, rx), [0, 1, 2, 3]);
+        let (tx, rx) = SPMCEphemeral::<u32, 4>::new().split();
+        assert_eq!(fill_drain(tx, rx), [0, 1, 2, 3]);
+        let mpmc = MPMCEphemeral::<u32, 4>::new();
+        assert_eq!(fill_drain(&mpmc, &mpmc), [0, 1, 2, 3]);
+        let scq = Arc::new(SCQEphemeral::<u32, 4>::new());
+        assert_eq!(fill_drain(scq.clone(), scq), [0, 1, 2, 3]);
+        // never full, the oldest went instead
+        let (tx, rx) = OverwriteBuffer::<u32, 4>::new().split();
+        assert_eq!(fill_drain(tx, rx), [4, 5, 6, 7]);
+        let mut small = SmallRing::<u32, 4>::new();
+        let (tx, rx) = small.split();
+        assert_eq!(fill_drain(tx, rx), [0, 1, 2, 3]);
+        // unbounded, everything fits
+        let (tx, rx) = LinkedMPSC::new().split();
+        assert_eq!(fill_drain(tx, rx), [0, 1, 2, 3, 4, 5, 6, 7]);
+        let seg = SegQueue::new();
+        assert_eq!(fill_drain(&seg, &seg), [0, 1, 2, 3, 4, 5, 6, 7]);
+        #[cfg(feature = "std")]
+        {
+            let stack = Arc::new(EphemeralStack::new());
+            assert_eq!(fill_drain(stack.clone(), stack), [7, 6, 5, 4, 3, 2, 1, 0]);
+        }
+    }
+
+    #[test]
+    fn test_tagged_handle() {
+        let (mut tx, mut rx) = PriorityBuffer::<u32, 2, 2>::new().split();
+        for (lane, val) in [(1, 0), (0, 1), (1, 2), (1, 3)] {
+            let res = tx.try_push((lane, val));
+            assert_eq!(res.is_ok(), val < 3);
+        }
+        assert_eq!(
+            core::iter::from_fn(|| rx.try_pop()).collect::<Vec<_>>(),
+            [1, 0, 2]
+        );
+
+        #[cfg(feature = "std")]
+        {
+            let delayed = DelayQueue::new();
+            let now = Instant::now();
+            (&delayed)
+                .try_push((0, now + Duration::from_secs(60)))
+                .unwrap();
+            (&delayed).try_push((1, now)).unwrap();
+            assert_eq!((&delayed).try_pop(), Some(1));
+            assert_eq!((&delayed).try_pop(), None);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_blocking_handle() {
+        use std::thread;
+
+        fn relay<P, C>(mut tx: P, mut rx: C) -> Vec<u32>
+        where
+            P: BlockingProduce<u32> + Send + 'static,
+            C: BlockingConsume<u32>,
+        {
+            let timeout = Duration::from_secs(5);
+            let produce_t = thread::spawn(move || {
+                for i in 0..100 {
+                    tx.push_timeout(i, timeout).unwrap();
+                }
+            });
+            let seen = (0..100).map(|_| rx.pop_timeout(timeout).unwrap()).collect();
+            produce_t.join().unwrap();
+            assert_eq!(rx.pop_timeout(Duration::from_millis(5)), None);
+            seen
+        }
+
+        let expected: Vec<u32> = (0..100).collect();
+        let (tx, rx) = SPSCEphemeral::<u32, 4>::new().split();
+        assert_eq!(relay(tx, rx), expected);
+        let (tx, rx) = channel::bounded::<u32, 4>();
+        assert_eq!(relay(tx, rx), expected);
+        let (tx, rx) = std_mpsc::sync_channel(4);
+        assert_eq!(relay(tx, rx), expected);
+        let queue = Arc::new(MPMCEphemeral::<u32, 4>::new());
+        assert_eq!(relay(queue.clone(), queue), expected);
+        let (tx, rx) =
+            broadcast::BroadcastEphemeral::<u32, 4>::new(broadcast::Policy::Block).split();
+        assert_eq!(relay(tx, rx), expected);
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_async_handle() {
+        async fn relay(mut tx: impl AsyncProduce<u32>, mut rx: impl AsyncConsume<u32>) {
+            for i in 0..10 {
+                tx.push_async(i).await.unwrap();
+                assert_eq!(rx.pop_async().await, Some(i));
+            }
+            drop(tx);
+            assert_eq!(rx.pop_async().await, None);
+        }
+
+        let (tx, rx) = SPSCEphemeral::<u32, 2>::new().split();
+        let relay = relay(AsyncProducer::from(tx), AsyncConsumer::from(rx));
+        // nothing ever waits, a noop waker will do
+        let mut relay = core::pin::pin!(relay);
+        let mut cx = Context::from_waker(core::task::Waker::noop());
+        assert_eq!(relay.as_mut().poll(&mut cx), Poll::Ready(()));
+    }
+}
@@ -0,0 +1,346 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    fmt, ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering},
+};
+
+use super::reclaim::{sealed, Protect, Reclaim};
+
+/// retires between scans of the hazard pointers
+const SCAN_EVERY: usize = 64;
+
+/// One published hazard, claimed by a `HazardPointer` while it lives
+struct Record {
+    hazard: AtomicPtr<()>,
+    in_use: AtomicBool,
+    next: *mut Record, // set once before linking
+}
+
+/// Retired allocation waiting for no hazard to point at it
+struct Retired {
+    ptr: *mut (),
+    free: unsafe fn(*mut ()),
+    next: *mut Retired,
+}
+
+/// Hazard pointer domain: readers publish the node they're about to
+/// dereference, retired nodes are freed by a scan once no hazard
+/// points at them. Unlike `Epoch` a stalled reader only keeps its
+/// one node alive
+pub struct Hazards {
+    records: AtomicPtr<Record>, // every record, unlinked only on drop
+    retired: AtomicPtr<Retired>,
+    pending: AtomicUsize, // retires so far, paces `reclaim`
+}
+
+impl Hazards {
+    pub const fn new() -> Self {
+        Self {
+            records: AtomicPtr::new(ptr::null_mut()),
+            retired: AtomicPtr::new(ptr::null_mut()),
+            pending: AtomicUsize::new(0),
+        }
+    }
+
+    /// A free hazard slot, a fresh one linked in when all are busy
+    pub fn hazard_pointer(&self) -> HazardPointer<'_> {
+        let mut node = self.records.load(Ordering::Acquire);
+        while !node.is_null() {
+            let record = unsafe { &*node };
+            if !record.in_use.load(Ordering::Relaxed)
+                && record
+                    .in_use
+                    .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                return HazardPointer {
+                    domain: self,
+                    record,
+                };
+            }
+            node = record.next;
+        }
+
+        let record = Box::into_raw(Box::new(Record {
+            hazard: AtomicPtr::new(ptr::null_mut()),
+            in_use: AtomicBool::new(true),
+            next: ptr::null_mut(),
+        }));
+        let mut head = self.records.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*record).next = head };
+            match self.records.compare_exchange_weak(
+                head,
+                record,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(current) => head = current,
+            }
+        }
+        HazardPointer {
+            domain: self,
+            record: unsafe { &*record },
+        }
+    }
+
+    /// Frees `ptr` once no hazard pointer protects it, every so
+    /// often scanning for what became free
+    ///
+    /// # Safety
+    /// `ptr` comes from `Box::into_raw` and was unlinked with a SeqCst
+    /// operation, so `protect` can't hand it out anymore
+    pub unsafe fn retire<T>(&self, ptr: *mut T) {
+        unsafe fn free<T>(ptr: *mut ()) {
+            drop(unsafe { Box::from_raw(ptr.cast::<T>()) });
+        }
+
+        let retired = Box::into_raw(Box::new(Retired {
+            ptr: ptr.cast(),
+            free: free::<T>,
+            next: ptr::null_mut(),
+        }));
+        self.push_retired(retired, retired);
+
+        if self.pending.fetch_add(1, Ordering::Relaxed) % SCAN_EVERY == SCAN_EVERY - 1 {
+            self.reclaim();
+        }
+    }
+
+    /// Frees every retired node no hazard pointer protects
+    pub fn reclaim(&self) {
+        // whoever swaps the list out owns it, protected entries go back
+        let mut node = self.retired.swap(ptr::null_mut(), Ordering::SeqCst);
+        if node.is_null() {
+            return;
+        }
+
+        let mut hazards = Vec::new();
+        let mut record = self.records.load(Ordering::Acquire);
+        while !record.is_null() {
+            let hazard = unsafe { (*record).hazard.load(Ordering::SeqCst) };
+            if !hazard.is_null() {
+                hazards.push(hazard);
+            }
+            record = unsafe { (*record).next };
+        }
+        hazards.sort_unstable();
+
+        let (mut kept, mut last) = (ptr::null_mut::<Retired>(), ptr::null_mut());
+        while !node.is_null() {
+            let retired = unsafe { &mut *node };
+            let next = retired.next;
+            if hazards.binary_search(&retired.ptr).is_err() {
+                unsafe { (retired.free)(retired.ptr) };
+                drop(unsafe { Box::from_raw(node) });
+            } else {
+                retired.next = kept;
+                if kept.is_null() {
+                    last = node;
+                }
+                kept = node;
+            }
+            node = next;
+        }
+
+        if !kept.is_null() {
+            self.push_retired(kept, last);
+        }
+    }
+
+    /// Links the chain `first..=last` into the retired list
+    fn push_retired(&self, first: *mut Retired, last: *mut Retired) {
+        let mut retired = self.retired.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*last).next = retired };
+            match self.retired.compare_exchange_weak(
+                retired,
+                first,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return,
+                Err(current) => retired = current,
+            }
+        }
+    }
+}
+
+impl Default for Hazards {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Hazards {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hazards").finish_non_exhaustive()
+    }
+}
+
+impl Drop for Hazards {
+    fn drop(&mut self) {
+        // no hazard pointer outlives the borrow, everything is unreachable
+        let mut node = *self.retired.get_mut();
+        while !node.is_null() {
+            let retired = unsafe { Box::from_raw(node) };
+            unsafe { (retired.free)(retired.ptr) };
+            node = retired.next;
+        }
+
+        let mut node = *self.records.get_mut();
+        while !node.is_null() {
+            let record = unsafe { Box::from_raw(node) };
+            node = record.next;
+        }
+    }
+}
+
+unsafe impl Send for Hazards {}
+unsafe impl Sync for Hazards {}
+
+impl sealed::Sealed for Hazards {}
+
+impl Reclaim for Hazards {
+    type Guard<'a> = HazardPointer<'a>;
+
+    fn pin(&self) -> HazardPointer<'_> {
+        self.hazard_pointer()
+    }
+}
+
+/// A single published hazard of a `Hazards` domain, the last node
+/// `protect` returned isn't freed until the next `protect`, `reset`
+/// or drop
+pub struct HazardPointer<'a> {
+    domain: &'a Hazards,
+    record: &'a Record,
+}
+
+impl HazardPointer<'_> {
+    /// Loads `src` and protects the node it points at, republishing
+    /// until `src` still holds it, so it wasn't retired in between
+    pub fn protect<T>(&self, src: &AtomicPtr<T>) -> *mut T {
+        let mut ptr = src.load(Ordering::SeqCst);
+        loop {
+            self.record.hazard.store(ptr.cast(), Ordering::SeqCst);
+            let now = src.load(Ordering::SeqCst);
+            if now == ptr {
+                return ptr;
+            }
+            ptr = now;
+        }
+    }
+
+    /// Stops protecting anything
+    pub fn reset(&self) {
+        self.record.hazard.store(ptr::null_mut(), Ordering::Release);
+    }
+}
+
+impl fmt::Debug for HazardPointer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HazardPointer")
+            .field("hazard", &self.record.hazard.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl Drop for HazardPointer<'_> {
+    fn drop(&mut self) {
+        self.reset();
+        self.record.in_use.store(false, Ordering::Release);
+    }
+}
+
+impl Protect for HazardPointer<'_> {
+    fn protect<N>(&self, src: &AtomicPtr<N>) -> *mut N {
+        HazardPointer::protect(self, src)
+    }
+
+    unsafe fn retire<N>(&self, ptr: *mut N) {
+        unsafe { self.domain.retire(ptr) };
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use alloc::sync::Arc;
+
+    struct DropCount(Arc<AtomicUsize>);
+
+    impl Drop for DropCount {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_protect_hazard() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let domain = Hazards::new();
+        let shared = AtomicPtr::new(Box::into_raw(Box::new(DropCount(drops.clone()))));
+
+        let reader = domain.hazard_pointer();
+        let old = reader.protect(&shared);
+        let fresh = Box::into_raw(Box::new(DropCount(drops.clone())));
+        shared.store(fresh, Ordering::SeqCst);
+        unsafe { domain.retire(old) };
+
+        // only the protected node is held back
+        domain.reclaim();
+        assert_eq!(drops.load(Ordering::Relaxed), 0);
+        reader.reset();
+        domain.reclaim();
+        assert_eq!(drops.load(Ordering::Relaxed), 1);
+
+        drop(unsafe { Box::from_raw(shared.into_inner()) });
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stress_hazard() {
+        use std::thread;
+
+        const THREADS: usize = 4;
+        const ITEMS: usize = if cfg!(miri) { 200 } else { 20000 };
+        let domain = Hazards::new();
+        let shared = AtomicPtr::new(Box::into_raw(Box::new(0usize)));
+
+        // a node freed while protected is a use-after-free
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let (domain, shared) = (&domain, &shared);
+                s.spawn(move || {
+                    for i in 0..ITEMS {
+                        let hazard = domain.hazard_pointer();
+                        let current = hazard.protect(shared);
+                        assert!(unsafe { *current } < THREADS * ITEMS);
+
+                        if i % 2 == t % 2 {
+                            let fresh = Box::into_raw(Box::new(t * ITEMS + i));
+                            if shared
+                                .compare_exchange(
+                                    current,
+                                    fresh,
+                                    Ordering::SeqCst,
+                                    Ordering::Relaxed,
+                                )
+                                .is_ok()
+                            {
+                                unsafe { domain.retire(current) };
+                            } else {
+                                drop(unsafe { Box::from_raw(fresh) });
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        drop(unsafe { Box::from_raw(shared.into_inner()) });
+    }
+}
@@ -0,0 +1,404 @@
+use std::{
+    fs::OpenOptions,
+    io,
+    marker::PhantomData,
+    mem::{align_of, size_of},
+    path::Path,
+    ptr::NonNull,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use bytemuck::Pod;
+use memmap2::MmapMut;
+
+use crate::util::CachePadded;
+
+/// "brainstm" in ASCII, written last once the header is filled in
+const MAGIC: u64 = u64::from_be_bytes(*b"brainstm");
+/// bumped whenever `Header` or the slot layout changes
+const VERSION: u32 = 1;
+/// smallest page size of the supported targets
+const PAGE: usize = 4096;
+
+/// Leads the mapping, the slots follow at `ShmRing::OFFSET`
+#[repr(C)]
+struct Header {
+    magic: AtomicU64,
+    version: u32,
+    item_size: u32,
+    item_align: u32,
+    capacity: u32,
+    head: CachePadded<AtomicU64>, // read position
+    tail: CachePadded<AtomicU64>, // write position
+}
+
+/// SPSC ring living in a shared file mapping, so a producer process
+/// and a consumer process can hand items over through it. Positions
+/// run free like `SPSCEphemeral`'s, the header records the version
+/// and the item layout so `open` refuses a ring built for another `T`
+/// or `N`. Items are `Pod`, whatever bytes the other side left behind
+/// are a valid `T`
+///
+/// The file stands in for a memfd, which an unrelated process has no
+/// path to `open` by. On a memory-backed filesystem such as `/dev/shm`
+/// it's the same RAM-only mapping, and it should only ever be touched
+/// through `ShmRing`
+///
+/// A mapping pushes and pops through `&mut self`, `split` hands its
+/// two sides to different threads. Nothing in the file says which
+/// mapping is the producer, keeping to one producer and one consumer
+/// across every process is up to the callers
+pub struct ShmRing<T: Pod, const N: usize> {
+    base: NonNull<u8>,
+    _map: MmapMut,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod, const N: usize> ShmRing<T, N> {
+    /// where the slots start, past the header
+    const OFFSET: usize = size_of::<Header>().next_multiple_of(align_of::<T>());
+    const SIZE: usize = Self::OFFSET + N * size_of::<T>();
+
+    /// Creates the file at `path` and sets up an empty ring in it
+    ///
+    /// # Errors
+    /// `AlreadyExists` when there's a file at `path` already. Shrinking
+    /// it would pull the pages from under any process that still has
+    /// the old ring mapped, which then dies of SIGBUS on its next push
+    /// or pop. Remove a stale ring first, the file goes away once the
+    /// last mapping of it does
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        const { assert!(N.is_power_of_two(), "arena size must be a power of two") };
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+        // zero filled, so `head` and `tail` start out at 0
+        file.set_len(Self::SIZE as u64)?;
+
+        let ring = Self::map(unsafe { MmapMut::map_mut(&file)? });
+        let header = ring.base.cast::<Header>().as_ptr();
+        unsafe {
+            (*header).version = VERSION;
+            (*header).item_size = size_of::<T>() as u32;
+            (*header).item_align = align_of::<T>() as u32;
+            (*header).capacity = N as u32;
+        }
+        ring.header().magic.store(MAGIC, Ordering::Release);
+        Ok(ring)
+    }
+
+    /// Maps the ring another process created at `path`
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        if file.metadata()?.len() < Self::SIZE as u64 {
+            return Err(invalid("file is too short for the ring"));
+        }
+
+        let ring = Self::map(unsafe { MmapMut::map_mut(&file)? });
+        let header = ring.header();
+        if header.magic.load(Ordering::Acquire) != MAGIC {
+            return Err(invalid("not a ring, or not set up yet"));
+        }
+        if header.version != VERSION {
+            return Err(invalid("ring was created by another layout version"));
+        }
+        if header.item_size as usize != size_of::<T>()
+            || header.item_align as usize != align_of::<T>()
+            || header.capacity as usize != N
+        {
+            return Err(invalid("ring was created for another item type or size"));
+        }
+        Ok(ring)
+    }
+
+    fn map(mut map: MmapMut) -> Self {
+        // mappings only start on a page boundary, slots can't be aligned past it
+        const { assert!(align_of::<T>() <= PAGE, "item alignment exceeds a page") };
+        Self {
+            base: NonNull::new(map.as_mut_ptr()).expect("mapping is never null"),
+            _map: map,
+            _marker: PhantomData,
+        }
+    }
+
+    fn header(&self) -> &Header {
+        unsafe { self.base.cast::<Header>().as_ref() }
+    }
+
+    /// Slot behind a free-running position
+    fn slot(&self, pos: u64) -> *mut T {
+        let idx = pos as usize & (N - 1);
+        unsafe { self.base.as_ptr().add(Self::OFFSET).cast::<T>().add(idx) }
+    }
+
+    /// Moves the mapping behind a producer/consumer pair, so only one
+    /// thread of this process can ever write and one can read
+    pub fn split(self) -> (ShmProducer<T, N>, ShmConsumer<T, N>) {
+        let ring = Arc::new(self);
+        (ShmProducer { ring: ring.clone() }, ShmConsumer { ring })
+    }
+
+    /// Pushes while nothing else in this process holds the mapping,
+    /// `split` pushes from another thread
+    pub fn push(&mut self, val: T) -> Result<(), T> {
+        self.write(val)
+    }
+
+    /// Pops while nothing else in this process holds the mapping,
+    /// `split` pops from another thread
+    pub fn pop(&mut self) -> Option<T> {
+        self.read()
+    }
+
+    /// Caller is the only producer, across every process
+    fn write(&self, val: T) -> Result<(), T> {
+        let header = self.header();
+        let head = header.head.load(Ordering::Acquire);
+        let tail = header.tail.load(Ordering::Relaxed);
+
+        // guard: full
+        if tail.wrapping_sub(head) == N as u64 {
+            return Err(val);
+        }
+
+        unsafe { self.slot(tail).write(val) };
+        header.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    /// Caller is the only consumer, across every process
+    fn read(&self) -> Option<T> {
+        let header = self.header();
+        let head = header.head.load(Ordering::Relaxed);
+        let tail = header.tail.load(Ordering::Acquire);
+
+        // guard: empty
+        if head == tail {
+            return None;
+        }
+
+        let val = unsafe { self.slot(head).read() };
+        header.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(val)
+    }
+
+    /// Pending items, approximate while the other side is busy
+    pub fn len(&self) -> usize {
+        let header = self.header();
+        let head = header.head.load(Ordering::Acquire);
+        let tail = header.tail.load(Ordering::Acquire);
+        (tail.wrapping_sub(head) as usize).min(N)
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+unsafe impl<T: Pod + Send, const N: usize> Send for ShmRing<T, N> {}
+unsafe impl<T: Pod + Send, const N: usize> Sync for ShmRing<T, N> {}
+
+/// Write half of a split `ShmRing`
+pub struct ShmProducer<T: Pod, const N: usize> {
+    ring: Arc<ShmRing<T, N>>,
+}
+
+impl<T: Pod, const N: usize> ShmProducer<T, N> {
+    /// Hands `val` back when full
+    pub fn push(&mut self, val: T) -> Result<(), T> {
+        self.ring.write(val)
+    }
+
+    /// Pending items, approximate while the consumer is busy
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+}
+
+/// Read half of a split `ShmRing`
+pub struct ShmConsumer<T: Pod, const N: usize> {
+    ring: Arc<ShmRing<T, N>>,
+}
+
+impl<T: Pod, const N: usize> ShmConsumer<T, N> {
+    /// `None` when empty
+    pub fn pop(&mut self) -> Option<T> {
+        self.ring.read()
+    }
+
+    /// Pending items, approximate while the producer is busy
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{env, fs, path::PathBuf, thread};
+
+    /// Per-test file in the temp dir, removed on drop
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            let file = format!("brainstorm-{}-{name}", std::process::id());
+            Self(env::temp_dir().join(file))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_seq_ipc() {
+        let path = TempPath::new("seq");
+        let mut producer = ShmRing::<u64, 4>::create(&path.0).unwrap();
+        // a second mapping of the same file, as another process would see it
+        let mut consumer = ShmRing::<u64, 4>::open(&path.0).unwrap();
+
+        for lap in 0..10 {
+            for i in 0..4 {
+                assert!(producer.push(lap * 4 + i).is_ok());
+            }
+            assert_eq!(producer.push(0), Err(0));
+            assert!(consumer.is_full());
+
+            for i in 0..4 {
+                assert_eq!(consumer.pop(), Some(lap * 4 + i));
+            }
+            assert_eq!(consumer.pop(), None);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_header_ipc() {
+        let path = TempPath::new("header");
+        ShmRing::<u32, 8>::create(&path.0).unwrap();
+
+        fn kind<R>(res: io::Result<R>) -> Option<io::ErrorKind> {
+            res.err().map(|err| err.kind())
+        }
+        assert_eq!(kind(ShmRing::<u32, 8>::open(&path.0)), None);
+        assert_eq!(
+            kind(ShmRing::<u32, 16>::open(&path.0)),
+            Some(io::ErrorKind::InvalidData)
+        );
+        assert_eq!(
+            kind(ShmRing::<u16, 8>::open(&path.0)),
+            Some(io::ErrorKind::InvalidData)
+        );
+
+        fs::write(&path.0, vec![0; 4096]).unwrap();
+        assert_eq!(
+            kind(ShmRing::<u32, 8>::open(&path.0)),
+            Some(io::ErrorKind::InvalidData)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_create_existing_ipc() {
+        let path = TempPath::new("existing");
+        let mut ring = ShmRing::<u32, 4>::create(&path.0).unwrap();
+        ring.push(1).unwrap();
+
+        // left alone, a mapping of it may still be in use
+        assert_eq!(
+            ShmRing::<u32, 4>::create(&path.0)
+                .err()
+                .map(|err| err.kind()),
+            Some(io::ErrorKind::AlreadyExists)
+        );
+        assert_eq!(ring.pop(), Some(1));
+
+        // unlinked, the old mapping lives on next to the new ring
+        fs::remove_file(&path.0).unwrap();
+        let mut fresh = ShmRing::<u32, 4>::create(&path.0).unwrap();
+        ring.push(2).unwrap();
+        assert_eq!((fresh.pop(), ring.pop()), (None, Some(2)));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_threaded_ipc() {
+        const ITEMS: u64 = 10000;
+        let path = TempPath::new("threaded");
+        let mut producer = ShmRing::<u64, 16>::create(&path.0).unwrap();
+        let mut consumer = ShmRing::<u64, 16>::open(&path.0).unwrap();
+
+        let produce_t = thread::spawn(move || {
+            for i in 0..ITEMS {
+                while producer.push(i).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        for i in 0..ITEMS {
+            loop {
+                match consumer.pop() {
+                    Some(val) => break assert_eq!(val, i),
+                    None => thread::yield_now(),
+                }
+            }
+        }
+        produce_t.join().unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_split_ipc() {
+        const ITEMS: u64 = 10000;
+        let path = TempPath::new("split");
+        let (mut producer, mut consumer) = ShmRing::<u64, 16>::create(&path.0).unwrap().split();
+
+        let produce_t = thread::spawn(move || {
+            for i in 0..ITEMS {
+                while producer.push(i).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        for i in 0..ITEMS {
+            loop {
+                match consumer.pop() {
+                    Some(val) => break assert_eq!(val, i),
+                    None => thread::yield_now(),
+                }
+            }
+        }
+        produce_t.join().unwrap();
+        assert!(consumer.is_empty());
+    }
+}
@@ -0,0 +1,132 @@
+use super::spsc::{pop, push, SPSCEphemeral};
+#[cfg(feature = "stats")]
+use super::stats::Stats;
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+
+/// SPSC ring for handing items from an interrupt handler to the main
+/// loop, `new` is const so it can sit in a `static` without lazy init
+///
+/// Pushes must all come from the same interrupt priority and pops
+/// from the code it preempts, so neither side ever runs twice at once.
+/// Nothing checks that from a shared `static`, hence the `unsafe` on
+/// both sides. Both are wait-free, a push only touches its slot and two
+/// atomics and never waits on the main loop
+pub struct IsrQueue<T, const N: usize> {
+    ring: SPSCEphemeral<T, N>,
+}
+
+impl<T, const N: usize> IsrQueue<T, N> {
+    const_fn! {
+        pub fn new() -> Self {
+            let ring = SPSCEphemeral::new();
+            #[cfg(feature = "tracing")]
+            let ring = ring.with_name("isr");
+            Self { ring }
+        }
+    }
+
+    const_fn! {
+        /// Names the queue in its `tracing` events, `isr` otherwise
+        #[cfg(feature = "tracing")]
+        pub fn with_name(mut self, name: &'static str) -> Self {
+            self.ring.state.trace = Label::new(name);
+            self
+        }
+    }
+
+    /// Never spins, a full queue hands `val` straight back
+    ///
+    /// # Safety
+    /// No other `push_from_isr` on this queue may run at the same time,
+    /// e.g. every caller is a handler of one interrupt priority, or a
+    /// single thread
+    pub unsafe fn push_from_isr(&self, val: T) -> Result<(), T> {
+        push(&self.ring, val)
+    }
+
+    /// Main loop side, `None` when nothing arrived yet
+    ///
+    /// # Safety
+    /// No other `pop` on this queue may run at the same time, e.g. only
+    /// the code the handler preempts pops, or a single thread
+    pub unsafe fn pop(&self) -> Option<T> {
+        pop(&self.ring)
+    }
+
+    /// Pending items, approximate while the handler may fire
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.ring.stats()
+    }
+}
+
+impl<T, const N: usize> Default for IsrQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use std::{iter, thread};
+
+    #[test]
+    fn test_static_isr() {
+        static QUEUE: IsrQueue<u32, 4> = IsrQueue::new();
+
+        // this thread is the only pusher and popper
+        unsafe {
+            for i in 0..4 {
+                assert!(QUEUE.push_from_isr(i).is_ok());
+            }
+            // full, handed back rather than waited on
+            assert_eq!(QUEUE.push_from_isr(4), Err(4));
+            assert!(QUEUE.is_full());
+
+            for i in 0..4 {
+                assert_eq!(QUEUE.pop(), Some(i));
+            }
+            assert_eq!(QUEUE.pop(), None);
+        }
+    }
+
+    #[test]
+    fn test_handoff_isr() {
+        const ITEMS: u32 = if cfg!(miri) { 200 } else { 10000 };
+        static QUEUE: IsrQueue<u32, 8> = IsrQueue::new();
+
+        // a thread standing in for the handler, dropping what doesn't
+        // fit, the only one pushing while this one is the only popper
+        let isr_t = thread::spawn(|| {
+            (0..ITEMS)
+                .filter(|&i| unsafe { QUEUE.push_from_isr(i) }.is_ok())
+                .count()
+        });
+
+        let mut popped = Vec::new();
+        while !isr_t.is_finished() {
+            match unsafe { QUEUE.pop() } {
+                Some(val) => popped.push(val),
+                None => thread::yield_now(),
+            }
+        }
+        let pushed = isr_t.join().unwrap();
+        popped.extend(iter::from_fn(|| unsafe { QUEUE.pop() }));
+
+        // whatever made it in comes out in order
+        assert_eq!(popped.len(), pushed);
+        assert!(popped.windows(2).all(|w| w[0] < w[1]));
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// Sub-buckets per power of two, each bucket spans 1/16 of its
+/// power, so a reported delay is within ~6% of the real one
+const SUB_BITS: u32 = 4;
+const SUB: usize = 1 << SUB_BITS;
+/// Exact buckets for `0..SUB` ns, then `SUB` per power up to 2^64
+const BUCKETS: usize = (64 - SUB_BITS as usize + 1) * SUB;
+
+/// Queueing delay of the items popped so far, push to pop, as of the
+/// `latency_stats()` call. Percentiles are bucket upper bounds of a
+/// log-linear histogram, `Duration::ZERO` before the first pop
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    /// items timed
+    pub count: u64,
+    pub p50: Duration,
+    pub p99: Duration,
+    pub p999: Duration,
+    /// longest an item waited, exact
+    pub max: Duration,
+}
+
+/// Push stamps of a ring's slots and the histogram of the delays
+/// popping finds, allocated on first use so `RingState::new` stays const
+pub(crate) struct Latency {
+    recorder: OnceLock<Recorder>,
+}
+
+struct Recorder {
+    stamps: Box<[AtomicU64]>, // push time of the item in each slot
+    buckets: Box<[AtomicU64]>,
+    max: AtomicU64,
+}
+
+impl Latency {
+    pub(crate) const fn new() -> Self {
+        Self {
+            recorder: OnceLock::new(),
+        }
+    }
+
+    /// Notes the push time of `pos`, before it's published
+    pub(crate) fn pushed(&self, size: usize, pos: u64) {
+        let stamp = now();
+        self.recorder(size).stamps[slot(size, pos)].store(stamp, Ordering::Relaxed);
+    }
+
+    /// Counts how long `pos` waited, once the consumer took it
+    pub(crate) fn popped(&self, size: usize, pos: u64) {
+        let recorder = self.recorder(size);
+        let pushed = recorder.stamps[slot(size, pos)].load(Ordering::Relaxed);
+        let delay = now().saturating_sub(pushed);
+        recorder.buckets[bucket(delay)].fetch_add(1, Ordering::Relaxed);
+        recorder.max.fetch_max(delay, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> LatencyStats {
+        let Some(recorder) = self.recorder.get() else {
+            return LatencyStats::default();
+        };
+        let counts: Vec<u64> = recorder
+            .buckets
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .collect();
+        let count = counts.iter().sum();
+        LatencyStats {
+            count,
+            p50: percentile(&counts, count, 0.5),
+            p99: percentile(&counts, count, 0.99),
+            p999: percentile(&counts, count, 0.999),
+            max: Duration::from_nanos(recorder.max.load(Ordering::Relaxed)),
+        }
+    }
+
+    fn recorder(&self, size: usize) -> &Recorder {
+        self.recorder.get_or_init(|| Recorder {
+            stamps: (0..size).map(|_| AtomicU64::new(0)).collect(),
+            buckets: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+            max: AtomicU64::new(0),
+        })
+    }
+}
+
+impl Default for Latency {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Nanoseconds since the first stamp anywhere, one clock for every ring
+fn now() -> u64 {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+fn slot(size: usize, pos: u64) -> usize {
+    pos as usize & (size - 1)
+}
+
+/// Exact below `SUB`, otherwise the power of two plus the next
+/// `SUB_BITS` bits under the top one
+fn bucket(nanos: u64) -> usize {
+    if nanos < SUB as u64 {
+        return nanos as usize;
+    }
+    let power = 63 - nanos.leading_zeros();
+    let sub = (nanos >> (power - SUB_BITS)) as usize & (SUB - 1);
+    (power - SUB_BITS + 1) as usize * SUB + sub
+}
+
+/// Largest delay that lands in `idx`
+fn upper_bound(idx: usize) -> u64 {
+    if idx < SUB {
+        return idx as u64;
+    }
+    let power = (idx / SUB) as u32 + SUB_BITS - 1;
+    let low = ((SUB + idx % SUB) as u64) << (power - SUB_BITS);
+    low + ((1u64 << (power - SUB_BITS)) - 1)
+}
+
+/// Smallest bucket bound at least `q` of the `total` items fit under
+fn percentile(counts: &[u64], total: u64, q: f64) -> Duration {
+    let rank = ((total as f64 * q).ceil() as u64).max(1);
+    let mut seen = 0;
+    for (idx, &count) in counts.iter().enumerate() {
+        seen += count;
+        if seen >= rank {
+            return Duration::from_nanos(upper_bound(idx));
+        }
+    }
+    Duration::ZERO
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use crate::ephemeral::spsc::SPSCEphemeral;
+    use std::thread;
+
+    #[test]
+    fn test_buckets_latency() {
+        for nanos in [0, 1, 15, 16, 17, 31, 32, 1000, 123_456_789, u64::MAX] {
+            let idx = bucket(nanos);
+            assert!(idx < BUCKETS);
+            assert!(upper_bound(idx) >= nanos, "{nanos} above its bucket");
+            // within a sixteenth of the delay
+            assert!(upper_bound(idx) - nanos <= nanos / SUB as u64);
+            if idx > 0 {
+                assert!(upper_bound(idx - 1) < nanos, "{nanos} fits a lower bucket");
+            }
+        }
+    }
+
+    #[test]
+    fn test_percentiles_latency() {
+        let mut counts = vec![0; BUCKETS];
+        counts[bucket(100)] = 990;
+        counts[bucket(10_000)] = 9;
+        counts[bucket(1_000_000)] = 1;
+
+        let at = |q| percentile(&counts, 1000, q).as_nanos() as u64;
+        assert_eq!(at(0.5), upper_bound(bucket(100)));
+        assert_eq!(at(0.99), upper_bound(bucket(100)));
+        assert_eq!(at(0.999), upper_bound(bucket(10_000)));
+        assert_eq!(at(1.0), upper_bound(bucket(1_000_000)));
+    }
+
+    #[test]
+    fn test_ring_latency() {
+        let (mut producer, mut consumer) = SPSCEphemeral::<u32, 4>::new().split();
+        assert_eq!(consumer.latency_stats(), LatencyStats::default());
+
+        producer.push(1).unwrap();
+        thread::sleep(Duration::from_millis(20));
+        producer.push_slice(&[2, 3]);
+        assert_eq!(consumer.drain().count(), 3);
+
+        let stats = consumer.latency_stats();
+        assert_eq!(stats.count, 3);
+        // two of three went straight through, the first one waited
+        assert!(stats.p50 < Duration::from_millis(20));
+        assert!(stats.p99 >= Duration::from_millis(20));
+        assert!(stats.max >= Duration::from_millis(20) && stats.max <= stats.p99);
+    }
+}
@@ -0,0 +1,304 @@
+use alloc::{boxed::Box, sync::Arc};
+#[cfg(feature = "std")]
+use core::time::Duration;
+use core::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use crate::util::CachePadded;
+
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+#[cfg(feature = "std")]
+use super::wait::retry_until;
+
+struct Node<T> {
+    next: AtomicPtr<Node<T>>,
+    val: MaybeUninit<T>,
+}
+
+impl<T> Node<T> {
+    fn alloc() -> *mut Self {
+        Box::into_raw(Box::new(Self {
+            next: AtomicPtr::new(ptr::null_mut()),
+            val: MaybeUninit::uninit(),
+        }))
+    }
+}
+
+/// Unbounded multi-producer/single-consumer queue of linked nodes.
+/// Producers swap themselves in as the newest node and link the
+/// previous one to it, so a push never retries. The consumer keeps
+/// the oldest node as a stub and hands popped nodes back for reuse
+pub struct LinkedMPSC<T> {
+    head: CachePadded<AtomicPtr<Node<T>>>, // newest node, producers swap here
+    tail: CachePadded<UnsafeCell<*mut Node<T>>>, // stub, consumer only
+    free: CachePadded<AtomicPtr<Node<T>>>, // popped nodes up for reuse
+    #[cfg(feature = "tracing")]
+    trace: Label,
+}
+
+impl<T> LinkedMPSC<T> {
+    pub fn new() -> Self {
+        let stub = Node::alloc();
+        Self {
+            head: CachePadded::new(AtomicPtr::new(stub)),
+            tail: CachePadded::new(UnsafeCell::new(stub)),
+            free: CachePadded::new(AtomicPtr::new(ptr::null_mut())),
+            #[cfg(feature = "tracing")]
+            trace: Label::new("linked"),
+        }
+    }
+
+    /// Names the queue in its `tracing` events, `linked` otherwise
+    #[cfg(feature = "tracing")]
+    pub fn with_name(mut self, name: &'static str) -> Self {
+        self.trace = Label::new(name);
+        self
+    }
+
+    /// Moves the queue behind a clonable producer and the one consumer
+    pub fn split(self) -> (Producer<T>, Consumer<T>) {
+        let queue = Arc::new(self);
+        let producer = Producer {
+            queue: queue.clone(),
+            cache: ptr::null_mut(),
+        };
+        (producer, Consumer { queue })
+    }
+}
+
+impl<T> Default for LinkedMPSC<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for LinkedMPSC<T> {
+    fn drop(&mut self) {
+        let stub = *self.tail.get_mut();
+        unsafe {
+            // everything after the stub still holds a value
+            let mut node = *(*stub).next.get_mut();
+            drop(Box::from_raw(stub));
+            while !node.is_null() {
+                let mut owned = Box::from_raw(node);
+                owned.val.assume_init_drop();
+                node = *owned.next.get_mut();
+            }
+            free_chain(*self.free.get_mut());
+        }
+    }
+}
+
+unsafe impl<T: Send> Send for LinkedMPSC<T> {}
+unsafe impl<T: Send> Sync for LinkedMPSC<T> {}
+
+/// Frees nodes linked through `next`, stopping at the first null
+unsafe fn free_chain<T>(mut node: *mut Node<T>) {
+    while !node.is_null() {
+        let next = *(*node).next.get_mut();
+        drop(Box::from_raw(node));
+        node = next;
+    }
+}
+
+/// Write half of a split `LinkedMPSC`, clone it per producer thread
+pub struct Producer<T> {
+    queue: Arc<LinkedMPSC<T>>,
+    cache: *mut Node<T>, // recycled nodes this producer took over
+}
+
+impl<T> Producer<T> {
+    /// Never fails and never retries, allocates only when
+    /// no popped node is around to reuse
+    pub fn push(&mut self, val: T) {
+        let node = self.node();
+        unsafe {
+            (*node).val.write(val);
+            (*node).next.store(ptr::null_mut(), Ordering::Relaxed);
+        }
+
+        let prev = self.queue.head.swap(node, Ordering::AcqRel);
+        // until this lands the consumer sees the queue end at `prev`
+        unsafe { (*prev).next.store(node, Ordering::Release) };
+        #[cfg(feature = "tracing")]
+        self.queue.trace.pushed_unbounded(1);
+    }
+
+    /// Next recycled node, taking over the whole free list when the
+    /// cache ran dry. Swapping the list out, rather than popping it
+    /// node by node, leaves no ABA window between producers
+    fn node(&mut self) -> *mut Node<T> {
+        if self.cache.is_null() {
+            self.cache = self.queue.free.swap(ptr::null_mut(), Ordering::Acquire);
+        }
+        if self.cache.is_null() {
+            return Node::alloc();
+        }
+
+        let node = self.cache;
+        self.cache = unsafe { (*node).next.load(Ordering::Relaxed) };
+        node
+    }
+}
+
+impl<T> Clone for Producer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            queue: self.queue.clone(),
+            cache: ptr::null_mut(),
+        }
+    }
+}
+
+impl<T> Drop for Producer<T> {
+    fn drop(&mut self) {
+        unsafe { free_chain(self.cache) };
+    }
+}
+
+unsafe impl<T: Send> Send for Producer<T> {}
+
+/// Read half of a split `LinkedMPSC`
+pub struct Consumer<T> {
+    queue: Arc<LinkedMPSC<T>>,
+}
+
+impl<T> Consumer<T> {
+    /// `None` when empty, or for the instant a push is halfway linked
+    pub fn pop(&mut self) -> Option<T> {
+        let q = &*self.queue;
+        let stub = unsafe { *q.tail.get() };
+        let next = unsafe { (*stub).next.load(Ordering::Acquire) };
+
+        // guard: empty
+        if next.is_null() {
+            #[cfg(feature = "tracing")]
+            q.trace.empty();
+            return None;
+        }
+
+        // `next` becomes the stub, its value moves out
+        let val = unsafe { (*next).val.assume_init_read() };
+        unsafe { *q.tail.get() = next };
+        self.recycle(stub);
+        #[cfg(feature = "tracing")]
+        q.trace.popped(1);
+        Some(val)
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up with `None` once `timeout` passed
+    pub fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+        #[cfg(feature = "tracing")]
+        let _span = self.queue.trace.blocking("pop_timeout");
+        retry_until(timeout, || self.pop())
+    }
+
+    fn recycle(&self, node: *mut Node<T>) {
+        let free = &self.queue.free;
+        let mut head = free.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*node).next.store(head, Ordering::Relaxed) };
+            match free.compare_exchange_weak(head, node, Ordering::Release, Ordering::Relaxed) {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    #[cfg(feature = "std")]
+    use std::thread;
+
+    #[test]
+    fn test_seq_linked() {
+        let (mut producer, mut consumer) = LinkedMPSC::new().split();
+
+        assert_eq!(consumer.pop(), None);
+        for lap in 0..10 {
+            for i in 0..100 {
+                producer.push(lap * 100 + i);
+            }
+            for i in 0..100 {
+                assert_eq!(consumer.pop(), Some(lap * 100 + i));
+            }
+            assert_eq!(consumer.pop(), None);
+        }
+    }
+
+    #[test]
+    fn test_recycle_linked() {
+        let (mut producer, mut consumer) = LinkedMPSC::new().split();
+
+        producer.push(1);
+        assert_eq!(consumer.pop(), Some(1));
+        // the old stub went to the free list, the next push takes it
+        let recycled = consumer.queue.free.load(Ordering::Relaxed);
+        producer.push(2);
+        assert_eq!(producer.queue.head.load(Ordering::Relaxed), recycled);
+        assert_eq!(consumer.pop(), Some(2));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_threaded_linked() {
+        const PRODUCERS: usize = 4;
+        const ITEMS: usize = if cfg!(miri) { 100 } else { 5000 };
+        let (producer, mut consumer) = LinkedMPSC::new().split();
+
+        let produce_ts: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let mut producer = producer.clone();
+                thread::spawn(move || {
+                    for i in 0..ITEMS {
+                        producer.push((p, i));
+                    }
+                })
+            })
+            .collect();
+        drop(producer);
+
+        // items of each producer come out in push order
+        let mut next = [0; PRODUCERS];
+        for _ in 0..PRODUCERS * ITEMS {
+            let (p, i) = consumer.pop_timeout(Duration::from_secs(5)).unwrap();
+            assert_eq!(i, next[p]);
+            next[p] += 1;
+        }
+        for produce_t in produce_ts {
+            produce_t.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_drop_linked() {
+        struct DropCount(Arc<AtomicUsize>);
+
+        impl Drop for DropCount {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let drops = Arc::new(AtomicUsize::new(0));
+        let (mut producer, mut consumer) = LinkedMPSC::new().split();
+
+        for _ in 0..5 {
+            producer.push(DropCount(drops.clone()));
+        }
+        drop(consumer.pop());
+        drop(consumer.pop());
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+        drop((producer, consumer));
+        assert_eq!(drops.load(Ordering::Relaxed), 5);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::util::CachePadded;
+
+use super::slot::EphemeralSlot;
+
+/// K independent one-value slots addressed by index, so a coordinator
+/// can hand work to one specific worker without a queue per worker.
+/// Each mailbox runs its own `EphemeralSlot` state machine and sits
+/// on its own cache line, workers polling theirs don't contend
+///
+/// Indices past K panic like slice indexing does
+pub struct Mailboxes<T, const K: usize> {
+    boxes: [CachePadded<EphemeralSlot<T>>; K],
+}
+
+impl<T, const K: usize> Mailboxes<T, K> {
+    #[cfg(not(loom))]
+    pub const fn new() -> Self {
+        Self {
+            boxes: [const { CachePadded::new(EphemeralSlot::new()) }; K],
+        }
+    }
+
+    #[cfg(loom)]
+    pub fn new() -> Self {
+        Self {
+            boxes: core::array::from_fn(|_| CachePadded::new(EphemeralSlot::new())),
+        }
+    }
+
+    /// Spins until mailbox `idx` is empty, then fills it
+    pub fn set(&self, idx: usize, value: T) {
+        self.boxes[idx].set(value);
+    }
+
+    /// Hands `value` back while mailbox `idx` is still full
+    pub fn try_set(&self, idx: usize, value: T) -> Result<(), T> {
+        self.boxes[idx].try_set(value)
+    }
+
+    /// Empties mailbox `idx`, `None` if nothing was there
+    pub fn get(&self, idx: usize) -> Option<T> {
+        self.boxes[idx].get()
+    }
+
+    /// Mailbox `idx` on its own, e.g. for a worker to hold on to
+    pub fn mailbox(&self, idx: usize) -> &EphemeralSlot<T> {
+        &self.boxes[idx]
+    }
+
+    pub const fn len(&self) -> usize {
+        K
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        K == 0
+    }
+}
+
+impl<T, const K: usize> Default for Mailboxes<T, K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_try_set_mailbox() {
+        let boxes = Mailboxes::<u32, 2>::new();
+
+        boxes.set(0, 1);
+        assert_eq!(boxes.try_set(0, 2), Err(2));
+        assert_eq!(boxes.try_set(1, 3), Ok(()));
+
+        assert_eq!(boxes.get(1), Some(3));
+        assert_eq!(boxes.mailbox(0).get(), Some(1));
+        assert_eq!(boxes.get(0), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_workers_mailbox() {
+        use std::thread;
+
+        const WORKERS: usize = 4;
+        const ITEMS: usize = if cfg!(miri) { 50 } else { 1000 };
+        let boxes = Mailboxes::<usize, WORKERS>::new();
+
+        // the coordinator deals items round robin, each worker
+        // only ever sees the ones addressed to it, in order
+        thread::scope(|s| {
+            for w in 0..WORKERS {
+                let mailbox = boxes.mailbox(w);
+                s.spawn(move || {
+                    for i in 0..ITEMS {
+                        let item = loop {
+                            match mailbox.get() {
+                                Some(item) => break item,
+                                None => thread::yield_now(),
+                            }
+                        };
+                        assert_eq!(item, i * WORKERS + w);
+                    }
+                });
+            }
+
+            for item in 0..ITEMS * WORKERS {
+                boxes.set(item % WORKERS, item);
+            }
+        });
+    }
+}
@@ -0,0 +1,222 @@
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "std")]
+use super::handle::BlockingConsume;
+use super::handle::Consume;
+
+/// Consumer over several sources each popping in key order, handing
+/// items out in key order across all of them, e.g. timestamped events
+/// spread over shards
+///
+/// Holds at most one item per source. An item only goes out once every
+/// source that may still push has one waiting, since an empty source's
+/// next item could sort first. A source counts as finished once it's
+/// `Consume::is_disconnected` and drained, handles that can't tell
+/// hold the merge up for good, `pop_ready` goes ahead without them.
+/// Equal keys go out in source order
+pub struct OrderedMerge<T, K, C, F> {
+    sources: Vec<Source<T, K, C>>,
+    key: F,
+}
+
+struct Source<T, K, C> {
+    inner: C,
+    head: Option<(K, T)>, // popped already, waiting for its turn
+    done: bool,
+}
+
+impl<T, K: Ord, C: Consume<T>, F: FnMut(&T) -> K> OrderedMerge<T, K, C, F> {
+    pub fn new<I: IntoIterator<Item = C>>(sources: I, key: F) -> Self {
+        let sources = sources
+            .into_iter()
+            .map(|inner| Source {
+                inner,
+                head: None,
+                done: false,
+            })
+            .collect();
+        Self { sources, key }
+    }
+
+    /// Smallest key of the items sources had waiting, passing over the
+    /// empty ones. For draining at shutdown or past a stalled source,
+    /// the order only holds among the sources that had an item
+    pub fn pop_ready(&mut self) -> Option<T> {
+        self.fill();
+        self.take_min()
+    }
+
+    /// Every source finished and nothing left held back
+    pub fn is_done(&self) -> bool {
+        self.sources
+            .iter()
+            .all(|source| source.done && source.head.is_none())
+    }
+
+    /// Items popped from the sources but not handed out yet
+    pub fn pending(&self) -> usize {
+        self.sources
+            .iter()
+            .filter(|source| source.head.is_some())
+            .count()
+    }
+
+    /// Pops a head for every source without one, true if none of
+    /// them is left that could still sort first
+    fn fill(&mut self) -> bool {
+        let mut ready = true;
+        for source in self.sources.iter_mut() {
+            if source.head.is_some() || source.done {
+                continue;
+            }
+            // checked first, a push racing the close is popped below
+            let closed = source.inner.is_disconnected();
+            match source.inner.try_pop() {
+                Some(val) => source.head = Some(((self.key)(&val), val)),
+                None if closed => source.done = true,
+                None => ready = false,
+            }
+        }
+        ready
+    }
+
+    fn take_min(&mut self) -> Option<T> {
+        // the first of equal keys wins
+        let (idx, _) = self
+            .sources
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, source)| Some((idx, &source.head.as_ref()?.0)))
+            .min_by(|(_, a), (_, b)| a.cmp(b))?;
+        let (_, val) = self.sources[idx].head.take()?;
+        Some(val)
+    }
+}
+
+impl<T, K: Ord, C: Consume<T>, F: FnMut(&T) -> K> Consume<T> for OrderedMerge<T, K, C, F> {
+    /// `None` while a source that may still push has nothing queued
+    fn try_pop(&mut self) -> Option<T> {
+        if !self.fill() {
+            return None;
+        }
+        self.take_min()
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.sources.iter().all(|source| source.done)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, K: Ord, C: BlockingConsume<T>, F: FnMut(&T) -> K> BlockingConsume<T>
+    for OrderedMerge<T, K, C, F>
+{
+    /// Waits on the sources holding the merge up one at a time,
+    /// `timeout` covers all of them
+    fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.fill() {
+                return self.take_min();
+            }
+            let left = deadline.saturating_duration_since(Instant::now());
+            // guard: out of time with a source still empty
+            if left.is_zero() {
+                return None;
+            }
+            let source = self
+                .sources
+                .iter_mut()
+                .find(|source| source.head.is_none() && !source.done)?;
+            if let Some(val) = source.inner.pop_timeout(left) {
+                source.head = Some(((self.key)(&val), val));
+            }
+        }
+    }
+}
+
+impl<T, K, C, F> fmt::Debug for OrderedMerge<T, K, C, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OrderedMerge")
+            .field("sources", &self.sources.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use crate::ephemeral::spsc::SPSCEphemeral;
+
+    #[test]
+    fn test_key_order_merge() {
+        let (mut txs, rxs): (Vec<_>, Vec<_>) = (0..3)
+            .map(|_| SPSCEphemeral::<(u64, char), 4>::new().split())
+            .unzip();
+        let mut merge = OrderedMerge::new(rxs, |&(ts, _)| ts);
+
+        txs[0].push_slice(&[(1, 'a'), (5, 'e')]);
+        txs[1].push_slice(&[(2, 'b'), (3, 'c')]);
+        // the third source could still push something earlier
+        assert_eq!(merge.try_pop(), None);
+        assert_eq!(merge.pending(), 2);
+
+        txs[2].push_slice(&[(4, 'd'), (6, 'f')]);
+        assert_eq!(merge.try_pop(), Some((1, 'a')));
+        assert_eq!(merge.try_pop(), Some((2, 'b')));
+        assert_eq!(merge.try_pop(), Some((3, 'c')));
+        // the second source ran dry
+        assert_eq!(merge.try_pop(), None);
+
+        txs.iter_mut().for_each(|tx| tx.close());
+        let rest: Vec<_> = core::iter::from_fn(|| merge.try_pop()).collect();
+        assert_eq!(rest, [(4, 'd'), (5, 'e'), (6, 'f')]);
+        assert!(merge.is_done() && merge.is_disconnected());
+    }
+
+    #[test]
+    fn test_pop_ready_merge() {
+        let (mut fast, slow) = SPSCEphemeral::<u32, 4>::new().split();
+        let (_stalled, idle) = SPSCEphemeral::<u32, 4>::new().split();
+        let mut merge = OrderedMerge::new([slow, idle], |&val| val);
+
+        fast.push_slice(&[7, 8]);
+        assert_eq!(merge.try_pop(), None);
+        assert_eq!(merge.pop_ready(), Some(7));
+        assert_eq!(merge.pop_ready(), Some(8));
+        assert_eq!(merge.pop_ready(), None);
+        assert!(!merge.is_done());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_blocking_merge() {
+        use std::thread;
+
+        let (mut left_tx, left) = SPSCEphemeral::<u32, 4>::new().split();
+        let (mut right_tx, right) = SPSCEphemeral::<u32, 4>::new().split();
+        let mut merge = OrderedMerge::new([left, right], |&val| val);
+        left_tx.push_slice(&[2, 4]);
+        let push_t = thread::spawn(move || {
+            right_tx.push_slice(&[1, 3]);
+            right_tx.close();
+        });
+
+        let timeout = Duration::from_secs(5);
+        let merged: Vec<_> = core::iter::from_fn(|| merge.pop_timeout(timeout))
+            .take(3)
+            .collect();
+        assert_eq!(merged, [1, 2, 3]);
+        push_t.join().unwrap();
+
+        // the right source is done, nothing left to wait for
+        assert_eq!(merge.pop_timeout(timeout), Some(4));
+        assert_eq!(merge.pop_timeout(Duration::from_millis(5)), None);
+        drop(left_tx);
+        assert_eq!(merge.pop_timeout(timeout), None);
+        assert!(merge.is_done());
+    }
+}
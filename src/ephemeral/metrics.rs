@@ -0,0 +1,251 @@
+use metrics::{counter, describe_counter, describe_gauge, gauge, Counter, Gauge, Unit};
+
+use super::{
+    bip, broadcast,
+    dynamic::DynBuffer,
+    isr::IsrQueue,
+    mpmc::MPMCEphemeral,
+    mpsc, overwrite,
+    padded::PaddedBuffer,
+    priority,
+    scq::SCQEphemeral,
+    small::{Index, SmallRing},
+    spmc,
+    spsc::{self, Ring, SPSCEphemeral},
+    stats::Stats,
+};
+
+/// A queue or queue handle whose `stats` `QueueMetrics` can export
+pub trait Observe {
+    fn stats(&self) -> Stats;
+    /// pending items, `len`
+    fn depth(&self) -> usize;
+    fn capacity(&self) -> usize;
+}
+
+macro_rules! observe {
+    ($($ty:ty $([$($generics:tt)*])?),* $(,)?) => {$(
+        impl$(<$($generics)*>)? Observe for $ty {
+            fn stats(&self) -> Stats {
+                <$ty>::stats(self)
+            }
+
+            fn depth(&self) -> usize {
+                <$ty>::len(self)
+            }
+
+            fn capacity(&self) -> usize {
+                <$ty>::capacity(self)
+            }
+        }
+    )*};
+}
+
+observe! {
+    SPSCEphemeral<T, N> [T, const N: usize],
+    PaddedBuffer<T, N> [T, const N: usize],
+    DynBuffer<T> [T],
+    spsc::Producer<R> [R: Ring],
+    spsc::Consumer<R> [R: Ring],
+    MPMCEphemeral<T, N> [T, const N: usize],
+    mpsc::MPSCEphemeral<T, N> [T, const N: usize],
+    SCQEphemeral<T, N> [T, const N: usize],
+    mpsc::Producer<T, N> [T, const N: usize],
+    mpsc::Consumer<T, N> [T, const N: usize],
+    spmc::SPMCEphemeral<T, N> [T, const N: usize],
+    spmc::Producer<T, N> [T, const N: usize],
+    spmc::Consumer<T, N> [T, const N: usize],
+    overwrite::OverwriteBuffer<T, N> [T, const N: usize],
+    overwrite::Producer<T, N> [T, const N: usize],
+    overwrite::Consumer<T, N> [T, const N: usize],
+    broadcast::Producer<T, N> [T: Clone, const N: usize],
+    broadcast::Subscriber<T, N> [T: Clone, const N: usize],
+    IsrQueue<T, N> [T, const N: usize],
+    SmallRing<T, N, A> [T, const N: usize, A: Index],
+    priority::PriorityBuffer<T, L, N> [T, const L: usize, const N: usize],
+    priority::Producer<T, L, N> [T, const L: usize, const N: usize],
+    priority::Consumer<T, L, N> [T, const L: usize, const N: usize],
+    bip::Writer,
+    bip::Reader,
+}
+
+/// One queue's counters and gauges in the `metrics` facade, all
+/// labelled `queue = name`, so whatever exporter the service installed
+/// (Prometheus or otherwise) picks them up
+///
+/// Registered on `new`, so the recorder must be installed first.
+/// Nothing is exported on the hot path, `record` copies a `stats`
+/// snapshot over, call it from a timer or the exporter's scrape
+///
+/// - `brainstorm_queue_depth`, `_capacity`, `_high_water`: gauges
+/// - `brainstorm_queue_pushes_total`, `_pops_total`: throughput
+/// - `brainstorm_queue_full_total`: pushes turned away, the drop count
+/// - `brainstorm_queue_empty_total`: pops that found nothing
+pub struct QueueMetrics {
+    depth: Gauge,
+    capacity: Gauge,
+    high_water: Gauge,
+    pushes: Counter,
+    pops: Counter,
+    full: Counter,
+    empty: Counter,
+    last: Stats, // as of the previous `record`
+}
+
+impl QueueMetrics {
+    pub fn new(name: &str) -> Self {
+        describe();
+        Self {
+            depth: gauge!("brainstorm_queue_depth", "queue" => name.to_owned()),
+            capacity: gauge!("brainstorm_queue_capacity", "queue" => name.to_owned()),
+            high_water: gauge!("brainstorm_queue_high_water", "queue" => name.to_owned()),
+            pushes: counter!("brainstorm_queue_pushes_total", "queue" => name.to_owned()),
+            pops: counter!("brainstorm_queue_pops_total", "queue" => name.to_owned()),
+            full: counter!("brainstorm_queue_full_total", "queue" => name.to_owned()),
+            empty: counter!("brainstorm_queue_empty_total", "queue" => name.to_owned()),
+            last: Stats::default(),
+        }
+    }
+
+    /// Copies `queue`'s counters over, the counters grow by what
+    /// happened since the last call. Any handle of the queue will do,
+    /// they all share one set of `stats`
+    pub fn record(&mut self, queue: &impl Observe) {
+        let stats = queue.stats();
+        self.depth.set(queue.depth() as f64);
+        self.capacity.set(queue.capacity() as f64);
+        self.high_water.set(stats.high_water as f64);
+
+        let grew = |now: usize, last: usize| now.wrapping_sub(last) as u64;
+        self.pushes.increment(grew(stats.pushes, self.last.pushes));
+        self.pops.increment(grew(stats.pops, self.last.pops));
+        self.full.increment(grew(stats.full, self.last.full));
+        self.empty.increment(grew(stats.empty, self.last.empty));
+        self.last = stats;
+    }
+}
+
+fn describe() {
+    describe_gauge!("brainstorm_queue_depth", Unit::Count, "items pending");
+    describe_gauge!(
+        "brainstorm_queue_capacity",
+        Unit::Count,
+        "slots in the arena"
+    );
+    describe_gauge!(
+        "brainstorm_queue_high_water",
+        Unit::Count,
+        "most items ever pending at once"
+    );
+    describe_counter!("brainstorm_queue_pushes_total", Unit::Count, "items pushed");
+    describe_counter!("brainstorm_queue_pops_total", Unit::Count, "items popped");
+    describe_counter!(
+        "brainstorm_queue_full_total",
+        Unit::Count,
+        "pushes turned away by a full queue"
+    );
+    describe_counter!(
+        "brainstorm_queue_empty_total",
+        Unit::Count,
+        "pops that found the queue empty"
+    );
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use metrics::{with_local_recorder, Histogram, Key, KeyName, Metadata, Recorder, SharedString};
+    use std::{
+        collections::BTreeMap,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    /// Keeps every counter and gauge as an atomic under `name{queue}`
+    #[derive(Default)]
+    struct Values(Mutex<BTreeMap<String, Arc<AtomicU64>>>);
+
+    impl Values {
+        fn handle(&self, key: &Key) -> Arc<AtomicU64> {
+            let queue = key.labels().find(|label| label.key() == "queue").unwrap();
+            let name = format!("{}{{{}}}", key.name(), queue.value());
+            self.0.lock().unwrap().entry(name).or_default().clone()
+        }
+
+        fn counter(&self, name: &str) -> u64 {
+            self.0.lock().unwrap()[name].load(Ordering::Relaxed)
+        }
+
+        fn gauge(&self, name: &str) -> f64 {
+            f64::from_bits(self.counter(name))
+        }
+    }
+
+    impl Recorder for Values {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::from_arc(self.handle(key))
+        }
+
+        fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::from_arc(self.handle(key))
+        }
+
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn test_record_metrics() {
+        let values = Values::default();
+        let mut src = SPSCEphemeral::<u32, 4>::new();
+        let mut metrics = with_local_recorder(&values, || QueueMetrics::new("jobs"));
+
+        for i in 0..5 {
+            let _ = src.push(i);
+        }
+        src.pop();
+        metrics.record(&src);
+        assert_eq!(values.counter("brainstorm_queue_pushes_total{jobs}"), 4);
+        assert_eq!(values.counter("brainstorm_queue_full_total{jobs}"), 1);
+        assert_eq!(values.gauge("brainstorm_queue_depth{jobs}"), 3.0);
+        assert_eq!(values.gauge("brainstorm_queue_capacity{jobs}"), 4.0);
+        assert_eq!(values.gauge("brainstorm_queue_high_water{jobs}"), 4.0);
+
+        // only what happened since is added on
+        while src.pop().is_some() {}
+        metrics.record(&src);
+        assert_eq!(values.counter("brainstorm_queue_pushes_total{jobs}"), 4);
+        assert_eq!(values.counter("brainstorm_queue_pops_total{jobs}"), 4);
+        assert_eq!(values.counter("brainstorm_queue_empty_total{jobs}"), 1);
+        assert_eq!(values.gauge("brainstorm_queue_depth{jobs}"), 0.0);
+    }
+
+    #[test]
+    fn test_handles_metrics() {
+        let values = Values::default();
+        let (producer, mut consumer) = mpsc::MPSCEphemeral::<u32, 8>::new().split();
+        let (mut sent, mut taken) = with_local_recorder(&values, || {
+            (QueueMetrics::new("sent"), QueueMetrics::new("taken"))
+        });
+
+        for i in 0..3 {
+            producer.push(i).unwrap();
+        }
+        assert_eq!(consumer.pop(), Some(0));
+        // two labels, one queue
+        sent.record(&producer);
+        taken.record(&consumer);
+        for queue in ["sent", "taken"] {
+            let pops = format!("brainstorm_queue_pops_total{{{queue}}}");
+            let depth = format!("brainstorm_queue_depth{{{queue}}}");
+            assert_eq!((values.counter(&pops), values.gauge(&depth)), (1, 2.0));
+        }
+    }
+}
@@ -1 +1,113 @@
-mod spsc;
+/// Derives `is_empty`/`is_full`/`free_space` from
+/// the surrounding impl's `len` and `capacity`
+macro_rules! occupancy {
+    () => {
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        pub fn is_full(&self) -> bool {
+            self.len() >= self.capacity()
+        }
+
+        pub fn free_space(&self) -> usize {
+            self.capacity().saturating_sub(self.len())
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+pub mod actor;
+pub mod adapt;
+#[cfg(feature = "affinity")]
+pub mod affinity;
+pub mod arena;
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod backpressure;
+pub mod bip;
+// casts caller storage to the ring's cells, which loom's aren't
+#[cfg(not(loom))]
+pub mod borrowed;
+#[cfg(feature = "std")]
+pub mod broadcast;
+pub mod cancel;
+#[cfg(feature = "std")]
+pub mod channel;
+pub mod deque;
+#[cfg(feature = "std")]
+pub mod duplex;
+pub mod dynamic;
+#[cfg(feature = "std")]
+pub mod eventbus;
+#[cfg(feature = "std")]
+pub mod executor;
+pub mod handle;
+pub mod hazard;
+#[cfg(feature = "ipc")]
+pub mod ipc;
+pub mod isr;
+#[cfg(feature = "latency")]
+pub mod latency;
+pub mod linked;
+pub mod mailbox;
+pub mod merge;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod mpmc;
+pub mod mpsc;
+pub mod multi;
+pub mod oneshot;
+pub mod overwrite;
+pub mod padded;
+#[cfg(feature = "persistent")]
+pub mod persistent;
+#[cfg(feature = "std")]
+pub mod pipeline;
+pub mod pool;
+pub mod priority;
+pub mod rcu;
+pub mod reclaim;
+pub mod rendezvous;
+#[cfg(feature = "std")]
+pub mod scope;
+pub mod scq;
+pub mod segment;
+pub mod select;
+pub mod sharded;
+#[cfg(feature = "std")]
+pub mod signal;
+pub mod slot;
+pub mod small;
+#[cfg(feature = "serde")]
+pub mod snapshot;
+pub mod spmc;
+pub mod spsc;
+#[cfg(feature = "std")]
+pub mod stack;
+pub mod statics;
+#[cfg(feature = "stats")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod std_mpsc;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod throttle;
+#[cfg(feature = "std")]
+pub mod timed;
+#[cfg(feature = "std")]
+pub mod token;
+#[cfg(feature = "tokio")]
+pub mod tokio_bridge;
+pub mod triple;
+pub mod wait;
+#[cfg(feature = "std")]
+pub mod watch;
+
+mod epoch;
+mod seq;
+#[cfg(feature = "tracing")]
+mod trace;
+#[cfg(feature = "debug-validate")]
+mod validate;
@@ -0,0 +1,338 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::time::Duration;
+use core::{iter, sync::atomic::AtomicUsize};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer};
+
+use crate::util::CachePadded;
+
+use super::seq::{drop_pending, len, pop_shared, push_shared, slots, SeqSlot};
+#[cfg(feature = "serde")]
+use super::snapshot::{self, Snapshot};
+#[cfg(feature = "stats")]
+use super::stats::{Counters, Stats};
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+#[cfg(feature = "std")]
+use super::wait::{push_until, retry_until, Timeout};
+
+/// Common face of the bounded multi-producer/multi-consumer rings,
+/// so the code around one can switch to the other
+///
+/// `MPMCEphemeral` claims positions by CAS, cheap while few threads
+/// share it but retried by every loser under contention. `SCQEphemeral`
+/// hands out tickets by fetch-add, about twice the work per item but
+/// nothing to retry, which only pays off with many cores fighting
+/// over it, bench both under the real load
+/// (`cargo bench --bench throughput -- contended`)
+pub trait BoundedMpmc: Sync {
+    type Item: Send;
+
+    fn push(&self, val: Self::Item) -> Result<(), Self::Item>;
+    fn pop(&self) -> Option<Self::Item>;
+    /// Pending items, approximate while other threads are busy
+    fn len(&self) -> usize;
+    fn capacity(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Bounded multi-producer/multi-consumer ring (Vyukov style),
+/// producers and consumers race on the indices via CAS
+/// and hand slots over through per-slot sequence stamps
+/// N:: arena size, a power of two >= 2
+pub struct MPMCEphemeral<T, const N: usize> {
+    bufr: [SeqSlot<T>; N],
+    head: CachePadded<AtomicUsize>, // read position
+    tail: CachePadded<AtomicUsize>, // write position
+    #[cfg(feature = "stats")]
+    stats: Counters,
+    #[cfg(feature = "tracing")]
+    trace: Label,
+}
+
+impl<T, const N: usize> MPMCEphemeral<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            bufr: slots(),
+            head: CachePadded::new(AtomicUsize::new(0)),
+            tail: CachePadded::new(AtomicUsize::new(0)),
+            #[cfg(feature = "stats")]
+            stats: Counters::new(),
+            #[cfg(feature = "tracing")]
+            trace: Label::new("mpmc"),
+        }
+    }
+
+    /// Names the ring in its `tracing` events, `mpmc` otherwise
+    #[cfg(feature = "tracing")]
+    pub const fn with_name(mut self, name: &'static str) -> Self {
+        self.trace = Label::new(name);
+        self
+    }
+
+    pub fn push(&self, val: T) -> Result<(), T> {
+        let res = push_shared(&self.bufr, &self.tail, val);
+        #[cfg(feature = "stats")]
+        match res {
+            Ok(()) => self.stats.pushed(1, self.len()),
+            Err(_) => self.stats.full(),
+        }
+        #[cfg(feature = "tracing")]
+        match res {
+            Ok(()) => self.trace.pushed(1, self.len()),
+            Err(_) => self.trace.full(),
+        }
+        res
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let val = pop_shared(&self.bufr, &self.head);
+        #[cfg(feature = "stats")]
+        match val {
+            Some(_) => self.stats.popped(1),
+            None => self.stats.empty(),
+        }
+        #[cfg(feature = "tracing")]
+        match val {
+            Some(_) => self.trace.popped(1),
+            None => self.trace.empty(),
+        }
+        val
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up once `timeout` passed, handing the value back
+    pub fn push_timeout(&self, val: T, timeout: Duration) -> Result<(), Timeout<T>> {
+        #[cfg(feature = "tracing")]
+        let _span = self.trace.blocking("push_timeout");
+        push_until(val, timeout, |val| self.push(val))
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up with `None` once `timeout` passed
+    pub fn pop_timeout(&self, timeout: Duration) -> Option<T> {
+        #[cfg(feature = "tracing")]
+        let _span = self.trace.blocking("pop_timeout");
+        retry_until(timeout, || self.pop())
+    }
+
+    /// Pending items, approximate while other handles are busy
+    pub fn len(&self) -> usize {
+        len(&self.head, &self.tail, N)
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.stats.snapshot()
+    }
+
+    /// Drops every pending item
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Remaining items in pop order
+    pub fn into_inner(self) -> Vec<T> {
+        iter::from_fn(|| self.pop()).collect()
+    }
+
+    /// Borrows what's queued for serializing, see `Snapshot`
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&mut self) -> Snapshot<'_, T> {
+        let (head, tail) = (*self.head.get_mut(), *self.tail.get_mut());
+        let items = (head..tail).map(|pos| pos & (N - 1));
+        // `&mut`, every slot in between was written and nobody moves it
+        Snapshot::new(items.map(|idx| unsafe { (*self.bufr[idx].value.get()).assume_init_ref() }))
+    }
+
+    /// A ring holding what a `snapshot` serialized, in the same order.
+    /// Fails on more items than fit
+    #[cfg(feature = "serde")]
+    pub fn restore<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let ring = Self::new();
+        snapshot::restore(deserializer, N, |val| ring.push(val))?;
+        Ok(ring)
+    }
+}
+
+impl<T, const N: usize> Default for MPMCEphemeral<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for MPMCEphemeral<T, N> {
+    fn drop(&mut self) {
+        let head = *self.head.get_mut();
+        let tail = *self.tail.get_mut();
+        drop_pending(&mut self.bufr, head, tail);
+    }
+}
+
+unsafe impl<T: Send, const N: usize> Sync for MPMCEphemeral<T, N> {}
+
+impl<T: Send, const N: usize> BoundedMpmc for MPMCEphemeral<T, N> {
+    type Item = T;
+
+    fn push(&self, val: T) -> Result<(), T> {
+        MPMCEphemeral::push(self, val)
+    }
+
+    fn pop(&self) -> Option<T> {
+        MPMCEphemeral::pop(self)
+    }
+
+    fn len(&self) -> usize {
+        MPMCEphemeral::len(self)
+    }
+
+    fn capacity(&self) -> usize {
+        N
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+    use std::thread;
+
+    const ITEMS: usize = if cfg!(miri) { 200 } else { 10000 };
+
+    #[test]
+    fn test_seq_mpmc() {
+        let src = MPMCEphemeral::<usize, 4>::new();
+
+        for lap in 0..100 {
+            for i in 0..4 {
+                assert!(src.push(lap * 4 + i).is_ok());
+            }
+            assert_eq!(src.push(0), Err(0));
+
+            for i in 0..4 {
+                assert_eq!(src.pop(), Some(lap * 4 + i));
+            }
+            assert_eq!(src.pop(), None);
+        }
+    }
+
+    #[test]
+    fn test_threaded_mpmc() {
+        let src = Arc::new(MPMCEphemeral::<usize, 16>::new());
+
+        let producers: Vec<_> = (0..4)
+            .map(|p| {
+                let producer = src.clone();
+                thread::spawn(move || {
+                    for i in p * ITEMS..(p + 1) * ITEMS {
+                        while producer.push(i).is_err() {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let consumers: Vec<_> = (0..4)
+            .map(|_| {
+                let consumer = src.clone();
+                thread::spawn(move || {
+                    let mut seen = Vec::with_capacity(ITEMS);
+                    while seen.len() < ITEMS {
+                        match consumer.pop() {
+                            Some(val) => seen.push(val),
+                            None => thread::yield_now(),
+                        }
+                    }
+                    seen
+                })
+            })
+            .collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        let mut seen: Vec<_> = consumers
+            .into_iter()
+            .flat_map(|consumer| consumer.join().unwrap())
+            .collect();
+        seen.sort_unstable();
+        assert_eq!(seen, (0..4 * ITEMS).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_drop_mpmc() {
+        struct DropCount(Arc<AtomicUsize>);
+
+        impl Drop for DropCount {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let drops = Arc::new(AtomicUsize::new(0));
+        let src = MPMCEphemeral::<DropCount, 4>::new();
+
+        for _ in 0..3 {
+            assert!(src.push(DropCount(drops.clone())).is_ok());
+            drop(src.pop());
+        }
+        for _ in 0..4 {
+            assert!(src.push(DropCount(drops.clone())).is_ok());
+        }
+        assert_eq!(drops.load(Ordering::Relaxed), 3);
+        drop(src);
+        assert_eq!(drops.load(Ordering::Relaxed), 7);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_timeout_mpmc() {
+        let src = Arc::new(MPMCEphemeral::<i32, 2>::new());
+        let timeout = Duration::from_millis(10);
+
+        assert_eq!(src.pop_timeout(timeout), None);
+        assert!(src.push_timeout(1, timeout).is_ok());
+        assert!(src.push_timeout(2, timeout).is_ok());
+        assert_eq!(src.push_timeout(4, timeout), Err(Timeout(4)));
+
+        let producer = src.clone();
+        let produce_t = thread::spawn(move || producer.push_timeout(3, Duration::from_secs(5)));
+        thread::sleep(timeout);
+        assert_eq!(src.pop_timeout(Duration::from_secs(5)), Some(1));
+        assert_eq!(src.pop_timeout(Duration::from_secs(5)), Some(2));
+        assert_eq!(src.pop_timeout(Duration::from_secs(5)), Some(3));
+        assert!(produce_t.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_len_mpmc() {
+        let src = MPMCEphemeral::<usize, 4>::new();
+        assert!(src.is_empty());
+        assert_eq!((src.len(), src.capacity(), src.free_space()), (0, 4, 4));
+
+        for i in 0..4 {
+            src.push(i).unwrap();
+        }
+        assert!(src.is_full());
+        assert_eq!((src.len(), src.free_space()), (4, 0));
+
+        src.pop().unwrap();
+        assert_eq!((src.len(), src.free_space()), (3, 1));
+    }
+}
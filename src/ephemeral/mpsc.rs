@@ -0,0 +1,379 @@
+use alloc::{sync::Arc, vec::Vec};
+#[cfg(feature = "std")]
+use core::time::Duration;
+use core::{
+    iter::{self, Take},
+    sync::atomic::AtomicUsize,
+};
+
+use crate::util::CachePadded;
+
+use super::seq::{drop_pending, len, pop_exclusive, push_shared, slots, SeqSlot};
+#[cfg(feature = "stats")]
+use super::stats::{Counters, Stats};
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+#[cfg(feature = "std")]
+use super::wait::{push_until, retry_until, Timeout};
+
+/// Bounded multi-producer/single-consumer ring,
+/// producers reserve the tail via CAS while the
+/// consumer owns the head outright
+/// N:: arena size, a power of two >= 2
+pub struct MPSCEphemeral<T, const N: usize> {
+    bufr: [SeqSlot<T>; N],
+    head: CachePadded<AtomicUsize>, // read position
+    tail: CachePadded<AtomicUsize>, // write position
+    #[cfg(feature = "stats")]
+    stats: Counters,
+    #[cfg(feature = "tracing")]
+    trace: Label,
+}
+
+impl<T, const N: usize> MPSCEphemeral<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            bufr: slots(),
+            head: CachePadded::new(AtomicUsize::new(0)),
+            tail: CachePadded::new(AtomicUsize::new(0)),
+            #[cfg(feature = "stats")]
+            stats: Counters::new(),
+            #[cfg(feature = "tracing")]
+            trace: Label::new("mpsc"),
+        }
+    }
+
+    /// Names the ring in its `tracing` events, `mpsc` otherwise
+    #[cfg(feature = "tracing")]
+    pub const fn with_name(mut self, name: &'static str) -> Self {
+        self.trace = Label::new(name);
+        self
+    }
+
+    /// Moves the buffer behind a clonable producer
+    /// and the one consumer allowed to read from it
+    pub fn split(self) -> (Producer<T, N>, Consumer<T, N>) {
+        let bufr = Arc::new(self);
+        let producer = Producer { bufr: bufr.clone() };
+        (producer, Consumer { bufr })
+    }
+
+    /// Pending items, approximate while other handles are busy
+    pub fn len(&self) -> usize {
+        len(&self.head, &self.tail, N)
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.stats.snapshot()
+    }
+
+    /// Drops every pending item
+    pub fn clear(&mut self) {
+        while pop_exclusive(&self.bufr, &self.head).is_some() {}
+    }
+
+    /// Remaining items in pop order
+    pub fn into_inner(self) -> Vec<T> {
+        iter::from_fn(|| pop_exclusive(&self.bufr, &self.head)).collect()
+    }
+}
+
+impl<T, const N: usize> Default for MPSCEphemeral<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for MPSCEphemeral<T, N> {
+    fn drop(&mut self) {
+        let head = *self.head.get_mut();
+        let tail = *self.tail.get_mut();
+        drop_pending(&mut self.bufr, head, tail);
+    }
+}
+
+unsafe impl<T: Send, const N: usize> Sync for MPSCEphemeral<T, N> {}
+
+/// Write half of a split `MPSCEphemeral`, clone it per producer thread
+pub struct Producer<T, const N: usize> {
+    bufr: Arc<MPSCEphemeral<T, N>>,
+}
+
+impl<T, const N: usize> Producer<T, N> {
+    pub fn push(&self, val: T) -> Result<(), T> {
+        let res = push_shared(&self.bufr.bufr, &self.bufr.tail, val);
+        #[cfg(feature = "stats")]
+        match res {
+            Ok(()) => self.bufr.stats.pushed(1, self.len()),
+            Err(_) => self.bufr.stats.full(),
+        }
+        #[cfg(feature = "tracing")]
+        match res {
+            Ok(()) => self.bufr.trace.pushed(1, self.len()),
+            Err(_) => self.bufr.trace.full(),
+        }
+        res
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up once `timeout` passed, handing the value back
+    pub fn push_timeout(&self, val: T, timeout: Duration) -> Result<(), Timeout<T>> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.trace.blocking("push_timeout");
+        push_until(val, timeout, |val| self.push(val))
+    }
+
+    /// Pending items, approximate while other handles are busy
+    pub fn len(&self) -> usize {
+        len(&self.bufr.head, &self.bufr.tail, N)
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.bufr.stats.snapshot()
+    }
+}
+
+impl<T, const N: usize> Clone for Producer<T, N> {
+    fn clone(&self) -> Self {
+        Self {
+            bufr: self.bufr.clone(),
+        }
+    }
+}
+
+/// Read half of a split `MPSCEphemeral`
+pub struct Consumer<T, const N: usize> {
+    bufr: Arc<MPSCEphemeral<T, N>>,
+}
+
+impl<T, const N: usize> Consumer<T, N> {
+    pub fn pop(&mut self) -> Option<T> {
+        let val = pop_exclusive(&self.bufr.bufr, &self.bufr.head);
+        #[cfg(feature = "stats")]
+        match val {
+            Some(_) => self.bufr.stats.popped(1),
+            None => self.bufr.stats.empty(),
+        }
+        #[cfg(feature = "tracing")]
+        match val {
+            Some(_) => self.bufr.trace.popped(1),
+            None => self.bufr.trace.empty(),
+        }
+        val
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up with `None` once `timeout` passed
+    pub fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.trace.blocking("pop_timeout");
+        retry_until(timeout, || self.pop())
+    }
+
+    /// Pops until the buffer looks empty
+    pub fn drain(&mut self) -> Drain<'_, T, N> {
+        Drain { consumer: self }
+    }
+
+    /// Pops at most `n` items, stopping early once the buffer looks empty
+    pub fn drain_up_to(&mut self, n: usize) -> Take<Drain<'_, T, N>> {
+        self.drain().take(n)
+    }
+
+    /// Pending items, approximate while other handles are busy
+    pub fn len(&self) -> usize {
+        len(&self.bufr.head, &self.bufr.tail, N)
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.bufr.stats.snapshot()
+    }
+}
+
+/// Ends at the first empty pop, later pushes can resume it
+impl<T, const N: usize> Iterator for Consumer<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
+
+/// Iterator returned by `Consumer::drain`
+pub struct Drain<'a, T, const N: usize> {
+    consumer: &'a mut Consumer<T, N>,
+}
+
+impl<T, const N: usize> Iterator for Drain<'_, T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.consumer.pop()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    const ITEMS: usize = if cfg!(miri) { 200 } else { 10000 };
+
+    #[test]
+    fn test_seq_mpsc() {
+        let (producer, mut consumer) = MPSCEphemeral::<usize, 8>::new().split();
+
+        for i in 0..8 {
+            assert!(producer.push(i).is_ok());
+        }
+        assert_eq!(producer.push(8), Err(8));
+
+        assert_eq!(
+            consumer.drain().collect::<Vec<_>>(),
+            (0..8).collect::<Vec<_>>()
+        );
+        assert_eq!(consumer.pop(), None);
+    }
+
+    #[test]
+    fn test_threaded_mpsc() {
+        let (producer, mut consumer) = MPSCEphemeral::<usize, 32>::new().split();
+
+        let producers: Vec<_> = (0..8)
+            .map(|p| {
+                let producer = producer.clone();
+                thread::spawn(move || {
+                    for i in p * ITEMS..(p + 1) * ITEMS {
+                        while producer.push(i).is_err() {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let consume_t = thread::spawn(move || {
+            let mut seen = Vec::with_capacity(8 * ITEMS);
+            while seen.len() < 8 * ITEMS {
+                seen.extend(consumer.drain());
+                thread::yield_now();
+            }
+            seen
+        });
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        let seen = consume_t.join().unwrap();
+
+        // each producer's items arrive in the order they were pushed
+        for p in 0..8 {
+            let own: Vec<_> = seen.iter().filter(|&&v| v / ITEMS == p).collect();
+            assert!(own.windows(2).all(|w| w[0] < w[1]));
+        }
+
+        let mut seen = seen;
+        seen.sort_unstable();
+        assert_eq!(seen, (0..8 * ITEMS).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_timeout_mpsc() {
+        let (producer, mut consumer) = MPSCEphemeral::<i32, 2>::new().split();
+        let timeout = Duration::from_millis(10);
+
+        assert_eq!(consumer.pop_timeout(timeout), None);
+        assert!(producer.push_timeout(1, timeout).is_ok());
+        assert!(producer.push_timeout(2, timeout).is_ok());
+        assert_eq!(producer.push_timeout(4, timeout), Err(Timeout(4)));
+
+        let produce_t = thread::spawn(move || {
+            thread::sleep(timeout);
+            producer.push_timeout(3, Duration::from_secs(5))
+        });
+        assert_eq!(consumer.pop_timeout(Duration::from_secs(5)), Some(1));
+        assert_eq!(consumer.pop_timeout(Duration::from_secs(5)), Some(2));
+        assert_eq!(consumer.pop_timeout(Duration::from_secs(5)), Some(3));
+        assert!(produce_t.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_len_mpsc() {
+        let src = MPSCEphemeral::<usize, 8>::new();
+        assert_eq!((src.len(), src.capacity()), (0, 8));
+
+        let (producer, mut consumer) = src.split();
+        for i in 0..5 {
+            producer.push(i).unwrap();
+        }
+        assert_eq!((producer.len(), producer.free_space()), (5, 3));
+        consumer.pop().unwrap();
+        assert_eq!((consumer.len(), consumer.free_space()), (4, 4));
+
+        while producer.push(0).is_ok() {}
+        assert!(producer.is_full() && consumer.is_full());
+        while consumer.pop().is_some() {}
+        assert!(producer.is_empty() && consumer.is_empty());
+    }
+
+    #[test]
+    fn test_drain_mpsc() {
+        let (producer, mut consumer) = MPSCEphemeral::<usize, 8>::new().split();
+
+        for i in 0..5 {
+            producer.push(i).unwrap();
+        }
+        assert_eq!(consumer.drain_up_to(3).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(consumer.by_ref().collect::<Vec<_>>(), [3, 4]);
+
+        producer.push(5).unwrap();
+        assert_eq!(consumer.next(), Some(5));
+        assert_eq!(consumer.next(), None);
+    }
+
+    #[test]
+    fn test_clear_mpsc() {
+        let mut src = MPSCEphemeral::<usize, 4>::new();
+        for i in 0..4 {
+            push_shared(&src.bufr, &src.tail, i).unwrap();
+        }
+        src.clear();
+        assert!(src.is_empty());
+
+        let (producer, mut consumer) = src.split();
+        producer.push(7).unwrap();
+        assert_eq!(consumer.pop(), Some(7));
+        drop((producer, consumer));
+
+        let src = MPSCEphemeral::<usize, 4>::new();
+        let (producer, consumer) = src.split();
+        for i in 0..3 {
+            producer.push(i).unwrap();
+        }
+        drop(producer);
+        let src = Arc::into_inner(consumer.bufr).unwrap();
+        assert_eq!(src.into_inner(), [0, 1, 2]);
+    }
+}
@@ -0,0 +1,142 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::spsc::{Producer, PushError, Ring};
+
+/// Producer end of several rings at once, every push goes to all of
+/// them or to none
+///
+/// A push first reserves a slot in each ring, a ring without room
+/// cancels the reservations made so far and nothing is published.
+/// Once all are held the commits can't fail, so no consumer is ever
+/// left with an item the others won't get. The commits are separate
+/// stores though, a consumer may see its copy before another ring
+/// got published
+pub struct MultiProducer<R: Ring> {
+    producers: Vec<Producer<R>>,
+}
+
+impl<R: Ring> MultiProducer<R> {
+    pub fn new(producers: Vec<Producer<R>>) -> Self {
+        assert!(!producers.is_empty(), "fan-out needs at least one ring");
+        Self { producers }
+    }
+
+    /// Publishes a clone of `val` to every ring, or hands it back
+    /// untouched. `Disconnected` once any of the consumers is gone,
+    /// the fan-out can't be complete from there on
+    pub fn push(&mut self, val: R::Item) -> Result<(), PushError<R::Item>>
+    where
+        R::Item: Clone,
+    {
+        publish(&mut self.producers, val).map_err(|val| {
+            if self.is_disconnected() {
+                PushError::Disconnected(val)
+            } else {
+                PushError::Full(val)
+            }
+        })
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.producers.iter().any(Producer::is_disconnected)
+    }
+
+    /// Room left for full fan-out pushes, the tightest of the rings
+    pub fn free_space(&self) -> usize {
+        self.producers
+            .iter()
+            .map(Producer::free_space)
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// The rings in the order they were passed to `new`
+    pub fn producers(&self) -> &[Producer<R>] {
+        &self.producers
+    }
+
+    pub fn into_inner(self) -> Vec<Producer<R>> {
+        self.producers
+    }
+}
+
+impl<R: Ring> fmt::Debug for MultiProducer<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiProducer")
+            .field("rings", &self.producers.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Holds the reservation of the first ring while the rest reserve
+/// theirs, so an `Err` anywhere unwinds every slot held above it
+fn publish<R: Ring>(producers: &mut [Producer<R>], val: R::Item) -> Result<(), R::Item>
+where
+    R::Item: Clone,
+{
+    let (first, rest) = producers
+        .split_first_mut()
+        .expect("`new` checked for a ring");
+    let Some(mut slot) = first.reserve() else {
+        return Err(val);
+    };
+
+    // guard: the last ring takes `val` itself
+    if rest.is_empty() {
+        slot.write(val);
+        slot.commit();
+        return Ok(());
+    }
+
+    let copy = val.clone();
+    publish(rest, val)?;
+    slot.write(copy);
+    slot.commit();
+    Ok(())
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use crate::ephemeral::spsc::{PopError, SPSCEphemeral};
+
+    #[test]
+    fn test_all_or_none_multi() {
+        let (left_tx, mut left) = SPSCEphemeral::<u32, 2>::new().split();
+        let (mut right_tx, mut right) = SPSCEphemeral::<u32, 2>::new().split();
+        right_tx.push(9).unwrap();
+        let mut producer = MultiProducer::new(vec![left_tx, right_tx]);
+
+        producer.push(0).unwrap();
+        assert_eq!(producer.free_space(), 0);
+        // left has room, right doesn't, so neither gets it
+        assert_eq!(producer.push(1), Err(PushError::Full(1)));
+        assert_eq!(producer.producers()[0].len(), 1);
+
+        assert_eq!(right.pop(), Ok(9));
+        producer.push(2).unwrap();
+        assert_eq!(left.drain().collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(right.drain().collect::<Vec<_>>(), [0, 2]);
+    }
+
+    #[test]
+    fn test_disconnected_multi() {
+        let (left_tx, mut left) = SPSCEphemeral::<String, 2>::new().split();
+        let (right_tx, right) = SPSCEphemeral::<String, 2>::new().split();
+        let mut producer = MultiProducer::new(vec![left_tx, right_tx]);
+
+        drop(right);
+        let err = producer.push("lost".to_string()).unwrap_err();
+        assert_eq!(err, PushError::Disconnected("lost".to_string()));
+        // the reservation in left was rolled back
+        assert_eq!(left.pop(), Err(PopError::Empty));
+        assert!(producer.is_disconnected());
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one ring")]
+    fn test_empty_multi() {
+        MultiProducer::<SPSCEphemeral<u32, 2>>::new(Vec::new());
+    }
+}
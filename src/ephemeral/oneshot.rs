@@ -0,0 +1,268 @@
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use core::time::Duration;
+use core::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+#[cfg(feature = "async")]
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "async")]
+use crate::util::AtomicWaker;
+
+use super::spsc::{Disconnected, PopError};
+use super::wait::{retry, WaitStrategy};
+#[cfg(feature = "std")]
+use super::wait::{retry_until, Backoff};
+
+const EMPTY: u8 = 0;
+const FULL: u8 = 1;
+const CLOSED: u8 = 2; // either side gone, or the value already taken
+
+struct Inner<T> {
+    state: AtomicU8,
+    value: UnsafeCell<MaybeUninit<T>>,
+    #[cfg(feature = "async")]
+    waker: AtomicWaker,
+}
+
+unsafe impl<T: Send> Sync for Inner<T> {}
+
+/// Single-use handoff of one value, the sender is consumed by `send`
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(Inner {
+        state: AtomicU8::new(EMPTY),
+        value: UnsafeCell::new(MaybeUninit::uninit()),
+        #[cfg(feature = "async")]
+        waker: AtomicWaker::new(),
+    });
+    (
+        Sender {
+            inner: inner.clone(),
+        },
+        Receiver { inner },
+    )
+}
+
+/// Sending half, dropping it unsent disconnects the receiver
+pub struct Sender<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Hands the value back if the receiver is gone
+    pub fn send(self, val: T) -> Result<(), T> {
+        let inner = &*self.inner;
+        unsafe { (*inner.value.get()).write(val) };
+
+        match inner
+            .state
+            .compare_exchange(EMPTY, FULL, Ordering::AcqRel, Ordering::Relaxed)
+        {
+            Ok(_) => {
+                #[cfg(feature = "async")]
+                inner.waker.wake();
+                Ok(())
+            }
+            Err(_) => Err(unsafe { (*inner.value.get()).assume_init_read() }),
+        }
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.inner.state.load(Ordering::Acquire) == CLOSED
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        // fails once sent, the value is the receiver's now
+        if self
+            .inner
+            .state
+            .compare_exchange(EMPTY, CLOSED, Ordering::AcqRel, Ordering::Relaxed)
+            .is_ok()
+        {
+            #[cfg(feature = "async")]
+            self.inner.waker.wake();
+        }
+    }
+}
+
+/// Receiving half, polls with `try_recv` or blocks with `recv`
+pub struct Receiver<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// `Disconnected` once the sender is gone unsent
+    /// or the value was already received
+    pub fn try_recv(&mut self) -> Result<T, PopError> {
+        let inner = &*self.inner;
+        match inner.state.load(Ordering::Acquire) {
+            EMPTY => Err(PopError::Empty),
+            FULL => {
+                let val = unsafe { (*inner.value.get()).assume_init_read() };
+                inner.state.store(CLOSED, Ordering::Relaxed);
+                Ok(val)
+            }
+            _ => Err(PopError::Disconnected),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    /// Waits with `Backoff` until the value arrives
+    /// or the sender is gone
+    pub fn recv(&mut self) -> Result<T, Disconnected> {
+        self.recv_with(&mut Backoff::new())
+    }
+
+    pub fn recv_with<W: WaitStrategy>(&mut self, wait: &mut W) -> Result<T, Disconnected> {
+        retry(wait, || self.attempt()).map_err(|_| Disconnected)
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up with `Empty` once `timeout` passed
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, PopError> {
+        retry_until(timeout, || self.attempt()).unwrap_or(Err(PopError::Empty))
+    }
+
+    /// One receive for the retry loops, `None` means try again
+    fn attempt(&mut self) -> Option<Result<T, PopError>> {
+        match self.try_recv() {
+            Err(PopError::Empty) => None,
+            res => Some(res),
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        // a value sent but never received is dropped here
+        if self.inner.state.swap(CLOSED, Ordering::Acquire) == FULL {
+            unsafe { (*self.inner.value.get()).assume_init_drop() };
+        }
+    }
+}
+
+/// Resolves with the value, or `Disconnected` if none is coming
+#[cfg(feature = "async")]
+impl<T> Future for Receiver<T> {
+    type Output = Result<T, Disconnected>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if let Some(res) = this.attempt() {
+            return Poll::Ready(res.map_err(|_| Disconnected));
+        }
+
+        // register first, then retry so a send in between isn't missed
+        this.inner.waker.register(cx.waker());
+        match this.attempt() {
+            Some(res) => Poll::Ready(res.map_err(|_| Disconnected)),
+            None => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    #[cfg(any(feature = "std", feature = "async"))]
+    use std::{thread, time::Duration};
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_seq_oneshot() {
+        let (sender, mut receiver) = channel();
+
+        assert_eq!(receiver.try_recv(), Err(PopError::Empty));
+        assert!(sender.send(7).is_ok());
+        assert_eq!(receiver.try_recv(), Ok(7));
+        assert_eq!(receiver.try_recv(), Err(PopError::Disconnected));
+        assert_eq!(receiver.recv(), Err(Disconnected));
+    }
+
+    #[test]
+    fn test_disconnect_oneshot() {
+        let (sender, mut receiver) = channel::<i32>();
+        drop(sender);
+        assert_eq!(receiver.try_recv(), Err(PopError::Disconnected));
+
+        let (sender, receiver) = channel();
+        drop(receiver);
+        assert!(sender.is_disconnected());
+        assert_eq!(sender.send(7), Err(7));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_threaded_oneshot() {
+        let (sender, mut receiver) = channel();
+
+        let send_t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            sender.send(String::from("ready")).unwrap();
+        });
+        assert_eq!(receiver.recv().as_deref(), Ok("ready"));
+        send_t.join().unwrap();
+
+        let (sender, mut receiver) = channel::<i32>();
+        let send_t = thread::spawn(move || drop(sender));
+        assert_eq!(receiver.recv(), Err(Disconnected));
+        send_t.join().unwrap();
+
+        let (_sender, mut receiver) = channel::<i32>();
+        let timeout = Duration::from_millis(10);
+        assert_eq!(receiver.recv_timeout(timeout), Err(PopError::Empty));
+    }
+
+    #[test]
+    fn test_drop_oneshot() {
+        struct DropCount(Arc<AtomicUsize>);
+
+        impl Drop for DropCount {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let drops = Arc::new(AtomicUsize::new(0));
+        let (sender, receiver) = channel();
+        assert!(sender.send(DropCount(drops.clone())).is_ok());
+        assert_eq!(drops.load(Ordering::Relaxed), 0);
+        drop(receiver);
+        assert_eq!(drops.load(Ordering::Relaxed), 1);
+
+        let (sender, mut receiver) = channel();
+        assert!(sender.send(DropCount(drops.clone())).is_ok());
+        drop(receiver.try_recv());
+        drop(receiver);
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_async_oneshot() {
+        let (sender, receiver) = channel();
+
+        let send_t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            sender.send(7).unwrap();
+        });
+        assert_eq!(futures::executor::block_on(receiver), Ok(7));
+        send_t.join().unwrap();
+
+        let (sender, receiver) = channel::<i32>();
+        let send_t = thread::spawn(move || drop(sender));
+        assert_eq!(futures::executor::block_on(receiver), Err(Disconnected));
+        send_t.join().unwrap();
+    }
+}
@@ -0,0 +1,311 @@
+use alloc::{sync::Arc, vec::Vec};
+#[cfg(feature = "std")]
+use core::time::Duration;
+use core::{
+    iter,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::util::CachePadded;
+
+use super::seq::{drop_pending, len, pop_shared, push_exclusive, slots, SeqSlot};
+#[cfg(feature = "stats")]
+use super::stats::{Counters, Stats};
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+#[cfg(feature = "std")]
+use super::wait::retry_until;
+use super::wait::Backoff;
+
+/// Bounded single-producer/single-consumer ring whose producer never
+/// waits for the consumer to catch up, a full ring evicts its oldest
+/// item instead. Producer and consumer both claim reads via CAS on
+/// the head. The one wait left is for a consumer halfway through
+/// reading the slot the push needs, which backs off
+/// N:: arena size, a power of two >= 2
+pub struct OverwriteBuffer<T, const N: usize> {
+    bufr: [SeqSlot<T>; N],
+    head: CachePadded<AtomicUsize>, // read position
+    tail: CachePadded<AtomicUsize>, // write position
+    #[cfg(feature = "stats")]
+    stats: Counters,
+    #[cfg(feature = "tracing")]
+    trace: Label,
+}
+
+impl<T, const N: usize> OverwriteBuffer<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            bufr: slots(),
+            head: CachePadded::new(AtomicUsize::new(0)),
+            tail: CachePadded::new(AtomicUsize::new(0)),
+            #[cfg(feature = "stats")]
+            stats: Counters::new(),
+            #[cfg(feature = "tracing")]
+            trace: Label::new("overwrite"),
+        }
+    }
+
+    /// Names the ring in its `tracing` events, `overwrite` otherwise
+    #[cfg(feature = "tracing")]
+    pub const fn with_name(mut self, name: &'static str) -> Self {
+        self.trace = Label::new(name);
+        self
+    }
+
+    pub fn split(self) -> (Producer<T, N>, Consumer<T, N>) {
+        let bufr = Arc::new(self);
+        let producer = Producer { bufr: bufr.clone() };
+        (producer, Consumer { bufr })
+    }
+
+    /// Pending items, approximate while other handles are busy
+    pub fn len(&self) -> usize {
+        len(&self.head, &self.tail, N)
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    /// `full` counts the pushes that had to evict
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.stats.snapshot()
+    }
+
+    /// Drops every pending item
+    pub fn clear(&mut self) {
+        while pop_shared(&self.bufr, &self.head).is_some() {}
+    }
+
+    /// Remaining items in pop order
+    pub fn into_inner(self) -> Vec<T> {
+        iter::from_fn(|| pop_shared(&self.bufr, &self.head)).collect()
+    }
+}
+
+impl<T, const N: usize> Default for OverwriteBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for OverwriteBuffer<T, N> {
+    fn drop(&mut self) {
+        let head = *self.head.get_mut();
+        let tail = *self.tail.get_mut();
+        drop_pending(&mut self.bufr, head, tail);
+    }
+}
+
+unsafe impl<T: Send, const N: usize> Sync for OverwriteBuffer<T, N> {}
+
+/// Write half of a split `OverwriteBuffer`
+pub struct Producer<T, const N: usize> {
+    bufr: Arc<OverwriteBuffer<T, N>>,
+}
+
+impl<T, const N: usize> Producer<T, N> {
+    /// Always goes through, hands back the item evicted to make room.
+    /// Backs off while the consumer finishes reading the slot it needs
+    pub fn push_overwrite(&mut self, mut val: T) -> Option<T> {
+        let b = &*self.bufr;
+        let mut evicted = None;
+        let mut backoff = Backoff::new();
+
+        loop {
+            match push_exclusive(&b.bufr, &b.tail, val) {
+                Ok(()) => break,
+                Err(back) => val = back,
+            }
+            // the consumer may free a slot first, then nothing is evicted.
+            // One eviction makes room, if the push still fails the
+            // consumer is reading the slot it needs, evicting more
+            // wouldn't free that one
+            if evicted.is_none() {
+                evicted = self.evict();
+                if evicted.is_some() {
+                    continue;
+                }
+            }
+            backoff.snooze();
+        }
+
+        // a push that evicted found the ring full
+        #[cfg(feature = "stats")]
+        {
+            if evicted.is_some() {
+                b.stats.full();
+            }
+            b.stats.pushed(1, self.len());
+        }
+        #[cfg(feature = "tracing")]
+        {
+            if evicted.is_some() {
+                b.trace.full();
+            }
+            b.trace.pushed(1, self.len());
+        }
+        evicted
+    }
+
+    /// Takes the oldest item, but only while the ring is full
+    fn evict(&self) -> Option<T> {
+        let b = &*self.bufr;
+        // the producer owns the tail, full means head sits a lap behind it
+        let pos = b.tail.load(Ordering::Relaxed).wrapping_sub(N);
+        let slot = &b.bufr[pos & (N - 1)];
+
+        // guard: no longer full, or the consumer is mid-read
+        if slot.seq.load(Ordering::Acquire) != pos.wrapping_add(1) {
+            return None;
+        }
+
+        b.head
+            .compare_exchange(
+                pos,
+                pos.wrapping_add(1),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            )
+            .ok()?;
+
+        let val = unsafe { (*slot.value.get()).as_ptr().read() };
+        // free the slot for the next lap
+        slot.seq.store(pos.wrapping_add(N), Ordering::Release);
+        Some(val)
+    }
+
+    /// Pending items, approximate while other handles are busy
+    pub fn len(&self) -> usize {
+        len(&self.bufr.head, &self.bufr.tail, N)
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.bufr.stats.snapshot()
+    }
+}
+
+/// Read half of a split `OverwriteBuffer`
+pub struct Consumer<T, const N: usize> {
+    bufr: Arc<OverwriteBuffer<T, N>>,
+}
+
+impl<T, const N: usize> Consumer<T, N> {
+    pub fn pop(&mut self) -> Option<T> {
+        let val = pop_shared(&self.bufr.bufr, &self.bufr.head);
+        #[cfg(feature = "stats")]
+        match val {
+            Some(_) => self.bufr.stats.popped(1),
+            None => self.bufr.stats.empty(),
+        }
+        #[cfg(feature = "tracing")]
+        match val {
+            Some(_) => self.bufr.trace.popped(1),
+            None => self.bufr.trace.empty(),
+        }
+        val
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up with `None` once `timeout` passed
+    pub fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.trace.blocking("pop_timeout");
+        retry_until(timeout, || self.pop())
+    }
+
+    /// Pending items, approximate while other handles are busy
+    pub fn len(&self) -> usize {
+        len(&self.bufr.head, &self.bufr.tail, N)
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.bufr.stats.snapshot()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_seq_overwrite() {
+        let (mut producer, mut consumer) = OverwriteBuffer::<i32, 4>::new().split();
+
+        for i in 0..4 {
+            assert_eq!(producer.push_overwrite(i), None);
+        }
+        // oldest go first
+        assert_eq!(producer.push_overwrite(4), Some(0));
+        assert_eq!(producer.push_overwrite(5), Some(1));
+
+        for i in 2..6 {
+            assert_eq!(consumer.pop(), Some(i));
+        }
+        assert_eq!(consumer.pop(), None);
+        assert_eq!(producer.push_overwrite(6), None);
+        assert_eq!(consumer.pop(), Some(6));
+    }
+
+    #[test]
+    fn test_threaded_overwrite() {
+        const ITEMS: usize = if cfg!(miri) { 200 } else { 10000 };
+        let (mut producer, mut consumer) = OverwriteBuffer::<usize, 8>::new().split();
+
+        let produce_t = thread::spawn(move || {
+            let mut evicted = 0;
+            for i in 0..ITEMS {
+                evicted += producer.push_overwrite(i).is_some() as usize;
+            }
+            evicted
+        });
+
+        let mut popped = Vec::new();
+        while popped.last() != Some(&(ITEMS - 1)) {
+            if let Some(val) = consumer.pop() {
+                popped.push(val);
+            }
+        }
+        let evicted = produce_t.join().unwrap();
+
+        // every item is either seen once, in order, or counted as evicted
+        assert!(popped.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(popped.len() + evicted, ITEMS);
+    }
+
+    #[test]
+    fn test_len_overwrite() {
+        let src = OverwriteBuffer::<i32, 4>::new();
+        assert_eq!((src.len(), src.capacity()), (0, 4));
+
+        let (mut producer, mut consumer) = src.split();
+        for i in 0..6 {
+            producer.push_overwrite(i);
+        }
+        // evictions keep it at capacity
+        assert!(producer.is_full());
+        assert_eq!((consumer.len(), consumer.free_space()), (4, 0));
+
+        consumer.pop().unwrap();
+        assert_eq!((producer.len(), producer.free_space()), (3, 1));
+    }
+}
@@ -0,0 +1,176 @@
+use alloc::vec::Vec;
+use core::{iter, mem::MaybeUninit};
+
+use crate::sync::UnsafeCell;
+use crate::util::CachePadded;
+
+use super::spsc::{
+    drop_pending, len, pop, push, sealed, split, Consumer, Producer, Ring, RingState,
+};
+#[cfg(feature = "stats")]
+use super::stats::Stats;
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+
+type Slot<T> = CachePadded<UnsafeCell<MaybeUninit<T>>>;
+
+#[cfg(not(loom))]
+const fn arena<T, const N: usize>() -> [Slot<T>; N] {
+    [const { CachePadded::new(UnsafeCell::new(MaybeUninit::uninit())) }; N]
+}
+
+#[cfg(loom)]
+fn arena<T, const N: usize>() -> [Slot<T>; N] {
+    core::array::from_fn(|_| CachePadded::new(UnsafeCell::new(MaybeUninit::uninit())))
+}
+
+/// SPSC ring whose slots are each rounded up to a cache line, so
+/// the producer writing one small hot item never invalidates the
+/// line the consumer is reading the previous one from. Trades N
+/// cache lines of memory for it, plain `SPSCEphemeral` packs slots
+/// N:: arena size, a power of two
+pub struct PaddedBuffer<T, const N: usize> {
+    bufr: [Slot<T>; N],
+    state: RingState,
+}
+
+impl<T, const N: usize> PaddedBuffer<T, N> {
+    const_fn! {
+        pub fn new() -> Self {
+            const { assert!(N.is_power_of_two(), "arena size must be a power of two") };
+
+            Self {
+                bufr: arena(),
+                state: RingState::new(),
+            }
+        }
+    }
+
+    const_fn! {
+        /// Names the ring in its `tracing` events, `spsc` otherwise
+        #[cfg(feature = "tracing")]
+        pub fn with_name(mut self, name: &'static str) -> Self {
+            self.state.trace = Label::new(name);
+            self
+        }
+    }
+
+    /// Moves the buffer behind a producer/consumer pair,
+    /// so only one thread can ever write and one can read
+    pub fn split(self) -> (Producer<Self>, Consumer<Self>) {
+        split(self)
+    }
+
+    /// Pushes while nothing else holds the ring, `split` pushes from
+    /// another thread
+    pub fn push(&mut self, val: T) -> Result<(), T> {
+        push(self, val)
+    }
+
+    /// Pops while nothing else holds the ring, `split` pops from
+    /// another thread
+    pub fn pop(&mut self) -> Option<T> {
+        pop(self)
+    }
+
+    /// Pending items, approximate while the other side is busy
+    pub fn len(&self) -> usize {
+        len(self)
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.state.stats.snapshot()
+    }
+
+    /// Drops every pending item
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Remaining items in pop order
+    pub fn into_inner(mut self) -> Vec<T> {
+        iter::from_fn(|| self.pop()).collect()
+    }
+}
+
+impl<T, const N: usize> Default for PaddedBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> sealed::Sealed for PaddedBuffer<T, N> {}
+
+unsafe impl<T, const N: usize> Ring for PaddedBuffer<T, N> {
+    type Item = T;
+
+    fn arena_size(&self) -> usize {
+        N
+    }
+
+    fn state(&self) -> &RingState {
+        &self.state
+    }
+
+    fn slot(&self, idx: usize) -> &UnsafeCell<MaybeUninit<T>> {
+        &self.bufr[idx]
+    }
+}
+
+impl<T, const N: usize> Drop for PaddedBuffer<T, N> {
+    fn drop(&mut self) {
+        drop_pending(self);
+    }
+}
+
+unsafe impl<T: Send, const N: usize> Sync for PaddedBuffer<T, N> {}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use core::mem;
+    use std::thread;
+
+    #[test]
+    fn test_layout_padded() {
+        let src = PaddedBuffer::<u8, 4>::new();
+        let first = src.slot(0) as *const _ as usize;
+        let second = src.slot(1) as *const _ as usize;
+
+        // neighbours never share a line
+        assert!(second - first >= 64);
+        assert_eq!(first % mem::align_of::<CachePadded<u8>>(), 0);
+    }
+
+    #[test]
+    fn test_threaded_padded() {
+        const ITEMS: u64 = if cfg!(miri) { 200 } else { 100000 };
+        let (mut producer, mut consumer) = PaddedBuffer::<u64, 8>::new().split();
+
+        let produce_t = thread::spawn(move || {
+            for i in 0..ITEMS {
+                while producer.push(i).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        for i in 0..ITEMS {
+            loop {
+                if let Ok(val) = consumer.pop() {
+                    assert_eq!(val, i);
+                    break;
+                }
+                thread::yield_now();
+            }
+        }
+        produce_t.join().unwrap();
+    }
+}
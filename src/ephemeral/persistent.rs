@@ -0,0 +1,573 @@
+use std::{
+    error::Error,
+    fmt,
+    fs::{File, OpenOptions, TryLockError},
+    io,
+    marker::PhantomData,
+    mem::{align_of, size_of},
+    path::Path,
+    ptr::NonNull,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use bytemuck::Pod;
+use memmap2::MmapMut;
+
+use crate::util::CachePadded;
+
+/// "brainwal" in ASCII, written last once the header is filled in
+const MAGIC: u64 = u64::from_be_bytes(*b"brainwal");
+/// bumped whenever `Header` or the slot layout changes
+const VERSION: u32 = 1;
+/// smallest page size of the supported targets
+const PAGE: usize = 4096;
+
+/// Leads the file, the slots follow at `FileRing::OFFSET`
+#[repr(C)]
+struct Header {
+    magic: AtomicU64,
+    version: u32,
+    item_size: u32,
+    item_align: u32,
+    capacity: u32,
+    head: CachePadded<AtomicU64>, // read position
+    tail: CachePadded<AtomicU64>, // write position
+}
+
+/// Leads each slot, what recovery goes by rather than the header's `tail`
+#[repr(C)]
+struct Stamp {
+    pos: u64, // position written + 1, 0 if never written
+    sum: u64, // checksum over the position and the item
+}
+
+/// When `FileRing::push` waits for the disk
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// the kernel writes pages back when it sees fit, `flush` forces it
+    #[default]
+    Never,
+    /// every push is on disk before it returns
+    Always,
+    /// a sync every `n` pushes, up to `n - 1` can be lost to a crash
+    Every(usize),
+}
+
+/// Why `FileRing::push` failed
+#[derive(Debug)]
+pub enum PushError<T> {
+    Full(T),
+    /// the item went in, but syncing it failed, it may not survive a crash
+    Sync(io::Error),
+}
+
+impl<T> fmt::Display for PushError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(_) => f.pad("ring is full"),
+            Self::Sync(err) => write!(f, "pushed, but syncing to disk failed: {err}"),
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for PushError<T> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Full(_) => None,
+            Self::Sync(err) => Some(err),
+        }
+    }
+}
+
+/// SPSC ring whose slots live in a file mapping, so what it holds
+/// outlives the process. The header records the layout and both
+/// positions like `ShmRing`'s, each slot is stamped with the position
+/// it was written at and a checksum, written ahead of the `tail` that
+/// publishes it
+///
+/// A crashed process loses nothing, the page cache has every write.
+/// What reaches the disk ahead of a power cut is up to the
+/// `FsyncPolicy`. Pops aren't synced on their own, so after a crash
+/// the items popped since the last sync are handed out again
+///
+/// `open` doesn't trust the header's positions. It keeps the run of
+/// intact slots from the recorded `head` up to the first slot that
+/// never made it to disk, and writes the repaired positions back.
+/// The file is locked while a `FileRing` has it. It pushes and pops
+/// through `&mut self`, `split` hands the two sides to different
+/// threads
+pub struct FileRing<T: Pod, const N: usize> {
+    base: NonNull<u8>,
+    map: MmapMut,
+    file: File, // holds the lock
+    policy: FsyncPolicy,
+    unsynced: AtomicUsize, // pushes since the last sync
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod, const N: usize> FileRing<T, N> {
+    const ALIGN: usize = max(align_of::<T>(), align_of::<Stamp>());
+    /// where the item sits in its slot, past the stamp
+    const ITEM: usize = size_of::<Stamp>().next_multiple_of(align_of::<T>());
+    const STRIDE: usize = (Self::ITEM + size_of::<T>()).next_multiple_of(Self::ALIGN);
+    /// where the slots start, past the header
+    const OFFSET: usize = size_of::<Header>().next_multiple_of(Self::ALIGN);
+    const SIZE: usize = Self::OFFSET + N * Self::STRIDE;
+
+    /// Creates or truncates the file at `path` and sets up an empty ring
+    pub fn create(path: impl AsRef<Path>, policy: FsyncPolicy) -> io::Result<Self> {
+        const { assert!(N.is_power_of_two(), "arena size must be a power of two") };
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        lock(&file)?;
+        // truncated only once locked, zero filled so nothing is stamped yet
+        file.set_len(0)?;
+        file.set_len(Self::SIZE as u64)?;
+
+        let ring = Self::map(file, policy)?;
+        let header = ring.base.cast::<Header>().as_ptr();
+        unsafe {
+            (*header).version = VERSION;
+            (*header).item_size = size_of::<T>() as u32;
+            (*header).item_align = align_of::<T>() as u32;
+            (*header).capacity = N as u32;
+        }
+        ring.header().magic.store(MAGIC, Ordering::Release);
+        ring.map.flush()?;
+        ring.file.sync_all()?;
+        Ok(ring)
+    }
+
+    /// Maps the ring left at `path` and repairs its positions, see the
+    /// type's doc
+    pub fn open(path: impl AsRef<Path>, policy: FsyncPolicy) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        lock(&file)?;
+        if file.metadata()?.len() < Self::SIZE as u64 {
+            return Err(invalid("file is too short for the ring"));
+        }
+
+        let ring = Self::map(file, policy)?;
+        let header = ring.header();
+        if header.magic.load(Ordering::Acquire) != MAGIC {
+            return Err(invalid("not a ring, or never fully created"));
+        }
+        if header.version != VERSION {
+            return Err(invalid("ring was created by another layout version"));
+        }
+        if header.item_size as usize != size_of::<T>()
+            || header.item_align as usize != align_of::<T>()
+            || header.capacity as usize != N
+        {
+            return Err(invalid("ring was created for another item type or size"));
+        }
+        ring.recover()?;
+        Ok(ring)
+    }
+
+    fn map(file: File, policy: FsyncPolicy) -> io::Result<Self> {
+        // mappings only start on a page boundary, slots can't be aligned past it
+        const { assert!(align_of::<T>() <= PAGE, "item alignment exceeds a page") };
+        let mut map = unsafe { MmapMut::map_mut(&file)? };
+        Ok(Self {
+            base: NonNull::new(map.as_mut_ptr()).expect("mapping is never null"),
+            map,
+            file,
+            policy,
+            unsynced: AtomicUsize::new(0),
+            _marker: PhantomData,
+        })
+    }
+
+    fn header(&self) -> &Header {
+        unsafe { self.base.cast::<Header>().as_ref() }
+    }
+
+    /// Offset of the slot behind a free-running position
+    fn offset(pos: u64) -> usize {
+        Self::OFFSET + (pos as usize & (N - 1)) * Self::STRIDE
+    }
+
+    fn stamp(&self, pos: u64) -> *mut Stamp {
+        unsafe { self.base.as_ptr().add(Self::offset(pos)).cast() }
+    }
+
+    fn item(&self, pos: u64) -> *mut T {
+        unsafe {
+            self.base
+                .as_ptr()
+                .add(Self::offset(pos) + Self::ITEM)
+                .cast()
+        }
+    }
+
+    /// Whether the slot behind `pos` holds an intact write of it
+    fn intact(&self, pos: u64) -> bool {
+        let stamp = unsafe { self.stamp(pos).read() };
+        stamp.pos == pos.wrapping_add(1) && stamp.sum == checksum(pos, unsafe { &*self.item(pos) })
+    }
+
+    /// Position the slot at `idx` was last written at, if intact
+    fn written(&self, idx: usize) -> Option<u64> {
+        let pos = unsafe { self.stamp(idx as u64).read() }
+            .pos
+            .checked_sub(1)?;
+        (pos as usize & (N - 1) == idx && self.intact(pos)).then_some(pos)
+    }
+
+    fn recover(&self) -> io::Result<()> {
+        let header = self.header();
+        let recorded = header.head.load(Ordering::Relaxed);
+        // pops since the last sync leave `head` behind, the oldest slot
+        // still written at or past it is where the items pick up
+        let head = (0..N)
+            .filter_map(|idx| self.written(idx))
+            .filter(|&pos| pos >= recorded)
+            .min()
+            .unwrap_or(recorded);
+        let mut tail = head;
+        while tail - head < N as u64 && self.intact(tail) {
+            tail += 1;
+        }
+
+        header.head.store(head, Ordering::Relaxed);
+        header.tail.store(tail, Ordering::Relaxed);
+        self.map.flush_range(0, size_of::<Header>())
+    }
+
+    /// Moves the ring behind a producer/consumer pair, so only one
+    /// thread can ever write and one can read
+    pub fn split(self) -> (FileProducer<T, N>, FileConsumer<T, N>) {
+        let ring = Arc::new(self);
+        (FileProducer { ring: ring.clone() }, FileConsumer { ring })
+    }
+
+    /// Pushes while nothing else holds the ring, `split` pushes from
+    /// another thread
+    pub fn push(&mut self, val: T) -> Result<(), PushError<T>> {
+        self.write(val)
+    }
+
+    /// Pops while nothing else holds the ring, `split` pops from
+    /// another thread
+    pub fn pop(&mut self) -> Option<T> {
+        self.read()
+    }
+
+    /// Caller is the only producer
+    fn write(&self, val: T) -> Result<(), PushError<T>> {
+        let header = self.header();
+        let head = header.head.load(Ordering::Acquire);
+        let tail = header.tail.load(Ordering::Relaxed);
+
+        // guard: full
+        if tail.wrapping_sub(head) == N as u64 {
+            return Err(PushError::Full(val));
+        }
+
+        unsafe {
+            self.item(tail).write(val);
+            self.stamp(tail).write(Stamp {
+                pos: tail.wrapping_add(1),
+                sum: checksum(tail, &val),
+            });
+        }
+        header.tail.store(tail.wrapping_add(1), Ordering::Release);
+        self.sync(tail).map_err(PushError::Sync)
+    }
+
+    fn sync(&self, pos: u64) -> io::Result<()> {
+        match self.policy {
+            FsyncPolicy::Never => Ok(()),
+            FsyncPolicy::Always => {
+                self.map.flush_range(Self::offset(pos), Self::STRIDE)?;
+                self.map.flush_range(0, size_of::<Header>())
+            }
+            FsyncPolicy::Every(n) => {
+                if self.unsynced.fetch_add(1, Ordering::Relaxed) + 1 < n {
+                    return Ok(());
+                }
+                self.flush()
+            }
+        }
+    }
+
+    /// Caller is the only consumer
+    fn read(&self) -> Option<T> {
+        let header = self.header();
+        let head = header.head.load(Ordering::Relaxed);
+        let tail = header.tail.load(Ordering::Acquire);
+
+        // guard: empty
+        if head == tail {
+            return None;
+        }
+
+        let val = unsafe { self.item(head).read() };
+        header.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(val)
+    }
+
+    /// Waits until every push and pop so far is on disk
+    pub fn flush(&self) -> io::Result<()> {
+        self.unsynced.store(0, Ordering::Relaxed);
+        self.map.flush()
+    }
+
+    pub fn policy(&self) -> FsyncPolicy {
+        self.policy
+    }
+
+    /// Pending items, approximate while the other side is busy
+    pub fn len(&self) -> usize {
+        let header = self.header();
+        let head = header.head.load(Ordering::Acquire);
+        let tail = header.tail.load(Ordering::Acquire);
+        (tail.wrapping_sub(head) as usize).min(N)
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+}
+
+const fn max(a: usize, b: usize) -> usize {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+/// FNV-1a over the position and the item's bytes
+fn checksum<T: Pod>(pos: u64, val: &T) -> u64 {
+    pos.to_le_bytes()
+        .iter()
+        .chain(bytemuck::bytes_of(val))
+        .fold(0xcbf2_9ce4_8422_2325, |sum, &byte| {
+            (sum ^ byte as u64).wrapping_mul(0x100_0000_01b3)
+        })
+}
+
+fn lock(file: &File) -> io::Result<()> {
+    file.try_lock().map_err(|err| match err {
+        TryLockError::WouldBlock => {
+            io::Error::new(io::ErrorKind::WouldBlock, "ring is open elsewhere")
+        }
+        TryLockError::Error(err) => err,
+    })
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+unsafe impl<T: Pod + Send, const N: usize> Send for FileRing<T, N> {}
+unsafe impl<T: Pod + Send, const N: usize> Sync for FileRing<T, N> {}
+
+/// Write half of a split `FileRing`
+pub struct FileProducer<T: Pod, const N: usize> {
+    ring: Arc<FileRing<T, N>>,
+}
+
+impl<T: Pod, const N: usize> FileProducer<T, N> {
+    /// Syncs as the ring's `FsyncPolicy` says
+    pub fn push(&mut self, val: T) -> Result<(), PushError<T>> {
+        self.ring.write(val)
+    }
+
+    /// Waits until every push and pop so far is on disk
+    pub fn flush(&self) -> io::Result<()> {
+        self.ring.flush()
+    }
+
+    /// Pending items, approximate while the consumer is busy
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+}
+
+/// Read half of a split `FileRing`
+pub struct FileConsumer<T: Pod, const N: usize> {
+    ring: Arc<FileRing<T, N>>,
+}
+
+impl<T: Pod, const N: usize> FileConsumer<T, N> {
+    /// `None` when empty, not synced on its own
+    pub fn pop(&mut self) -> Option<T> {
+        self.ring.read()
+    }
+
+    /// Waits until every push and pop so far is on disk
+    pub fn flush(&self) -> io::Result<()> {
+        self.ring.flush()
+    }
+
+    /// Pending items, approximate while the producer is busy
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use std::{env, fs, path::PathBuf, thread};
+
+    /// Per-test file in the temp dir, removed on drop
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            let file = format!("brainstorm-{}-wal-{name}", std::process::id());
+            Self(env::temp_dir().join(file))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn drain<const N: usize>(ring: &mut FileRing<u64, N>) -> Vec<u64> {
+        std::iter::from_fn(|| ring.pop()).collect()
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_restart_persistent() {
+        let path = TempPath::new("restart");
+        let mut ring = FileRing::<u64, 8>::create(&path.0, FsyncPolicy::Always).unwrap();
+        for i in 0..10 {
+            if i >= 8 {
+                assert!(matches!(ring.push(i), Err(PushError::Full(_))));
+                continue;
+            }
+            ring.push(i).unwrap();
+        }
+        assert_eq!((ring.pop(), ring.pop()), (Some(0), Some(1)));
+        ring.flush().unwrap();
+        drop(ring);
+
+        // as the next run of the process would find it
+        let mut ring = FileRing::<u64, 8>::open(&path.0, FsyncPolicy::Every(4)).unwrap();
+        assert_eq!(ring.len(), 6);
+        ring.push(8).unwrap();
+        assert_eq!(drain(&mut ring), [2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_recover_persistent() {
+        let path = TempPath::new("recover");
+        let mut ring = FileRing::<u64, 4>::create(&path.0, FsyncPolicy::Never).unwrap();
+        // moved along, so the items wrap the arena
+        for i in 0..6 {
+            ring.push(i).unwrap();
+            if i < 3 {
+                ring.pop();
+            }
+        }
+        let header = ring.header();
+        // positions lost in the crash, as if no pop or push got synced
+        header.head.store(0, Ordering::Relaxed);
+        header.tail.store(1, Ordering::Relaxed);
+        drop(ring);
+
+        // 2 was popped but its slot is still intact, so it's handed out again
+        let mut ring = FileRing::<u64, 4>::open(&path.0, FsyncPolicy::Never).unwrap();
+        assert_eq!(drain(&mut ring), [2, 3, 4, 5]);
+        for i in 6..9 {
+            ring.push(i).unwrap();
+        }
+        // a torn slot ends the run, what follows it is dropped
+        unsafe { *ring.item(7) ^= 1 };
+        drop(ring);
+
+        let mut ring = FileRing::<u64, 4>::open(&path.0, FsyncPolicy::Never).unwrap();
+        assert_eq!(drain(&mut ring), [6]);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_split_persistent() {
+        const ITEMS: u64 = 1000;
+        let path = TempPath::new("split");
+        let ring = FileRing::<u64, 16>::create(&path.0, FsyncPolicy::Never).unwrap();
+        let (mut producer, mut consumer) = ring.split();
+
+        let produce_t = thread::spawn(move || {
+            for i in 0..ITEMS {
+                while let Err(PushError::Full(_)) = producer.push(i) {
+                    thread::yield_now();
+                }
+            }
+            producer.flush().unwrap();
+        });
+
+        for i in 0..ITEMS {
+            loop {
+                match consumer.pop() {
+                    Some(val) => break assert_eq!(val, i),
+                    None => thread::yield_now(),
+                }
+            }
+        }
+        produce_t.join().unwrap();
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_header_persistent() {
+        let path = TempPath::new("header");
+        let ring = FileRing::<u32, 8>::create(&path.0, FsyncPolicy::Never).unwrap();
+
+        fn kind<R>(res: io::Result<R>) -> Option<io::ErrorKind> {
+            res.err().map(|err| err.kind())
+        }
+        // one ring per file at a time
+        assert_eq!(
+            kind(FileRing::<u32, 8>::open(&path.0, FsyncPolicy::Never)),
+            Some(io::ErrorKind::WouldBlock)
+        );
+        drop(ring);
+
+        assert_eq!(
+            kind(FileRing::<u32, 8>::open(&path.0, FsyncPolicy::Never)),
+            None
+        );
+        assert_eq!(
+            kind(FileRing::<u16, 8>::open(&path.0, FsyncPolicy::Never)),
+            Some(io::ErrorKind::InvalidData)
+        );
+        fs::write(&path.0, vec![0; 4096]).unwrap();
+        assert_eq!(
+            kind(FileRing::<u32, 8>::open(&path.0, FsyncPolicy::Never)),
+            Some(io::ErrorKind::InvalidData)
+        );
+    }
+}
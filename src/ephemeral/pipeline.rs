@@ -0,0 +1,384 @@
+use core::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+#[cfg(feature = "affinity")]
+use std::io;
+use std::{
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+#[cfg(feature = "affinity")]
+use super::affinity::spawn_pinned;
+use super::{
+    channel::{RecvError, SendError},
+    mpmc::MPMCEphemeral,
+    wait::{retry, Backoff},
+};
+
+/// slots between two stages, a full link blocks the stage feeding it
+const LINK: usize = 64;
+/// Nap of a blocked stage once spinning and yielding didn't help
+const PARK: Duration = Duration::from_micros(50);
+
+/// Ring between two stages plus who is still around on either side.
+/// MPMC since a stage running several threads pushes and pops from all
+struct Link<T> {
+    queue: MPMCEphemeral<T, LINK>,
+    senders: AtomicUsize,
+    receivers: AtomicUsize,
+}
+
+impl<T> Link<T> {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            queue: MPMCEphemeral::new(),
+            senders: AtomicUsize::new(0),
+            receivers: AtomicUsize::new(0),
+        })
+    }
+
+    fn tx(self: &Arc<Self>) -> Tx<T> {
+        self.senders.fetch_add(1, Ordering::Relaxed);
+        Tx(self.clone())
+    }
+
+    fn rx(self: &Arc<Self>) -> Rx<T> {
+        self.receivers.fetch_add(1, Ordering::Relaxed);
+        Rx(self.clone())
+    }
+}
+
+struct Tx<T>(Arc<Link<T>>);
+
+impl<T> Tx<T> {
+    /// Waits for room, hands `val` back once nobody receives anymore
+    fn send(&self, val: T) -> Result<(), T> {
+        let mut pending = Some(val);
+        let mut wait = Backoff::with_park(PARK);
+        retry(&mut wait, || {
+            let val = pending.take()?;
+            if self.0.receivers.load(Ordering::Acquire) == 0 {
+                return Some(Err(val));
+            }
+            match self.0.queue.push(val) {
+                Ok(()) => Some(Ok(())),
+                Err(val) => {
+                    pending = Some(val);
+                    None
+                }
+            }
+        })
+    }
+}
+
+impl<T> Drop for Tx<T> {
+    fn drop(&mut self) {
+        // pairs with `recv`'s acquire, every push so far is visible
+        self.0.senders.fetch_sub(1, Ordering::Release);
+    }
+}
+
+struct Rx<T>(Arc<Link<T>>);
+
+impl<T> Rx<T> {
+    /// Waits for an item, `None` once every sender is gone and the link drained
+    fn recv(&self) -> Option<T> {
+        let mut wait = Backoff::with_park(PARK);
+        retry(&mut wait, || match self.0.queue.pop() {
+            Some(val) => Some(Some(val)),
+            // recheck, a push may have landed before the last sender left
+            None if self.0.senders.load(Ordering::Acquire) == 0 => Some(self.0.queue.pop()),
+            None => None,
+        })
+    }
+}
+
+impl<T> Drop for Rx<T> {
+    fn drop(&mut self) {
+        self.0.receivers.fetch_sub(1, Ordering::Release);
+    }
+}
+
+type Build<I, O> = Box<dyn FnOnce(Rx<I>, usize, &mut Threads) -> Rx<O>>;
+
+/// Stage threads spawned so far, and under `run_pinned` the cores
+/// they go on in turn
+#[derive(Default)]
+struct Threads {
+    handles: Vec<JoinHandle<()>>,
+    #[cfg(feature = "affinity")]
+    cores: Vec<usize>,
+    #[cfg(feature = "affinity")]
+    failed: Option<io::Error>,
+}
+
+impl Threads {
+    fn spawn(&mut self, f: impl FnOnce() + Send + 'static) {
+        #[cfg(feature = "affinity")]
+        if !self.cores.is_empty() {
+            let core = self.cores[self.handles.len() % self.cores.len()];
+            match spawn_pinned(core, f) {
+                Ok(handle) => self.handles.push(handle),
+                Err(err) => drop(self.failed.get_or_insert(err)),
+            }
+            return;
+        }
+        self.handles.push(thread::spawn(f));
+    }
+}
+
+/// Chain of stages, each running on its own threads and connected
+/// to the next by a bounded ring, a slow stage backs the ones before
+/// it up instead of letting links grow
+///
+/// Shutdown travels both ways: once every `Input` is dropped the
+/// stages drain and stop in order until `Output` reports the end,
+/// and once `Output` is dropped the stages stop feeding each other
+/// until `Input::send` fails. With more than one thread per stage
+/// items may come out in a different order than they went in
+pub struct Pipeline<I, O> {
+    build: Build<I, O>,
+}
+
+impl<I: Send + 'static> Pipeline<I, I> {
+    pub fn new() -> Self {
+        Self {
+            build: Box::new(|rx, _, _| rx),
+        }
+    }
+}
+
+impl<I: Send + 'static> Default for Pipeline<I, I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: Send + 'static, O: Send + 'static> Pipeline<I, O> {
+    /// Appends a stage mapping every item with `f`
+    pub fn stage<P, F>(self, f: F) -> Pipeline<I, P>
+    where
+        P: Send + 'static,
+        F: Fn(O) -> P + Send + Sync + 'static,
+    {
+        let prev = self.build;
+        let f = Arc::new(f);
+        Pipeline {
+            build: Box::new(move |input, threads, spawned| {
+                let rx = prev(input, threads, spawned);
+                let link = Link::new();
+                for _ in 0..threads {
+                    let (rx, tx, f) = (rx.0.rx(), link.tx(), f.clone());
+                    spawned.spawn(move || {
+                        while let Some(val) = rx.recv() {
+                            if tx.send((*f)(val)).is_err() {
+                                return;
+                            }
+                        }
+                    });
+                }
+                link.rx()
+            }),
+        }
+    }
+
+    /// Spawns `threads` threads per stage, at least one, and hands
+    /// back both ends
+    pub fn run(self, threads: usize) -> (Input<I>, Output<O>) {
+        self.start(threads, Threads::default())
+    }
+
+    /// `run` with the stage threads pinned to `cores`, handed out in
+    /// turn from the first stage on, so neighbouring stages can share
+    /// a cache. If one can't be pinned, the rest shut down
+    ///
+    /// Panics if `cores` is empty
+    #[cfg(feature = "affinity")]
+    pub fn run_pinned(self, threads: usize, cores: &[usize]) -> io::Result<(Input<I>, Output<O>)> {
+        assert!(!cores.is_empty(), "pinning needs at least one core");
+        let spawned = Threads {
+            cores: cores.to_vec(),
+            ..Threads::default()
+        };
+        let (input, mut output) = self.start(threads, spawned);
+        match output.failed.take() {
+            // dropping both ends stops the stages that did start
+            Some(err) => Err(err),
+            None => Ok((input, output)),
+        }
+    }
+
+    fn start(self, threads: usize, mut spawned: Threads) -> (Input<I>, Output<O>) {
+        let link = Link::new();
+        let input = Input { tx: link.tx() };
+        let rx = (self.build)(link.rx(), threads.max(1), &mut spawned);
+        #[cfg(feature = "affinity")]
+        let failed = spawned.failed;
+        let output = Output {
+            rx,
+            handles: spawned.handles,
+            #[cfg(feature = "affinity")]
+            failed,
+        };
+        (input, output)
+    }
+}
+
+impl<I, O> fmt::Debug for Pipeline<I, O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Pipeline { .. }")
+    }
+}
+
+/// Feeding end of a running `Pipeline`
+pub struct Input<T> {
+    tx: Tx<T>,
+}
+
+impl<T> Input<T> {
+    /// Waits for room in the first link, fails only once the
+    /// pipeline shut down from the other end
+    pub fn send(&self, val: T) -> Result<(), SendError<T>> {
+        self.tx.send(val).map_err(SendError)
+    }
+}
+
+impl<T> Clone for Input<T> {
+    fn clone(&self) -> Self {
+        Self { tx: self.tx.0.tx() }
+    }
+}
+
+impl<T> fmt::Debug for Input<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Input { .. }")
+    }
+}
+
+/// Draining end of a running `Pipeline`
+pub struct Output<T> {
+    rx: Rx<T>,
+    handles: Vec<JoinHandle<()>>,
+    #[cfg(feature = "affinity")]
+    failed: Option<io::Error>, // taken by `run_pinned`
+}
+
+impl<T> Output<T> {
+    /// Waits for an item, fails once every `Input` is gone and
+    /// everything sent made it through
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.rx.recv().ok_or(RecvError)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        core::iter::from_fn(|| self.rx.recv())
+    }
+
+    /// Shuts the pipeline down from this end and waits for every
+    /// stage thread, `Err` with the first panic a stage hit
+    pub fn join(self) -> thread::Result<()> {
+        drop(self.rx);
+        let mut res = Ok(());
+        for handle in self.handles {
+            if let Err(panic) = handle.join() {
+                res = res.and(Err(panic));
+            }
+        }
+        res
+    }
+}
+
+impl<T> fmt::Debug for Output<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Output")
+            .field("threads", &self.handles.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+
+    const ITEMS: u64 = if cfg!(miri) { 100 } else { 10000 };
+
+    #[test]
+    fn test_stages_pipeline() {
+        let (input, output) = Pipeline::new()
+            .stage(|x: u64| x * 2)
+            .stage(|x| x.to_string())
+            .run(1);
+
+        thread::scope(|s| {
+            s.spawn(move || {
+                for i in 0..ITEMS {
+                    input.send(i).unwrap();
+                }
+            });
+
+            // one thread per stage keeps the order
+            let seen: Vec<_> = output.iter().collect();
+            let expected: Vec<_> = (0..ITEMS).map(|i| (i * 2).to_string()).collect();
+            assert_eq!(seen, expected);
+        });
+        output.join().unwrap();
+    }
+
+    #[test]
+    fn test_threads_pipeline() {
+        let (input, output) = Pipeline::new().stage(|x: u64| x + 1).run(4);
+
+        let feed_t = thread::spawn(move || {
+            for i in 0..ITEMS {
+                input.send(i).unwrap();
+            }
+        });
+        let sum: u64 = output.iter().sum();
+        feed_t.join().unwrap();
+
+        assert_eq!(sum, (1..=ITEMS).sum());
+        output.join().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "affinity")]
+    #[cfg_attr(miri, ignore)]
+    fn test_pinned_pipeline() {
+        use crate::ephemeral::affinity::cores;
+
+        let allowed = cores().unwrap();
+        let (input, output) = Pipeline::new()
+            .stage(|x: u64| x + 1)
+            .stage(|x| (x, cores().unwrap()))
+            .run_pinned(1, &allowed[..1])
+            .unwrap();
+
+        input.send(1).unwrap();
+        drop(input);
+        assert_eq!(output.recv().unwrap(), (2, allowed[..1].to_vec()));
+        output.join().unwrap();
+
+        let res = Pipeline::new()
+            .stage(|x: u64| x)
+            .run_pinned(1, &[usize::MAX]);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_shutdown_pipeline() {
+        let (input, output) = Pipeline::new()
+            .stage(|x: u64| if x == 3 { panic!("stage failed") } else { x })
+            .run(1);
+
+        // the panicking stage takes its links down with it
+        for i in 0..4 {
+            input.send(i).unwrap();
+        }
+        assert_eq!(output.iter().collect::<Vec<_>>(), [0, 1, 2]);
+        assert!(output.join().is_err());
+        assert_eq!(input.send(4), Err(SendError(4)));
+    }
+}
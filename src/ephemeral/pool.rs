@@ -0,0 +1,189 @@
+use core::{
+    fmt,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+};
+
+use super::mpmc::MPMCEphemeral;
+
+/// Recycles up to N objects through an MPMC ring so hot paths reuse
+/// their buffers instead of allocating. `get` hands out a guard that
+/// puts the object back on drop, falling back to `init` while the
+/// pool is empty; objects coming back to a full pool are dropped
+///
+/// Recycled objects keep their old contents, clear them after `get`
+/// N:: arena size, a power of two >= 2
+pub struct ObjectPool<T, const N: usize> {
+    free: MPMCEphemeral<T, N>,
+    init: fn() -> T,
+}
+
+impl<T, const N: usize> ObjectPool<T, N> {
+    /// Empty pool building new objects with `init` when it runs dry
+    pub const fn new_with(init: fn() -> T) -> Self {
+        Self {
+            free: MPMCEphemeral::new(),
+            init,
+        }
+    }
+
+    /// A recycled object, or a fresh one from `init` if there's none
+    pub fn get(&self) -> Pooled<'_, T, N> {
+        let val = self.free.pop().unwrap_or_else(self.init);
+        Pooled::new(self, val)
+    }
+
+    /// A recycled object, `None` instead of building one
+    pub fn try_get(&self) -> Option<Pooled<'_, T, N>> {
+        self.free.pop().map(|val| Pooled::new(self, val))
+    }
+
+    /// Adds `val` to the free objects, handing it back if the pool is full
+    pub fn put(&self, val: T) -> Result<(), T> {
+        self.free.push(val)
+    }
+
+    /// Free objects right now, approximate while other threads are busy
+    pub fn len(&self) -> usize {
+        self.free.len()
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+}
+
+impl<T: Default, const N: usize> ObjectPool<T, N> {
+    pub const fn new() -> Self {
+        Self::new_with(T::default)
+    }
+}
+
+impl<T: Default, const N: usize> Default for ObjectPool<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for ObjectPool<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObjectPool")
+            .field("len", &self.len())
+            .field("capacity", &N)
+            .finish()
+    }
+}
+
+/// An object taken from `ObjectPool`, returned to it on drop
+pub struct Pooled<'a, T, const N: usize> {
+    pool: &'a ObjectPool<T, N>,
+    val: ManuallyDrop<T>,
+}
+
+impl<'a, T, const N: usize> Pooled<'a, T, N> {
+    fn new(pool: &'a ObjectPool<T, N>, val: T) -> Self {
+        Self {
+            pool,
+            val: ManuallyDrop::new(val),
+        }
+    }
+
+    /// Keeps the object for good instead of returning it
+    pub fn detach(self) -> T {
+        let mut this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never dropped, so `val` is taken only once
+        unsafe { ManuallyDrop::take(&mut this.val) }
+    }
+}
+
+impl<T, const N: usize> Deref for Pooled<'_, T, N> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.val
+    }
+}
+
+impl<T, const N: usize> DerefMut for Pooled<'_, T, N> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.val
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for Pooled<'_, T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.val, f)
+    }
+}
+
+impl<T, const N: usize> Drop for Pooled<'_, T, N> {
+    fn drop(&mut self) {
+        // SAFETY: `val` is taken here only, the guard is gone afterwards
+        let val = unsafe { ManuallyDrop::take(&mut self.val) };
+        // a full pool drops the object
+        let _ = self.pool.put(val);
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_recycle_pool() {
+        static POOL: ObjectPool<Vec<u8>, 2> = ObjectPool::new();
+
+        let mut bufr = POOL.get();
+        bufr.extend_from_slice(b"abc");
+        let ptr = bufr.as_ptr();
+        drop(bufr);
+        assert_eq!(POOL.len(), 1);
+
+        // same allocation, old contents included
+        let bufr = POOL.try_get().unwrap();
+        assert_eq!((bufr.as_ptr(), &bufr[..]), (ptr, &b"abc"[..]));
+        assert!(POOL.try_get().is_none());
+
+        let kept = bufr.detach();
+        assert!(POOL.is_empty());
+        assert_eq!(kept, b"abc");
+    }
+
+    #[test]
+    fn test_full_pool() {
+        let pool = ObjectPool::<Vec<u8>, 2>::new_with(|| Vec::with_capacity(16));
+
+        let held: Vec<_> = (0..3).map(|_| pool.get()).collect();
+        assert!(held.iter().all(|bufr| bufr.capacity() >= 16));
+        drop(held);
+
+        assert!(pool.is_full());
+        assert_eq!(pool.put(Vec::new()), Err(Vec::new()));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_threaded_pool() {
+        use std::thread;
+
+        const ITEMS: usize = if cfg!(miri) { 100 } else { 10000 };
+        let pool = ObjectPool::<Vec<usize>, 4>::new();
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for i in 0..ITEMS {
+                        let mut bufr = pool.get();
+                        bufr.clear();
+                        bufr.push(i);
+                        assert_eq!(bufr[..], [i]);
+                    }
+                });
+            }
+        });
+        assert!(pool.len() <= 4);
+    }
+}
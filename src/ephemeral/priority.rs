@@ -0,0 +1,402 @@
+use alloc::{sync::Arc, vec::Vec};
+#[cfg(feature = "std")]
+use core::time::Duration;
+use core::{
+    iter::{self, Take},
+    sync::atomic::AtomicUsize,
+};
+
+use crate::util::CachePadded;
+
+use super::seq::{drop_pending, len, pop_exclusive, push_shared, slots, SeqSlot};
+#[cfg(feature = "stats")]
+use super::stats::{Counters, Stats};
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+#[cfg(feature = "std")]
+use super::wait::{push_until, retry_until, Timeout};
+
+/// One MPSC ring per priority level
+struct Lane<T, const N: usize> {
+    bufr: [SeqSlot<T>; N],
+    head: CachePadded<AtomicUsize>, // read position
+    tail: CachePadded<AtomicUsize>, // write position
+}
+
+impl<T, const N: usize> Lane<T, N> {
+    const fn new() -> Self {
+        Self {
+            bufr: slots(),
+            head: CachePadded::new(AtomicUsize::new(0)),
+            tail: CachePadded::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+/// Multi-producer/single-consumer buffer with `L` priority lanes
+/// of `N` slots each, lane 0 being the most urgent. The consumer
+/// always pops from the most urgent lane that holds anything, items
+/// of the same lane come out in push order
+///
+/// A push landing in a more urgent lane while a pop is already past
+/// it is picked up by the next pop
+/// L:: lane count, N:: slots per lane, a power of two >= 2
+pub struct PriorityBuffer<T, const L: usize, const N: usize> {
+    lanes: [Lane<T, N>; L],
+    #[cfg(feature = "stats")]
+    stats: Counters,
+    #[cfg(feature = "tracing")]
+    trace: Label,
+}
+
+impl<T, const L: usize, const N: usize> PriorityBuffer<T, L, N> {
+    pub const fn new() -> Self {
+        const { assert!(L > 0, "a priority buffer needs at least one lane") };
+        Self {
+            lanes: [const { Lane::new() }; L],
+            #[cfg(feature = "stats")]
+            stats: Counters::new(),
+            #[cfg(feature = "tracing")]
+            trace: Label::new("priority"),
+        }
+    }
+
+    /// Names the buffer in its `tracing` events, `priority` otherwise.
+    /// Events don't tell the lanes apart, `pending` is over all of them
+    #[cfg(feature = "tracing")]
+    pub const fn with_name(mut self, name: &'static str) -> Self {
+        self.trace = Label::new(name);
+        self
+    }
+
+    /// Moves the buffer behind a clonable producer
+    /// and the one consumer allowed to read from it
+    pub fn split(self) -> (Producer<T, L, N>, Consumer<T, L, N>) {
+        let bufr = Arc::new(self);
+        let producer = Producer { bufr: bufr.clone() };
+        (producer, Consumer { bufr })
+    }
+
+    /// Pending items over every lane, approximate while other handles are busy
+    pub fn len(&self) -> usize {
+        self.lanes
+            .iter()
+            .map(|lane| len(&lane.head, &lane.tail, N))
+            .sum()
+    }
+
+    pub const fn capacity(&self) -> usize {
+        L * N
+    }
+
+    occupancy!();
+
+    /// Over all lanes together
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.stats.snapshot()
+    }
+
+    /// Drops every pending item
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Remaining items in pop order
+    pub fn into_inner(self) -> Vec<T> {
+        iter::from_fn(|| self.pop()).collect()
+    }
+
+    /// Fails when `priority` is out of range or its lane is full
+    fn push(&self, priority: usize, val: T) -> Result<(), T> {
+        let res = match self.lanes.get(priority) {
+            Some(lane) => push_shared(&lane.bufr, &lane.tail, val),
+            None => return Err(val),
+        };
+        #[cfg(feature = "stats")]
+        match res {
+            Ok(()) => self.stats.pushed(1, self.len()),
+            Err(_) => self.stats.full(),
+        }
+        #[cfg(feature = "tracing")]
+        match res {
+            Ok(()) => self.trace.pushed(1, self.len()),
+            Err(_) => self.trace.full(),
+        }
+        res
+    }
+
+    /// Caller must be the only consumer
+    fn pop(&self) -> Option<T> {
+        let val = self
+            .lanes
+            .iter()
+            .find_map(|lane| pop_exclusive(&lane.bufr, &lane.head));
+        #[cfg(feature = "stats")]
+        match val {
+            Some(_) => self.stats.popped(1),
+            None => self.stats.empty(),
+        }
+        #[cfg(feature = "tracing")]
+        match val {
+            Some(_) => self.trace.popped(1),
+            None => self.trace.empty(),
+        }
+        val
+    }
+}
+
+impl<T, const L: usize, const N: usize> Default for PriorityBuffer<T, L, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const L: usize, const N: usize> Drop for PriorityBuffer<T, L, N> {
+    fn drop(&mut self) {
+        for lane in &mut self.lanes {
+            let head = *lane.head.get_mut();
+            let tail = *lane.tail.get_mut();
+            drop_pending(&mut lane.bufr, head, tail);
+        }
+    }
+}
+
+unsafe impl<T: Send, const L: usize, const N: usize> Sync for PriorityBuffer<T, L, N> {}
+
+/// Write half of a split `PriorityBuffer`, clone it per producer thread
+pub struct Producer<T, const L: usize, const N: usize> {
+    bufr: Arc<PriorityBuffer<T, L, N>>,
+}
+
+impl<T, const L: usize, const N: usize> Producer<T, L, N> {
+    /// Queues `val` in lane `priority`, 0 being the most urgent.
+    /// Hands it back when that lane is full or doesn't exist
+    pub fn push(&self, priority: usize, val: T) -> Result<(), T> {
+        self.bufr.push(priority, val)
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up once `timeout` passed, handing the value back
+    pub fn push_timeout(
+        &self,
+        priority: usize,
+        val: T,
+        timeout: Duration,
+    ) -> Result<(), Timeout<T>> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.trace.blocking("push_timeout");
+        push_until(val, timeout, |val| self.push(priority, val))
+    }
+
+    /// Pending items, approximate while other handles are busy
+    pub fn len(&self) -> usize {
+        self.bufr.len()
+    }
+
+    pub const fn capacity(&self) -> usize {
+        L * N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.bufr.stats.snapshot()
+    }
+}
+
+impl<T, const L: usize, const N: usize> Clone for Producer<T, L, N> {
+    fn clone(&self) -> Self {
+        Self {
+            bufr: self.bufr.clone(),
+        }
+    }
+}
+
+/// Read half of a split `PriorityBuffer`
+pub struct Consumer<T, const L: usize, const N: usize> {
+    bufr: Arc<PriorityBuffer<T, L, N>>,
+}
+
+impl<T, const L: usize, const N: usize> Consumer<T, L, N> {
+    /// Next item of the most urgent non-empty lane
+    pub fn pop(&mut self) -> Option<T> {
+        self.bufr.pop()
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up with `None` once `timeout` passed
+    pub fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.trace.blocking("pop_timeout");
+        retry_until(timeout, || self.pop())
+    }
+
+    /// Pops until every lane looks empty
+    pub fn drain(&mut self) -> Drain<'_, T, L, N> {
+        Drain { consumer: self }
+    }
+
+    /// Pops at most `n` items, stopping early once every lane looks empty
+    pub fn drain_up_to(&mut self, n: usize) -> Take<Drain<'_, T, L, N>> {
+        self.drain().take(n)
+    }
+
+    /// Pending items, approximate while other handles are busy
+    pub fn len(&self) -> usize {
+        self.bufr.len()
+    }
+
+    pub const fn capacity(&self) -> usize {
+        L * N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.bufr.stats.snapshot()
+    }
+}
+
+/// Ends at the first empty pop, later pushes can resume it
+impl<T, const L: usize, const N: usize> Iterator for Consumer<T, L, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
+
+/// Iterator returned by `Consumer::drain`
+pub struct Drain<'a, T, const L: usize, const N: usize> {
+    consumer: &'a mut Consumer<T, L, N>,
+}
+
+impl<T, const L: usize, const N: usize> Iterator for Drain<'_, T, L, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.consumer.pop()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::sync::atomic::Ordering;
+    use std::{sync::Barrier, thread};
+
+    const ITEMS: usize = if cfg!(miri) { 100 } else { 2000 };
+
+    #[test]
+    fn test_seq_priority() {
+        let (producer, mut consumer) = PriorityBuffer::<i32, 3, 4>::new().split();
+
+        assert!(producer.push(2, 20).is_ok());
+        assert!(producer.push(1, 10).is_ok());
+        assert!(producer.push(2, 21).is_ok());
+        assert!(producer.push(0, 0).is_ok());
+        assert!(producer.push(1, 11).is_ok());
+        assert_eq!(producer.push(3, 30), Err(30));
+        assert_eq!(producer.len(), 5);
+
+        assert_eq!(consumer.drain().collect::<Vec<_>>(), [0, 10, 11, 20, 21]);
+        assert_eq!(consumer.pop(), None);
+
+        // a full lane doesn't spill into the others
+        for i in 0..4 {
+            producer.push(1, i).unwrap();
+        }
+        assert_eq!(producer.push(1, 4), Err(4));
+        assert!(producer.push(0, 5).is_ok());
+    }
+
+    #[test]
+    fn test_threaded_priority() {
+        const LANES: usize = 4;
+        let (producer, mut consumer) = PriorityBuffer::<(usize, usize), LANES, 8>::new().split();
+        let start = Arc::new(Barrier::new(LANES + 1));
+
+        // every producer feeds its own lane while the consumer keeps popping
+        let producers: Vec<_> = (0..LANES)
+            .map(|lane| {
+                let producer = producer.clone();
+                let start = start.clone();
+                thread::spawn(move || {
+                    start.wait();
+                    for i in 0..ITEMS {
+                        while producer.push(lane, (lane, i)).is_err() {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        start.wait();
+        let mut seen = vec![Vec::new(); LANES];
+        while seen.iter().map(Vec::len).sum::<usize>() < LANES * ITEMS {
+            let Some((lane, i)) = consumer.pop() else {
+                thread::yield_now();
+                continue;
+            };
+            seen[lane].push(i);
+        }
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        // nothing lost, each lane in push order
+        for lane in seen {
+            assert_eq!(lane, (0..ITEMS).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_urgent_first_priority() {
+        let (producer, mut consumer) = PriorityBuffer::<usize, 2, 64>::new().split();
+
+        // both lanes filled concurrently, the consumer waits for all of it
+        let producers: Vec<_> = (0..4)
+            .map(|p| {
+                let producer = producer.clone();
+                thread::spawn(move || {
+                    for i in 0..16 {
+                        producer.push(p % 2, p % 2 * 100 + i).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        let popped: Vec<_> = consumer.drain().collect();
+        assert_eq!(popped.len(), 64);
+        // every urgent item comes out before any of the others
+        assert!(popped[..32].iter().all(|&v| v < 100));
+        assert!(popped[32..].iter().all(|&v| v >= 100));
+    }
+
+    #[test]
+    fn test_drop_priority() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        struct DropCount(Arc<AtomicUsize>);
+        impl Drop for DropCount {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let src = PriorityBuffer::<DropCount, 2, 4>::new();
+        for lane in 0..2 {
+            for _ in 0..3 {
+                assert!(src.push(lane, DropCount(drops.clone())).is_ok());
+            }
+        }
+        drop(src);
+        assert_eq!(drops.load(Ordering::Relaxed), 6);
+    }
+}
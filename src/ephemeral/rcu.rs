@@ -0,0 +1,222 @@
+use alloc::{boxed::Box, sync::Arc};
+use core::{
+    fmt,
+    marker::PhantomData,
+    mem,
+    ops::Deref,
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use super::reclaim::{Epoch, Protect, Reclaim};
+
+/// Shared pointer to read-mostly data, e.g. config every worker reads
+/// on each item and someone swaps out now and then. Readers `load` a
+/// snapshot without touching the refcount, writers publish a whole
+/// new value and the one it replaced stays alive for readers still
+/// holding it, freed through R once none can
+///
+/// The current `Arc` sits in its own box, which is what gets retired
+pub struct RcuCell<T, R = Epoch> {
+    current: AtomicPtr<Arc<T>>,
+    reclaim: R, // frees replaced boxes
+    _marker: PhantomData<Arc<T>>,
+}
+
+impl<T> RcuCell<T> {
+    pub fn new(val: Arc<T>) -> Self {
+        Self::with_reclaim(val, Epoch::new())
+    }
+}
+
+impl<T, R: Reclaim> RcuCell<T, R> {
+    /// Frees replaced values through `reclaim`, e.g. `Hazards`
+    pub fn with_reclaim(val: Arc<T>, reclaim: R) -> Self {
+        Self {
+            current: AtomicPtr::new(Box::into_raw(Box::new(val))),
+            reclaim,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Snapshot of the current value, stays valid however many stores
+    /// land meanwhile. Holds back reclamation while it lives, so keep
+    /// it short or take `Guard::to_arc`
+    pub fn load(&self) -> Guard<'_, T, R> {
+        let guard = self.reclaim.pin();
+        let current = guard.protect(&self.current);
+        Guard {
+            _guard: guard,
+            current,
+        }
+    }
+
+    /// The current value as an `Arc` of its own
+    pub fn load_full(&self) -> Arc<T> {
+        self.load().to_arc()
+    }
+
+    pub fn store(&self, val: Arc<T>) {
+        drop(self.swap(val));
+    }
+
+    /// Publishes `val`, returning the value it replaced
+    pub fn swap(&self, val: Arc<T>) -> Arc<T> {
+        let guard = self.reclaim.pin();
+        let fresh = Box::into_raw(Box::new(val));
+        // SeqCst for `retire`
+        let old = self.current.swap(fresh, Ordering::SeqCst);
+        let prev = unsafe { Arc::clone(&*old) };
+        unsafe { guard.retire(old) };
+        prev
+    }
+
+    /// Publishes `f` of the current value, calling it again on a fresher
+    /// one whenever another writer got in first. Returns the value `f`
+    /// was last given, the one its result replaced
+    pub fn rcu(&self, mut f: impl FnMut(&T) -> T) -> Arc<T> {
+        let guard = self.reclaim.pin();
+        let mut current = guard.protect(&self.current);
+        loop {
+            // protected, so `current` can't be freed and its address
+            // reused before the CAS (ABA)
+            let fresh = Box::into_raw(Box::new(Arc::new(f(unsafe { &**current }))));
+            match self
+                .current
+                .compare_exchange(current, fresh, Ordering::SeqCst, Ordering::Relaxed)
+            {
+                Ok(_) => {
+                    let prev = unsafe { Arc::clone(&*current) };
+                    unsafe { guard.retire(current) };
+                    return prev;
+                }
+                Err(_) => {
+                    drop(unsafe { Box::from_raw(fresh) });
+                    current = guard.protect(&self.current);
+                }
+            }
+        }
+    }
+
+    pub fn into_inner(mut self) -> Arc<T> {
+        let current = mem::replace(self.current.get_mut(), ptr::null_mut());
+        *unsafe { Box::from_raw(current) }
+    }
+}
+
+impl<T: Default> Default for RcuCell<T> {
+    fn default() -> Self {
+        Self::new(Arc::default())
+    }
+}
+
+impl<T, R> Drop for RcuCell<T, R> {
+    fn drop(&mut self) {
+        let current = *self.current.get_mut();
+        // guard: moved out by `into_inner`
+        if !current.is_null() {
+            drop(unsafe { Box::from_raw(current) });
+        }
+    }
+}
+
+impl<T: fmt::Debug, R: Reclaim> fmt::Debug for RcuCell<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RcuCell").field(&*self.load()).finish()
+    }
+}
+
+/// Snapshot `RcuCell::load` handed out
+pub struct Guard<'a, T, R: Reclaim + 'a> {
+    _guard: R::Guard<'a>,
+    current: *const Arc<T>,
+}
+
+impl<T, R: Reclaim> Guard<'_, T, R> {
+    /// Keeps the value alive past the guard, at the cost of a refcount
+    pub fn to_arc(&self) -> Arc<T> {
+        unsafe { Arc::clone(&*self.current) }
+    }
+}
+
+impl<T, R: Reclaim> Deref for Guard<'_, T, R> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.current }
+    }
+}
+
+impl<T: fmt::Debug, R: Reclaim> fmt::Debug for Guard<'_, T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use crate::ephemeral::hazard::Hazards;
+    use core::sync::atomic::AtomicUsize;
+
+    struct DropCount<'a>(&'a AtomicUsize, u32);
+
+    impl Drop for DropCount<'_> {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_rcu() {
+        let drops = AtomicUsize::new(0);
+        let cell = RcuCell::new(Arc::new(DropCount(&drops, 1)));
+
+        // the old value outlives the store for as long as it's held
+        let snapshot = cell.load();
+        let kept = cell.swap(Arc::new(DropCount(&drops, 2)));
+        assert_eq!((snapshot.1, kept.1, cell.load().1), (1, 1, 2));
+        drop((snapshot, kept));
+
+        cell.store(Arc::new(DropCount(&drops, 3)));
+        assert_eq!(cell.load_full().1, 3);
+        drop(cell);
+        assert_eq!(drops.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_into_inner_rcu() {
+        let drops = AtomicUsize::new(0);
+        let cell = RcuCell::with_reclaim(Arc::new(DropCount(&drops, 1)), Hazards::new());
+
+        assert_eq!(cell.rcu(|old| DropCount(&drops, old.1 + 1)).1, 1);
+        let last = cell.into_inner();
+        assert_eq!(last.1, 2);
+        assert_eq!(drops.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_threaded_rcu() {
+        use std::thread;
+
+        const THREADS: usize = 4;
+        const ITEMS: usize = if cfg!(miri) { 50 } else { 5000 };
+        let cell = RcuCell::new(Arc::new(0usize));
+
+        // readers only ever see values some writer published, and
+        // every `rcu` lands exactly once
+        thread::scope(|s| {
+            for _ in 0..THREADS {
+                s.spawn(|| {
+                    for _ in 0..ITEMS {
+                        let before = *cell.load();
+                        let prev = cell.rcu(|old| old + 1);
+                        assert!(*prev >= before);
+                    }
+                });
+            }
+        });
+        assert_eq!(*cell.load(), THREADS * ITEMS);
+    }
+}
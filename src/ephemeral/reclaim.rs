@@ -0,0 +1,78 @@
+use core::{
+    fmt,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use super::epoch::{self, Collector};
+
+pub(super) mod sealed {
+    pub trait Sealed {}
+}
+
+/// How `EphemeralStack`, `SegQueue` and `RcuCell` free what they unlink,
+/// picked through their last generic parameter: `Epoch` batches frees
+/// cheaply but a stalled thread holds every free back, `Hazards` only
+/// keeps what's protected right now at the cost of a validated load
+pub trait Reclaim: Default + Send + Sync + sealed::Sealed {
+    #[doc(hidden)]
+    type Guard<'a>: Protect
+    where
+        Self: 'a;
+
+    #[doc(hidden)]
+    fn pin(&self) -> Self::Guard<'_>;
+}
+
+/// Held for the duration of one operation
+#[doc(hidden)]
+pub trait Protect {
+    /// Loads `src`, the node stays allocated while the guard lives
+    fn protect<N>(&self, src: &AtomicPtr<N>) -> *mut N;
+
+    /// # Safety
+    /// `ptr` comes from `Box::into_raw` and was unlinked with a SeqCst
+    /// operation, so guards taken from here on can't reach it
+    unsafe fn retire<N>(&self, ptr: *mut N);
+}
+
+/// Epoch-based reclamation, the default
+pub struct Epoch(Collector);
+
+impl Epoch {
+    pub const fn new() -> Self {
+        Self(Collector::new())
+    }
+}
+
+impl Default for Epoch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Epoch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Epoch").finish_non_exhaustive()
+    }
+}
+
+impl sealed::Sealed for Epoch {}
+
+impl Reclaim for Epoch {
+    type Guard<'a> = epoch::Guard<'a>;
+
+    fn pin(&self) -> epoch::Guard<'_> {
+        self.0.pin()
+    }
+}
+
+impl Protect for epoch::Guard<'_> {
+    fn protect<N>(&self, src: &AtomicPtr<N>) -> *mut N {
+        // SeqCst against the epoch, see `Collector::pin`
+        src.load(Ordering::SeqCst)
+    }
+
+    unsafe fn retire<N>(&self, ptr: *mut N) {
+        unsafe { self.defer_free(ptr) };
+    }
+}
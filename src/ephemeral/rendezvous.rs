@@ -0,0 +1,454 @@
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use core::time::Duration;
+use core::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+#[cfg(feature = "async")]
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "async")]
+use crate::util::AtomicWaker;
+
+use super::spsc::{Disconnected, PopError};
+#[cfg(any(feature = "std", feature = "async"))]
+use super::wait::Backoff;
+use super::wait::{retry, WaitStrategy};
+#[cfg(feature = "std")]
+use super::wait::{retry_until, Timeout};
+
+// phase of the current handoff, in the low bits
+const EMPTY: u8 = 0;
+const FULL: u8 = 1; // offered, the sender may still take it back
+const READING: u8 = 2; // claimed by the receiver
+const TAKEN: u8 = 3; // received, waiting for the sender to notice
+const PHASE: u8 = 0b011;
+/// set once either side is gone, next to whatever phase it left
+const CLOSED: u8 = 0b100;
+
+struct Inner<T> {
+    state: AtomicU8,
+    value: UnsafeCell<MaybeUninit<T>>,
+    #[cfg(feature = "async")]
+    sender_waker: AtomicWaker,
+    #[cfg(feature = "async")]
+    receiver_waker: AtomicWaker,
+}
+
+// the value is only touched by the side the phase hands it to
+unsafe impl<T: Send> Sync for Inner<T> {}
+
+/// Zero-capacity channel, every send waits until the receiver
+/// took that very value, so the two sides meet on each item
+/// and the sender is never more than one item ahead
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(Inner {
+        state: AtomicU8::new(EMPTY),
+        value: UnsafeCell::new(MaybeUninit::uninit()),
+        #[cfg(feature = "async")]
+        sender_waker: AtomicWaker::new(),
+        #[cfg(feature = "async")]
+        receiver_waker: AtomicWaker::new(),
+    });
+    (
+        Sender {
+            inner: inner.clone(),
+        },
+        Receiver { inner },
+    )
+}
+
+/// Sending half, holds on to an offered value until it's received
+pub struct Sender<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Sender<T> {
+    #[cfg(feature = "std")]
+    /// Waits with `Backoff` until the receiver took `val`,
+    /// hands it back if the receiver is gone
+    pub fn send(&mut self, val: T) -> Result<(), T> {
+        self.send_with(val, &mut Backoff::new())
+    }
+
+    pub fn send_with<W: WaitStrategy>(&mut self, val: T, wait: &mut W) -> Result<(), T> {
+        self.offer(val)?;
+        retry(wait, || self.poll_taken())
+    }
+
+    #[cfg(feature = "std")]
+    /// Takes `val` back once `timeout` passed without a receiver
+    /// picking it up, also when the receiver is gone
+    pub fn send_timeout(&mut self, val: T, timeout: Duration) -> Result<(), Timeout<T>> {
+        self.offer(val).map_err(Timeout)?;
+        if let Some(res) = retry_until(timeout, || self.poll_taken()) {
+            return res.map_err(Timeout);
+        }
+
+        match self.retract() {
+            Some(val) => Err(Timeout(val)),
+            // claimed just in time, the receive is as good as done
+            None => retry(&mut Backoff::new(), || self.poll_taken()).map_err(Timeout),
+        }
+    }
+
+    #[cfg(feature = "async")]
+    /// Resolves once the receiver took `val`,
+    /// or hands it back if the receiver is gone
+    pub fn send_async(&mut self, val: T) -> SendFuture<'_, T> {
+        SendFuture {
+            sender: self,
+            val: Some(val),
+            offered: false,
+        }
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.inner.state.load(Ordering::Acquire) & CLOSED != 0
+    }
+
+    /// Puts `val` up for the receiver
+    fn offer(&mut self, val: T) -> Result<(), T> {
+        let inner = &*self.inner;
+        unsafe { (*inner.value.get()).write(val) };
+
+        // outside a send the phase is always `EMPTY`, this only fails once closed
+        match inner
+            .state
+            .compare_exchange(EMPTY, FULL, Ordering::Release, Ordering::Relaxed)
+        {
+            Ok(_) => {
+                #[cfg(feature = "async")]
+                inner.receiver_waker.wake();
+                Ok(())
+            }
+            Err(_) => Err(unsafe { (*inner.value.get()).assume_init_read() }),
+        }
+    }
+
+    /// Whether the offered value is through, `None` means it's still pending
+    fn poll_taken(&mut self) -> Option<Result<(), T>> {
+        let state = self.inner.state.load(Ordering::Acquire);
+        match state & PHASE {
+            TAKEN => {
+                // only the sender moves off `TAKEN`, the receiver may just add `CLOSED`
+                self.inner.state.fetch_and(!PHASE, Ordering::Relaxed);
+                Some(Ok(()))
+            }
+            FULL if state & CLOSED != 0 => self.retract().map(Err),
+            _ => None,
+        }
+    }
+
+    /// Takes the offered value back unless the receiver already claimed it
+    fn retract(&mut self) -> Option<T> {
+        let inner = &*self.inner;
+        let mut state = inner.state.load(Ordering::Relaxed);
+        while state & PHASE == FULL {
+            match inner.state.compare_exchange_weak(
+                state,
+                state & CLOSED,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(unsafe { (*inner.value.get()).assume_init_read() }),
+                Err(current) => state = current,
+            }
+        }
+        None
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.inner.state.fetch_or(CLOSED, Ordering::Release);
+        #[cfg(feature = "async")]
+        self.inner.receiver_waker.wake();
+    }
+}
+
+/// Receiving half, each receive completes the one pending send
+pub struct Receiver<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Takes the offered value if a sender is waiting on it right now,
+    /// `Disconnected` once the sender is gone
+    pub fn try_recv(&mut self) -> Result<T, PopError> {
+        let inner = &*self.inner;
+        let state = inner.state.load(Ordering::Relaxed);
+        match state & PHASE {
+            FULL => {}
+            EMPTY if state & CLOSED != 0 => return Err(PopError::Disconnected),
+            _ => return Err(PopError::Empty),
+        }
+
+        // the sender may take it back in the meantime
+        if inner
+            .state
+            .compare_exchange(
+                state,
+                READING | (state & CLOSED),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .is_err()
+        {
+            return Err(PopError::Empty);
+        }
+
+        let val = unsafe { (*inner.value.get()).assume_init_read() };
+        // READING -> TAKEN, keeping a `CLOSED` that came in between
+        inner.state.fetch_add(TAKEN - READING, Ordering::Release);
+        #[cfg(feature = "async")]
+        inner.sender_waker.wake();
+        Ok(val)
+    }
+
+    #[cfg(feature = "std")]
+    /// Waits with `Backoff` until a value is sent or the sender is gone
+    pub fn recv(&mut self) -> Result<T, Disconnected> {
+        self.recv_with(&mut Backoff::new())
+    }
+
+    pub fn recv_with<W: WaitStrategy>(&mut self, wait: &mut W) -> Result<T, Disconnected> {
+        retry(wait, || self.attempt()).map_err(|_| Disconnected)
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up with `Empty` once `timeout` passed
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, PopError> {
+        retry_until(timeout, || self.attempt()).unwrap_or(Err(PopError::Empty))
+    }
+
+    #[cfg(feature = "async")]
+    /// Resolves with the next value, or `Disconnected` once the sender is gone
+    pub fn recv_async(&mut self) -> RecvFuture<'_, T> {
+        RecvFuture { receiver: self }
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.inner.state.load(Ordering::Acquire) & CLOSED != 0
+    }
+
+    /// One receive for the retry loops, `None` means try again
+    fn attempt(&mut self) -> Option<Result<T, PopError>> {
+        match self.try_recv() {
+            Err(PopError::Empty) => None,
+            res => Some(res),
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        // an offered value stays the sender's, it takes it back
+        self.inner.state.fetch_or(CLOSED, Ordering::Release);
+        #[cfg(feature = "async")]
+        self.inner.sender_waker.wake();
+    }
+}
+
+/// Future returned by `Sender::send_async`
+#[cfg(feature = "async")]
+pub struct SendFuture<'a, T> {
+    sender: &'a mut Sender<T>,
+    val: Option<T>,
+    offered: bool, // in the slot, waiting for the receiver
+}
+
+// the pending value is moved in and out, never pinned
+#[cfg(feature = "async")]
+impl<T> Unpin for SendFuture<'_, T> {}
+
+#[cfg(feature = "async")]
+impl<T> Future for SendFuture<'_, T> {
+    type Output = Result<(), T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if let Some(val) = this.val.take() {
+            if let Err(val) = this.sender.offer(val) {
+                return Poll::Ready(Err(val));
+            }
+            this.offered = true;
+        }
+        assert!(this.offered, "`SendFuture` polled after completion");
+
+        if let Some(res) = this.sender.poll_taken() {
+            this.offered = false;
+            return Poll::Ready(res);
+        }
+
+        // register first, then recheck so a receive in between isn't missed
+        this.sender.inner.sender_waker.register(cx.waker());
+        match this.sender.poll_taken() {
+            Some(res) => {
+                this.offered = false;
+                Poll::Ready(res)
+            }
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Dropping a pending send takes the value back,
+/// or waits out a receive that already claimed it
+#[cfg(feature = "async")]
+impl<T> Drop for SendFuture<'_, T> {
+    fn drop(&mut self) {
+        if self.offered && self.sender.retract().is_none() {
+            let _ = retry(&mut Backoff::new(), || self.sender.poll_taken());
+        }
+    }
+}
+
+/// Future returned by `Receiver::recv_async`
+#[cfg(feature = "async")]
+pub struct RecvFuture<'a, T> {
+    receiver: &'a mut Receiver<T>,
+}
+
+#[cfg(feature = "async")]
+impl<T> Future for RecvFuture<'_, T> {
+    type Output = Result<T, Disconnected>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let receiver = &mut *self.receiver;
+        if let Some(res) = receiver.attempt() {
+            return Poll::Ready(res.map_err(|_| Disconnected));
+        }
+
+        // register first, then retry so an offer in between isn't missed
+        receiver.inner.receiver_waker.register(cx.waker());
+        match receiver.attempt() {
+            Some(res) => Poll::Ready(res.map_err(|_| Disconnected)),
+            None => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use crate::ephemeral::wait::Spin;
+    #[cfg(feature = "std")]
+    use std::{sync::atomic::AtomicUsize, thread, time::Duration};
+
+    #[cfg(feature = "std")]
+    const ITEMS: usize = if cfg!(miri) { 100 } else { 2000 };
+
+    #[test]
+    fn test_seq_rendezvous() {
+        let (mut sender, mut receiver) = channel::<i32>();
+
+        // nobody waiting on either side
+        assert_eq!(receiver.try_recv(), Err(PopError::Empty));
+        drop(receiver);
+        assert!(sender.is_disconnected());
+        assert_eq!(sender.send_with(1, &mut Spin), Err(1));
+
+        let (sender, mut receiver) = channel::<i32>();
+        drop(sender);
+        assert_eq!(receiver.try_recv(), Err(PopError::Disconnected));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_timeout_rendezvous() {
+        let (mut sender, mut receiver) = channel();
+        let timeout = Duration::from_millis(10);
+
+        // without a receiver the value never leaves
+        assert_eq!(sender.send_timeout(7, timeout), Err(Timeout(7)));
+        assert_eq!(receiver.try_recv(), Err(PopError::Empty));
+        assert_eq!(receiver.recv_timeout(timeout), Err(PopError::Empty));
+
+        let recv_t = thread::spawn(move || {
+            thread::sleep(timeout);
+            receiver.recv()
+        });
+        assert_eq!(sender.send_timeout(8, Duration::from_secs(10)), Ok(()));
+        assert_eq!(recv_t.join().unwrap(), Ok(8));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_pairing_rendezvous() {
+        let (mut sender, mut receiver) = channel();
+        let sent = Arc::new(AtomicUsize::new(0));
+
+        let send_t = {
+            let sent = sent.clone();
+            thread::spawn(move || {
+                for i in 0..ITEMS {
+                    sender.send(i).unwrap();
+                    sent.store(i + 1, Ordering::SeqCst);
+                }
+            })
+        };
+
+        for i in 0..ITEMS {
+            assert_eq!(receiver.recv(), Ok(i));
+            if i % 64 == 0 {
+                thread::sleep(Duration::from_micros(100));
+            }
+            // the sender can't get past the item that wasn't received yet
+            assert!(sent.load(Ordering::SeqCst) <= i + 1);
+        }
+        send_t.join().unwrap();
+        assert_eq!(receiver.recv(), Err(Disconnected));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_disconnect_rendezvous() {
+        // a sender blocked on a receiver that leaves gets the value back
+        let (mut sender, receiver) = channel();
+        let send_t = thread::spawn(move || sender.send(String::from("lost")));
+        thread::sleep(Duration::from_millis(10));
+        drop(receiver);
+        assert_eq!(send_t.join().unwrap(), Err(String::from("lost")));
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", feature = "async"))]
+    fn test_async_rendezvous() {
+        use futures::executor::block_on;
+
+        let (mut sender, mut receiver) = channel();
+        let send_t = thread::spawn(move || {
+            block_on(async {
+                for i in 0..ITEMS {
+                    sender.send_async(i).await.unwrap();
+                }
+            })
+        });
+
+        block_on(async {
+            for i in 0..ITEMS {
+                assert_eq!(receiver.recv_async().await, Ok(i));
+            }
+            assert_eq!(receiver.recv_async().await, Err(Disconnected));
+        });
+        send_t.join().unwrap();
+
+        // a send dropped before anyone took it takes the value back
+        let (mut sender, mut receiver) = channel::<i32>();
+        let waker = futures::task::noop_waker();
+        let mut send = sender.send_async(1);
+        assert!(Pin::new(&mut send)
+            .poll(&mut Context::from_waker(&waker))
+            .is_pending());
+        drop(send);
+        assert_eq!(receiver.try_recv(), Err(PopError::Empty));
+    }
+}
@@ -0,0 +1,331 @@
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    thread::{self, ScopedJoinHandle},
+};
+
+use super::{mpmc::MPMCEphemeral, segment::SegQueue, spsc, stack::EphemeralStack};
+use super::{reclaim::Reclaim, wait::Backoff};
+
+/// A queue `Scope::produce` can push into through a shared borrow
+pub trait Push: Sync {
+    type Item: Send;
+    /// false if only one thread may ever push, `produce` then
+    /// refuses a second producer
+    const MANY: bool;
+
+    fn push(&self, val: Self::Item) -> Result<(), Self::Item>;
+}
+
+/// A queue `Scope::consume` can pop from through a shared borrow
+pub trait Pop: Sync {
+    type Item: Send;
+    /// false if only one thread may ever pop, `consume` then
+    /// refuses a second consumer
+    const MANY: bool;
+
+    fn pop(&self) -> Option<Self::Item>;
+}
+
+impl<R: spsc::Ring + Sync> Push for R
+where
+    R::Item: Send,
+{
+    type Item = R::Item;
+    const MANY: bool = false;
+
+    fn push(&self, val: R::Item) -> Result<(), R::Item> {
+        spsc::push(self, val)
+    }
+}
+
+impl<R: spsc::Ring + Sync> Pop for R
+where
+    R::Item: Send,
+{
+    type Item = R::Item;
+    const MANY: bool = false;
+
+    fn pop(&self) -> Option<R::Item> {
+        spsc::pop(self)
+    }
+}
+
+impl<T: Send, const N: usize> Push for MPMCEphemeral<T, N> {
+    type Item = T;
+    const MANY: bool = true;
+
+    fn push(&self, val: T) -> Result<(), T> {
+        MPMCEphemeral::push(self, val)
+    }
+}
+
+impl<T: Send, const N: usize> Pop for MPMCEphemeral<T, N> {
+    type Item = T;
+    const MANY: bool = true;
+
+    fn pop(&self) -> Option<T> {
+        MPMCEphemeral::pop(self)
+    }
+}
+
+impl<T: Send, R: Reclaim> Push for SegQueue<T, R> {
+    type Item = T;
+    const MANY: bool = true;
+
+    fn push(&self, val: T) -> Result<(), T> {
+        SegQueue::push(self, val);
+        Ok(())
+    }
+}
+
+impl<T: Send, R: Reclaim> Pop for SegQueue<T, R> {
+    type Item = T;
+    const MANY: bool = true;
+
+    fn pop(&self) -> Option<T> {
+        SegQueue::pop(self)
+    }
+}
+
+impl<T: Send, R: Reclaim> Push for EphemeralStack<T, R> {
+    type Item = T;
+    const MANY: bool = true;
+
+    fn push(&self, val: T) -> Result<(), T> {
+        EphemeralStack::push(self, val);
+        Ok(())
+    }
+}
+
+impl<T: Send, R: Reclaim> Pop for EphemeralStack<T, R> {
+    type Item = T;
+    const MANY: bool = true;
+
+    fn pop(&self) -> Option<T> {
+        EphemeralStack::pop(self)
+    }
+}
+
+/// Who works on one buffer, keyed by its address
+struct Buffer {
+    addr: usize,
+    producers: usize,
+    consumers: usize,
+    live: Arc<Live>,
+}
+
+#[derive(Default)]
+struct Live {
+    producers: AtomicUsize,
+    consumers: AtomicUsize,
+}
+
+#[derive(Default)]
+struct State {
+    buffers: Mutex<Vec<Buffer>>,
+    sealed: AtomicBool, // the closure passed to `run` returned
+}
+
+impl State {
+    /// Counts one more producer or consumer of `addr`,
+    /// panicking if the buffer only takes one
+    fn register(&self, addr: usize, producer: bool, many: bool) -> Arc<Live> {
+        let mut buffers = self.buffers.lock().unwrap_or_else(PoisonError::into_inner);
+        let idx = match buffers.iter().position(|buffer| buffer.addr == addr) {
+            Some(idx) => idx,
+            None => {
+                buffers.push(Buffer {
+                    addr,
+                    producers: 0,
+                    consumers: 0,
+                    live: Arc::default(),
+                });
+                buffers.len() - 1
+            }
+        };
+
+        let buffer = &mut buffers[idx];
+        let (count, live, role) = if producer {
+            (&mut buffer.producers, &buffer.live.producers, "producer")
+        } else {
+            (&mut buffer.consumers, &buffer.live.consumers, "consumer")
+        };
+        assert!(
+            many || *count == 0,
+            "a second {role} on a single-{role} queue"
+        );
+        *count += 1;
+        live.fetch_add(1, Ordering::Relaxed);
+        buffer.live.clone()
+    }
+}
+
+/// Counts its thread out however it ends, panics included
+struct Leave<'a>(&'a AtomicUsize);
+
+impl Drop for Leave<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// Runs `f` with a scope for producer and consumer threads that
+/// borrow their buffer, e.g. one living on the caller's stack, and
+/// returns once every one of them finished, like `thread::scope`
+///
+/// Consumers keep popping until everything registered to produce
+/// into their buffer by the time `f` returns is done and drained
+pub fn run<'env, F, T>(f: F) -> T
+where
+    F: for<'scope> FnOnce(&Scope<'scope, 'env>) -> T,
+{
+    thread::scope(|inner| {
+        let scope = Scope {
+            inner,
+            state: Arc::default(),
+        };
+        let out = f(&scope);
+        scope.state.sealed.store(true, Ordering::Release);
+        out
+    })
+}
+
+/// Spawns the threads of a `run` call
+pub struct Scope<'scope, 'env: 'scope> {
+    inner: &'scope thread::Scope<'scope, 'env>,
+    state: Arc<State>,
+}
+
+impl<'scope> Scope<'scope, '_> {
+    /// Pushes each of `items` into `buf` on a thread of its own,
+    /// waiting while it's full
+    ///
+    /// Panics if `buf` takes a single producer and already has one,
+    /// the thread panics if `buf` stays full once every consumer
+    /// `consume` started on it is gone
+    pub fn produce<Q, I>(&self, buf: &'scope Q, items: I) -> ScopedJoinHandle<'scope, ()>
+    where
+        Q: Push,
+        I: IntoIterator<Item = Q::Item> + Send + 'scope,
+    {
+        let live = self.state.register(addr(buf), true, Q::MANY);
+        let state = self.state.clone();
+        self.inner.spawn(move || {
+            let _leave = Leave(&live.producers);
+            for mut val in items {
+                let mut backoff = Backoff::new();
+                while let Err(back) = buf.push(val) {
+                    // guard: nobody is left to make room
+                    if state.sealed.load(Ordering::Acquire)
+                        && live.consumers.load(Ordering::Acquire) == 0
+                    {
+                        panic!("pushing onto a full buffer nobody consumes");
+                    }
+                    val = back;
+                    backoff.snooze();
+                }
+            }
+        })
+    }
+
+    /// Pops from `buf` on a thread of its own, handing each item to
+    /// `f`, until its producers finished and it ran dry
+    ///
+    /// Panics if `buf` takes a single consumer and already has one
+    pub fn consume<Q, F>(&self, buf: &'scope Q, mut f: F) -> ScopedJoinHandle<'scope, ()>
+    where
+        Q: Pop,
+        F: FnMut(Q::Item) + Send + 'scope,
+    {
+        let live = self.state.register(addr(buf), false, Q::MANY);
+        let state = self.state.clone();
+        self.inner.spawn(move || {
+            let _leave = Leave(&live.consumers);
+            let mut backoff = Backoff::new();
+            loop {
+                if let Some(val) = buf.pop() {
+                    f(val);
+                    backoff.reset();
+                    continue;
+                }
+                // every push happened before the count dropped,
+                // so one more empty pop means drained for good
+                if state.sealed.load(Ordering::Acquire)
+                    && live.producers.load(Ordering::Acquire) == 0
+                {
+                    match buf.pop() {
+                        Some(val) => f(val),
+                        None => return,
+                    }
+                    continue;
+                }
+                backoff.snooze();
+            }
+        })
+    }
+
+    /// Spawns any other thread into the scope, see `thread::Scope::spawn`
+    pub fn spawn<F, T>(&self, f: F) -> ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce() -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        self.inner.spawn(f)
+    }
+}
+
+fn addr<Q>(buf: &Q) -> usize {
+    buf as *const Q as usize
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use crate::ephemeral::spsc::SPSCEphemeral;
+
+    #[test]
+    fn test_borrowed_scope() {
+        const ITEMS: u32 = if cfg!(miri) { 100 } else { 10000 };
+        let buf = SPSCEphemeral::<u32, 8>::new();
+        let mut seen = Vec::new();
+
+        // a stack buffer and a borrowed sink, no Arc in sight
+        run(|s| {
+            s.produce(&buf, 0..ITEMS);
+            s.consume(&buf, |val| seen.push(val));
+        });
+        assert_eq!(seen, (0..ITEMS).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_many_scope() {
+        const ITEMS: u32 = if cfg!(miri) { 50 } else { 5000 };
+        let buf = MPMCEphemeral::<u32, 16>::new();
+        let total = AtomicUsize::new(0);
+
+        // consumers registered first still wait for the producers
+        run(|s| {
+            for _ in 0..2 {
+                s.consume(&buf, |val| {
+                    total.fetch_add(val as usize, Ordering::Relaxed);
+                });
+            }
+            for p in 0..3 {
+                s.produce(&buf, (0..ITEMS).map(move |i| p * ITEMS + i));
+            }
+        });
+        let expected = (0..3 * ITEMS as usize).sum::<usize>();
+        assert_eq!(total.into_inner(), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "a second producer on a single-producer queue")]
+    fn test_single_scope() {
+        let buf = SPSCEphemeral::<u32, 8>::new();
+        run(|s| {
+            s.produce(&buf, 0..4);
+            s.produce(&buf, 4..8);
+        });
+    }
+}
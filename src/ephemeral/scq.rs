@@ -0,0 +1,468 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::time::Duration;
+use core::{
+    cell::UnsafeCell,
+    iter,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicIsize, AtomicUsize, Ordering},
+};
+
+use crate::util::CachePadded;
+
+use super::mpmc::BoundedMpmc;
+#[cfg(feature = "stats")]
+use super::stats::{Counters, Stats};
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+#[cfg(feature = "std")]
+use super::wait::{push_until, retry_until, Timeout};
+
+/// Bounded multi-producer/multi-consumer ring (SCQ, Nikolaev 2019),
+/// producers and consumers take tickets by fetch-add instead of
+/// racing a CAS on the indices, so heavy contention costs no retries
+///
+/// Slots are handed around by index through two rings of `2 * N`
+/// cycle-stamped entries, `free` holding the empty slots and `taken`
+/// the full ones in push order. A push moves an index from the one to
+/// the other, a pop moves it back
+///
+/// `MPMCEphemeral` is quicker uncontended, see `BoundedMpmc`
+/// N:: arena size, a power of two
+pub struct SCQEphemeral<T, const N: usize> {
+    bufr: [UnsafeCell<MaybeUninit<T>>; N],
+    taken: IndexRing<N>,
+    free: IndexRing<N>,
+    #[cfg(feature = "stats")]
+    stats: Counters,
+    #[cfg(feature = "tracing")]
+    trace: Label,
+}
+
+impl<T, const N: usize> SCQEphemeral<T, N> {
+    pub const fn new() -> Self {
+        const { assert!(N.is_power_of_two(), "arena size must be a power of two") };
+
+        Self {
+            bufr: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            taken: IndexRing::empty(),
+            free: IndexRing::full(),
+            #[cfg(feature = "stats")]
+            stats: Counters::new(),
+            #[cfg(feature = "tracing")]
+            trace: Label::new("scq"),
+        }
+    }
+
+    /// Names the ring in its `tracing` events, `scq` otherwise
+    #[cfg(feature = "tracing")]
+    pub const fn with_name(mut self, name: &'static str) -> Self {
+        self.trace = Label::new(name);
+        self
+    }
+
+    pub fn push(&self, val: T) -> Result<(), T> {
+        let res = match self.free.dequeue() {
+            Some(idx) => {
+                // the index is ours alone until it goes into `taken`
+                unsafe { (*self.bufr[idx].get()).write(val) };
+                self.taken.enqueue(idx);
+                Ok(())
+            }
+            None => Err(val),
+        };
+        #[cfg(feature = "stats")]
+        match res {
+            Ok(()) => self.stats.pushed(1, self.len()),
+            Err(_) => self.stats.full(),
+        }
+        #[cfg(feature = "tracing")]
+        match res {
+            Ok(()) => self.trace.pushed(1, self.len()),
+            Err(_) => self.trace.full(),
+        }
+        res
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let val = self.taken.dequeue().map(|idx| {
+            // same, until it goes back into `free`
+            let val = unsafe { (*self.bufr[idx].get()).assume_init_read() };
+            self.free.enqueue(idx);
+            val
+        });
+        #[cfg(feature = "stats")]
+        match val {
+            Some(_) => self.stats.popped(1),
+            None => self.stats.empty(),
+        }
+        #[cfg(feature = "tracing")]
+        match val {
+            Some(_) => self.trace.popped(1),
+            None => self.trace.empty(),
+        }
+        val
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up once `timeout` passed, handing the value back
+    pub fn push_timeout(&self, val: T, timeout: Duration) -> Result<(), Timeout<T>> {
+        #[cfg(feature = "tracing")]
+        let _span = self.trace.blocking("push_timeout");
+        push_until(val, timeout, |val| self.push(val))
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up with `None` once `timeout` passed
+    pub fn pop_timeout(&self, timeout: Duration) -> Option<T> {
+        #[cfg(feature = "tracing")]
+        let _span = self.trace.blocking("pop_timeout");
+        retry_until(timeout, || self.pop())
+    }
+
+    /// Pending items, approximate while other handles are busy,
+    /// pushes count from their ticket on
+    pub fn len(&self) -> usize {
+        self.taken.len()
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.stats.snapshot()
+    }
+
+    /// Drops every pending item
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Remaining items in pop order
+    pub fn into_inner(self) -> Vec<T> {
+        iter::from_fn(|| self.pop()).collect()
+    }
+}
+
+impl<T, const N: usize> Default for SCQEphemeral<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for SCQEphemeral<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+unsafe impl<T: Send, const N: usize> Sync for SCQEphemeral<T, N> {}
+
+impl<T: Send, const N: usize> BoundedMpmc for SCQEphemeral<T, N> {
+    type Item = T;
+
+    fn push(&self, val: T) -> Result<(), T> {
+        SCQEphemeral::push(self, val)
+    }
+
+    fn pop(&self) -> Option<T> {
+        SCQEphemeral::pop(self)
+    }
+
+    fn len(&self) -> usize {
+        SCQEphemeral::len(self)
+    }
+
+    fn capacity(&self) -> usize {
+        N
+    }
+}
+
+/// Reloads of an empty entry a pop spends waiting on the push that
+/// took its ticket, before closing the entry on it
+const SPINS: usize = 128;
+
+/// Ring of `2 * N` entries carrying slot indices below `N`, each entry
+/// stamped with the cycle `pos / (2 * N)` it was last written in.
+/// An entry packs, low bits first, the index, `BOTTOM` for none, then
+/// a safe bit, cleared by a pop that passed the entry while a push
+/// of an older cycle still held it, then the cycle
+///
+/// Never holds more than `N` indices, so a push always finds an entry
+/// within a lap. `threshold` bounds how far pops keep walking once
+/// the ring might be empty, below zero they stop at the first check
+struct IndexRing<const N: usize> {
+    head: CachePadded<AtomicUsize>,
+    tail: CachePadded<AtomicUsize>,
+    threshold: CachePadded<AtomicIsize>,
+    entries: [[AtomicUsize; 2]; N],
+}
+
+impl<const N: usize> IndexRing<N> {
+    const SLOTS: usize = 2 * N;
+    /// Index bits all set, no index
+    const BOTTOM: usize = Self::SLOTS - 1;
+    const SAFE: usize = Self::SLOTS;
+    /// Index and safe bits all set, `entry | CYCLE` leaves the cycle
+    const CYCLE: usize = 2 * Self::SLOTS - 1;
+    /// Failed pops it takes to walk past every pending index
+    const THRESHOLD: isize = 3 * N as isize - 1;
+
+    const fn empty() -> Self {
+        Self {
+            head: CachePadded::new(AtomicUsize::new(0)),
+            tail: CachePadded::new(AtomicUsize::new(0)),
+            threshold: CachePadded::new(AtomicIsize::new(-1)),
+            // an all ones cycle is one before cycle 0, safe and empty
+            entries: [const { [const { AtomicUsize::new(usize::MAX) }; 2] }; N],
+        }
+    }
+
+    /// Holding every index, in order
+    const fn full() -> Self {
+        let mut ring = Self::empty();
+        let mut idx = 0;
+        while idx < N {
+            // cycle 0, safe
+            ring.entries[idx / 2][idx % 2] = AtomicUsize::new(Self::SAFE | idx);
+            idx += 1;
+        }
+        ring.tail = CachePadded::new(AtomicUsize::new(N));
+        ring.threshold = CachePadded::new(AtomicIsize::new(Self::THRESHOLD));
+        ring
+    }
+
+    fn entry(&self, pos: usize) -> &AtomicUsize {
+        &self.entries.as_flattened()[pos & (Self::SLOTS - 1)]
+    }
+
+    fn enqueue(&self, idx: usize) {
+        loop {
+            let tail = self.tail.fetch_add(1, Ordering::AcqRel);
+            let cycle = (tail << 1) | Self::CYCLE;
+            let slot = self.entry(tail);
+            let mut entry = slot.load(Ordering::Acquire);
+
+            loop {
+                let ecycle = entry | Self::CYCLE;
+                // empty from an older cycle, and if a pop marked it
+                // unsafe, no pop may have passed this ticket since
+                let open = entry == ecycle
+                    || (entry == ecycle ^ Self::SAFE
+                        && !before(tail, self.head.load(Ordering::Acquire)));
+
+                // guard: taken or closed, on to the next ticket
+                if !(before(ecycle, cycle) && open) {
+                    break;
+                }
+
+                match slot.compare_exchange_weak(
+                    entry,
+                    (cycle & !Self::BOTTOM) | idx,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                ) {
+                    Ok(_) => {
+                        if self.threshold.load(Ordering::Acquire) != Self::THRESHOLD {
+                            self.threshold.store(Self::THRESHOLD, Ordering::Release);
+                        }
+                        return;
+                    }
+                    Err(current) => entry = current,
+                }
+            }
+        }
+    }
+
+    fn dequeue(&self) -> Option<usize> {
+        // guard: walked past everything, empty
+        if self.threshold.load(Ordering::Acquire) < 0 {
+            return None;
+        }
+
+        loop {
+            let head = self.head.fetch_add(1, Ordering::AcqRel);
+            let cycle = (head << 1) | Self::CYCLE;
+            let slot = self.entry(head);
+            let mut spins = 0;
+
+            'entry: loop {
+                let mut entry = slot.load(Ordering::Acquire);
+                loop {
+                    let ecycle = entry | Self::CYCLE;
+                    if ecycle == cycle {
+                        // keeps cycle and safe bit, drops the index
+                        slot.fetch_or(Self::BOTTOM, Ordering::AcqRel);
+                        return Some(entry & Self::BOTTOM);
+                    }
+
+                    let closed = if entry | Self::SAFE != ecycle {
+                        // another cycle's index, whoever pops it next
+                        // lap must not see this entry as reusable
+                        let unsafe_entry = entry & !Self::SAFE;
+                        if entry == unsafe_entry {
+                            break 'entry;
+                        }
+                        unsafe_entry
+                    } else {
+                        // a push may be about to fill it
+                        spins += 1;
+                        if spins <= SPINS {
+                            continue 'entry;
+                        }
+                        cycle ^ (!entry & Self::SAFE)
+                    };
+
+                    // guard: a newer cycle already, nothing to close
+                    if !before(ecycle, cycle) {
+                        break 'entry;
+                    }
+                    match slot.compare_exchange_weak(
+                        entry,
+                        closed,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    ) {
+                        Ok(_) => break 'entry,
+                        Err(current) => entry = current,
+                    }
+                }
+            }
+
+            let tail = self.tail.load(Ordering::Acquire);
+            if !before(head.wrapping_add(1), tail) {
+                self.catch_up(tail, head.wrapping_add(1));
+                self.threshold.fetch_sub(1, Ordering::AcqRel);
+                return None;
+            }
+            if self.threshold.fetch_sub(1, Ordering::AcqRel) <= 0 {
+                return None;
+            }
+        }
+    }
+
+    /// Pulls `tail` up to `head` once pops overtook it, so pushes
+    /// don't take tickets that were already passed
+    fn catch_up(&self, mut tail: usize, mut head: usize) {
+        while self
+            .tail
+            .compare_exchange_weak(tail, head, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            head = self.head.load(Ordering::Acquire);
+            tail = self.tail.load(Ordering::Acquire);
+            if !before(tail, head) {
+                break;
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        // failed pops run `head` past `tail` until they catch it up
+        (tail.wrapping_sub(head) as isize).clamp(0, N as isize) as usize
+    }
+}
+
+/// `a < b`, for positions and cycles that may have wrapped
+fn before(a: usize, b: usize) -> bool {
+    (a.wrapping_sub(b) as isize) < 0
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use std::{sync::Arc, thread};
+
+    const ITEMS: usize = if cfg!(miri) { 200 } else { 10000 };
+
+    #[test]
+    fn test_seq_scq() {
+        let src = SCQEphemeral::<usize, 4>::new();
+        assert_eq!(src.pop(), None);
+
+        for lap in 0..100 {
+            for i in 0..4 {
+                assert!(src.push(lap * 4 + i).is_ok());
+            }
+            assert_eq!(src.push(0), Err(0));
+            assert!(src.is_full());
+
+            for i in 0..4 {
+                assert_eq!(src.pop(), Some(lap * 4 + i));
+            }
+            assert_eq!(src.pop(), None);
+            assert!(src.is_empty());
+        }
+        // a lone slot works too
+        let one = SCQEphemeral::<u8, 1>::new();
+        assert_eq!(
+            (one.push(1), one.push(2), one.pop()),
+            (Ok(()), Err(2), Some(1))
+        );
+    }
+
+    #[test]
+    fn test_threaded_scq() {
+        let src = Arc::new(SCQEphemeral::<usize, 16>::new());
+
+        let producers: Vec<_> = (0..4)
+            .map(|p| {
+                let producer = src.clone();
+                thread::spawn(move || {
+                    for i in p * ITEMS..(p + 1) * ITEMS {
+                        while producer.push(i).is_err() {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let consumers: Vec<_> = (0..4)
+            .map(|_| {
+                let consumer = src.clone();
+                thread::spawn(move || {
+                    let mut seen = Vec::with_capacity(ITEMS);
+                    while seen.len() < ITEMS {
+                        match consumer.pop() {
+                            Some(val) => seen.push(val),
+                            None => thread::yield_now(),
+                        }
+                    }
+                    seen
+                })
+            })
+            .collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        let mut seen: Vec<_> = consumers
+            .into_iter()
+            .flat_map(|consumer| consumer.join().unwrap())
+            .collect();
+        seen.sort_unstable();
+        assert_eq!(seen, (0..4 * ITEMS).collect::<Vec<_>>());
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn test_drop_scq() {
+        let val = Arc::new(());
+        let src = SCQEphemeral::<Arc<()>, 4>::new();
+        for _ in 0..3 {
+            src.push(val.clone()).unwrap();
+        }
+        drop(src.pop());
+        assert_eq!(Arc::strong_count(&val), 3);
+        drop(src);
+        assert_eq!(Arc::strong_count(&val), 1);
+    }
+}
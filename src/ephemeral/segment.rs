@@ -0,0 +1,319 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    iter, ptr,
+    sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
+};
+
+use crate::util::CachePadded;
+
+use super::reclaim::{Epoch, Protect, Reclaim};
+use super::seq::{pop_shared_once, push_shared, slots, SeqSlot};
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+
+/// slots per segment
+const SEGMENT: usize = 32;
+
+/// One lap of a stamped-slot ring, written through once and
+/// then unlinked, so `head`/`tail` never go past `SEGMENT`
+struct Segment<T> {
+    bufr: [SeqSlot<T>; SEGMENT],
+    head: CachePadded<AtomicUsize>, // read position
+    tail: CachePadded<AtomicUsize>, // write position
+    next: AtomicPtr<Segment<T>>,
+}
+
+impl<T> Segment<T> {
+    fn alloc() -> *mut Self {
+        Box::into_raw(Box::new(Self {
+            bufr: slots(),
+            head: CachePadded::new(AtomicUsize::new(0)),
+            tail: CachePadded::new(AtomicUsize::new(0)),
+            next: AtomicPtr::new(ptr::null_mut()),
+        }))
+    }
+}
+
+/// Unbounded multi-producer/multi-consumer queue of linked segments,
+/// each one a single lap of the bounded MPMC ring layout. A full
+/// segment links the next, a drained one is unlinked and freed
+/// through R, epochs unless picked otherwise, once no push or pop
+/// can still look at it
+pub struct SegQueue<T, R = Epoch> {
+    head: CachePadded<AtomicPtr<Segment<T>>>, // oldest segment
+    tail: CachePadded<AtomicPtr<Segment<T>>>, // newest segment
+    reclaim: R,                               // frees drained segments
+    #[cfg(feature = "tracing")]
+    trace: Label,
+}
+
+impl<T> SegQueue<T> {
+    pub fn new() -> Self {
+        Self::with_reclaim(Epoch::new())
+    }
+}
+
+impl<T, R: Reclaim> SegQueue<T, R> {
+    /// Frees drained segments through `reclaim`, e.g. `Hazards`
+    pub fn with_reclaim(reclaim: R) -> Self {
+        let segment = Segment::alloc();
+        Self {
+            head: CachePadded::new(AtomicPtr::new(segment)),
+            tail: CachePadded::new(AtomicPtr::new(segment)),
+            reclaim,
+            #[cfg(feature = "tracing")]
+            trace: Label::new("segment"),
+        }
+    }
+
+    /// Names the queue in its `tracing` events, `segment` otherwise
+    #[cfg(feature = "tracing")]
+    pub fn with_name(mut self, name: &'static str) -> Self {
+        self.trace = Label::new(name);
+        self
+    }
+
+    pub fn push(&self, mut val: T) {
+        let guard = self.reclaim.pin();
+
+        loop {
+            let ptr = guard.protect(&self.tail);
+            let segment = unsafe { &*ptr };
+            match push_shared(&segment.bufr, &segment.tail, val) {
+                Ok(()) => break,
+                Err(back) => val = back,
+            }
+
+            // full, link a fresh segment unless someone beat us to it
+            let mut next = segment.next.load(Ordering::Acquire);
+            if next.is_null() {
+                let fresh = Segment::alloc();
+                next = match segment.next.compare_exchange(
+                    ptr::null_mut(),
+                    fresh,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                ) {
+                    Ok(_) => fresh,
+                    Err(current) => {
+                        drop(unsafe { Box::from_raw(fresh) });
+                        current
+                    }
+                };
+            }
+            let _ = self
+                .tail
+                .compare_exchange(ptr, next, Ordering::SeqCst, Ordering::Relaxed);
+        }
+        #[cfg(feature = "tracing")]
+        self.trace.pushed_unbounded(1);
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = self.reclaim.pin();
+
+        loop {
+            let ptr = guard.protect(&self.head);
+            let segment = unsafe { &*ptr };
+            if let Some(val) = pop_shared_once(&segment.bufr, &segment.head) {
+                #[cfg(feature = "tracing")]
+                self.trace.popped(1);
+                return Some(val);
+            }
+
+            // guard: empty, the segment still has room or nothing follows it
+            let next = segment.next.load(Ordering::Acquire);
+            if segment.head.load(Ordering::Acquire) < SEGMENT || next.is_null() {
+                #[cfg(feature = "tracing")]
+                self.trace.empty();
+                return None;
+            }
+
+            // drained, move on; the tail must not point at a retired segment
+            if self
+                .head
+                .compare_exchange(ptr, next, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+            {
+                let _ = self
+                    .tail
+                    .compare_exchange(ptr, next, Ordering::SeqCst, Ordering::Relaxed);
+                unsafe { guard.retire(ptr) };
+            }
+        }
+    }
+
+    /// Drops every pending item
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Remaining items in pop order
+    pub fn into_inner(self) -> Vec<T> {
+        iter::from_fn(|| self.pop()).collect()
+    }
+}
+
+impl<T, R: Reclaim> Default for SegQueue<T, R> {
+    fn default() -> Self {
+        Self::with_reclaim(R::default())
+    }
+}
+
+impl<T, R> Drop for SegQueue<T, R> {
+    fn drop(&mut self) {
+        let mut segment = *self.head.get_mut();
+        while !segment.is_null() {
+            let mut owned = unsafe { Box::from_raw(segment) };
+            let head = *owned.head.get_mut();
+            let tail = (*owned.tail.get_mut()).min(SEGMENT);
+            for slot in &mut owned.bufr[head..tail] {
+                unsafe { slot.value.get_mut().assume_init_drop() };
+            }
+            segment = *owned.next.get_mut();
+        }
+    }
+}
+
+unsafe impl<T: Send, R: Send> Send for SegQueue<T, R> {}
+unsafe impl<T: Send, R: Sync> Sync for SegQueue<T, R> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_seq_segment() {
+        let queue = SegQueue::new();
+
+        assert_eq!(queue.pop(), None);
+        for lap in 0..3 {
+            // spans several segments
+            for i in 0..100 {
+                queue.push(lap * 100 + i);
+            }
+            for i in 0..100 {
+                assert_eq!(queue.pop(), Some(lap * 100 + i));
+            }
+            assert_eq!(queue.pop(), None);
+        }
+    }
+
+    #[test]
+    fn test_threaded_segment() {
+        const THREADS: usize = 4;
+        const ITEMS: usize = if cfg!(miri) { 100 } else { 5000 };
+        let queue = SegQueue::new();
+        let popped = AtomicUsize::new(0);
+
+        let mut seen: Vec<_> = thread::scope(|s| {
+            for t in 0..THREADS {
+                let queue = &queue;
+                s.spawn(move || {
+                    for i in 0..ITEMS {
+                        queue.push(t * ITEMS + i);
+                    }
+                });
+            }
+            let consume_ts: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    let (queue, popped) = (&queue, &popped);
+                    s.spawn(move || {
+                        let mut seen = Vec::new();
+                        while popped.load(Ordering::Relaxed) < THREADS * ITEMS {
+                            match queue.pop() {
+                                Some(val) => {
+                                    seen.push(val);
+                                    popped.fetch_add(1, Ordering::Relaxed);
+                                }
+                                None => thread::yield_now(),
+                            }
+                        }
+                        seen
+                    })
+                })
+                .collect();
+            consume_ts
+                .into_iter()
+                .flat_map(|consume_t| consume_t.join().unwrap())
+                .collect()
+        });
+
+        seen.sort_unstable();
+        assert_eq!(seen, (0..THREADS * ITEMS).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_hazard_segment() {
+        use crate::ephemeral::hazard::Hazards;
+
+        const THREADS: usize = 4;
+        const ITEMS: usize = if cfg!(miri) { 100 } else { 5000 };
+        let queue = SegQueue::<usize, Hazards>::default();
+
+        // every thread sees each producer's items in order
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let queue = &queue;
+                s.spawn(move || {
+                    let mut last = [None; THREADS];
+                    for i in 0..ITEMS {
+                        queue.push(t * ITEMS + i);
+                        // another push may still be writing the oldest slot
+                        let val = loop {
+                            match queue.pop() {
+                                Some(val) => break val,
+                                None => thread::yield_now(),
+                            }
+                        };
+                        let from = val / ITEMS;
+                        assert!(last[from] < Some(val));
+                        last[from] = Some(val);
+                    }
+                });
+            }
+        });
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_drop_segment() {
+        struct DropCount(Arc<AtomicUsize>);
+
+        impl Drop for DropCount {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let drops = Arc::new(AtomicUsize::new(0));
+        let queue = SegQueue::new();
+
+        for _ in 0..100 {
+            queue.push(DropCount(drops.clone()));
+        }
+        for _ in 0..40 {
+            drop(queue.pop());
+        }
+        assert_eq!(drops.load(Ordering::Relaxed), 40);
+        drop(queue);
+        assert_eq!(drops.load(Ordering::Relaxed), 100);
+    }
+
+    #[test]
+    fn test_into_inner_segment() {
+        let mut queue = SegQueue::new();
+        for i in 0..50 {
+            queue.push(i);
+        }
+        queue.clear();
+        assert_eq!(queue.pop(), None);
+
+        for i in 0..70 {
+            queue.push(i);
+        }
+        assert_eq!(queue.into_inner(), (0..70).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,186 @@
+use alloc::{boxed::Box, vec::Vec};
+#[cfg(feature = "std")]
+use core::time::Duration;
+
+#[cfg(feature = "std")]
+use super::spsc::Disconnected;
+use super::spsc::{Consumer, PopError, Ring};
+#[cfg(feature = "std")]
+use super::wait::{retry, retry_until, Backoff};
+
+/// A queue `Select` can pop from
+trait Source<T> {
+    fn try_pop(&mut self) -> Result<T, PopError>;
+}
+
+impl<R: Ring> Source<R::Item> for &mut Consumer<R> {
+    fn try_pop(&mut self) -> Result<R::Item, PopError> {
+        self.pop()
+    }
+}
+
+/// Waits on several consumers at once, handing out the first item
+/// any of them has along with the index it was registered under
+///
+/// Polling starts past the consumer served last, so a busy queue
+/// can't starve the others. Disconnected consumers are skipped, once
+/// all of them are drained every call reports `Disconnected`
+pub struct Select<'a, T> {
+    sources: Vec<Box<dyn Source<T> + 'a>>,
+    next: usize, // where the next round starts polling
+}
+
+impl<'a, T> Select<'a, T> {
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+            next: 0,
+        }
+    }
+
+    /// Registers `consumer`, returning the index its items come with
+    pub fn add<R: Ring<Item = T>>(&mut self, consumer: &'a mut Consumer<R>) -> usize {
+        self.sources.push(Box::new(consumer));
+        self.sources.len() - 1
+    }
+
+    /// Builder flavour of `add`, indices count up from 0
+    pub fn with<R: Ring<Item = T>>(mut self, consumer: &'a mut Consumer<R>) -> Self {
+        self.add(consumer);
+        self
+    }
+
+    /// One round over every consumer, `Empty` if none had an item
+    pub fn try_select(&mut self) -> Result<(usize, T), PopError> {
+        let count = self.sources.len();
+        let mut disconnected = 0;
+
+        for step in 0..count {
+            let idx = (self.next + step) % count;
+            match self.sources[idx].try_pop() {
+                Ok(val) => {
+                    self.next = (idx + 1) % count;
+                    return Ok((idx, val));
+                }
+                Err(PopError::Disconnected) => disconnected += 1,
+                Err(PopError::Empty) => {}
+            }
+        }
+
+        // nothing registered never becomes ready either
+        if disconnected == count {
+            Err(PopError::Disconnected)
+        } else {
+            Err(PopError::Empty)
+        }
+    }
+
+    #[cfg(feature = "std")]
+    /// Waits with `Backoff` until an item arrives on any
+    /// consumer, or every producer is gone
+    pub fn select(&mut self) -> Result<(usize, T), Disconnected> {
+        retry(&mut Backoff::new(), || self.attempt()).map_err(|_| Disconnected)
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up with `Empty` once `timeout` passed
+    pub fn select_timeout(&mut self, timeout: Duration) -> Result<(usize, T), PopError> {
+        retry_until(timeout, || self.attempt()).unwrap_or(Err(PopError::Empty))
+    }
+
+    /// One round for the retry loops, `None` means try again
+    #[cfg(feature = "std")]
+    fn attempt(&mut self) -> Option<Result<(usize, T), PopError>> {
+        match self.try_select() {
+            Err(PopError::Empty) => None,
+            res => Some(res),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+}
+
+impl<T> Default for Select<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use crate::ephemeral::spsc::SPSCEphemeral;
+
+    #[test]
+    fn test_fair_select() {
+        let (mut p0, mut c0) = SPSCEphemeral::<i32, 8>::new().split();
+        let (mut p1, mut c1) = SPSCEphemeral::<i32, 8>::new().split();
+        for i in 0..4 {
+            p0.push(i).unwrap();
+            p1.push(10 + i).unwrap();
+        }
+
+        let mut select = Select::new().with(&mut c0).with(&mut c1);
+        let order: Vec<_> = (0..8).map(|_| select.try_select().unwrap()).collect();
+        // alternates rather than draining the first queue
+        assert_eq!(
+            order,
+            [
+                (0, 0),
+                (1, 10),
+                (0, 1),
+                (1, 11),
+                (0, 2),
+                (1, 12),
+                (0, 3),
+                (1, 13)
+            ]
+        );
+        assert_eq!(select.try_select(), Err(PopError::Empty));
+
+        drop(p0);
+        drop(p1);
+        assert_eq!(select.try_select(), Err(PopError::Disconnected));
+        assert_eq!(
+            Select::<i32>::new().try_select(),
+            Err(PopError::Disconnected)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_threaded_select() {
+        use crate::ephemeral::dynamic::DynBuffer;
+        use std::thread;
+
+        let (mut p0, mut c0) = SPSCEphemeral::<i32, 4>::new().split();
+        let (mut p1, mut c1) = DynBuffer::<i32>::with_capacity(4).split();
+
+        let mut select = Select::new();
+        let idx0 = select.add(&mut c0);
+        let idx1 = select.add(&mut c1);
+        assert_eq!(
+            select.select_timeout(Duration::from_millis(10)),
+            Err(PopError::Empty)
+        );
+
+        let produce_t = thread::spawn(move || {
+            p1.push_blocking(1).unwrap();
+            drop(p1);
+            thread::sleep(Duration::from_millis(10));
+            p0.push_blocking(0).unwrap();
+        });
+
+        assert_eq!(select.select(), Ok((idx1, 1)));
+        // the closed consumer is skipped while the other one is still open
+        assert_eq!(select.select(), Ok((idx0, 0)));
+        assert_eq!(select.select(), Err(Disconnected));
+        produce_t.join().unwrap();
+    }
+}
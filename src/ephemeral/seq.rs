@@ -0,0 +1,188 @@
+use core::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Ring slot stamped with the position it expects next:
+/// `pos` means writable, `pos + 1` means readable
+pub(crate) struct SeqSlot<T> {
+    pub(crate) seq: AtomicUsize,
+    pub(crate) value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// Slot `i` starts stamped with `i`, i.e. writable on the first lap
+pub(crate) const fn slots<T, const N: usize>() -> [SeqSlot<T>; N] {
+    // a lone slot can't tell "readable" from "writable next lap"
+    const {
+        assert!(
+            N >= 2 && N.is_power_of_two(),
+            "arena size must be a power of two >= 2"
+        )
+    };
+
+    let mut bufr = [const {
+        SeqSlot {
+            seq: AtomicUsize::new(0),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }; N];
+
+    let mut i = 0;
+    while i < N {
+        bufr[i].seq = AtomicUsize::new(i);
+        i += 1;
+    }
+    bufr
+}
+
+/// Heap-allocated `slots` for a size only known at runtime,
+/// rounded up to a power of two >= 2
+#[cfg(feature = "std")]
+pub(crate) fn boxed_slots<T>(capacity: usize) -> alloc::boxed::Box<[SeqSlot<T>]> {
+    (0..capacity.max(2).next_power_of_two())
+        .map(|i| SeqSlot {
+            seq: AtomicUsize::new(i),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        })
+        .collect()
+}
+
+/// Items between `head` and `tail`, approximate while
+/// other threads push or pop
+pub(crate) fn len(head: &AtomicUsize, tail: &AtomicUsize, cap: usize) -> usize {
+    // head first, a pop landing in between can't push it past tail
+    let head = head.load(Ordering::Acquire);
+    let tail = tail.load(Ordering::Acquire);
+    tail.wrapping_sub(head).min(cap)
+}
+
+/// Claims a write position via CAS on `tail`, safe for many producers
+pub(crate) fn push_shared<T>(bufr: &[SeqSlot<T>], tail: &AtomicUsize, val: T) -> Result<(), T> {
+    let mut pos = tail.load(Ordering::Relaxed);
+
+    loop {
+        let slot = &bufr[pos & (bufr.len() - 1)];
+        let seq = slot.seq.load(Ordering::Acquire);
+        let diff = seq.wrapping_sub(pos) as isize;
+
+        // guard: full, slot still holds last lap's value
+        if diff < 0 {
+            return Err(val);
+        }
+
+        // another producer claimed it, catch up
+        if diff > 0 {
+            pos = tail.load(Ordering::Relaxed);
+            continue;
+        }
+
+        match tail.compare_exchange_weak(
+            pos,
+            pos.wrapping_add(1),
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => {
+                unsafe { (*slot.value.get()).as_mut_ptr().write(val) };
+                slot.seq.store(pos.wrapping_add(1), Ordering::Release);
+                return Ok(());
+            }
+            Err(current) => pos = current,
+        }
+    }
+}
+
+/// Writes at `tail` without CAS, caller must be the only producer
+pub(crate) fn push_exclusive<T>(bufr: &[SeqSlot<T>], tail: &AtomicUsize, val: T) -> Result<(), T> {
+    let pos = tail.load(Ordering::Relaxed);
+    let slot = &bufr[pos & (bufr.len() - 1)];
+
+    // guard: full, slot still holds last lap's value
+    if slot.seq.load(Ordering::Acquire) != pos {
+        return Err(val);
+    }
+
+    unsafe { (*slot.value.get()).as_mut_ptr().write(val) };
+    slot.seq.store(pos.wrapping_add(1), Ordering::Release);
+    tail.store(pos.wrapping_add(1), Ordering::Release);
+    Ok(())
+}
+
+/// Claims a read position via CAS on `head`, safe for many consumers
+pub(crate) fn pop_shared<T>(bufr: &[SeqSlot<T>], head: &AtomicUsize) -> Option<T> {
+    pop_claimed(bufr, head, true)
+}
+
+/// Like `pop_shared`, but the slot is never handed back for another
+/// lap, so a ring used for a single lap stays full once written through
+pub(crate) fn pop_shared_once<T>(bufr: &[SeqSlot<T>], head: &AtomicUsize) -> Option<T> {
+    pop_claimed(bufr, head, false)
+}
+
+fn pop_claimed<T>(bufr: &[SeqSlot<T>], head: &AtomicUsize, relap: bool) -> Option<T> {
+    let mut pos = head.load(Ordering::Relaxed);
+
+    loop {
+        let slot = &bufr[pos & (bufr.len() - 1)];
+        let seq = slot.seq.load(Ordering::Acquire);
+        let diff = seq.wrapping_sub(pos.wrapping_add(1)) as isize;
+
+        // guard: empty, slot not written yet
+        if diff < 0 {
+            return None;
+        }
+
+        // another consumer claimed it, catch up
+        if diff > 0 {
+            pos = head.load(Ordering::Relaxed);
+            continue;
+        }
+
+        match head.compare_exchange_weak(
+            pos,
+            pos.wrapping_add(1),
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => {
+                let val = unsafe { (*slot.value.get()).as_ptr().read() };
+                // free the slot for the next lap
+                if relap {
+                    slot.seq
+                        .store(pos.wrapping_add(bufr.len()), Ordering::Release);
+                }
+                return Some(val);
+            }
+            Err(current) => pos = current,
+        }
+    }
+}
+
+/// Reads at `head` without CAS, caller must be the only consumer
+pub(crate) fn pop_exclusive<T>(bufr: &[SeqSlot<T>], head: &AtomicUsize) -> Option<T> {
+    let pos = head.load(Ordering::Relaxed);
+    let slot = &bufr[pos & (bufr.len() - 1)];
+
+    // guard: empty, slot not written yet
+    if slot.seq.load(Ordering::Acquire) != pos.wrapping_add(1) {
+        return None;
+    }
+
+    let val = unsafe { (*slot.value.get()).as_ptr().read() };
+    slot.seq
+        .store(pos.wrapping_add(bufr.len()), Ordering::Release);
+    head.store(pos.wrapping_add(1), Ordering::Release);
+    Some(val)
+}
+
+/// Drops the values still sitting between `head` and `tail`,
+/// only sound once no handle can touch the ring anymore
+pub(crate) fn drop_pending<T>(bufr: &mut [SeqSlot<T>], head: usize, tail: usize) {
+    let len = bufr.len();
+    let mut pos = head;
+    while pos != tail {
+        unsafe { bufr[pos & (len - 1)].value.get_mut().assume_init_drop() };
+        pos = pos.wrapping_add(1);
+    }
+}
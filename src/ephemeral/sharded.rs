@@ -0,0 +1,253 @@
+use alloc::{boxed::Box, sync::Arc};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::dynamic::DynBuffer;
+use super::spsc::{pop, push};
+
+/// Multi-producer/single-consumer queue made of one SPSC ring per
+/// producer, so producers never contend on a shared tail
+///
+/// A producer registers for a shard of its own with
+/// `Producers::register`, the consumer goes round the shards taking
+/// one item from each in turn. Items of one producer come out in the
+/// order it pushed them, there is no order across producers, not even
+/// for a push made after seeing another producer's push land
+pub struct ShardedMpsc<T> {
+    shards: Box<[Shard<T>]>,
+}
+
+struct Shard<T> {
+    ring: DynBuffer<T>,
+    claimed: AtomicBool, // a `ProducerToken` owns the pushing end
+}
+
+impl<T> ShardedMpsc<T> {
+    /// Room for `producers` registered at once, each shard holding
+    /// at least `capacity` items
+    pub fn new(producers: usize, capacity: usize) -> Self {
+        assert!(producers > 0, "a sharded queue needs at least one shard");
+        let shards = (0..producers)
+            .map(|_| Shard {
+                ring: DynBuffer::with_capacity(capacity),
+                claimed: AtomicBool::new(false),
+            })
+            .collect();
+        Self { shards }
+    }
+
+    /// Moves the shards behind a clonable registry for producers
+    /// and the one consumer allowed to read from them
+    pub fn split(self) -> (Producers<T>, Consumer<T>) {
+        let shards = Arc::new(self);
+        let producers = Producers {
+            shards: shards.clone(),
+        };
+        (producers, Consumer { shards, next: 0 })
+    }
+
+    /// Pending items of every shard, approximate while handles are busy
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.ring.len()).sum()
+    }
+
+    /// Total of every shard
+    pub fn capacity(&self) -> usize {
+        self.shards.iter().map(|shard| shard.ring.capacity()).sum()
+    }
+
+    occupancy!();
+
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+}
+
+/// Hands out the shards of a `ShardedMpsc`, one per `ProducerToken`
+#[derive(Clone)]
+pub struct Producers<T> {
+    shards: Arc<ShardedMpsc<T>>,
+}
+
+impl<T> Producers<T> {
+    /// A free shard for the calling thread, `None` while every shard is
+    /// taken. Dropping the token frees its shard for the next one,
+    /// items still queued there keep their place
+    pub fn register(&self) -> Option<ProducerToken<T>> {
+        let idx = self.shards.shards.iter().position(|shard| {
+            // pairs with the release in the last owner's drop
+            shard
+                .claimed
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+        })?;
+        Some(ProducerToken {
+            shards: self.shards.clone(),
+            idx,
+        })
+    }
+}
+
+/// Pushing end of one shard, the only producer of its ring
+pub struct ProducerToken<T> {
+    shards: Arc<ShardedMpsc<T>>,
+    idx: usize,
+}
+
+impl<T> ProducerToken<T> {
+    /// Fails when this producer's shard is full, the others don't help
+    pub fn push(&mut self, val: T) -> Result<(), T> {
+        push(&self.shard().ring, val)
+    }
+
+    /// Which shard this token owns
+    pub fn index(&self) -> usize {
+        self.idx
+    }
+
+    /// Pending items in this producer's shard
+    pub fn len(&self) -> usize {
+        self.shard().ring.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shard().ring.capacity()
+    }
+
+    occupancy!();
+
+    fn shard(&self) -> &Shard<T> {
+        &self.shards.shards[self.idx]
+    }
+}
+
+impl<T> Drop for ProducerToken<T> {
+    fn drop(&mut self) {
+        self.shard().claimed.store(false, Ordering::Release);
+    }
+}
+
+/// Popping end of every shard
+pub struct Consumer<T> {
+    shards: Arc<ShardedMpsc<T>>,
+    next: usize, // shard to look at first
+}
+
+impl<T> Consumer<T> {
+    /// One item from the next shard round that has any, so a busy
+    /// producer can't starve the rest. Looks at every shard before
+    /// giving up, unregistered ones included
+    pub fn pop(&mut self) -> Option<T> {
+        let shards = &self.shards.shards;
+        for i in 0..shards.len() {
+            let idx = (self.next + i) % shards.len();
+            if let Some(val) = pop(&shards[idx].ring) {
+                self.next = idx + 1;
+                return Some(val);
+            }
+        }
+        None
+    }
+
+    /// Pending items of every shard, approximate while producers are busy
+    pub fn len(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shards.capacity()
+    }
+
+    occupancy!();
+}
+
+/// Ends at the first pop that finds every shard empty
+impl<T> Iterator for Consumer<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_round_robin_sharded() {
+        let (producers, mut consumer) = ShardedMpsc::new(4, 4).split();
+        let (mut a, mut b) = (producers.register().unwrap(), producers.register().unwrap());
+        for val in [1, 2, 3] {
+            a.push(val).unwrap();
+        }
+        for val in [10, 20] {
+            b.push(val).unwrap();
+        }
+
+        // per producer order holds, 10 was pushed after 3 yet overtakes 2
+        assert_eq!(consumer.by_ref().collect::<Vec<_>>(), [1, 10, 2, 20, 3]);
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn test_register_sharded() {
+        let (producers, mut consumer) = ShardedMpsc::new(2, 2).split();
+        let mut a = producers.register().unwrap();
+        let _b = producers.register().unwrap();
+        assert!(producers.register().is_none());
+
+        a.push(1).unwrap();
+        a.push(2).unwrap();
+        assert_eq!(a.push(3), Err(3));
+        let shard = a.index();
+        drop(a);
+
+        // the freed shard comes back with its items in place
+        let mut c = producers.register().unwrap();
+        assert_eq!(c.index(), shard);
+        assert_eq!(consumer.pop(), Some(1));
+        c.push(3).unwrap();
+        assert_eq!(consumer.by_ref().collect::<Vec<_>>(), [2, 3]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_threads_sharded() {
+        use std::thread;
+
+        const PRODUCERS: usize = 8;
+        const ITEMS: usize = 500;
+        let (producers, mut consumer) = ShardedMpsc::new(PRODUCERS, 16).split();
+        let produce_ts: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let producers = producers.clone();
+                thread::spawn(move || {
+                    let mut token = producers.register().unwrap();
+                    for i in 0..ITEMS {
+                        let mut val = (p, i);
+                        while let Err(back) = token.push(val) {
+                            val = back;
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let mut last = [None; PRODUCERS];
+        let mut popped = 0;
+        while popped < PRODUCERS * ITEMS {
+            let Some((p, i)) = consumer.pop() else {
+                thread::yield_now();
+                continue;
+            };
+            // in order per producer, interleaved any way across them
+            assert!(last[p] < Some(i));
+            last[p] = Some(i);
+            popped += 1;
+        }
+        produce_ts.into_iter().for_each(|t| t.join().unwrap());
+        assert_eq!(consumer.pop(), None);
+    }
+}
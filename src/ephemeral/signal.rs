@@ -0,0 +1,189 @@
+use std::{
+    sync::{Condvar, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// Flag plus payload any thread can `raise` and any number of
+/// waiters see, each raise wakes all of them, for config reloads
+/// and wake-alls that neither the queues nor `Watch` cover
+///
+/// Raises are counted in epochs, a `Waiter` remembers the last one
+/// it saw and is handed each newer epoch once. Raises landing before
+/// the waiter comes back coalesce, it gets the newest payload and
+/// can tell from its `epoch` how many went by
+pub struct Signal<T> {
+    raised: Mutex<Raised<T>>,
+    cond: Condvar,
+}
+
+struct Raised<T> {
+    epoch: u64,
+    val: Option<T>, // payload of `epoch`, `None` before the first raise
+}
+
+impl<T: Clone> Signal<T> {
+    /// Starts out at epoch 0, never raised
+    pub const fn new() -> Self {
+        Self {
+            raised: Mutex::new(Raised {
+                epoch: 0,
+                val: None,
+            }),
+            cond: Condvar::new(),
+        }
+    }
+
+    /// Sets the payload, bumps the epoch and wakes every waiter
+    pub fn raise(&self, val: T) {
+        let mut raised = self.lock();
+        raised.epoch += 1;
+        raised.val = Some(val);
+        drop(raised);
+        self.cond.notify_all();
+    }
+
+    /// A waiter for the raises from here on, the current one counts
+    /// as seen
+    pub fn subscribe(&self) -> Waiter<'_, T> {
+        Waiter {
+            signal: self,
+            seen: self.epoch(),
+        }
+    }
+
+    /// Raises so far
+    pub fn epoch(&self) -> u64 {
+        self.lock().epoch
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Raised<T>> {
+        self.raised.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T: Clone> Default for Signal<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One observer of a `Signal`, see `Signal::subscribe`
+pub struct Waiter<'a, T> {
+    signal: &'a Signal<T>,
+    seen: u64, // epoch last handed out
+}
+
+impl<T: Clone> Waiter<'_, T> {
+    /// Payload of a raise newer than the last one seen, if any
+    pub fn try_recv(&mut self) -> Option<T> {
+        let raised = self.signal.lock();
+        self.take(&raised)
+    }
+
+    /// Blocks until the signal is raised past the last epoch seen
+    pub fn wait(&mut self) -> T {
+        let mut raised = self.signal.lock();
+        loop {
+            if let Some(val) = self.take(&raised) {
+                return val;
+            }
+            raised = (self.signal.cond)
+                .wait(raised)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Gives up with `None` once `timeout` passed
+    pub fn wait_timeout(&mut self, timeout: Duration) -> Option<T> {
+        let deadline = Instant::now() + timeout;
+        let mut raised = self.signal.lock();
+        loop {
+            if let Some(val) = self.take(&raised) {
+                return Some(val);
+            }
+            let left = deadline.checked_duration_since(Instant::now())?;
+            raised = (self.signal.cond)
+                .wait_timeout(raised, left)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /// Epoch of the last payload handed out, or of `subscribe`
+    pub fn epoch(&self) -> u64 {
+        self.seen
+    }
+
+    fn take(&mut self, raised: &Raised<T>) -> Option<T> {
+        // guard: nothing newer
+        if raised.epoch == self.seen {
+            return None;
+        }
+        self.seen = raised.epoch;
+        raised.val.clone()
+    }
+}
+
+impl<T> Clone for Waiter<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            signal: self.signal,
+            seen: self.seen,
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use std::{sync::Barrier, thread};
+
+    #[test]
+    fn test_seq_signal() {
+        let signal = Signal::new();
+        signal.raise("before");
+        let mut waiter = signal.subscribe();
+        // raised before it subscribed
+        assert_eq!(waiter.try_recv(), None);
+
+        signal.raise("reload");
+        assert_eq!(waiter.try_recv(), Some("reload"));
+        assert_eq!(waiter.try_recv(), None);
+
+        // two in a row coalesce into the newest
+        signal.raise("a");
+        signal.raise("b");
+        assert_eq!(waiter.wait(), "b");
+        assert_eq!((waiter.epoch(), signal.epoch()), (4, 4));
+        assert_eq!(waiter.wait_timeout(Duration::from_millis(5)), None);
+    }
+
+    #[test]
+    fn test_wake_all_signal() {
+        const WAITERS: usize = 4;
+        const RAISES: u64 = if cfg!(miri) { 3 } else { 50 };
+        let signal = Signal::new();
+        let ready = Barrier::new(WAITERS + 1);
+        let done = Barrier::new(WAITERS + 1);
+
+        thread::scope(|s| {
+            for _ in 0..WAITERS {
+                s.spawn(|| {
+                    let mut waiter = signal.subscribe();
+                    for round in 1..=RAISES {
+                        ready.wait();
+                        // every waiter sees every raise, once
+                        assert_eq!(waiter.wait(), round);
+                        assert_eq!(waiter.try_recv(), None);
+                        done.wait();
+                    }
+                });
+            }
+            for round in 1..=RAISES {
+                ready.wait();
+                signal.raise(round);
+                done.wait();
+            }
+        });
+    }
+}
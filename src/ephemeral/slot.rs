@@ -0,0 +1,417 @@
+use core::{
+    mem::{self, MaybeUninit},
+    sync::atomic::Ordering,
+};
+
+use crate::sync::{AtomicU8, UnsafeCell};
+
+use super::wait::Backoff;
+
+/// One-value handoff cell, a lock-free stand-in for a
+/// `Mutex<Option<T>>` that any number of threads may share
+pub struct EphemeralSlot<T> {
+    value: UnsafeCell<MaybeUninit<T>>,
+    state: AtomicU8,
+}
+
+// only the thread that moved `state` to `WRITING`/`READING`
+// touches `value` until it hands the slot on
+const EMPTY: u8 = 0;
+const WRITING: u8 = 1;
+const FULL: u8 = 2;
+const READING: u8 = 3;
+
+impl<T> EphemeralSlot<T> {
+    const_fn! {
+        pub fn new() -> Self {
+            Self {
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+                state: AtomicU8::new(EMPTY),
+            }
+        }
+    }
+
+    // spins until the slot is free, one producer at a time
+    pub fn set(&self, value: T) {
+        let mut backoff = Backoff::new();
+        while self
+            .state
+            .compare_exchange(EMPTY, WRITING, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            backoff.snooze();
+        }
+        self.value.with_mut(|slot| unsafe { (*slot).write(value) });
+        self.state.store(FULL, Ordering::Release);
+    }
+
+    /// `set` handing `value` back instead of waiting for the slot
+    pub fn try_set(&self, value: T) -> Result<(), T> {
+        if self
+            .state
+            .compare_exchange(EMPTY, WRITING, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(value);
+        }
+        self.value.with_mut(|slot| unsafe { (*slot).write(value) });
+        self.state.store(FULL, Ordering::Release);
+        Ok(())
+    }
+
+    pub fn get(&self) -> Option<T> {
+        self.state
+            .compare_exchange(FULL, READING, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+        let value = self
+            .value
+            .with(|slot| unsafe { (*slot).assume_init_read() });
+        self.state.store(EMPTY, Ordering::Release);
+        Some(value)
+    }
+
+    /// `get` spinning until a producer fills the slot
+    pub fn take_blocking(&self) -> T {
+        let mut backoff = Backoff::new();
+        loop {
+            match self.get() {
+                Some(value) => return value,
+                None => backoff.snooze(),
+            }
+        }
+    }
+
+    /// Puts `value` in whether or not the slot is full, handing back
+    /// the one it displaced in the same transition. Waits out a `set`
+    /// or `get` another thread is halfway through
+    pub fn swap(&self, value: T) -> Option<T> {
+        self.exchange(Some(value))
+    }
+
+    /// `swap`, by the name `Option::replace` goes by
+    pub fn replace(&self, value: T) -> Option<T> {
+        self.swap(value)
+    }
+
+    /// Empties the slot like `get`, but waits out a `set` or `get`
+    /// another thread is halfway through instead of coming back `None`
+    pub fn take(&self) -> Option<T> {
+        self.exchange(None)
+    }
+
+    /// Exchanges the slot's content for `value`, `None` empties it
+    fn exchange(&self, mut value: Option<T>) -> Option<T> {
+        let mut backoff = Backoff::new();
+        loop {
+            match self.state.load(Ordering::Relaxed) {
+                EMPTY => match value {
+                    None => return None,
+                    Some(new) => match self.try_set(new) {
+                        Ok(()) => return None,
+                        // filled under us, exchange with that instead
+                        Err(new) => value = Some(new),
+                    },
+                },
+                FULL if self
+                    .state
+                    .compare_exchange(FULL, WRITING, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok() =>
+                {
+                    let next = if value.is_some() { FULL } else { EMPTY };
+                    let old = self.value.with_mut(|slot| unsafe {
+                        let old = (*slot).assume_init_read();
+                        if let Some(new) = value {
+                            (*slot).write(new);
+                        }
+                        old
+                    });
+                    self.state.store(next, Ordering::Release);
+                    return Some(old);
+                }
+                _ => backoff.snooze(),
+            }
+        }
+    }
+
+    /// Runs `f` on the held value where it sits, no `get` and `set`
+    /// round trip for a read-modify-write. `None` when the slot is
+    /// empty. Waits out a transition another thread is halfway
+    /// through, `get` meanwhile comes back `None` as with `swap`
+    pub fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        let _held = self.hold()?;
+        Some(
+            self.value
+                .with_mut(|slot| f(unsafe { (*slot).assume_init_mut() })),
+        )
+    }
+
+    /// Puts in `f` of the held value, handing back the one it
+    /// displaced. `None` when empty or `f` turned it down with `None`,
+    /// which leaves the value be. `f` runs once with the slot held,
+    /// there's no lost race to retry as with `AtomicUsize::fetch_update`
+    pub fn fetch_update(&self, f: impl FnOnce(&T) -> Option<T>) -> Option<T> {
+        let _held = self.hold()?;
+        self.value.with_mut(|slot| unsafe {
+            let new = f((*slot).assume_init_ref())?;
+            Some(mem::replace((*slot).assume_init_mut(), new))
+        })
+    }
+
+    /// Claims a full slot for an in-place change, `None` once empty
+    fn hold(&self) -> Option<Held<'_>> {
+        let mut backoff = Backoff::new();
+        loop {
+            match self.state.load(Ordering::Relaxed) {
+                EMPTY => return None,
+                FULL if self
+                    .state
+                    .compare_exchange(FULL, WRITING, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok() =>
+                {
+                    return Some(Held(&self.state))
+                }
+                _ => backoff.snooze(),
+            }
+        }
+    }
+}
+
+/// A full slot claimed in place, republished full once dropped,
+/// also when the closure changing it panics
+struct Held<'a>(&'a AtomicU8);
+
+impl Drop for Held<'_> {
+    fn drop(&mut self) {
+        self.0.store(FULL, Ordering::Release);
+    }
+}
+
+impl<T> Default for EphemeralSlot<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for EphemeralSlot<T> {
+    fn drop(&mut self) {
+        if self.state.load(Ordering::Acquire) == FULL {
+            self.value
+                .with_mut(|slot| unsafe { (*slot).assume_init_drop() });
+        }
+    }
+}
+
+unsafe impl<T: Send> Sync for EphemeralSlot<T> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_producers_slot() {
+        const ITEMS: usize = if cfg!(miri) { 50 } else { 1000 };
+        let source = EphemeralSlot::new();
+
+        // two producers racing for the slot, nothing lost or doubled
+        let mut seen: Vec<_> = thread::scope(|s| {
+            for p in 0..2 {
+                let source = &source;
+                s.spawn(move || {
+                    for i in 0..ITEMS {
+                        source.set(p * ITEMS + i);
+                    }
+                });
+            }
+            (0..2 * ITEMS)
+                .map(|_| loop {
+                    match source.get() {
+                        Some(value) => break value,
+                        None => thread::yield_now(),
+                    }
+                })
+                .collect()
+        });
+
+        seen.sort_unstable();
+        assert_eq!(seen, (0..2 * ITEMS).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_swap_slot() {
+        let source = EphemeralSlot::new();
+
+        assert_eq!(source.swap(String::from("a")), None);
+        assert_eq!(source.replace(String::from("b")).as_deref(), Some("a"));
+        assert_eq!(source.take().as_deref(), Some("b"));
+        assert_eq!(source.take(), None);
+        assert_eq!(source.get(), None);
+
+        // the held value is dropped with the slot
+        source.replace(String::from("c"));
+    }
+
+    #[test]
+    fn test_update_slot() {
+        const ITEMS: usize = if cfg!(miri) { 50 } else { 1000 };
+        let source = EphemeralSlot::new();
+        assert_eq!(source.update(|count: &mut usize| *count += 1), None);
+
+        source.set(0);
+        thread::scope(|s| {
+            for _ in 0..2 {
+                s.spawn(|| {
+                    for _ in 0..ITEMS {
+                        source.update(|count| *count += 1).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(source.update(|count| *count), Some(2 * ITEMS));
+
+        assert_eq!(
+            source.fetch_update(|&count| Some(count / 2)),
+            Some(2 * ITEMS)
+        );
+        assert_eq!(source.fetch_update(|_| None), None);
+        assert_eq!(source.get(), Some(ITEMS));
+        assert_eq!(source.fetch_update(|_| Some(0)), None);
+    }
+
+    #[test]
+    fn test_update_panic_slot() {
+        let source = EphemeralSlot::new();
+        source.set(vec![1]);
+
+        // a panicking update still republishes the value
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            source.update(|vals| {
+                vals.push(2);
+                panic!("mid-update");
+            })
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(source.get(), Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn test_take_blocking_slot() {
+        const ITEMS: usize = if cfg!(miri) { 50 } else { 1000 };
+        let source = EphemeralSlot::new();
+
+        // replacing never loses a value the consumer hasn't seen yet,
+        // every one is either taken or displaced back to the producer
+        let (taken, displaced) = thread::scope(|s| {
+            let produce_t = s.spawn(|| {
+                let displaced: Vec<_> = (0..ITEMS).filter_map(|i| source.replace(i)).collect();
+                source.set(ITEMS);
+                displaced
+            });
+            let mut taken = Vec::new();
+            while taken.last() != Some(&ITEMS) {
+                taken.push(source.take_blocking());
+            }
+            (taken, produce_t.join().unwrap())
+        });
+
+        let mut seen: Vec<_> = taken.into_iter().chain(displaced).collect();
+        seen.sort_unstable();
+        assert_eq!(seen, (0..=ITEMS).collect::<Vec<_>>());
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_test {
+    use super::*;
+    use crate::sync::Arc;
+    use loom::thread;
+
+    /// Spins until `source` hands out a value
+    fn take(source: &EphemeralSlot<usize>) -> usize {
+        loop {
+            match source.get() {
+                Some(value) => return value,
+                None => thread::yield_now(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_loom_handshake_slot() {
+        loom::model(|| {
+            let source = Arc::new(EphemeralSlot::new());
+
+            let producer = source.clone();
+            let produce_t = thread::spawn(move || {
+                producer.set(1);
+                producer.set(2);
+            });
+
+            assert_eq!(take(&source), 1);
+            assert_eq!(take(&source), 2);
+            produce_t.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn test_loom_swap_slot() {
+        loom::model(|| {
+            let source = Arc::new(EphemeralSlot::new());
+            source.set(0);
+
+            let producer = source.clone();
+            let produce_t = thread::spawn(move || producer.replace(1));
+
+            // whichever order, both values come out exactly once
+            let taken = source.swap(2).unwrap();
+            let displaced = produce_t.join().unwrap().unwrap();
+            let rest = source.get().unwrap();
+            let mut seen = [taken, displaced, rest];
+            seen.sort_unstable();
+            assert_eq!(seen, [0, 1, 2]);
+        });
+    }
+
+    #[test]
+    fn test_loom_update_slot() {
+        loom::model(|| {
+            let source = Arc::new(EphemeralSlot::new());
+            source.set(1);
+
+            let updater = source.clone();
+            let update_t = thread::spawn(move || updater.update(|value| *value += 1));
+
+            // the update lands before the value is taken or not at all
+            let taken = take(&source);
+            match update_t.join().unwrap() {
+                Some(()) => assert_eq!(taken, 2),
+                None => assert_eq!(taken, 1),
+            }
+        });
+    }
+
+    #[test]
+    fn test_loom_producers_slot() {
+        // three spinning threads, bounded to keep the model finite
+        let mut model = loom::model::Builder::new();
+        model.preemption_bound = Some(1);
+
+        model.check(|| {
+            let source = Arc::new(EphemeralSlot::new());
+
+            let produce_ts: Vec<_> = (1..=2)
+                .map(|value| {
+                    let producer = source.clone();
+                    thread::spawn(move || producer.set(value))
+                })
+                .collect();
+
+            let (a, b) = (take(&source), take(&source));
+            assert_eq!(a + b, 3);
+            assert_ne!(a, b);
+            for produce_t in produce_ts {
+                produce_t.join().unwrap();
+            }
+        });
+    }
+}
@@ -0,0 +1,372 @@
+use core::{mem::MaybeUninit, sync::atomic::Ordering};
+
+use crate::sync::{AtomicU16, AtomicU32, AtomicU8, UnsafeCell};
+
+use super::spsc::arena;
+#[cfg(feature = "stats")]
+use super::stats::{Counters, Stats};
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Atomic a `SmallRing` keeps its two positions in, `AtomicU8`,
+/// `AtomicU16` or `AtomicU32`. Positions wrap at its width, so the
+/// arena may take at most half the positions it can count
+pub trait Index: sealed::Sealed {
+    /// largest arena size the positions can tell full from empty in
+    const MAX: usize;
+    #[doc(hidden)]
+    const MASK: usize;
+    /// a fresh atomic at 0 each time it's named
+    #[cfg(not(loom))]
+    #[doc(hidden)]
+    const ZERO: Self;
+
+    #[cfg(loom)]
+    #[doc(hidden)]
+    fn zero() -> Self;
+
+    #[doc(hidden)]
+    fn load(&self, order: Ordering) -> usize;
+    #[doc(hidden)]
+    fn store(&self, pos: usize, order: Ordering);
+}
+
+macro_rules! index {
+    ($($atomic:ident($bits:ty)),* $(,)?) => {$(
+        impl sealed::Sealed for $atomic {}
+
+        impl Index for $atomic {
+            const MAX: usize = 1 << (<$bits>::BITS - 1);
+            const MASK: usize = <$bits>::MAX as usize;
+            #[cfg(not(loom))]
+            const ZERO: Self = $atomic::new(0);
+
+            #[cfg(loom)]
+            fn zero() -> Self {
+                $atomic::new(0)
+            }
+
+            fn load(&self, order: Ordering) -> usize {
+                $atomic::load(self, order) as usize
+            }
+
+            fn store(&self, pos: usize, order: Ordering) {
+                // wraps at the width, like the other side's positions
+                $atomic::store(self, pos as $bits, order)
+            }
+        }
+    )*};
+}
+
+index! {
+    AtomicU8(u8),
+    AtomicU16(u16),
+    AtomicU32(u32),
+}
+
+/// SPSC ring for small MCUs, whose control state is two `A`s and
+/// nothing else, so a 32-slot queue of bytes costs two bytes on top
+/// of its slots. Only loads and stores touch them, no CAS, which even
+/// a Cortex-M0 has for every index width
+///
+/// The positions aren't padded apart, on a chip without a cache that
+/// costs nothing. Up to 128 slots with the default `AtomicU8`,
+/// 32768 with `AtomicU16`, 2^31 with `AtomicU32`. The `stats` and
+/// `tracing` features add their counters and name on top
+pub struct SmallRing<T, const N: usize, A: Index = AtomicU8> {
+    bufr: [UnsafeCell<MaybeUninit<T>>; N],
+    head: A, // read position
+    tail: A, // write position
+    #[cfg(feature = "stats")]
+    stats: Counters,
+    #[cfg(feature = "tracing")]
+    trace: Label,
+}
+
+impl<T, const N: usize, A: Index> SmallRing<T, N, A> {
+    const_fn! {
+        pub fn new() -> Self {
+            const { assert!(N.is_power_of_two(), "arena size must be a power of two") };
+            const { assert!(N <= A::MAX, "arena size past what the index type can count") };
+
+            Self {
+                bufr: arena(),
+                #[cfg(not(loom))]
+                head: A::ZERO,
+                #[cfg(not(loom))]
+                tail: A::ZERO,
+                #[cfg(loom)]
+                head: A::zero(),
+                #[cfg(loom)]
+                tail: A::zero(),
+                #[cfg(feature = "stats")]
+                stats: Counters::new(),
+                #[cfg(feature = "tracing")]
+                trace: Label::new("small"),
+            }
+        }
+    }
+
+    const_fn! {
+        /// Names the ring in its `tracing` events, `small` otherwise
+        #[cfg(feature = "tracing")]
+        pub fn with_name(mut self, name: &'static str) -> Self {
+            self.trace = Label::new(name);
+            self
+        }
+    }
+
+    /// Borrows the ring as a producer/consumer pair, so one thread can
+    /// write while another reads. The handles borrow the ring, so no
+    /// flag in the control state has to stop a second split
+    pub fn split(&mut self) -> (Producer<'_, T, N, A>, Consumer<'_, T, N, A>) {
+        let ring = &*self;
+        (Producer { ring }, Consumer { ring })
+    }
+
+    /// Pushes while nothing else holds the ring, `split` pushes from
+    /// another thread
+    pub fn push(&mut self, val: T) -> Result<(), T> {
+        self.write(val)
+    }
+
+    /// Pops while nothing else holds the ring, `split` pops from
+    /// another thread
+    pub fn pop(&mut self) -> Option<T> {
+        self.read()
+    }
+
+    /// Caller is the only producer
+    fn write(&self, val: T) -> Result<(), T> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        let head = self.head.load(Ordering::Acquire);
+
+        // guard: full
+        if tail.wrapping_sub(head) & A::MASK == N {
+            #[cfg(feature = "stats")]
+            self.stats.full();
+            #[cfg(feature = "tracing")]
+            self.trace.full();
+            return Err(val);
+        }
+
+        unsafe { self.slot(tail).with_mut(|slot| (*slot).write(val)) };
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+        #[cfg(feature = "stats")]
+        self.stats.pushed(1, self.len());
+        #[cfg(feature = "tracing")]
+        self.trace.pushed(1, self.len());
+        Ok(())
+    }
+
+    /// Caller is the only consumer
+    fn read(&self) -> Option<T> {
+        let head = self.head.load(Ordering::Relaxed);
+        // pairs with the producer's release, the slot is written once seen
+        let tail = self.tail.load(Ordering::Acquire);
+
+        // guard: empty
+        if head == tail {
+            #[cfg(feature = "stats")]
+            self.stats.empty();
+            #[cfg(feature = "tracing")]
+            self.trace.empty();
+            return None;
+        }
+
+        let val = unsafe { self.slot(head).with(|slot| (*slot).assume_init_read()) };
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+        #[cfg(feature = "stats")]
+        self.stats.popped(1);
+        #[cfg(feature = "tracing")]
+        self.trace.popped(1);
+        Some(val)
+    }
+
+    /// Pending items, approximate while the other side is busy
+    pub fn len(&self) -> usize {
+        // head first, a pop landing in between can't push it past tail
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        (tail.wrapping_sub(head) & A::MASK).min(N)
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.stats.snapshot()
+    }
+
+    fn slot(&self, pos: usize) -> &UnsafeCell<MaybeUninit<T>> {
+        &self.bufr[pos & (N - 1)]
+    }
+}
+
+impl<T, const N: usize, A: Index> Drop for SmallRing<T, N, A> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+impl<T, const N: usize, A: Index> Default for SmallRing<T, N, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl<T: Send, const N: usize, A: Index> Sync for SmallRing<T, N, A> {}
+
+/// Write half of a split `SmallRing`
+pub struct Producer<'a, T, const N: usize, A: Index = AtomicU8> {
+    ring: &'a SmallRing<T, N, A>,
+}
+
+impl<T, const N: usize, A: Index> Producer<'_, T, N, A> {
+    /// Hands `val` back when full
+    pub fn push(&mut self, val: T) -> Result<(), T> {
+        self.ring.write(val)
+    }
+
+    /// Pending items, approximate while the consumer is busy
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+}
+
+/// Read half of a split `SmallRing`
+pub struct Consumer<'a, T, const N: usize, A: Index = AtomicU8> {
+    ring: &'a SmallRing<T, N, A>,
+}
+
+impl<T, const N: usize, A: Index> Consumer<'_, T, N, A> {
+    /// `None` when empty
+    pub fn pop(&mut self) -> Option<T> {
+        self.ring.read()
+    }
+
+    /// Pending items, approximate while the producer is busy
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_size_small() {
+        let mut queue = SmallRing::<u8, 32>::new();
+
+        // what `stats` and `tracing` keep comes on top
+        #[cfg(not(any(feature = "stats", feature = "tracing")))]
+        {
+            assert_eq!(size_of::<SmallRing<u8, 32>>(), 32 + 2);
+            assert_eq!(size_of::<SmallRing<u8, 32, AtomicU16>>(), 32 + 4);
+        }
+        assert!(queue.push(1).is_ok());
+        assert_eq!((queue.len(), queue.pop()), (1, Some(1)));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_wrap_small() {
+        // the largest arena a u8 position allows, over many wraps
+        let mut src = SmallRing::<u32, 128>::new();
+
+        for lap in 0..20 {
+            for i in 0..128 {
+                assert!(src.push(lap * 128 + i).is_ok());
+            }
+            assert_eq!(src.push(0), Err(0));
+            assert!(src.is_full());
+            for i in 0..128 {
+                assert_eq!(src.pop(), Some(lap * 128 + i));
+            }
+            assert_eq!(src.pop(), None);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_threaded_small() {
+        use std::thread;
+
+        const ITEMS: u32 = if cfg!(miri) { 200 } else { 10000 };
+        let mut src = SmallRing::<u32, 4, AtomicU16>::new();
+        let (mut producer, mut consumer) = src.split();
+
+        thread::scope(|s| {
+            s.spawn(move || {
+                for i in 0..ITEMS {
+                    while producer.push(i).is_err() {
+                        thread::yield_now();
+                    }
+                }
+            });
+            for i in 0..ITEMS {
+                loop {
+                    match consumer.pop() {
+                        Some(val) => break assert_eq!(val, i),
+                        None => thread::yield_now(),
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_test {
+    use super::*;
+    use crate::sync::Arc;
+    use loom::thread;
+
+    #[test]
+    fn test_loom_handoff_small() {
+        loom::model(|| {
+            // smaller than the items, so the producer wraps the arena
+            let src = Arc::new(SmallRing::<usize, 2>::new());
+
+            let producer = src.clone();
+            let produce_t = thread::spawn(move || {
+                for i in 0..3 {
+                    while producer.write(i).is_err() {
+                        thread::yield_now();
+                    }
+                }
+            });
+
+            for i in 0..3 {
+                loop {
+                    match src.read() {
+                        Some(val) => break assert_eq!(val, i),
+                        None => thread::yield_now(),
+                    }
+                }
+            }
+            produce_t.join().unwrap();
+        });
+    }
+}
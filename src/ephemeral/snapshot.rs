@@ -0,0 +1,152 @@
+use alloc::vec::Vec;
+use core::{fmt, marker::PhantomData};
+
+use serde::{
+    de::{Deserialize, Deserializer, Error, SeqAccess, Visitor},
+    Serialize, Serializer,
+};
+
+/// Items a queue held when `snapshot` was taken, borrowed in pop
+/// order and serialized as a plain sequence, which the queue's
+/// `restore` reads back. The queue stays borrowed, so nothing is
+/// pushed or popped until the snapshot is written out
+pub struct Snapshot<'a, T> {
+    items: Vec<&'a T>,
+}
+
+impl<'a, T> Snapshot<'a, T> {
+    pub(crate) fn new(items: impl Iterator<Item = &'a T>) -> Self {
+        Self {
+            items: items.collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl<T: Serialize> Serialize for Snapshot<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(&self.items)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Snapshot<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.items).finish()
+    }
+}
+
+/// Feeds each item of a serialized sequence to `push`, failing
+/// on the first one past `capacity` that it turns away
+pub(crate) fn restore<'de, D, T, F>(
+    deserializer: D,
+    capacity: usize,
+    push: F,
+) -> Result<(), D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+    F: FnMut(T) -> Result<(), T>,
+{
+    deserializer.deserialize_seq(Restore {
+        capacity,
+        push,
+        _marker: PhantomData,
+    })
+}
+
+struct Restore<T, F> {
+    capacity: usize,
+    push: F,
+    _marker: PhantomData<fn(T)>,
+}
+
+impl<'de, T, F> Visitor<'de> for Restore<T, F>
+where
+    T: Deserialize<'de>,
+    F: FnMut(T) -> Result<(), T>,
+{
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a sequence of at most {} items", self.capacity)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+        let mut restored = 0;
+        while let Some(val) = seq.next_element()? {
+            if (self.push)(val).is_err() {
+                return Err(A::Error::invalid_length(restored + 1, &self));
+            }
+            restored += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use crate::ephemeral::{dynamic::DynBuffer, mpmc::MPMCEphemeral, spsc::SPSCEphemeral};
+    use alloc::string::{String, ToString};
+    use postcard::{to_allocvec, Deserializer};
+
+    #[test]
+    fn test_spsc_snapshot() {
+        let mut src = SPSCEphemeral::<u32, 4>::new();
+        // moved along, so the items wrap the arena
+        for i in 0..6 {
+            src.push(i).unwrap();
+            if i < 3 {
+                src.pop();
+            }
+        }
+
+        let bytes = to_allocvec(&src.snapshot()).unwrap();
+        // what `collect_seq` gives for the items themselves
+        assert_eq!(bytes, to_allocvec(&[3u32, 4, 5][..]).unwrap());
+        // a snapshot leaves the items where they were
+        assert_eq!(src.len(), 3);
+
+        let restored = SPSCEphemeral::<u32, 4>::restore(&mut Deserializer::from_bytes(&bytes));
+        assert_eq!(restored.unwrap().into_inner(), [3, 4, 5]);
+    }
+
+    #[test]
+    fn test_full_snapshot() {
+        let mut src = DynBuffer::with_capacity(8);
+        for i in 0..5 {
+            src.push(i.to_string()).unwrap();
+        }
+        let bytes = to_allocvec(&src.snapshot()).unwrap();
+
+        let restored = DynBuffer::<String>::restore(8, &mut Deserializer::from_bytes(&bytes));
+        assert_eq!(restored.unwrap().into_inner(), ["0", "1", "2", "3", "4"]);
+
+        // more than the ring takes
+        let restored = DynBuffer::<String>::restore(4, &mut Deserializer::from_bytes(&bytes));
+        assert!(restored.is_err());
+    }
+
+    #[test]
+    fn test_mpmc_snapshot() {
+        let mut src = MPMCEphemeral::<u64, 4>::new();
+        for i in 0..7 {
+            src.push(i).unwrap();
+            if i < 4 {
+                src.pop();
+            }
+        }
+
+        let snapshot = src.snapshot();
+        assert_eq!(snapshot.len(), 3);
+        let bytes = to_allocvec(&snapshot).unwrap();
+        let restored = MPMCEphemeral::<u64, 4>::restore(&mut Deserializer::from_bytes(&bytes));
+        assert_eq!(restored.unwrap().into_inner(), [4, 5, 6]);
+    }
+}
@@ -0,0 +1,318 @@
+use alloc::{sync::Arc, vec::Vec};
+#[cfg(feature = "std")]
+use core::time::Duration;
+use core::{iter, sync::atomic::AtomicUsize};
+
+use crate::util::CachePadded;
+
+use super::seq::{drop_pending, len, pop_shared, push_exclusive, slots, SeqSlot};
+#[cfg(feature = "stats")]
+use super::stats::{Counters, Stats};
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+#[cfg(feature = "std")]
+use super::wait::{push_until, retry_until, Timeout};
+
+/// Bounded single-producer/multi-consumer ring for work distribution,
+/// consumers compete for items via CAS on the head while the
+/// producer owns the tail outright
+/// N:: arena size, a power of two >= 2
+pub struct SPMCEphemeral<T, const N: usize> {
+    bufr: [SeqSlot<T>; N],
+    head: CachePadded<AtomicUsize>, // read position
+    tail: CachePadded<AtomicUsize>, // write position
+    #[cfg(feature = "stats")]
+    stats: Counters,
+    #[cfg(feature = "tracing")]
+    trace: Label,
+}
+
+impl<T, const N: usize> SPMCEphemeral<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            bufr: slots(),
+            head: CachePadded::new(AtomicUsize::new(0)),
+            tail: CachePadded::new(AtomicUsize::new(0)),
+            #[cfg(feature = "stats")]
+            stats: Counters::new(),
+            #[cfg(feature = "tracing")]
+            trace: Label::new("spmc"),
+        }
+    }
+
+    /// Names the ring in its `tracing` events, `spmc` otherwise
+    #[cfg(feature = "tracing")]
+    pub const fn with_name(mut self, name: &'static str) -> Self {
+        self.trace = Label::new(name);
+        self
+    }
+
+    /// Moves the buffer behind the one producer allowed
+    /// to write to it and a clonable consumer
+    pub fn split(self) -> (Producer<T, N>, Consumer<T, N>) {
+        let bufr = Arc::new(self);
+        let producer = Producer { bufr: bufr.clone() };
+        (producer, Consumer { bufr })
+    }
+
+    /// Pending items, approximate while other handles are busy
+    pub fn len(&self) -> usize {
+        len(&self.head, &self.tail, N)
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.stats.snapshot()
+    }
+
+    /// Drops every pending item
+    pub fn clear(&mut self) {
+        while pop_shared(&self.bufr, &self.head).is_some() {}
+    }
+
+    /// Remaining items in pop order
+    pub fn into_inner(self) -> Vec<T> {
+        iter::from_fn(|| pop_shared(&self.bufr, &self.head)).collect()
+    }
+}
+
+impl<T, const N: usize> Default for SPMCEphemeral<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for SPMCEphemeral<T, N> {
+    fn drop(&mut self) {
+        let head = *self.head.get_mut();
+        let tail = *self.tail.get_mut();
+        drop_pending(&mut self.bufr, head, tail);
+    }
+}
+
+unsafe impl<T: Send, const N: usize> Sync for SPMCEphemeral<T, N> {}
+
+/// Write half of a split `SPMCEphemeral`
+pub struct Producer<T, const N: usize> {
+    bufr: Arc<SPMCEphemeral<T, N>>,
+}
+
+impl<T, const N: usize> Producer<T, N> {
+    pub fn push(&mut self, val: T) -> Result<(), T> {
+        let res = push_exclusive(&self.bufr.bufr, &self.bufr.tail, val);
+        #[cfg(feature = "stats")]
+        match res {
+            Ok(()) => self.bufr.stats.pushed(1, self.len()),
+            Err(_) => self.bufr.stats.full(),
+        }
+        #[cfg(feature = "tracing")]
+        match res {
+            Ok(()) => self.bufr.trace.pushed(1, self.len()),
+            Err(_) => self.bufr.trace.full(),
+        }
+        res
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up once `timeout` passed, handing the value back
+    pub fn push_timeout(&mut self, val: T, timeout: Duration) -> Result<(), Timeout<T>> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.trace.blocking("push_timeout");
+        push_until(val, timeout, |val| self.push(val))
+    }
+
+    /// Pops the oldest item like a consumer would, to make room
+    pub(crate) fn evict(&self) -> Option<T> {
+        pop_shared(&self.bufr.bufr, &self.bufr.head)
+    }
+
+    /// Pending items, approximate while other handles are busy
+    pub fn len(&self) -> usize {
+        len(&self.bufr.head, &self.bufr.tail, N)
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.bufr.stats.snapshot()
+    }
+}
+
+/// Read half of a split `SPMCEphemeral`, clone it per worker thread
+pub struct Consumer<T, const N: usize> {
+    bufr: Arc<SPMCEphemeral<T, N>>,
+}
+
+impl<T, const N: usize> Consumer<T, N> {
+    pub fn pop(&self) -> Option<T> {
+        let val = pop_shared(&self.bufr.bufr, &self.bufr.head);
+        #[cfg(feature = "stats")]
+        match val {
+            Some(_) => self.bufr.stats.popped(1),
+            None => self.bufr.stats.empty(),
+        }
+        #[cfg(feature = "tracing")]
+        match val {
+            Some(_) => self.bufr.trace.popped(1),
+            None => self.bufr.trace.empty(),
+        }
+        val
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up with `None` once `timeout` passed
+    pub fn pop_timeout(&self, timeout: Duration) -> Option<T> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.trace.blocking("pop_timeout");
+        retry_until(timeout, || self.pop())
+    }
+
+    /// Pending items, approximate while other handles are busy
+    pub fn len(&self) -> usize {
+        len(&self.bufr.head, &self.bufr.tail, N)
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.bufr.stats.snapshot()
+    }
+}
+
+impl<T, const N: usize> Clone for Consumer<T, N> {
+    fn clone(&self) -> Self {
+        Self {
+            bufr: self.bufr.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    const ITEMS: usize = if cfg!(miri) { 400 } else { 40000 };
+
+    #[test]
+    fn test_seq_spmc() {
+        let (mut producer, consumer) = SPMCEphemeral::<usize, 8>::new().split();
+
+        for i in 0..8 {
+            assert!(producer.push(i).is_ok());
+        }
+        assert_eq!(producer.push(8), Err(8));
+
+        let other = consumer.clone();
+        for i in 0..8 {
+            let src = if i % 2 == 0 { &consumer } else { &other };
+            assert_eq!(src.pop(), Some(i));
+        }
+        assert_eq!(consumer.pop(), None);
+    }
+
+    #[test]
+    fn test_threaded_spmc() {
+        let (mut producer, consumer) = SPMCEphemeral::<usize, 32>::new().split();
+        let taken = Arc::new(AtomicUsize::new(0));
+
+        let produce_t = thread::spawn(move || {
+            for i in 0..ITEMS {
+                while producer.push(i).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let consumers: Vec<_> = (0..4)
+            .map(|_| {
+                let consumer = consumer.clone();
+                let taken = taken.clone();
+                thread::spawn(move || {
+                    let mut seen = Vec::new();
+                    while taken.load(Ordering::Relaxed) < ITEMS {
+                        match consumer.pop() {
+                            Some(val) => {
+                                seen.push(val);
+                                taken.fetch_add(1, Ordering::Relaxed);
+                            }
+                            None => thread::yield_now(),
+                        }
+                    }
+                    seen
+                })
+            })
+            .collect();
+
+        produce_t.join().unwrap();
+
+        let mut seen: Vec<_> = consumers
+            .into_iter()
+            .flat_map(|consumer| {
+                let seen = consumer.join().unwrap();
+                // a single consumer still observes items in order
+                assert!(seen.windows(2).all(|w| w[0] < w[1]));
+                seen
+            })
+            .collect();
+        seen.sort_unstable();
+        assert_eq!(seen, (0..ITEMS).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_timeout_spmc() {
+        let (mut producer, consumer) = SPMCEphemeral::<i32, 2>::new().split();
+        let timeout = Duration::from_millis(10);
+
+        assert_eq!(consumer.pop_timeout(timeout), None);
+        assert!(producer.push_timeout(1, timeout).is_ok());
+        assert!(producer.push_timeout(2, timeout).is_ok());
+        assert_eq!(producer.push_timeout(4, timeout), Err(Timeout(4)));
+
+        let produce_t = thread::spawn(move || {
+            thread::sleep(timeout);
+            producer.push_timeout(3, Duration::from_secs(5))
+        });
+        assert_eq!(consumer.pop_timeout(Duration::from_secs(5)), Some(1));
+        assert_eq!(consumer.pop_timeout(Duration::from_secs(5)), Some(2));
+        assert_eq!(consumer.pop_timeout(Duration::from_secs(5)), Some(3));
+        assert!(produce_t.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_len_spmc() {
+        let src = SPMCEphemeral::<usize, 8>::new();
+        assert_eq!((src.len(), src.capacity()), (0, 8));
+
+        let (mut producer, consumer) = src.split();
+        for i in 0..5 {
+            producer.push(i).unwrap();
+        }
+        assert_eq!((producer.len(), producer.free_space()), (5, 3));
+        consumer.pop().unwrap();
+        assert_eq!((consumer.len(), consumer.free_space()), (4, 4));
+
+        while producer.push(0).is_ok() {}
+        assert!(producer.is_full() && consumer.is_full());
+        while consumer.pop().is_some() {}
+        assert!(producer.is_empty() && consumer.is_empty());
+    }
+}
@@ -1,75 +1,1203 @@
-use std::{
-    cell::UnsafeCell,
-    mem::MaybeUninit,
-    sync::atomic::{AtomicUsize, Ordering}, usize,
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::time::Duration;
+use core::{
+    error::Error,
+    fmt,
+    iter::{self, Take},
+    mem::{ManuallyDrop, MaybeUninit},
+    ops::{Deref, DerefMut},
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer};
 
+use crate::sync::{long_wait, Arc, AtomicBool, AtomicU64, UnsafeCell};
+#[cfg(feature = "async")]
+use crate::util::AtomicWaker;
+use crate::util::CachePadded;
+#[cfg(feature = "notify")]
+use crate::util::Notify;
+
+#[cfg(feature = "std")]
+use super::adapt::TimeoutChunks;
+use super::adapt::{Batch, Filter, Map};
+use super::cancel::{CancellationToken, Interrupted};
+#[cfg(feature = "latency")]
+use super::latency::{Latency, LatencyStats};
+#[cfg(feature = "serde")]
+use super::snapshot::{self, Snapshot};
+#[cfg(feature = "stats")]
+use super::stats::{Counters, Stats};
+#[cfg(feature = "std")]
+use super::token::{ConsumerToken, Owners, ProducerToken};
+#[cfg(feature = "tracing")]
+use super::trace::Label;
+#[cfg(feature = "debug-validate")]
+use super::validate::Stamps;
+#[cfg(feature = "notify")]
+use super::wait::retry_notified;
+#[cfg(all(feature = "std", not(feature = "notify")))]
+use super::wait::Backoff;
+use super::wait::{retry, WaitStrategy};
+#[cfg(feature = "std")]
+use super::wait::{retry_until, Timeout};
+
+/// Orderings of every ring index and flag access. The tuned ones by
+/// default, SeqCst all round under `strict-ordering`, for ruling out
+/// an ordering bug while debugging and for TSAN runs
+mod order {
+    use core::sync::atomic::Ordering;
+
+    #[cfg(not(feature = "strict-ordering"))]
+    pub const ACQUIRE: Ordering = Ordering::Acquire;
+    #[cfg(not(feature = "strict-ordering"))]
+    pub const RELEASE: Ordering = Ordering::Release;
+    #[cfg(not(feature = "strict-ordering"))]
+    pub const RELAXED: Ordering = Ordering::Relaxed;
+
+    #[cfg(feature = "strict-ordering")]
+    pub const ACQUIRE: Ordering = Ordering::SeqCst;
+    #[cfg(feature = "strict-ordering")]
+    pub const RELEASE: Ordering = Ordering::SeqCst;
+    #[cfg(feature = "strict-ordering")]
+    pub const RELAXED: Ordering = Ordering::SeqCst;
+}
+
+pub(crate) mod sealed {
+    pub trait Sealed {}
+}
+
+/// Slot storage behind a single-producer/single-consumer ring,
+/// lets every arena layout share the same split handles. Sealed,
+/// only the crate's arenas are rings
+///
+/// `head` and `tail` are free-running positions, the slot
+/// of a position is `pos & (arena_size - 1)` and `tail - head`
+/// is the number of pending items, so no slot goes to waste
+///
+/// # Safety
+/// `arena_size` must be a power of two, `slot` must hand out
+/// the same cell for a given `idx < arena_size()` every time, and
+/// `state` must only ever be touched by the ring operations
+/// in this module
+pub unsafe trait Ring: sealed::Sealed {
+    type Item;
+
+    fn arena_size(&self) -> usize;
+    fn state(&self) -> &RingState;
+    // the cell type is the crate's own, nothing outside can use it
+    #[doc(hidden)]
+    fn slot(&self, idx: usize) -> &UnsafeCell<MaybeUninit<Self::Item>>;
+
+    /// NUMA node the slots were placed on, if any
+    #[cfg(feature = "numa")]
+    fn node(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Indices and flags the two handles of a ring share
+pub struct RingState {
+    pub(crate) head: CachePadded<AtomicU64>, // read position
+    pub(crate) tail: CachePadded<AtomicU64>, // write position
+    pub(crate) closed: AtomicBool,
+    // async tasks parked on either side of the ring
+    #[cfg(feature = "async")]
+    pub(crate) producer_waker: AtomicWaker,
+    #[cfg(feature = "async")]
+    pub(crate) consumer_waker: AtomicWaker,
+    // threads asleep in a blocking push/pop
+    #[cfg(feature = "notify")]
+    pub(crate) producer_notify: Notify,
+    #[cfg(feature = "notify")]
+    pub(crate) consumer_notify: Notify,
+    #[cfg(feature = "stats")]
+    pub(crate) stats: Counters,
+    #[cfg(feature = "latency")]
+    pub(crate) latency: Latency,
+    #[cfg(feature = "tracing")]
+    pub(crate) trace: Label,
+    #[cfg(feature = "debug-validate")]
+    pub(crate) stamps: Stamps,
+    // threads the sides got bound to through tokens
+    #[cfg(feature = "std")]
+    pub(crate) owners: Owners,
+}
+
+impl RingState {
+    const_fn! {
+        pub fn new() -> Self {
+            Self {
+                head: CachePadded::new(AtomicU64::new(0)),
+                tail: CachePadded::new(AtomicU64::new(0)),
+                closed: AtomicBool::new(false),
+                #[cfg(feature = "async")]
+                producer_waker: AtomicWaker::new(),
+                #[cfg(feature = "async")]
+                consumer_waker: AtomicWaker::new(),
+                #[cfg(feature = "notify")]
+                producer_notify: Notify::new(),
+                #[cfg(feature = "notify")]
+                consumer_notify: Notify::new(),
+                #[cfg(feature = "stats")]
+                stats: Counters::new(),
+                #[cfg(feature = "latency")]
+                latency: Latency::new(),
+                #[cfg(feature = "tracing")]
+                trace: Label::new("spsc"),
+                #[cfg(feature = "debug-validate")]
+                stamps: Stamps::new(),
+                #[cfg(feature = "std")]
+                owners: Owners::new(),
+            }
+        }
+    }
+
+    fn close(&self) {
+        self.closed.store(true, order::RELEASE);
+        self.wake_producer();
+        self.wake_consumer();
+    }
+
+    /// Wakes a task or thread waiting for room
+    fn wake_producer(&self) {
+        #[cfg(feature = "async")]
+        self.producer_waker.wake();
+        #[cfg(feature = "notify")]
+        if self.producer_notify.wake() {
+            #[cfg(feature = "tracing")]
+            self.trace.woke("producer");
+        }
+    }
+
+    /// Wakes a task or thread waiting for an item
+    fn wake_consumer(&self) {
+        #[cfg(feature = "async")]
+        self.consumer_waker.wake();
+        #[cfg(feature = "notify")]
+        if self.consumer_notify.wake() {
+            #[cfg(feature = "tracing")]
+            self.trace.woke("consumer");
+        }
+    }
+}
+
+impl Default for RingState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pending items, approximate while either handle is busy
+pub(crate) fn len<R: Ring>(b: &R) -> usize {
+    // head first, a pop landing in between can't push it past tail
+    let head = b.state().head.load(order::ACQUIRE);
+    let tail = b.state().tail.load(order::ACQUIRE);
+    (tail.wrapping_sub(head) as usize).min(b.arena_size())
+}
+
+/// Slot behind a free-running position
+fn slot_at<R: Ring>(b: &R, pos: u64) -> &UnsafeCell<MaybeUninit<R::Item>> {
+    b.slot(pos as usize & (b.arena_size() - 1))
+}
+
+/// # Safety
+/// The slot behind `pos` must be free and owned by the caller
+unsafe fn write_at<R: Ring>(b: &R, pos: u64, val: R::Item) {
+    #[cfg(feature = "debug-validate")]
+    b.state().stamps.writing(b.arena_size(), pos);
+    slot_at(b, pos).with_mut(|slot| (*slot).write(val));
+    #[cfg(feature = "debug-validate")]
+    b.state().stamps.written(b.arena_size(), pos);
+    #[cfg(feature = "latency")]
+    b.state().latency.pushed(b.arena_size(), pos);
+}
+
+/// # Safety
+/// The slot behind `pos` must hold an item owned by the caller
+unsafe fn read_at<R: Ring>(b: &R, pos: u64) -> R::Item {
+    #[cfg(feature = "debug-validate")]
+    b.state().stamps.reading(b.arena_size(), pos);
+    let val = slot_at(b, pos).with(|slot| (*slot).assume_init_read());
+    // still ours, or a write raced the read and `val` may be torn
+    #[cfg(feature = "debug-validate")]
+    {
+        b.state().stamps.reading(b.arena_size(), pos);
+        b.state().stamps.read(b.arena_size(), pos);
+    }
+    #[cfg(feature = "latency")]
+    b.state().latency.popped(b.arena_size(), pos);
+    val
+}
+
+/// # Safety
+/// Same as `read_at`
+unsafe fn drop_at<R: Ring>(b: &R, pos: u64) {
+    #[cfg(feature = "debug-validate")]
+    b.state().stamps.reading(b.arena_size(), pos);
+    slot_at(b, pos).with_mut(|slot| (*slot).assume_init_drop());
+    #[cfg(feature = "debug-validate")]
+    b.state().stamps.read(b.arena_size(), pos);
+}
+
+/// Uninitialized arena for `SPSCEphemeral` and `SmallRing`
+#[cfg(not(loom))]
+pub(crate) const fn arena<T, const N: usize>() -> [UnsafeCell<MaybeUninit<T>>; N] {
+    [const { UnsafeCell::new(MaybeUninit::uninit()) }; N]
+}
+
+#[cfg(loom)]
+pub(crate) fn arena<T, const N: usize>() -> [UnsafeCell<MaybeUninit<T>>; N] {
+    core::array::from_fn(|_| UnsafeCell::new(MaybeUninit::uninit()))
+}
+
+/// Why `Consumer::pop` came back empty handed
+///
+/// Only the handles that can outlive their other side return it, see
+/// `PushError`, the shared queues just return `None` when empty
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PopError {
+    Empty,
+    /// the producer is gone and nothing is left queued
+    Disconnected,
+}
+
+/// Why `Producer::try_push` handed the value back
+///
+/// Only the handles that can outlive their other side return it, the
+/// split rings, their async and duplex forms and the backpressure
+/// producer. The shared queues, `mpsc`, `mpmc`, `spmc` and the rest,
+/// have no other side to lose and hand the value back as `Err(val)`
+/// when full
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PushError<T> {
+    Full(T),
+    /// the consumer is gone, nothing pushed would ever be popped
+    Disconnected(T),
+}
+
+impl<T> PushError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            Self::Full(val) | Self::Disconnected(val) => val,
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        matches!(self, Self::Full(_))
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        matches!(self, Self::Disconnected(_))
+    }
+}
+
+/// The other handle was closed or dropped
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Disconnected;
+
+impl<T> fmt::Display for PushError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(_) => f.pad("pushing onto a full ring"),
+            Self::Disconnected(_) => f.pad("pushing onto a disconnected ring"),
+        }
+    }
+}
+
+impl fmt::Display for PopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.pad("popping from an empty ring"),
+            Self::Disconnected => f.pad("popping from a disconnected ring"),
+        }
+    }
+}
+
+impl fmt::Display for Disconnected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("the other side disconnected")
+    }
+}
+
+impl<T: fmt::Debug> Error for PushError<T> {}
+impl Error for PopError {}
+impl Error for Disconnected {}
 
 /// Preallocates memory and attempts to increase
 /// consume/produce efficiency by using an arena
-/// N:: arena size
+/// N:: arena size, a power of two
 pub struct SPSCEphemeral<T, const N: usize> {
-    bufr: UnsafeCell<[MaybeUninit<T>; N]>,
-    head: AtomicUsize, // read index
-    tail: AtomicUsize, // write index
+    bufr: [UnsafeCell<MaybeUninit<T>>; N],
+    pub(crate) state: RingState,
+}
+
+impl<T, const N: usize> SPSCEphemeral<T, N> {
+    const_fn! {
+        pub fn new() -> Self {
+            const { assert!(N.is_power_of_two(), "arena size must be a power of two") };
+
+            Self {
+                bufr: arena(),
+                state: RingState::new(),
+            }
+        }
+    }
+
+    const_fn! {
+        /// Names the ring in its `tracing` events, `spsc` otherwise
+        #[cfg(feature = "tracing")]
+        pub fn with_name(mut self, name: &'static str) -> Self {
+            self.state.trace = Label::new(name);
+            self
+        }
+    }
+
+    /// Moves the buffer behind a producer/consumer pair,
+    /// so only one thread can ever write and one can read
+    pub fn split(self) -> (Producer<Self>, Consumer<Self>) {
+        split(self)
+    }
+
+    /// Pushes while nothing else holds the ring, `split` or
+    /// `producer_token` push from another thread
+    pub fn push(&mut self, val: T) -> Result<(), T> {
+        push(self, val)
+    }
+
+    /// Pops while nothing else holds the ring, `split` or
+    /// `consumer_token` pop from another thread
+    pub fn pop(&mut self) -> Option<T> {
+        pop(self)
+    }
+
+    /// `push` through `&self` that catches a second producer
+    /// thread, see `ProducerToken`
+    #[cfg(feature = "std")]
+    pub fn producer_token(&self) -> ProducerToken<'_, Self> {
+        ProducerToken::new(self)
+    }
+
+    /// `pop` through `&self` that catches a second consumer thread
+    #[cfg(feature = "std")]
+    pub fn consumer_token(&self) -> ConsumerToken<'_, Self> {
+        ConsumerToken::new(self)
+    }
+
+    pub fn try_push(&mut self, val: T) -> Result<(), T> {
+        self.push(val)
+    }
+
+    pub fn try_pop(&mut self) -> Option<T> {
+        self.pop()
+    }
+
+    /// Pending items, approximate while the other side is busy
+    pub fn len(&self) -> usize {
+        len(self)
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.state.stats.snapshot()
+    }
+
+    /// Queueing delay histogram of what was popped so far
+    #[cfg(feature = "latency")]
+    pub fn latency_stats(&self) -> LatencyStats {
+        self.state.latency.snapshot()
+    }
+
+    /// Drops every pending item
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Remaining items in pop order
+    pub fn into_inner(mut self) -> Vec<T> {
+        iter::from_fn(|| self.pop()).collect()
+    }
+
+    /// Borrows what's queued for serializing, see `Snapshot`
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&mut self) -> Snapshot<'_, T> {
+        Snapshot::new(pending(self))
+    }
+
+    /// A ring holding what a `snapshot` serialized, in the same order.
+    /// Fails on more items than fit
+    #[cfg(feature = "serde")]
+    pub fn restore<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let mut ring = Self::new();
+        snapshot::restore(deserializer, N, |val| ring.push(val))?;
+        Ok(ring)
+    }
+}
+
+impl<T, const N: usize> Default for SPSCEphemeral<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> sealed::Sealed for SPSCEphemeral<T, N> {}
+
+unsafe impl<T, const N: usize> Ring for SPSCEphemeral<T, N> {
+    type Item = T;
+
+    fn arena_size(&self) -> usize {
+        N
+    }
+
+    fn state(&self) -> &RingState {
+        &self.state
+    }
+
+    fn slot(&self, idx: usize) -> &UnsafeCell<MaybeUninit<T>> {
+        &self.bufr[idx]
+    }
+}
+
+/// Moves any ring behind a producer/consumer pair
+pub(crate) fn split<R: Ring>(ring: R) -> (Producer<R>, Consumer<R>) {
+    let bufr = Arc::new(ring);
+    let producer = Producer {
+        head: bufr.state().head.load(order::ACQUIRE),
+        bufr: bufr.clone(),
+    };
+    let consumer = Consumer {
+        tail: bufr.state().tail.load(order::ACQUIRE),
+        bufr,
+    };
+    (producer, consumer)
+}
+
+/// Write half of a split ring, dropping it closes the ring
+pub struct Producer<R: Ring> {
+    pub(crate) bufr: Arc<R>,
+    head: u64, // last seen read position, only refreshed on apparent full
+}
+
+impl<R: Ring> Producer<R> {
+    /// Fails when full or once the consumer is gone
+    pub fn push(&mut self, val: R::Item) -> Result<(), R::Item> {
+        let (tail, free) = self.claim(1);
+
+        // guard: full or closed
+        if free == 0 {
+            #[cfg(feature = "stats")]
+            self.bufr.state().stats.full();
+            #[cfg(feature = "tracing")]
+            self.bufr.state().trace.full();
+            return Err(val);
+        }
+
+        unsafe { write_at(&*self.bufr, tail, val) };
+        self.publish(tail.wrapping_add(1));
+        Ok(())
+    }
+
+    /// Copies as much of `vals` as fits, publishing it in one go
+    pub fn push_slice(&mut self, vals: &[R::Item]) -> usize
+    where
+        R::Item: Copy,
+    {
+        self.push_iter(vals.iter().copied())
+    }
+
+    /// Moves items out of `vals` while there is room, publishing them
+    /// in one go. Items past the free space are never pulled, pass
+    /// `by_ref()` to keep them
+    pub fn push_iter<I: IntoIterator<Item = R::Item>>(&mut self, vals: I) -> usize {
+        let (tail, free) = self.claim(usize::MAX);
+        let b = &*self.bufr;
+
+        let mut pushed = 0;
+        for val in vals.into_iter().take(free) {
+            unsafe { write_at(b, tail.wrapping_add(pushed as u64), val) };
+            pushed += 1;
+        }
+
+        self.publish(tail.wrapping_add(pushed as u64));
+        pushed
+    }
+
+    /// Hands out the next free slot to build a value in place,
+    /// nothing is published until `WriteSlot::commit`
+    pub fn reserve(&mut self) -> Option<WriteSlot<'_, R>> {
+        let (pos, free) = self.claim(1);
+        (free == 1).then_some(WriteSlot {
+            producer: self,
+            pos,
+            init: false,
+        })
+    }
+
+    /// Tells the consumer no more items are coming,
+    /// whatever is queued already can still be popped
+    pub fn close(&mut self) {
+        self.bufr.state().close();
+    }
+
+    /// Closed by either side, pushes fail from here on
+    pub fn is_disconnected(&self) -> bool {
+        self.bufr.state().closed.load(order::ACQUIRE)
+    }
+
+    /// Pending items, approximate while the other side is busy
+    pub fn len(&self) -> usize {
+        len(&*self.bufr)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.bufr.arena_size()
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.bufr.state().stats.snapshot()
+    }
+
+    /// Queueing delay histogram of what was popped so far
+    #[cfg(feature = "latency")]
+    pub fn latency_stats(&self) -> LatencyStats {
+        self.bufr.state().latency.snapshot()
+    }
+
+    /// NUMA node the ring's memory sits on, the one to run this side on
+    #[cfg(feature = "numa")]
+    pub fn preferred_node(&self) -> Option<usize> {
+        self.bufr.node()
+    }
+
+    /// Whether the next push can go through
+    #[cfg(feature = "async")]
+    pub(crate) fn has_room(&mut self) -> bool {
+        self.claim(1).1 == 1
+    }
+
+    /// Makes everything before `tail` visible to the consumer
+    fn publish(&self, tail: u64) {
+        let state = self.bufr.state();
+        #[cfg(any(feature = "stats", feature = "tracing"))]
+        {
+            let pushed = tail.wrapping_sub(state.tail.load(order::RELAXED)) as usize;
+            let pending = tail.wrapping_sub(state.head.load(order::RELAXED)) as usize;
+            #[cfg(feature = "stats")]
+            state.stats.pushed(pushed, pending);
+            #[cfg(feature = "tracing")]
+            state.trace.pushed(pushed, pending);
+        }
+        state.tail.store(tail, order::RELEASE);
+        state.wake_consumer();
+    }
+
+    /// Current write position and up to `wanted` free slots after it,
+    /// the cached head is only refreshed when it looks too short.
+    /// A closed ring has no free slots
+    fn claim(&mut self, wanted: usize) -> (u64, usize) {
+        let b = &*self.bufr;
+        let state = b.state();
+        let tail = state.tail.load(order::RELAXED);
+        let size = b.arena_size();
+
+        if state.closed.load(order::RELAXED) {
+            return (tail, 0);
+        }
+
+        let mut free = size - tail.wrapping_sub(self.head) as usize;
+        if free < wanted {
+            self.head = state.head.load(order::ACQUIRE);
+            free = size - tail.wrapping_sub(self.head) as usize;
+        }
+        (tail, free.min(wanted))
+    }
+
+    /// `push` telling a full ring from a gone consumer
+    pub fn try_push(&mut self, val: R::Item) -> Result<(), PushError<R::Item>> {
+        self.push(val).map_err(|val| match self.is_disconnected() {
+            true => PushError::Disconnected(val),
+            false => PushError::Full(val),
+        })
+    }
+
+    #[cfg(all(feature = "std", not(feature = "notify")))]
+    /// Waits with `Backoff` until there is room,
+    /// hands the value back if the consumer is gone
+    pub fn push_blocking(&mut self, val: R::Item) -> Result<(), R::Item> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.state().trace.blocking("push_blocking");
+        self.push_blocking_with(val, &mut Backoff::new())
+    }
+
+    #[cfg(feature = "notify")]
+    /// Spins briefly, then sleeps until the consumer frees a slot,
+    /// hands the value back if the consumer is gone
+    pub fn push_blocking(&mut self, val: R::Item) -> Result<(), R::Item> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.state().trace.blocking("push_blocking");
+        let bufr = Arc::clone(&self.bufr);
+        let mut pending = Some(val);
+        retry_notified(&bufr.state().producer_notify, || self.attempt(&mut pending));
+        pending.map_or(Ok(()), Err)
+    }
+
+    pub fn push_blocking_with<W: WaitStrategy>(
+        &mut self,
+        val: R::Item,
+        wait: &mut W,
+    ) -> Result<(), R::Item> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.state().trace.blocking("push_blocking_with");
+        let mut pending = Some(val);
+        retry(wait, || self.attempt(&mut pending));
+        pending.map_or(Ok(()), Err)
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up once `timeout` passed or the consumer is gone,
+    /// handing the value back
+    pub fn push_timeout(
+        &mut self,
+        val: R::Item,
+        timeout: Duration,
+    ) -> Result<(), Timeout<R::Item>> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.state().trace.blocking("push_timeout");
+        let mut pending = Some(val);
+        retry_until(timeout, || self.attempt(&mut pending));
+        pending.map_or(Ok(()), |val| Err(Timeout(val)))
+    }
+
+    /// `push_blocking` that also hands the value back
+    /// once `token` is cancelled
+    pub fn push_cancellable(
+        &mut self,
+        val: R::Item,
+        token: &CancellationToken,
+    ) -> Result<(), R::Item> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.state().trace.blocking("push_cancellable");
+        let mut pending = Some(val);
+        let _ = token.retry(&mut long_wait(), || self.attempt(&mut pending));
+        pending.map_or(Ok(()), Err)
+    }
+
+    /// One push for the retry loops, `None` means try again
+    /// and `pending` keeps the value until it went through
+    fn attempt(&mut self, pending: &mut Option<R::Item>) -> Option<()> {
+        match self.push(pending.take()?) {
+            Ok(()) => Some(()),
+            Err(val) => {
+                *pending = Some(val);
+                self.is_disconnected().then_some(())
+            }
+        }
+    }
+}
+
+impl<R: Ring> Drop for Producer<R> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Read half of a split ring, dropping it closes the ring
+pub struct Consumer<R: Ring> {
+    pub(crate) bufr: Arc<R>,
+    tail: u64, // last seen write position, only refreshed on apparent empty
+}
+
+impl<R: Ring> Consumer<R> {
+    /// `Disconnected` only once the producer is gone
+    /// and every item it pushed has been popped
+    pub fn pop(&mut self) -> Result<R::Item, PopError> {
+        let (head, ready) = self.ready(1);
+
+        // guard: empty, or drained after the producer left
+        if ready == 0 {
+            if !self.is_disconnected() {
+                #[cfg(feature = "stats")]
+                self.bufr.state().stats.empty();
+                #[cfg(feature = "tracing")]
+                self.bufr.state().trace.empty();
+                return Err(PopError::Empty);
+            }
+            // items published right before the close may have landed since
+            if self.ready(1).1 == 0 {
+                return Err(PopError::Disconnected);
+            }
+            return self.pop();
+        }
+
+        let val = unsafe { read_at(&*self.bufr, head) };
+        self.release(head.wrapping_add(1));
+        Ok(val)
+    }
+
+    /// Moves up to `max` items into `out`, releasing their slots in one go
+    pub fn pop_batch(&mut self, out: &mut Vec<R::Item>, max: usize) -> usize {
+        let (head, ready) = self.ready(max);
+        let b = &*self.bufr;
+
+        out.reserve(ready);
+        for i in 0..ready {
+            out.push(unsafe { read_at(b, head.wrapping_add(i as u64)) });
+        }
+
+        self.release(head.wrapping_add(ready as u64));
+        ready
+    }
+
+    /// Moves every item visible right now into `out`, for consumers
+    /// working in bursts. One acquire of the tail and one release of
+    /// the head however many came out, where `drain` touches both per
+    /// item. Items are appended, clearing `out` between bursts keeps
+    /// its capacity for the next
+    pub fn pop_all_into(&mut self, out: &mut Vec<R::Item>) -> usize {
+        self.pop_batch(out, usize::MAX)
+    }
+
+    /// Pops until the ring looks empty
+    pub fn drain(&mut self) -> Drain<'_, R> {
+        Drain { consumer: self }
+    }
+
+    /// Pops at most `n` items, stopping early once the ring looks empty
+    pub fn drain_up_to(&mut self, n: usize) -> Take<Drain<'_, R>> {
+        self.drain().take(n)
+    }
+
+    /// `Consume::map_items` without the import
+    pub fn map_items<U, F: FnMut(R::Item) -> U>(self, f: F) -> Map<R::Item, Self, F> {
+        Map::new(self, f)
+    }
+
+    /// `Consume::filter_items`
+    pub fn filter_items<F: FnMut(&R::Item) -> bool>(self, f: F) -> Filter<R::Item, Self, F> {
+        Filter::new(self, f)
+    }
+
+    /// `Consume::batch`
+    pub fn batch(self, max: usize) -> Batch<R::Item, Self> {
+        Batch::new(self, max)
+    }
+
+    /// `Consume::timeout_chunks`
+    #[cfg(feature = "std")]
+    pub fn timeout_chunks(self, max: usize, linger: Duration) -> TimeoutChunks<R::Item, Self> {
+        TimeoutChunks::new(self, max, linger)
+    }
+
+    /// Borrows the next item where it sits, it stays
+    /// queued unless taken out of the `ReadSlot`
+    pub fn peek_slot(&mut self) -> Option<ReadSlot<'_, R>> {
+        let (pos, ready) = self.ready(1);
+        (ready == 1).then_some(ReadSlot {
+            consumer: self,
+            pos,
+        })
+    }
+
+    /// Next item without popping it. Only the consumer frees slots
+    /// and popping needs `&mut self`, so the item stays put for as
+    /// long as it is borrowed
+    pub fn peek(&self) -> Option<&R::Item> {
+        let state = self.bufr.state();
+        let head = state.head.load(order::RELAXED);
+
+        // the cached tail can't be refreshed from `&self`
+        if self.tail == head && state.tail.load(order::ACQUIRE) == head {
+            return None;
+        }
+        Some(unsafe { slot_at(&*self.bufr, head).with(|slot| (*slot).assume_init_ref()) })
+    }
+
+    /// Closed by either side, queued items can still be popped
+    pub fn is_disconnected(&self) -> bool {
+        self.bufr.state().closed.load(order::ACQUIRE)
+    }
+
+    /// Pending items, approximate while the other side is busy
+    pub fn len(&self) -> usize {
+        len(&*self.bufr)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.bufr.arena_size()
+    }
+
+    occupancy!();
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.bufr.state().stats.snapshot()
+    }
+
+    /// Queueing delay histogram of what was popped so far
+    #[cfg(feature = "latency")]
+    pub fn latency_stats(&self) -> LatencyStats {
+        self.bufr.state().latency.snapshot()
+    }
+
+    /// NUMA node the ring's memory sits on, the one to run this side on
+    #[cfg(feature = "numa")]
+    pub fn preferred_node(&self) -> Option<usize> {
+        self.bufr.node()
+    }
+
+    /// Hands every slot before `head` back to the producer
+    fn release(&self, head: u64) {
+        let state = self.bufr.state();
+        #[cfg(any(feature = "stats", feature = "tracing"))]
+        {
+            let popped = head.wrapping_sub(state.head.load(order::RELAXED)) as usize;
+            #[cfg(feature = "stats")]
+            state.stats.popped(popped);
+            #[cfg(feature = "tracing")]
+            state.trace.popped(popped);
+        }
+        state.head.store(head, order::RELEASE);
+        state.wake_producer();
+    }
+
+    /// Current read position and up to `wanted` items after it,
+    /// the cached tail is only refreshed when it looks too short
+    fn ready(&mut self, wanted: usize) -> (u64, usize) {
+        let state = self.bufr.state();
+        let head = state.head.load(order::RELAXED);
+
+        let mut ready = self.tail.wrapping_sub(head) as usize;
+        if ready < wanted {
+            self.tail = state.tail.load(order::ACQUIRE);
+            ready = self.tail.wrapping_sub(head) as usize;
+        }
+        (head, ready.min(wanted))
+    }
+
+    pub fn try_pop(&mut self) -> Result<R::Item, PopError> {
+        self.pop()
+    }
+
+    #[cfg(all(feature = "std", not(feature = "notify")))]
+    /// Waits with `Backoff` until an item arrives
+    /// or the producer is gone
+    pub fn pop_blocking(&mut self) -> Result<R::Item, Disconnected> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.state().trace.blocking("pop_blocking");
+        self.pop_blocking_with(&mut Backoff::new())
+    }
+
+    #[cfg(feature = "notify")]
+    /// Spins briefly, then sleeps until an item arrives
+    /// or the producer is gone
+    pub fn pop_blocking(&mut self) -> Result<R::Item, Disconnected> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.state().trace.blocking("pop_blocking");
+        let bufr = Arc::clone(&self.bufr);
+        retry_notified(&bufr.state().consumer_notify, || self.attempt()).map_err(|_| Disconnected)
+    }
+
+    pub fn pop_blocking_with<W: WaitStrategy>(
+        &mut self,
+        wait: &mut W,
+    ) -> Result<R::Item, Disconnected> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.state().trace.blocking("pop_blocking_with");
+        retry(wait, || self.attempt()).map_err(|_| Disconnected)
+    }
+
+    #[cfg(feature = "std")]
+    /// Gives up with `Empty` once `timeout` passed
+    pub fn pop_timeout(&mut self, timeout: Duration) -> Result<R::Item, PopError> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.state().trace.blocking("pop_timeout");
+        retry_until(timeout, || self.attempt()).unwrap_or(Err(PopError::Empty))
+    }
+
+    /// `pop_blocking` that gives up once `token` is cancelled,
+    /// items already queued still come out first
+    pub fn pop_cancellable(&mut self, token: &CancellationToken) -> Result<R::Item, Interrupted> {
+        #[cfg(feature = "tracing")]
+        let _span = self.bufr.state().trace.blocking("pop_cancellable");
+        token
+            .retry(&mut long_wait(), || self.attempt())?
+            .map_err(|_| Interrupted::Disconnected)
+    }
+
+    /// One pop for the retry loops, `None` means try again
+    fn attempt(&mut self) -> Option<Result<R::Item, PopError>> {
+        match self.pop() {
+            Err(PopError::Empty) => None,
+            res => Some(res),
+        }
+    }
+}
+
+impl<R: Ring> Drop for Consumer<R> {
+    fn drop(&mut self) {
+        self.bufr.state().close();
+    }
 }
 
-impl<T, const N: usize> SPSCEphemeral<T, N> {
-    pub const fn new() -> Self {
-        Self {
-            bufr: unsafe { MaybeUninit::uninit().assume_init() },
-            head: AtomicUsize::new(0),
-            tail: AtomicUsize::new(0),
+// replaces the auto impl, `peek` hands out `&R::Item` from `&self`
+unsafe impl<R: Ring + Send + Sync> Sync for Consumer<R> where R::Item: Sync {}
+
+/// Ends at the first empty pop, later pushes can resume it
+impl<R: Ring> Iterator for Consumer<R> {
+    type Item = R::Item;
+
+    fn next(&mut self) -> Option<R::Item> {
+        self.pop().ok()
+    }
+}
+
+/// Iterator returned by `Consumer::drain`
+pub struct Drain<'a, R: Ring> {
+    consumer: &'a mut Consumer<R>,
+}
+
+impl<R: Ring> Iterator for Drain<'_, R> {
+    type Item = R::Item;
+
+    fn next(&mut self) -> Option<R::Item> {
+        self.consumer.pop().ok()
+    }
+}
+
+/// Free slot reserved by `Producer::reserve`,
+/// dropping it without `commit` cancels the push
+pub struct WriteSlot<'a, R: Ring> {
+    producer: &'a mut Producer<R>,
+    pos: u64,
+    init: bool,
+}
+
+impl<R: Ring> WriteSlot<'_, R> {
+    /// Uninitialized storage, fill it and call `assume_init`
+    pub fn as_mut_ptr(&mut self) -> *mut R::Item {
+        slot_at(&*self.producer.bufr, self.pos).with_mut(|slot| slot.cast())
+    }
+
+    /// # Safety
+    /// A valid value must have been written through `as_mut_ptr`
+    pub unsafe fn assume_init(&mut self) {
+        self.init = true;
+    }
+
+    pub fn write(&mut self, val: R::Item) -> &mut R::Item {
+        let ptr = self.as_mut_ptr();
+        if self.init {
+            unsafe { ptr.drop_in_place() };
+        }
+        self.init = true;
+        unsafe {
+            ptr.write(val);
+            &mut *ptr
+        }
+    }
+
+    /// Publishes the value to the consumer
+    pub fn commit(self) {
+        assert!(self.init, "committed a slot that was never written");
+
+        let slot = ManuallyDrop::new(self);
+        #[cfg(feature = "debug-validate")]
+        {
+            let b = &*slot.producer.bufr;
+            b.state().stamps.writing(b.arena_size(), slot.pos);
+            b.state().stamps.written(b.arena_size(), slot.pos);
+        }
+        #[cfg(feature = "latency")]
+        {
+            let b = &*slot.producer.bufr;
+            b.state().latency.pushed(b.arena_size(), slot.pos);
+        }
+        slot.producer.publish(slot.pos.wrapping_add(1));
+    }
+}
+
+impl<R: Ring> Drop for WriteSlot<'_, R> {
+    fn drop(&mut self) {
+        if self.init {
+            unsafe { self.as_mut_ptr().drop_in_place() };
+        }
+    }
+}
+
+/// Queued item borrowed by `Consumer::peek_slot`,
+/// dropping it leaves the item at the front of the ring
+pub struct ReadSlot<'a, R: Ring> {
+    consumer: &'a mut Consumer<R>,
+    pos: u64,
+}
+
+impl<R: Ring> ReadSlot<'_, R> {
+    /// Moves the item out and frees its slot
+    pub fn take(self) -> R::Item {
+        let slot = ManuallyDrop::new(self);
+        let val = unsafe { read_at(&*slot.consumer.bufr, slot.pos) };
+        slot.release_slot();
+        val
+    }
+
+    /// Drops the item in place and frees its slot
+    pub fn release(self) {
+        let slot = ManuallyDrop::new(self);
+        let b = &*slot.consumer.bufr;
+        unsafe { drop_at(b, slot.pos) };
+        #[cfg(feature = "latency")]
+        b.state().latency.popped(b.arena_size(), slot.pos);
+        slot.release_slot();
+    }
+
+    fn release_slot(&self) {
+        self.consumer.release(self.pos.wrapping_add(1));
+    }
+}
+
+impl<R: Ring> Deref for ReadSlot<'_, R> {
+    type Target = R::Item;
+
+    fn deref(&self) -> &R::Item {
+        unsafe { slot_at(&*self.consumer.bufr, self.pos).with(|slot| (*slot).assume_init_ref()) }
+    }
+}
+
+impl<R: Ring> DerefMut for ReadSlot<'_, R> {
+    fn deref_mut(&mut self) -> &mut R::Item {
+        unsafe {
+            slot_at(&*self.consumer.bufr, self.pos).with_mut(|slot| (*slot).assume_init_mut())
         }
     }
 }
 
-pub fn sink_value<T, const N: usize>(b: &SPSCEphemeral<T, N>, val: T) -> Result<(), T> {
-    let head = b.head.load(Ordering::Acquire);
-    let tail = b.tail.load(Ordering::Relaxed);
-    let next = (tail + 1) % N;
+#[deprecated(note = "use `SPSCEphemeral::push` instead")]
+pub fn sink_value<T, const N: usize>(b: &mut SPSCEphemeral<T, N>, val: T) -> Result<(), T> {
+    b.push(val)
+}
+
+#[deprecated(note = "use `SPSCEphemeral::pop` instead")]
+pub fn spit_value<T, const N: usize>(b: &mut SPSCEphemeral<T, N>) -> Option<T> {
+    b.pop()
+}
+
+pub(crate) fn push<R: Ring>(b: &R, val: R::Item) -> Result<(), R::Item> {
+    let state = b.state();
+    let head = state.head.load(order::ACQUIRE);
+    let tail = state.tail.load(order::RELAXED);
 
-    // guard: empty or full
-    if next == head {
+    // guard: full
+    if tail.wrapping_sub(head) == b.arena_size() as u64 {
+        #[cfg(feature = "stats")]
+        state.stats.full();
+        #[cfg(feature = "tracing")]
+        state.trace.full();
         return Err(val);
     }
 
-    unsafe { (*b.bufr.get())[tail].as_mut_ptr().write(val) };
-    b.tail.store(next, Ordering::Release);
+    unsafe { write_at(b, tail, val) };
+    #[cfg(feature = "stats")]
+    state
+        .stats
+        .pushed(1, tail.wrapping_add(1).wrapping_sub(head) as usize);
+    #[cfg(feature = "tracing")]
+    state
+        .trace
+        .pushed(1, tail.wrapping_add(1).wrapping_sub(head) as usize);
+    state.tail.store(tail.wrapping_add(1), order::RELEASE);
 
     Ok(())
 }
 
-pub fn spit_value<T, const N: usize>(b: &SPSCEphemeral<T, N>) -> Option<T> {
-    let head = b.head.load(Ordering::Acquire);
-    let tail = b.tail.load(Ordering::Relaxed);
-    let next = (head + 1) % N;
+pub(crate) fn pop<R: Ring>(b: &R) -> Option<R::Item> {
+    let state = b.state();
+    let head = state.head.load(order::RELAXED);
+    // pairs with the producer's release, the slot is written once seen
+    let tail = state.tail.load(order::ACQUIRE);
 
     // guard: empty
     if head == tail {
+        #[cfg(feature = "stats")]
+        state.stats.empty();
+        #[cfg(feature = "tracing")]
+        state.trace.empty();
         return None;
     }
 
-    let val = unsafe { (*b.bufr.get())[head].as_ptr().read() };
-    b.head.store(next, Ordering::Release);
+    let val = unsafe { read_at(b, head) };
+    #[cfg(feature = "stats")]
+    state.stats.popped(1);
+    #[cfg(feature = "tracing")]
+    state.trace.popped(1);
+    state.head.store(head.wrapping_add(1), order::RELEASE);
     Some(val)
 }
 
-unsafe impl<T> Sync for SPSCEphemeral<T> {}
+/// Pending items in pop order, `&mut` so neither side can move them
+#[cfg(feature = "serde")]
+pub(crate) fn pending<R: Ring>(b: &mut R) -> impl Iterator<Item = &R::Item> {
+    let b = &*b;
+    let head = b.state().head.load(order::RELAXED);
+    let tail = b.state().tail.load(order::RELAXED);
+    (0..tail.wrapping_sub(head)).map(move |i| unsafe {
+        slot_at(b, head.wrapping_add(i)).with(|slot| (*slot).assume_init_ref())
+    })
+}
+
+/// Drops whatever is left between head and tail,
+/// called from the rings' own `Drop` so no handle is alive
+pub(crate) fn drop_pending<R: Ring>(b: &R) {
+    let mut head = b.state().head.load(order::RELAXED);
+    let tail = b.state().tail.load(order::RELAXED);
+
+    while head != tail {
+        unsafe { drop_at(b, head) };
+        head = head.wrapping_add(1);
+    }
+}
+
+impl<T, const N: usize> Drop for SPSCEphemeral<T, N> {
+    fn drop(&mut self) {
+        drop_pending(self);
+    }
+}
+
+unsafe impl<T: Send, const N: usize> Sync for SPSCEphemeral<T, N> {}
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Arc;
     use std::thread;
 
+    struct DropCount(Arc<AtomicUsize>);
+
+    impl Drop for DropCount {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
     #[test]
     fn test_seq_spsc() {
-        let src = SPSCEphemeral::<i32>::new();
+        let mut src = SPSCEphemeral::<i32, 16>::new();
 
         for i in 0..10000 {
-            if sink_value(&src, i).is_ok() {
-                let tmp = spit_value(&src).expect("Failed to produce");
+            if src.push(i).is_ok() {
+                let tmp = src.pop().expect("Failed to produce");
                 assert_eq!(tmp, i);
                 continue;
             }
@@ -77,24 +1205,122 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_full_arena_spsc() {
+        let (mut producer, mut consumer) = SPSCEphemeral::<i32, 4>::new().split();
+
+        for lap in 0..100 {
+            for i in 0..4 {
+                assert!(producer.push(lap * 4 + i).is_ok());
+            }
+            assert_eq!(producer.push(-1), Err(-1));
+            assert_eq!(producer.try_push(-1), Err(PushError::Full(-1)));
+
+            for i in 0..4 {
+                assert_eq!(consumer.pop(), Ok(lap * 4 + i));
+            }
+            assert_eq!(consumer.pop(), Err(PopError::Empty));
+        }
+    }
+
+    #[test]
+    fn test_aligned_spsc() {
+        use crate::ephemeral::{dynamic::DynBuffer, padded::PaddedBuffer};
+
+        #[repr(align(32))]
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        struct Simd([f32; 8]);
+
+        // every slot of every layout keeps the payload's alignment
+        fn roundtrip<R: Ring<Item = Simd>>(ring: R) {
+            let (mut producer, mut consumer) = split(ring);
+            for lap in 0..3 {
+                for i in 0..8 {
+                    producer.push(Simd([(lap * 8 + i) as f32; 8])).unwrap();
+                    let val = consumer.peek().unwrap();
+                    assert_eq!(val as *const Simd as usize % 32, 0);
+                    assert_eq!(consumer.pop(), Ok(Simd([(lap * 8 + i) as f32; 8])));
+                }
+            }
+        }
+
+        roundtrip(SPSCEphemeral::<Simd, 8>::new());
+        roundtrip(DynBuffer::with_capacity(8));
+        roundtrip(PaddedBuffer::<Simd, 8>::new());
+    }
+
+    #[test]
+    fn test_errors_spsc() {
+        let (mut producer, consumer) = SPSCEphemeral::<i32, 2>::new().split();
+        let err: &dyn Error = &PopError::Empty;
+        assert_eq!(err.to_string(), "popping from an empty ring");
+
+        producer.push(1).unwrap();
+        producer.push(2).unwrap();
+        let full = producer.try_push(3).unwrap_err();
+        assert!(full.is_full());
+        assert_eq!(full.to_string(), "pushing onto a full ring");
+
+        drop(consumer);
+        assert!(producer.try_push(3).unwrap_err().is_disconnected());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_free_fn_spsc() {
+        let mut src = SPSCEphemeral::<i32, 4>::new();
+
+        assert!(sink_value(&mut src, 1).is_ok());
+        assert_eq!(src.try_pop(), Some(1));
+        assert!(src.try_push(2).is_ok());
+        assert_eq!(spit_value(&mut src), Some(2));
+        assert_eq!(spit_value(&mut src), None);
+    }
+
     #[test]
     fn test_threaded_spsc() {
-        let src = Arc::new(SPSCEphemeral::<i32>::new());
+        let (mut producer, mut consumer) = SPSCEphemeral::<i32, 16>::new().split();
+
+        let produce_t = thread::spawn(move || {
+            for i in 0..10000 {
+                while producer.push(i).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let consume_t = thread::spawn(move || {
+            for i in 0..10000 {
+                loop {
+                    if let Ok(result) = consumer.pop() {
+                        assert_eq!(result, i);
+                        break;
+                    }
+                    thread::yield_now();
+                }
+            }
+        });
+
+        produce_t.join().unwrap();
+        consume_t.join().unwrap();
+    }
+
+    #[test]
+    fn test_split_spsc() {
+        let (mut producer, mut consumer) = SPSCEphemeral::<i32, 16>::new().split();
 
-        let producer = src.clone();
         let produce_t = thread::spawn(move || {
             for i in 0..10000 {
-                while sink_value(&producer, i).is_err() {
+                while producer.push(i).is_err() {
                     thread::yield_now();
                 }
             }
         });
 
-        let consumer = src.clone();
         let consume_t = thread::spawn(move || {
             for i in 0..10000 {
                 loop {
-                    if let Some(result) = spit_value(&consumer) {
+                    if let Ok(result) = consumer.pop() {
                         assert_eq!(result, i);
                         break;
                     }
@@ -106,4 +1332,411 @@ mod test {
         produce_t.join().unwrap();
         consume_t.join().unwrap();
     }
+
+    #[test]
+    fn test_drop_spsc() {
+        let drops = Arc::new(AtomicUsize::new(0));
+
+        let mut src = SPSCEphemeral::<DropCount, 8>::new();
+        for _ in 0..6 {
+            assert!(src.push(DropCount(drops.clone())).is_ok());
+        }
+        drop(src.pop());
+        assert_eq!(drops.load(Ordering::Relaxed), 1);
+        drop(src);
+        assert_eq!(drops.load(Ordering::Relaxed), 6);
+
+        // wrapped around, pending items straddle the end of the arena
+        let (mut producer, mut consumer) = SPSCEphemeral::<DropCount, 4>::new().split();
+        for _ in 0..3 {
+            assert!(producer.push(DropCount(drops.clone())).is_ok());
+            drop(consumer.pop());
+        }
+        for _ in 0..3 {
+            assert!(producer.push(DropCount(drops.clone())).is_ok());
+        }
+        drop(producer);
+        assert_eq!(drops.load(Ordering::Relaxed), 9);
+        drop(consumer);
+        assert_eq!(drops.load(Ordering::Relaxed), 12);
+    }
+
+    #[cfg(feature = "std")]
+    fn blocking_roundtrip<W: WaitStrategy + Copy + Send + 'static>(mut wait: W, items: i32) {
+        let (mut producer, mut consumer) = SPSCEphemeral::<i32, 4>::new().split();
+
+        let produce_t = thread::spawn(move || {
+            for i in 0..items {
+                producer.push_blocking_with(i, &mut wait).unwrap();
+            }
+        });
+
+        let consume_t = thread::spawn(move || {
+            for i in 0..items {
+                assert_eq!(consumer.pop_blocking_with(&mut wait), Ok(i));
+            }
+        });
+
+        produce_t.join().unwrap();
+        consume_t.join().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_blocking_spsc() {
+        use crate::ephemeral::wait::{Spin, SpinPark, SpinYield};
+
+        // pure spinning only hands over on preemption when cores are scarce
+        blocking_roundtrip(Spin, 100);
+        blocking_roundtrip(SpinYield::default(), 10000);
+        blocking_roundtrip(SpinPark::default(), 10000);
+
+        let (mut producer, mut consumer) = SPSCEphemeral::<i32, 4>::new().split();
+        producer.push_blocking(7).unwrap();
+        assert_eq!(consumer.pop_blocking(), Ok(7));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_timeout_spsc() {
+        let (mut producer, mut consumer) = SPSCEphemeral::<i32, 2>::new().split();
+        let timeout = Duration::from_millis(10);
+
+        assert_eq!(consumer.pop_timeout(timeout), Err(PopError::Empty));
+        assert!(producer.push_timeout(1, timeout).is_ok());
+        assert!(producer.push_timeout(2, timeout).is_ok());
+        assert_eq!(producer.push_timeout(4, timeout), Err(Timeout(4)));
+
+        let produce_t = thread::spawn(move || {
+            thread::sleep(timeout);
+            producer.push_timeout(3, Duration::from_secs(5))
+        });
+        assert_eq!(consumer.pop_timeout(Duration::from_secs(5)), Ok(1));
+        assert_eq!(consumer.pop_timeout(Duration::from_secs(5)), Ok(2));
+        assert_eq!(consumer.pop_timeout(Duration::from_secs(5)), Ok(3));
+        assert!(produce_t.join().unwrap().is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_close_spsc() {
+        let (mut producer, mut consumer) = SPSCEphemeral::<i32, 4>::new().split();
+
+        assert!(producer.push(1).is_ok());
+        assert!(producer.push(2).is_ok());
+        producer.close();
+        assert!(producer.is_disconnected() && consumer.is_disconnected());
+        assert_eq!(producer.push(3), Err(3));
+
+        // queued items outlive the close
+        assert_eq!(consumer.pop(), Ok(1));
+        assert_eq!(consumer.pop_blocking(), Ok(2));
+        assert_eq!(consumer.pop(), Err(PopError::Disconnected));
+        assert_eq!(consumer.pop_blocking(), Err(Disconnected));
+
+        let (mut producer, consumer) = SPSCEphemeral::<i32, 4>::new().split();
+        drop(consumer);
+        assert_eq!(producer.push(1), Err(1));
+        assert_eq!(producer.try_push(1), Err(PushError::Disconnected(1)));
+        assert_eq!(producer.push_blocking(2), Err(2));
+        assert_eq!(
+            producer.push_timeout(3, Duration::from_secs(5)),
+            Err(Timeout(3))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_drop_disconnects_spsc() {
+        let (mut producer, mut consumer) = SPSCEphemeral::<i32, 2>::new().split();
+
+        let produce_t = thread::spawn(move || {
+            for i in 0..100 {
+                producer.push_blocking(i).unwrap();
+            }
+        });
+
+        let mut popped = Vec::new();
+        while let Ok(val) = consumer.pop_blocking() {
+            popped.push(val);
+        }
+        assert_eq!(popped, (0..100).collect::<Vec<_>>());
+        produce_t.join().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "notify")]
+    fn test_notify_spsc() {
+        const ITEMS: i32 = if cfg!(miri) { 100 } else { 10000 };
+        let (mut producer, mut consumer) = SPSCEphemeral::<i32, 2>::new().split();
+
+        // the consumer outwaits its spins and falls asleep on each side
+        let produce_t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            for i in 0..ITEMS {
+                producer.push_blocking(i).unwrap();
+            }
+            thread::sleep(Duration::from_millis(20));
+        });
+
+        for i in 0..ITEMS {
+            assert_eq!(consumer.pop_blocking(), Ok(i));
+        }
+        // woken by the drop
+        assert_eq!(consumer.pop_blocking(), Err(Disconnected));
+        produce_t.join().unwrap();
+    }
+
+    #[test]
+    fn test_batch_spsc() {
+        let (mut producer, mut consumer) = SPSCEphemeral::<i32, 8>::new().split();
+        let mut out = Vec::new();
+
+        assert_eq!(producer.push_slice(&[0, 1, 2, 3, 4]), 5);
+        assert_eq!(consumer.pop_batch(&mut out, 3), 3);
+        assert_eq!(out, [0, 1, 2]);
+
+        // wraps around the end of the arena, only 6 slots are free
+        assert_eq!(producer.push_slice(&[5, 6, 7, 8, 9, 10, 11]), 6);
+        assert_eq!(producer.push(-1), Err(-1));
+
+        out.clear();
+        assert_eq!(consumer.pop_batch(&mut out, usize::MAX), 8);
+        assert_eq!(out, [3, 4, 5, 6, 7, 8, 9, 10]);
+        assert_eq!(consumer.pop_batch(&mut out, 4), 0);
+
+        let mut rest = 20..30;
+        assert_eq!(producer.push_iter(rest.by_ref()), 8);
+        assert_eq!(rest.next(), Some(28));
+
+        out.clear();
+        assert_eq!(consumer.pop_batch(&mut out, 8), 8);
+        assert_eq!(out, (20..28).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_pop_all_spsc() {
+        let (mut producer, mut consumer) = SPSCEphemeral::<String, 4>::new().split();
+        let mut out = Vec::new();
+        assert_eq!(consumer.pop_all_into(&mut out), 0);
+
+        // bursts wrapping the arena, `out` reused across them
+        for burst in 0..5 {
+            let vals: Vec<_> = (0..3).map(|i| (burst * 3 + i).to_string()).collect();
+            assert_eq!(producer.push_iter(vals.iter().cloned()), 3);
+            out.clear();
+            assert_eq!(consumer.pop_all_into(&mut out), 3);
+            assert_eq!(out, vals);
+        }
+        assert!(out.capacity() >= 3);
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn test_slots_spsc() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let (mut producer, mut consumer) = SPSCEphemeral::<DropCount, 2>::new().split();
+
+        // cancelled: never visible, the written value is dropped
+        let mut slot = producer.reserve().unwrap();
+        slot.write(DropCount(drops.clone()));
+        drop(slot);
+        assert_eq!(drops.load(Ordering::Relaxed), 1);
+        assert!(consumer.peek_slot().is_none());
+
+        let mut slot = producer.reserve().unwrap();
+        unsafe {
+            slot.as_mut_ptr().write(DropCount(drops.clone()));
+            slot.assume_init();
+        }
+        slot.commit();
+        producer.reserve().unwrap().write(DropCount(drops.clone()));
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+
+        let mut slot = producer.reserve().unwrap();
+        slot.write(DropCount(drops.clone()));
+        slot.commit();
+        assert!(producer.reserve().is_none());
+
+        // peeking leaves the item queued
+        assert!(consumer.peek_slot().is_some());
+        consumer.peek_slot().unwrap().release();
+        assert_eq!(drops.load(Ordering::Relaxed), 3);
+        let taken = consumer.peek_slot().unwrap().take();
+        assert!(consumer.peek_slot().is_none());
+        drop(taken);
+        assert_eq!(drops.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn test_slots_in_place_spsc() {
+        let (mut producer, mut consumer) = SPSCEphemeral::<[u8; 64], 4>::new().split();
+
+        let mut slot = producer.reserve().unwrap();
+        slot.write([0; 64])[..3].copy_from_slice(b"abc");
+        slot.commit();
+
+        let mut slot = consumer.peek_slot().unwrap();
+        assert_eq!(&slot[..3], b"abc");
+        slot[0] = b'x';
+        assert_eq!(consumer.pop().unwrap()[..3], *b"xbc");
+    }
+
+    #[test]
+    fn test_peek_spsc() {
+        let (mut producer, mut consumer) = SPSCEphemeral::<String, 4>::new().split();
+
+        assert_eq!(consumer.peek(), None);
+        assert!(producer.push(String::from("low")).is_ok());
+        assert!(producer.push(String::from("high")).is_ok());
+
+        // peeking again sees the same item until it is popped
+        assert_eq!(consumer.peek().map(String::as_str), Some("low"));
+        assert_eq!(consumer.peek().map(String::as_str), Some("low"));
+        assert_eq!(consumer.pop().as_deref(), Ok("low"));
+        assert_eq!(consumer.peek().map(String::as_str), Some("high"));
+        assert_eq!(consumer.pop().as_deref(), Ok("high"));
+        assert_eq!(consumer.peek(), None);
+    }
+
+    #[test]
+    fn test_order_spsc() {
+        use Ordering::*;
+
+        let expected = if cfg!(feature = "strict-ordering") {
+            [SeqCst; 3]
+        } else {
+            [Acquire, Release, Relaxed]
+        };
+        assert_eq!([order::ACQUIRE, order::RELEASE, order::RELAXED], expected);
+    }
+
+    #[test]
+    fn test_len_spsc() {
+        let mut src = SPSCEphemeral::<i32, 4>::new();
+        assert!(src.is_empty());
+        assert_eq!((src.len(), src.capacity(), src.free_space()), (0, 4, 4));
+        src.push(0).unwrap();
+        assert_eq!((src.len(), src.free_space()), (1, 3));
+
+        let (mut producer, mut consumer) = src.split();
+        for i in 1..4 {
+            producer.push(i).unwrap();
+        }
+        assert!(producer.is_full() && consumer.is_full());
+        assert_eq!((consumer.len(), consumer.free_space()), (4, 0));
+
+        consumer.pop().unwrap();
+        assert_eq!((producer.len(), producer.free_space()), (3, 1));
+        while consumer.pop().is_ok() {}
+        assert!(producer.is_empty() && consumer.is_empty());
+    }
+
+    #[test]
+    fn test_drain_spsc() {
+        let (mut producer, mut consumer) = SPSCEphemeral::<i32, 8>::new().split();
+
+        producer.push_slice(&[0, 1, 2, 3, 4]);
+        assert_eq!(consumer.drain_up_to(2).collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(consumer.drain().collect::<Vec<_>>(), [2, 3, 4]);
+        assert_eq!(consumer.drain_up_to(3).next(), None);
+
+        // the consumer itself picks up again after running dry
+        producer.push_slice(&[5, 6]);
+        assert_eq!(consumer.by_ref().map(|i| i * 10).sum::<i32>(), 110);
+        producer.push(7).unwrap();
+        for i in &mut consumer {
+            assert_eq!(i, 7);
+        }
+    }
+
+    #[test]
+    fn test_clear_spsc() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let mut src = SPSCEphemeral::<DropCount, 4>::new();
+
+        for _ in 0..3 {
+            assert!(src.push(DropCount(drops.clone())).is_ok());
+        }
+        src.clear();
+        assert_eq!(drops.load(Ordering::Relaxed), 3);
+        assert!(src.is_empty());
+
+        // still usable afterwards
+        for _ in 0..4 {
+            assert!(src.push(DropCount(drops.clone())).is_ok());
+        }
+        assert_eq!(src.into_inner().len(), 4);
+        assert_eq!(drops.load(Ordering::Relaxed), 7);
+
+        let mut src = SPSCEphemeral::<i32, 4>::new();
+        for i in 0..4 {
+            src.push(i).unwrap();
+        }
+        src.pop();
+        src.push(4).unwrap();
+        assert_eq!(src.into_inner(), [1, 2, 3, 4]);
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_test {
+    use super::*;
+    use loom::thread;
+
+    const ITEMS: usize = 3;
+
+    #[test]
+    fn test_loom_split_spsc() {
+        loom::model(|| {
+            // smaller than ITEMS, so the producer wraps the arena
+            let (mut producer, mut consumer) = SPSCEphemeral::<usize, 2>::new().split();
+
+            let produce_t = thread::spawn(move || {
+                for i in 0..ITEMS {
+                    let mut val = i;
+                    while let Err(back) = producer.push(val) {
+                        val = back;
+                        thread::yield_now();
+                    }
+                }
+            });
+
+            for i in 0..ITEMS {
+                loop {
+                    match consumer.pop() {
+                        Ok(val) => break assert_eq!(val, i),
+                        Err(_) => thread::yield_now(),
+                    }
+                }
+            }
+            produce_t.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn test_loom_shared_spsc() {
+        loom::model(|| {
+            let src = Arc::new(SPSCEphemeral::<usize, 2>::new());
+
+            let producer = src.clone();
+            let produce_t = thread::spawn(move || {
+                for i in 0..ITEMS {
+                    while push(&*producer, i).is_err() {
+                        thread::yield_now();
+                    }
+                }
+            });
+
+            for i in 0..ITEMS {
+                loop {
+                    match pop(&*src) {
+                        Some(val) => break assert_eq!(val, i),
+                        None => thread::yield_now(),
+                    }
+                }
+            }
+            produce_t.join().unwrap();
+        });
+    }
 }
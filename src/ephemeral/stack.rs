@@ -0,0 +1,370 @@
+use core::{
+    cell::{Cell, UnsafeCell},
+    hint, iter,
+    mem::{ManuallyDrop, MaybeUninit},
+    ptr,
+    sync::atomic::{AtomicPtr, AtomicU8, Ordering},
+};
+
+use crate::util::CachePadded;
+
+use super::reclaim::{Epoch, Protect, Reclaim};
+
+/// exchange slots a contended push/pop pair can meet in
+const ELIMINATION: usize = 4;
+/// spins an offered value waits for a taker
+const OFFER_SPINS: u32 = 64;
+
+struct Node<T> {
+    val: ManuallyDrop<T>,
+    next: AtomicPtr<Node<T>>,
+}
+
+// exchange slot states, only the pusher that claimed a slot writes
+// `WRITING`/`OFFERED`/`EMPTY`, only the popper that won it `TAKEN`
+const EMPTY: u8 = 0;
+const WRITING: u8 = 1;
+const OFFERED: u8 = 2;
+const TAKING: u8 = 3;
+const TAKEN: u8 = 4;
+
+struct Exchange<T> {
+    state: AtomicU8,
+    val: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// Unbounded lock-free LIFO, a CAS on `head` pushes and pops.
+/// Popped nodes are freed through R, epochs unless picked otherwise,
+/// once no push or pop could still look at them, and pairs that
+/// keep losing the CAS try to hand values over directly
+pub struct EphemeralStack<T, R = Epoch> {
+    head: CachePadded<AtomicPtr<Node<T>>>,
+    reclaim: R, // frees popped nodes
+    exchange: [CachePadded<Exchange<T>>; ELIMINATION],
+}
+
+impl<T> EphemeralStack<T> {
+    pub const fn new() -> Self {
+        Self::with_reclaim(Epoch::new())
+    }
+}
+
+impl<T, R: Reclaim> EphemeralStack<T, R> {
+    /// Frees popped nodes through `reclaim`, e.g. `Hazards`
+    pub const fn with_reclaim(reclaim: R) -> Self {
+        Self {
+            head: CachePadded::new(AtomicPtr::new(ptr::null_mut())),
+            reclaim,
+            exchange: [const {
+                CachePadded::new(Exchange {
+                    state: AtomicU8::new(EMPTY),
+                    val: UnsafeCell::new(MaybeUninit::uninit()),
+                })
+            }; ELIMINATION],
+        }
+    }
+
+    pub fn push(&self, val: T) {
+        let node = Box::into_raw(Box::new(Node {
+            val: ManuallyDrop::new(val),
+            next: AtomicPtr::new(ptr::null_mut()),
+        }));
+
+        // protected as well, `head` may be popped before the CAS and
+        // must not be freed and its address reused meanwhile (ABA)
+        let guard = self.reclaim.pin();
+        loop {
+            let head = guard.protect(&self.head);
+            unsafe { (*node).next.store(head, Ordering::Relaxed) };
+
+            if self
+                .head
+                .compare_exchange_weak(head, node, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+            {
+                break;
+            }
+
+            // contended, a popper may take the value directly
+            if self.offer(unsafe { &mut (*node).val }) {
+                drop(unsafe { Box::from_raw(node) });
+                break;
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = self.reclaim.pin();
+
+        let node = loop {
+            let head = guard.protect(&self.head);
+            // guard: empty
+            if head.is_null() {
+                return None;
+            }
+
+            let next = unsafe { (*head).next.load(Ordering::Relaxed) };
+            if self
+                .head
+                .compare_exchange_weak(head, next, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+            {
+                break head;
+            }
+
+            // contended, a pusher may hand its value over directly
+            if let Some(val) = self.take() {
+                return Some(val);
+            }
+        };
+
+        // only the winning pop moves the value out, the node stays
+        // allocated for pops that loaded it before the CAS
+        let val = unsafe { ManuallyDrop::take(&mut (*node).val) };
+        unsafe { guard.retire(node) };
+        Some(val)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+
+    /// Drops every pending item
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Remaining items in pop order, newest first
+    pub fn into_inner(self) -> Vec<T> {
+        iter::from_fn(|| self.pop()).collect()
+    }
+
+    /// Parks `val` in an exchange slot for a while, true if a popper
+    /// took it, otherwise it is moved back into `val`
+    fn offer(&self, val: &mut ManuallyDrop<T>) -> bool {
+        let slot = &self.exchange[random_slot()];
+        if slot
+            .state
+            .compare_exchange(EMPTY, WRITING, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+
+        unsafe { (*slot.val.get()).write(ManuallyDrop::take(val)) };
+        slot.state.store(OFFERED, Ordering::Release);
+
+        for _ in 0..OFFER_SPINS {
+            hint::spin_loop();
+        }
+
+        // nobody came, take the value back
+        if slot
+            .state
+            .compare_exchange(OFFERED, WRITING, Ordering::Acquire, Ordering::Acquire)
+            .is_ok()
+        {
+            *val = ManuallyDrop::new(unsafe { (*slot.val.get()).assume_init_read() });
+            slot.state.store(EMPTY, Ordering::Release);
+            return false;
+        }
+
+        // a popper is reading it out
+        while slot.state.load(Ordering::Acquire) != TAKEN {
+            hint::spin_loop();
+        }
+        slot.state.store(EMPTY, Ordering::Release);
+        true
+    }
+
+    /// Takes a value a contended pusher offered, if any
+    fn take(&self) -> Option<T> {
+        let slot = &self.exchange[random_slot()];
+        slot.state
+            .compare_exchange(OFFERED, TAKING, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+
+        let val = unsafe { (*slot.val.get()).assume_init_read() };
+        slot.state.store(TAKEN, Ordering::Release);
+        Some(val)
+    }
+}
+
+/// Cheap per-thread xorshift, spreads contended threads over the slots
+fn random_slot() -> usize {
+    thread_local! {
+        static SEED: Cell<u32> = const { Cell::new(0) };
+    }
+
+    SEED.with(|seed| {
+        let mut x = seed.get();
+        if x == 0 {
+            // any nonzero start, differs per thread
+            x = (seed as *const Cell<u32> as usize as u32) | 1;
+        }
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        seed.set(x);
+        x as usize % ELIMINATION
+    })
+}
+
+impl<T, R: Reclaim> Default for EphemeralStack<T, R> {
+    fn default() -> Self {
+        Self::with_reclaim(R::default())
+    }
+}
+
+impl<T, R> Drop for EphemeralStack<T, R> {
+    fn drop(&mut self) {
+        let mut node = *self.head.get_mut();
+        while !node.is_null() {
+            let mut owned = unsafe { Box::from_raw(node) };
+            unsafe { ManuallyDrop::drop(&mut owned.val) };
+            node = *owned.next.get_mut();
+        }
+    }
+}
+
+unsafe impl<T: Send, R: Sync> Sync for EphemeralStack<T, R> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::{atomic::AtomicUsize, Arc, Barrier};
+    use std::thread;
+
+    const THREADS: usize = 8;
+    const ITEMS: usize = if cfg!(miri) { 100 } else { 2000 };
+
+    struct DropCount(Arc<AtomicUsize>, usize);
+
+    impl Drop for DropCount {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_seq_stack() {
+        let stack = EphemeralStack::new();
+
+        assert!(stack.is_empty());
+        for i in 0..10 {
+            stack.push(i);
+        }
+        for i in (0..10).rev() {
+            assert_eq!(stack.pop(), Some(i));
+        }
+        assert_eq!(stack.pop(), None);
+    }
+
+    #[test]
+    fn test_lifo_stack() {
+        let stack = EphemeralStack::new();
+        let barrier = Barrier::new(THREADS);
+
+        // every push lands before any pop, so each popper sees
+        // a given pusher's items newest first
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let (stack, barrier) = (&stack, &barrier);
+                s.spawn(move || {
+                    for i in 0..ITEMS {
+                        stack.push((t, i));
+                    }
+                    barrier.wait();
+
+                    let mut last = [usize::MAX; THREADS];
+                    while let Some((from, i)) = stack.pop() {
+                        assert!(i < last[from]);
+                        last[from] = i;
+                    }
+                });
+            }
+        });
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn test_stress_stack() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let stack = EphemeralStack::new();
+
+        let popped: usize = thread::scope(|s| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|t| {
+                    let (stack, drops) = (&stack, drops.clone());
+                    s.spawn(move || {
+                        let mut seen = Vec::new();
+                        for i in 0..ITEMS {
+                            stack.push(DropCount(drops.clone(), t * ITEMS + i));
+                            if i % 2 == 0 {
+                                seen.extend(stack.pop().map(|item| item.1));
+                            }
+                        }
+                        seen
+                    })
+                })
+                .collect();
+
+            let mut seen: Vec<_> = handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect();
+            // nothing popped twice
+            seen.sort_unstable();
+            seen.dedup();
+            seen.len()
+        });
+
+        assert_eq!(drops.load(Ordering::Relaxed), popped);
+        drop(stack);
+        assert_eq!(drops.load(Ordering::Relaxed), THREADS * ITEMS);
+    }
+
+    #[test]
+    fn test_hazard_stack() {
+        use crate::ephemeral::hazard::Hazards;
+
+        let stack = EphemeralStack::with_reclaim(Hazards::new());
+        let popped = AtomicUsize::new(0);
+
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let (stack, popped) = (&stack, &popped);
+                s.spawn(move || {
+                    for i in 0..ITEMS {
+                        stack.push(t * ITEMS + i);
+                        if stack.pop().is_some() {
+                            popped.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                });
+            }
+        });
+
+        let left = stack.into_inner().len();
+        assert_eq!(popped.into_inner() + left, THREADS * ITEMS);
+    }
+
+    #[test]
+    fn test_clear_stack() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let mut stack = EphemeralStack::new();
+
+        for i in 0..5 {
+            stack.push(DropCount(drops.clone(), i));
+        }
+        stack.clear();
+        assert_eq!(drops.load(Ordering::Relaxed), 5);
+        assert!(stack.is_empty());
+
+        let stack = EphemeralStack::new();
+        for i in 0..5 {
+            stack.push(i);
+        }
+        assert_eq!(stack.into_inner(), [4, 3, 2, 1, 0]);
+    }
+}
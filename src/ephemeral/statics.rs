@@ -0,0 +1,160 @@
+use core::{mem::MaybeUninit, sync::atomic::Ordering};
+
+use crate::sync::{AtomicBool, UnsafeCell};
+
+use super::spsc::{sealed, split, Consumer, Producer, Ring, RingState, SPSCEphemeral};
+
+/// Write half of a split `StaticRing`, `'static` like the ring
+pub type StaticProducer<T, const N: usize> = Producer<&'static StaticRing<T, N>>;
+/// Read half of a split `StaticRing`
+pub type StaticConsumer<T, const N: usize> = Consumer<&'static StaticRing<T, N>>;
+
+/// SPSC ring to declare as a `static`, for queues set up before
+/// `main` or fed from an interrupt handler, see `static_queue!`.
+/// `new` is const, and `split` hands out the usual handles once,
+/// borrowing the ring for good instead of moving it
+/// N:: arena size, a power of two
+pub struct StaticRing<T, const N: usize> {
+    ring: SPSCEphemeral<T, N>,
+    taken: AtomicBool, // the handles were handed out
+}
+
+impl<T, const N: usize> StaticRing<T, N> {
+    const_fn! {
+        pub fn new() -> Self {
+            Self {
+                ring: SPSCEphemeral::new(),
+                taken: AtomicBool::new(false),
+            }
+        }
+    }
+
+    /// The producer/consumer pair, only ever once per ring
+    ///
+    /// # Panics
+    /// On a second call, a second pair would be a second producer
+    pub fn split(&'static self) -> (StaticProducer<T, N>, StaticConsumer<T, N>) {
+        self.try_split()
+            .expect("split a static ring that was already split")
+    }
+
+    /// `split`, `None` once the handles were handed out
+    pub fn try_split(&'static self) -> Option<(StaticProducer<T, N>, StaticConsumer<T, N>)> {
+        // guard: handed out already
+        if self.taken.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        Some(split(self))
+    }
+
+    /// `split` was called, successfully or not
+    pub fn is_split(&self) -> bool {
+        self.taken.load(Ordering::Acquire)
+    }
+
+    /// Pending items, approximate while the handles are busy
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    occupancy!();
+}
+
+impl<T, const N: usize> Default for StaticRing<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> sealed::Sealed for &'static StaticRing<T, N> {}
+
+unsafe impl<T, const N: usize> Ring for &'static StaticRing<T, N> {
+    type Item = T;
+
+    fn arena_size(&self) -> usize {
+        N
+    }
+
+    fn state(&self) -> &RingState {
+        self.ring.state()
+    }
+
+    fn slot(&self, idx: usize) -> &UnsafeCell<MaybeUninit<T>> {
+        self.ring.slot(idx)
+    }
+}
+
+/// Declares a `static` `StaticRing`, `static_queue!(EVENTS: Event, 64)`
+/// for a ring of 64 `Event`s, visibility and attributes go in front
+#[macro_export]
+macro_rules! static_queue {
+    ($(#[$attr:meta])* $vis:vis $name:ident: $ty:ty, $cap:expr $(,)?) => {
+        $(#[$attr])*
+        $vis static $name: $crate::ephemeral::statics::StaticRing<$ty, { $cap }> =
+            $crate::ephemeral::statics::StaticRing::new();
+    };
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use std::thread;
+
+    #[test]
+    fn test_split_once_statics() {
+        static_queue!(QUEUE: u32, 4);
+
+        assert!(!QUEUE.is_split());
+        let (mut producer, mut consumer) = QUEUE.split();
+        assert!(QUEUE.is_split() && QUEUE.try_split().is_none());
+
+        producer.push_slice(&[1, 2, 3]);
+        assert_eq!(QUEUE.len(), 3);
+        assert_eq!(consumer.drain().collect::<Vec<_>>(), [1, 2, 3]);
+
+        drop(producer);
+        assert!(consumer.is_disconnected());
+    }
+
+    #[test]
+    #[should_panic(expected = "already split")]
+    fn test_split_twice_statics() {
+        static_queue!(QUEUE: u32, 4);
+
+        let _handles = QUEUE.split();
+        let _again = QUEUE.split();
+    }
+
+    #[test]
+    fn test_threaded_statics() {
+        const ITEMS: u32 = if cfg!(miri) { 200 } else { 10000 };
+        static_queue! {
+            /// stand-in for a queue an interrupt handler feeds
+            pub(crate) EVENTS: u32, 8
+        }
+
+        let (mut producer, mut consumer) = EVENTS.split();
+        let produce_t = thread::spawn(move || {
+            for i in 0..ITEMS {
+                while producer.push(i).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut next = 0;
+        while next < ITEMS {
+            match consumer.pop() {
+                Ok(val) => {
+                    assert_eq!(val, next);
+                    next += 1;
+                }
+                Err(_) => thread::yield_now(),
+            }
+        }
+        produce_t.join().unwrap();
+    }
+}
@@ -0,0 +1,195 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::util::CachePadded;
+
+/// Counters of a buffer as of the `stats()` call. Each counter is
+/// exact on its own, but they are read one by one while other
+/// handles keep going, so they needn't add up to `len` exactly
+///
+/// Every bounded buffer keeps them, and so does the work-stealing
+/// deque. `LinkedMPSC` and `SegQueue` don't, they count nothing
+/// pending to take a high-water mark of
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// items that made it in
+    pub pushes: usize,
+    /// items taken out
+    pub pops: usize,
+    /// push attempts turned away by a full buffer,
+    /// every failed retry of a blocking push counts
+    pub full: usize,
+    /// pop attempts that found nothing
+    pub empty: usize,
+    /// most items ever pending at once
+    pub high_water: usize,
+}
+
+/// Relaxed counters a buffer bumps as it goes, producer and
+/// consumer side sit on separate lines so they don't contend
+pub(crate) struct Counters {
+    producer: CachePadded<ProducerSide>,
+    consumer: CachePadded<ConsumerSide>,
+}
+
+struct ProducerSide {
+    pushes: AtomicUsize,
+    full: AtomicUsize,
+    high_water: AtomicUsize,
+}
+
+struct ConsumerSide {
+    pops: AtomicUsize,
+    empty: AtomicUsize,
+}
+
+impl Counters {
+    pub const fn new() -> Self {
+        Self {
+            producer: CachePadded::new(ProducerSide {
+                pushes: AtomicUsize::new(0),
+                full: AtomicUsize::new(0),
+                high_water: AtomicUsize::new(0),
+            }),
+            consumer: CachePadded::new(ConsumerSide {
+                pops: AtomicUsize::new(0),
+                empty: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// `count` items went in, leaving `pending` queued
+    pub fn pushed(&self, count: usize, pending: usize) {
+        self.producer.pushes.fetch_add(count, Ordering::Relaxed);
+        self.producer
+            .high_water
+            .fetch_max(pending, Ordering::Relaxed);
+    }
+
+    pub fn popped(&self, count: usize) {
+        self.consumer.pops.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn full(&self) {
+        self.producer.full.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn empty(&self) {
+        self.consumer.empty.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> Stats {
+        Stats {
+            pushes: self.producer.pushes.load(Ordering::Relaxed),
+            pops: self.consumer.pops.load(Ordering::Relaxed),
+            full: self.producer.full.load(Ordering::Relaxed),
+            empty: self.consumer.empty.load(Ordering::Relaxed),
+            high_water: self.producer.high_water.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use crate::ephemeral::{
+        mpmc::MPMCEphemeral, mpsc::MPSCEphemeral, overwrite::OverwriteBuffer, spmc::SPMCEphemeral,
+        spsc::SPSCEphemeral,
+    };
+
+    #[test]
+    fn test_ring_stats() {
+        let mut src = SPSCEphemeral::<i32, 4>::new();
+        assert_eq!(src.pop(), None);
+        src.push(1).unwrap();
+        src.push(2).unwrap();
+        assert_eq!(src.pop(), Some(1));
+
+        let (mut producer, mut consumer) = src.split();
+        assert_eq!(producer.push_slice(&[3, 4, 5, 6]), 3);
+        assert_eq!(producer.push(7), Err(7));
+        assert_eq!(consumer.drain().count(), 4);
+        assert!(consumer.pop().is_err());
+
+        let stats = consumer.stats();
+        assert_eq!(producer.stats(), stats);
+        assert_eq!(
+            stats,
+            Stats {
+                pushes: 5,
+                pops: 5,
+                full: 1,
+                empty: 3,
+                high_water: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn test_seq_stats() {
+        let src = MPMCEphemeral::<i32, 2>::new();
+        for i in 0..3 {
+            let _ = src.push(i);
+        }
+        while src.pop().is_some() {}
+        assert_eq!(
+            src.stats(),
+            Stats {
+                pushes: 2,
+                pops: 2,
+                full: 1,
+                empty: 1,
+                high_water: 2,
+            }
+        );
+
+        let (producer, mut consumer) = MPSCEphemeral::<i32, 4>::new().split();
+        producer.push(1).unwrap();
+        assert_eq!(consumer.pop(), Some(1));
+        producer.push(2).unwrap();
+        let stats = consumer.stats();
+        assert_eq!((stats.pushes, stats.pops, stats.high_water), (2, 1, 1));
+
+        let (mut producer, consumer) = SPMCEphemeral::<i32, 2>::new().split();
+        let _ = producer.push(1);
+        let _ = producer.push(2);
+        let _ = producer.push(3);
+        while consumer.clone().pop().is_some() {}
+        assert_eq!(
+            consumer.stats(),
+            Stats {
+                pushes: 2,
+                pops: 2,
+                full: 1,
+                empty: 1,
+                high_water: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_overwrite_stats() {
+        let (mut producer, mut consumer) = OverwriteBuffer::<i32, 2>::new().split();
+        for i in 0..4 {
+            producer.push_overwrite(i);
+        }
+        while consumer.pop().is_some() {}
+
+        // every push went through, the last two by evicting
+        assert_eq!(
+            producer.stats(),
+            Stats {
+                pushes: 4,
+                pops: 2,
+                full: 2,
+                empty: 1,
+                high_water: 2,
+            }
+        );
+    }
+}
@@ -0,0 +1,472 @@
+use core::{
+    cell::Cell,
+    fmt,
+    marker::PhantomData,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+use std::{sync::Arc, time::Duration};
+
+use crate::util::CachePadded;
+
+pub use super::channel::{RecvError, RecvTimeoutError, SendError, TryRecvError, TrySendError};
+use super::{
+    segment::SegQueue,
+    seq::{boxed_slots, drop_pending, pop_exclusive, push_shared, SeqSlot},
+    wait::{retry, retry_until, Backoff},
+};
+
+/// Nap of a blocked `send`/`recv` once spinning and yielding didn't help
+const PARK: Duration = Duration::from_micros(50);
+
+/// Heap MPSC ring behind `sync_channel`, the arena is rounded up
+/// but `queued` holds it to the exact bound
+struct Ring<T> {
+    bufr: Box<[SeqSlot<T>]>,
+    head: CachePadded<AtomicUsize>,   // read position
+    tail: CachePadded<AtomicUsize>,   // write position
+    queued: CachePadded<AtomicUsize>, // places taken, pushes in flight included
+    bound: usize,
+}
+
+impl<T> Ring<T> {
+    /// Takes one of the `bound` places, `false` when they're all taken
+    fn reserve(&self) -> bool {
+        let mut queued = self.queued.load(Ordering::Relaxed);
+        while queued < self.bound {
+            match self.queued.compare_exchange_weak(
+                queued,
+                queued + 1,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(current) => queued = current,
+            }
+        }
+        false
+    }
+
+    fn release(&self) {
+        self.queued.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl<T> Drop for Ring<T> {
+    fn drop(&mut self) {
+        let head = *self.head.get_mut();
+        let tail = *self.tail.get_mut();
+        drop_pending(&mut self.bufr, head, tail);
+    }
+}
+
+// one per channel, inside its `Arc`, boxing the bigger one buys nothing
+#[allow(clippy::large_enum_variant)]
+enum Queue<T> {
+    Unbounded(SegQueue<T>),
+    Bounded(Ring<T>),
+}
+
+/// Queue plus who is still around, so either side can report a disconnect
+struct Chan<T> {
+    queue: Queue<T>,
+    senders: AtomicUsize,
+    receiver: AtomicBool,
+}
+
+impl<T> Chan<T> {
+    fn new(queue: Queue<T>) -> Arc<Self> {
+        Arc::new(Self {
+            queue,
+            senders: AtomicUsize::new(1),
+            receiver: AtomicBool::new(true),
+        })
+    }
+
+    fn push(&self, val: T) -> Result<(), TrySendError<T>> {
+        if !self.receiver.load(Ordering::Acquire) {
+            return Err(TrySendError::Disconnected(val));
+        }
+
+        match &self.queue {
+            Queue::Unbounded(queue) => {
+                queue.push(val);
+                Ok(())
+            }
+            Queue::Bounded(ring) => {
+                if !ring.reserve() {
+                    return Err(TrySendError::Full(val));
+                }
+                push_shared(&ring.bufr, &ring.tail, val).map_err(|val| {
+                    ring.release();
+                    TrySendError::Full(val)
+                })
+            }
+        }
+    }
+
+    /// Caller must be the one receiver
+    fn pop(&self) -> Option<T> {
+        match &self.queue {
+            Queue::Unbounded(queue) => queue.pop(),
+            Queue::Bounded(ring) => {
+                let val = pop_exclusive(&ring.bufr, &ring.head)?;
+                // the slot is free again, its place can go
+                ring.release();
+                Some(val)
+            }
+        }
+    }
+
+    fn add_sender(self: &Arc<Self>) -> Arc<Self> {
+        self.senders.fetch_add(1, Ordering::Relaxed);
+        self.clone()
+    }
+
+    fn drop_sender(&self) {
+        // pairs with the receiver's acquire, every push so far is visible
+        self.senders.fetch_sub(1, Ordering::Release);
+    }
+}
+
+// the ring's slots are handed over through their stamps
+unsafe impl<T: Send> Sync for Chan<T> {}
+
+/// Unbounded channel over a `SegQueue`, a stand-in for
+/// `std::sync::mpsc::channel`
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let chan = Chan::new(Queue::Unbounded(SegQueue::new()));
+    (Sender { chan: chan.clone() }, Receiver::new(chan))
+}
+
+/// Bounded channel over a heap MPSC ring, a stand-in for
+/// `std::sync::mpsc::sync_channel` holding at most `bound` messages
+///
+/// Panics on a `bound` of 0, a ring has no rendezvous mode, see
+/// `rendezvous::channel` for that
+pub fn sync_channel<T>(bound: usize) -> (SyncSender<T>, Receiver<T>) {
+    assert!(bound > 0, "a sync channel needs room for one message");
+    let ring = Ring {
+        bufr: boxed_slots(bound),
+        head: CachePadded::new(AtomicUsize::new(0)),
+        tail: CachePadded::new(AtomicUsize::new(0)),
+        queued: CachePadded::new(AtomicUsize::new(0)),
+        bound,
+    };
+    let chan = Chan::new(Queue::Bounded(ring));
+    (SyncSender { chan: chan.clone() }, Receiver::new(chan))
+}
+
+/// Sending half of `channel`, mirrors `std::sync::mpsc::Sender`
+pub struct Sender<T> {
+    chan: Arc<Chan<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Never waits, fails only once the receiver is gone
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        self.chan.push(t).map_err(|err| SendError(err.into_inner()))
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            chan: self.chan.add_sender(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.chan.drop_sender();
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Sender { .. }")
+    }
+}
+
+/// Sending half of `sync_channel`, mirrors `std::sync::mpsc::SyncSender`
+pub struct SyncSender<T> {
+    chan: Arc<Chan<T>>,
+}
+
+impl<T> SyncSender<T> {
+    /// Waits for room, fails only once the receiver is gone
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        let mut pending = Some(t);
+        let mut wait = Backoff::with_park(PARK);
+        retry(&mut wait, || match self.try_send(pending.take()?) {
+            Ok(()) => Some(Ok(())),
+            Err(TrySendError::Disconnected(t)) => Some(Err(SendError(t))),
+            Err(TrySendError::Full(t)) => {
+                pending = Some(t);
+                None
+            }
+        })
+    }
+
+    pub fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        self.chan.push(t)
+    }
+}
+
+impl<T> Clone for SyncSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            chan: self.chan.add_sender(),
+        }
+    }
+}
+
+impl<T> Drop for SyncSender<T> {
+    fn drop(&mut self) {
+        self.chan.drop_sender();
+    }
+}
+
+impl<T> fmt::Debug for SyncSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("SyncSender { .. }")
+    }
+}
+
+/// Receiving half of either channel, mirrors `std::sync::mpsc::Receiver`.
+/// Like it the receiver is `Send` but not `Sync`, which keeps pops
+/// on the one thread holding it
+pub struct Receiver<T> {
+    chan: Arc<Chan<T>>,
+    _unsync: PhantomData<Cell<()>>,
+}
+
+impl<T> Receiver<T> {
+    fn new(chan: Arc<Chan<T>>) -> Self {
+        Self {
+            chan,
+            _unsync: PhantomData,
+        }
+    }
+
+    /// Waits for a message, fails only once every sender
+    /// is gone and nothing is left queued
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut wait = Backoff::with_park(PARK);
+        retry(&mut wait, || match self.try_recv() {
+            Ok(t) => Some(Ok(t)),
+            Err(TryRecvError::Disconnected) => Some(Err(RecvError)),
+            Err(TryRecvError::Empty) => None,
+        })
+    }
+
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        if let Some(t) = self.chan.pop() {
+            return Ok(t);
+        }
+
+        if self.chan.senders.load(Ordering::Acquire) != 0 {
+            return Err(TryRecvError::Empty);
+        }
+        // the last sender may have pushed right before leaving
+        self.chan.pop().ok_or(TryRecvError::Disconnected)
+    }
+
+    /// `recv` giving up once `timeout` passed
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        retry_until(timeout, || match self.try_recv() {
+            Ok(t) => Some(Ok(t)),
+            Err(TryRecvError::Disconnected) => Some(Err(RecvTimeoutError::Disconnected)),
+            Err(TryRecvError::Empty) => None,
+        })
+        .unwrap_or(Err(RecvTimeoutError::Timeout))
+    }
+
+    /// Blocks for every message until the senders are gone
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { rx: self }
+    }
+
+    /// Messages already queued, without waiting for more
+    pub fn try_iter(&self) -> TryIter<'_, T> {
+        TryIter { rx: self }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.chan.receiver.store(false, Ordering::Release);
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Receiver { .. }")
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Receiver<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for Receiver<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { rx: self }
+    }
+}
+
+/// Iterator returned by `Receiver::iter`
+pub struct Iter<'a, T> {
+    rx: &'a Receiver<T>,
+}
+
+impl<T> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+/// Iterator returned by `Receiver::try_iter`
+pub struct TryIter<'a, T> {
+    rx: &'a Receiver<T>,
+}
+
+impl<T> Iterator for TryIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.try_recv().ok()
+    }
+}
+
+/// Iterator returned by `Receiver::into_iter`
+pub struct IntoIter<T> {
+    rx: Receiver<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use std::thread;
+
+    const ITEMS: usize = if cfg!(miri) { 100 } else { 10000 };
+
+    #[test]
+    fn test_seq_std_mpsc() {
+        let (tx, rx) = channel();
+        // unbounded, far past a single segment
+        for i in 0..100 {
+            tx.send(i).unwrap();
+        }
+        assert_eq!(
+            rx.try_iter().collect::<Vec<_>>(),
+            (0..100).collect::<Vec<_>>()
+        );
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+
+        let (tx, rx) = sync_channel(2);
+        tx.send(1).unwrap();
+        tx.try_send(2).unwrap();
+        assert_eq!(tx.try_send(3), Err(TrySendError::Full(3)));
+        assert_eq!(rx.recv(), Ok(1));
+        assert_eq!(rx.recv_timeout(Duration::from_millis(1)), Ok(2));
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(1)),
+            Err(RecvTimeoutError::Timeout)
+        );
+    }
+
+    #[test]
+    fn test_bound_std_mpsc() {
+        // an arena of 4, but no more than 3 go in
+        let (tx, rx) = sync_channel(3);
+        for lap in 0..3 {
+            for i in 0..3 {
+                tx.try_send(lap * 3 + i).unwrap();
+            }
+            assert_eq!(tx.try_send(9), Err(TrySendError::Full(9)));
+            assert_eq!(rx.try_iter().count(), 3);
+        }
+
+        let (tx, rx) = sync_channel(1);
+        tx.send(1).unwrap();
+        assert_eq!(tx.try_send(2), Err(TrySendError::Full(2)));
+        assert_eq!(rx.recv(), Ok(1));
+        tx.try_send(2).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "room for one message")]
+    fn test_zero_bound_std_mpsc() {
+        let _ = sync_channel::<u32>(0);
+    }
+
+    #[test]
+    fn test_disconnect_std_mpsc() {
+        let (tx, rx) = sync_channel(4);
+        let other = tx.clone();
+        tx.send(1).unwrap();
+        drop(tx);
+        other.send(2).unwrap();
+        drop(other);
+
+        // whatever was queued still comes out first
+        assert_eq!(rx.iter().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(rx.recv(), Err(RecvError));
+
+        let (tx, rx) = channel();
+        drop(rx);
+        assert_eq!(tx.send(1), Err(SendError(1)));
+    }
+
+    #[test]
+    fn test_threaded_std_mpsc() {
+        const SENDERS: usize = 4;
+        let (tx, rx) = sync_channel(8);
+        let (unbounded_tx, unbounded_rx) = channel();
+
+        let senders: Vec<_> = (0..SENDERS)
+            .map(|s| {
+                let tx = tx.clone();
+                let unbounded_tx = unbounded_tx.clone();
+                thread::spawn(move || {
+                    for i in 0..ITEMS {
+                        tx.send(s * ITEMS + i).unwrap();
+                        unbounded_tx.send(s * ITEMS + i).unwrap();
+                    }
+                })
+            })
+            .collect();
+        drop((tx, unbounded_tx));
+
+        // ends once the last sender hung up
+        let mut seen: Vec<_> = rx.into_iter().collect();
+        for sender in senders {
+            sender.join().unwrap();
+        }
+        let mut unbounded_seen: Vec<_> = unbounded_rx.into_iter().collect();
+
+        seen.sort_unstable();
+        unbounded_seen.sort_unstable();
+        assert_eq!(seen, (0..SENDERS * ITEMS).collect::<Vec<_>>());
+        assert_eq!(seen, unbounded_seen);
+    }
+}
@@ -0,0 +1,393 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{
+    error::Error,
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Shared clock the histories of one run stamp their operations with,
+/// for checking a queue of your own, or a new wrapper around one of
+/// these, against what a FIFO queue may do
+///
+/// Each thread records into its own `History`, through whatever
+/// handle it has, and `check_fifo` goes over all of them once the
+/// threads are joined
+pub struct Recorder {
+    clock: AtomicUsize,
+}
+
+impl Recorder {
+    pub const fn new() -> Self {
+        Self {
+            clock: AtomicUsize::new(0),
+        }
+    }
+
+    /// An empty history for one thread of the run
+    pub fn history<T>(&self) -> History<'_, T> {
+        History {
+            recorder: self,
+            events: Vec::new(),
+        }
+    }
+
+    // SeqCst, so a tick taken after another op ended orders after it
+    fn tick(&self) -> usize {
+        self.clock.fetch_add(1, Ordering::SeqCst)
+    }
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder")
+            .field("clock", &self.clock.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+/// Operations one thread ran, in the order it ran them
+#[derive(Debug)]
+pub struct History<'a, T> {
+    recorder: &'a Recorder,
+    events: Vec<Event<T>>,
+}
+
+impl<T: Clone> History<'_, T> {
+    /// Runs `push` with `val`, recording it if it went in. Values
+    /// must be distinct across the whole run, see `check_fifo`
+    pub fn push<E>(&mut self, val: T, push: impl FnOnce(T) -> Result<(), E>) -> Result<(), E> {
+        let start = self.recorder.tick();
+        push(val.clone())?;
+        let end = self.recorder.tick();
+        self.events.push(Event {
+            op: Op::Push(val),
+            start,
+            end,
+        });
+        Ok(())
+    }
+
+    /// Runs `pop`, recording what it returned, nothing included
+    pub fn pop(&mut self, pop: impl FnOnce() -> Option<T>) -> Option<T> {
+        let start = self.recorder.tick();
+        let val = pop();
+        let end = self.recorder.tick();
+        self.events.push(Event {
+            op: Op::Pop(val.clone()),
+            start,
+            end,
+        });
+        val
+    }
+}
+
+impl<T> History<'_, T> {
+    pub fn events(&self) -> &[Event<T>] {
+        &self.events
+    }
+}
+
+/// One recorded operation. It took effect somewhere between the
+/// `start` and `end` ticks, two don't overlap if one's `end` comes
+/// before the other's `start`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event<T> {
+    pub op: Op<T>,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Op<T> {
+    Push(T),
+    Pop(Option<T>),
+}
+
+/// Why a run isn't one a FIFO queue could have produced, with the
+/// events that show it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation<T> {
+    /// popped a value nobody pushed
+    Unpushed { pop: Event<T> },
+    /// popped the same value twice
+    Twice { first: Event<T>, second: Event<T> },
+    /// the pop was over before the push began
+    BeforePush { push: Event<T>, pop: Event<T> },
+    /// `second` was pushed after `first` went in, but popped before
+    /// `first` was, `first.1` is `None` if it never was
+    Reordered {
+        first: (Event<T>, Option<Event<T>>),
+        second: (Event<T>, Event<T>),
+    },
+    /// came back empty while `pending` sat in the queue throughout
+    Empty { pop: Event<T>, pending: Event<T> },
+}
+
+impl<T: fmt::Debug> fmt::Display for Violation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unpushed { pop } => write!(f, "{pop:?} popped a value nobody pushed"),
+            Self::Twice { first, second } => {
+                write!(f, "{first:?} and {second:?} popped the same value")
+            }
+            Self::BeforePush { push, pop } => write!(f, "{pop:?} ended before {push:?} began"),
+            Self::Reordered { first, second } => {
+                write!(
+                    f,
+                    "{:?} was pushed after {:?} but popped first",
+                    second.0, first.0
+                )
+            }
+            Self::Empty { pop, pending } => {
+                write!(f, "{pop:?} found nothing while {pending:?} was queued")
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for Violation<T> {}
+
+/// Checks the recorded run is one some sequence of the operations,
+/// each taking effect between its ticks, explains on a FIFO queue
+///
+/// Needs every pushed value distinct, which makes the check exact
+/// and `O(n log n)`: a run is linearizable unless it hits one of the
+/// `Violation`s. Items left in the queue at the end are fine as long
+/// as nothing pushed after them got popped
+///
+/// Panics if a value was pushed twice
+pub fn check_fifo<'a, T: Ord + Clone + 'a>(
+    histories: impl IntoIterator<Item = History<'a, T>>,
+) -> Result<(), Violation<T>> {
+    let mut entries: Vec<Entry<T>> = Vec::new();
+    let mut pushed = BTreeMap::new();
+    let mut pops = Vec::new();
+    let mut empties = Vec::new();
+    for event in histories.into_iter().flat_map(|history| history.events) {
+        match &event.op {
+            Op::Push(val) => {
+                let prev = pushed.insert(val.clone(), entries.len());
+                assert!(
+                    prev.is_none(),
+                    "check_fifo needs distinct values, one was pushed twice"
+                );
+                entries.push(Entry {
+                    push: event,
+                    pop: None,
+                });
+            }
+            Op::Pop(Some(_)) => pops.push(event),
+            Op::Pop(None) => empties.push(event),
+        }
+    }
+
+    for pop in pops {
+        let Op::Pop(Some(val)) = &pop.op else {
+            unreachable!()
+        };
+        let Some(&idx) = pushed.get(val) else {
+            return Err(Violation::Unpushed { pop });
+        };
+        let entry = &mut entries[idx];
+        if let Some(first) = &entry.pop {
+            return Err(Violation::Twice {
+                first: first.clone(),
+                second: pop,
+            });
+        }
+        if pop.end < entry.push.start {
+            return Err(Violation::BeforePush {
+                push: entry.push.clone(),
+                pop,
+            });
+        }
+        entry.pop = Some(pop);
+    }
+
+    // a pushed value must leave before anything pushed after it
+    let mut pushes: Vec<usize> = (0..entries.len()).collect();
+    pushes.sort_by_key(|&idx| entries[idx].push.start);
+    let mut finished = Finished::new(&entries);
+    for idx in pushes {
+        let second = &entries[idx];
+        let Some(pop) = &second.pop else { continue };
+        let Some((left, first)) = finished.before(second.push.start) else {
+            continue;
+        };
+        if left > pop.end {
+            let first = &entries[first];
+            return Err(Violation::Reordered {
+                first: (first.push.clone(), first.pop.clone()),
+                second: (second.push.clone(), pop.clone()),
+            });
+        }
+    }
+
+    // nor can the queue look empty while a value sits in it
+    empties.sort_by_key(|pop| pop.start);
+    let mut finished = Finished::new(&entries);
+    for pop in empties {
+        let Some((left, pending)) = finished.before(pop.start) else {
+            continue;
+        };
+        if left > pop.end {
+            return Err(Violation::Empty {
+                pop,
+                pending: entries[pending].push.clone(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+struct Entry<T> {
+    push: Event<T>,
+    pop: Option<Event<T>>,
+}
+
+/// Walks the pushes in the order they ended, tracking the one that
+/// stayed queued the longest
+struct Finished<'a, T> {
+    entries: &'a [Entry<T>],
+    order: Vec<usize>,
+    next: usize,
+    latest: Option<(usize, usize)>, // (tick its pop began, entry)
+}
+
+impl<'a, T> Finished<'a, T> {
+    fn new(entries: &'a [Entry<T>]) -> Self {
+        let mut order: Vec<usize> = (0..entries.len()).collect();
+        order.sort_by_key(|&idx| entries[idx].push.end);
+        Self {
+            entries,
+            order,
+            next: 0,
+            latest: None,
+        }
+    }
+
+    /// Of the pushes that ended before `tick`, the latest start of
+    /// its pop and which push that was, never popped counting as
+    /// latest of all. `tick` must not decrease between calls
+    fn before(&mut self, tick: usize) -> Option<(usize, usize)> {
+        while let Some(&idx) = self.order.get(self.next) {
+            let entry = &self.entries[idx];
+            if entry.push.end >= tick {
+                break;
+            }
+            let left = entry.pop.as_ref().map_or(usize::MAX, |pop| pop.start);
+            if self.latest.is_none_or(|(latest, _)| left > latest) {
+                self.latest = Some((left, idx));
+            }
+            self.next += 1;
+        }
+        self.latest
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use alloc::collections::VecDeque;
+    use core::cell::RefCell;
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_mpmc_testing() {
+        use crate::ephemeral::mpmc::MPMCEphemeral;
+        use std::thread;
+
+        const ITEMS: u32 = if cfg!(miri) { 50 } else { 5000 };
+        let recorder = Recorder::new();
+        let queue = MPMCEphemeral::<u32, 8>::new();
+
+        // two of each side, full and empty queues included, each
+        // consumer taking half
+        let histories: Vec<_> = thread::scope(|s| {
+            let producers = (0..2).map(|p| {
+                let (recorder, queue) = (&recorder, &queue);
+                s.spawn(move || {
+                    let mut history = recorder.history();
+                    for val in (0..ITEMS).map(|i| p * ITEMS + i) {
+                        while history.push(val, |val| queue.push(val)).is_err() {
+                            thread::yield_now();
+                        }
+                    }
+                    history
+                })
+            });
+            let consumers = (0..2).map(|_| {
+                s.spawn(|| {
+                    let mut history = recorder.history();
+                    let mut popped = 0;
+                    while popped < ITEMS {
+                        match history.pop(|| queue.pop()) {
+                            Some(_) => popped += 1,
+                            None => thread::yield_now(),
+                        }
+                    }
+                    history
+                })
+            });
+            let handles: Vec<_> = producers.chain(consumers).collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(check_fifo(histories), Ok(()));
+    }
+
+    #[test]
+    fn test_reordered_testing() {
+        let recorder = Recorder::new();
+        let stack = RefCell::new(Vec::new());
+
+        // a stack passed off as a queue
+        let mut history = recorder.history();
+        for val in 1..=2 {
+            history
+                .push(val, |val| {
+                    stack.borrow_mut().push(val);
+                    Ok::<_, ()>(())
+                })
+                .unwrap();
+        }
+        assert_eq!(history.pop(|| stack.borrow_mut().pop()), Some(2));
+        match check_fifo([history]) {
+            Err(Violation::Reordered { first, second }) => {
+                assert_eq!((first.0.op, first.1), (Op::Push(1), None));
+                assert_eq!(second.0.op, Op::Push(2));
+            }
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_empty_testing() {
+        let recorder = Recorder::new();
+        let queue = RefCell::new(VecDeque::from([]));
+
+        // a pop that misses the one item in there
+        let mut history = recorder.history();
+        history
+            .push(1, |val| {
+                queue.borrow_mut().push_back(val);
+                Ok::<_, ()>(())
+            })
+            .unwrap();
+        history.pop(|| None);
+        history.pop(|| queue.borrow_mut().pop_front());
+        let err = check_fifo([history]).unwrap_err();
+        assert!(matches!(err, Violation::Empty { .. }));
+
+        let mut history = recorder.history();
+        history.pop(|| Some(7));
+        let err = check_fifo([history]).unwrap_err();
+        assert!(matches!(err, Violation::Unpushed { .. }));
+    }
+}
@@ -0,0 +1,190 @@
+use core::{error::Error, fmt, marker::PhantomData};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::handle::Produce;
+
+/// Why `Throttled::try_push` handed the value back
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThrottleError<T> {
+    /// the rate budget is used up for now
+    Limited(T),
+    /// the budget allowed it, the wrapped handle refused it
+    Rejected(T),
+}
+
+impl<T> ThrottleError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            Self::Limited(val) | Self::Rejected(val) => val,
+        }
+    }
+
+    pub fn is_limited(&self) -> bool {
+        matches!(self, Self::Limited(_))
+    }
+}
+
+impl<T> fmt::Display for ThrottleError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Limited(_) => f.pad("pushing past the rate limit"),
+            Self::Rejected(_) => f.pad("the throttled producer refused the push"),
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for ThrottleError<T> {}
+
+/// Token bucket in front of a producer handle, lets through `rate`
+/// items per second on average and at most `burst` back to back after
+/// a quiet spell, so upstream can't flood downstream consumers
+///
+/// Kept as the instant the bucket would be full again (GCRA), so
+/// refilling is one comparison and no timer runs in the background.
+/// Items the wrapped handle refuses don't use up the budget
+pub struct Throttled<T, P> {
+    inner: P,
+    interval: Duration,  // one token's worth of time
+    tolerance: Duration, // how far `tat` may run ahead of now
+    tat: Instant,        // theoretical arrival time of the next item
+    _item: PhantomData<fn(T)>,
+}
+
+impl<T, P: Produce<T>> Throttled<T, P> {
+    /// Wraps `inner`, starting with a full bucket
+    ///
+    /// Panics if `rate` or `burst` is 0
+    pub fn new(inner: P, rate: u32, burst: u32) -> Self {
+        assert!(rate > 0 && burst > 0, "rate and burst must be at least 1");
+        let interval = Duration::from_secs(1) / rate;
+        Self {
+            inner,
+            interval,
+            tolerance: interval * (burst - 1),
+            tat: Instant::now(),
+            _item: PhantomData,
+        }
+    }
+
+    /// Fails with `Limited` while the budget is used up,
+    /// `Rejected` if the wrapped handle refused the value
+    pub fn try_push(&mut self, val: T) -> Result<(), ThrottleError<T>> {
+        let now = Instant::now();
+        if !self.wait_time(now).is_zero() {
+            return Err(ThrottleError::Limited(val));
+        }
+        self.commit(now, val).map_err(ThrottleError::Rejected)
+    }
+
+    /// Sleeps until the budget allows one more item, then pushes it,
+    /// handing it back if the wrapped handle refused it
+    pub fn push(&mut self, val: T) -> Result<(), T> {
+        let mut now = Instant::now();
+        loop {
+            let wait = self.wait_time(now);
+            if wait.is_zero() {
+                return self.commit(now, val);
+            }
+            thread::sleep(wait);
+            now = Instant::now();
+        }
+    }
+
+    /// How long until the next item is let through, zero if right away
+    pub fn ready_in(&self) -> Duration {
+        self.wait_time(Instant::now())
+    }
+
+    pub fn get_ref(&self) -> &P {
+        &self.inner
+    }
+
+    /// Pushing through this skips the budget
+    pub fn get_mut(&mut self) -> &mut P {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+
+    fn wait_time(&self, now: Instant) -> Duration {
+        self.tat.saturating_duration_since(now + self.tolerance)
+    }
+
+    /// Pushes `val` and takes one token, only if it went through
+    fn commit(&mut self, now: Instant, val: T) -> Result<(), T> {
+        self.inner.try_push(val)?;
+        self.tat = self.tat.max(now) + self.interval;
+        Ok(())
+    }
+}
+
+impl<T, P: Produce<T>> Produce<T> for Throttled<T, P> {
+    fn try_push(&mut self, val: T) -> Result<(), T> {
+        Throttled::try_push(self, val).map_err(ThrottleError::into_inner)
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.inner.is_disconnected()
+    }
+}
+
+impl<T, P> fmt::Debug for Throttled<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Throttled")
+            .field("interval", &self.interval)
+            .field("tolerance", &self.tolerance)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use crate::ephemeral::spsc::SPSCEphemeral;
+
+    #[test]
+    fn test_burst_throttle() {
+        let (producer, mut consumer) = SPSCEphemeral::<u32, 8>::new().split();
+        // one a second, nothing refills while the test runs
+        let mut throttled = Throttled::new(producer, 1, 3);
+
+        for i in 0..3 {
+            throttled.try_push(i).unwrap();
+        }
+        assert_eq!(throttled.try_push(3), Err(ThrottleError::Limited(3)));
+        assert!(throttled.ready_in() > Duration::ZERO);
+        assert_eq!(consumer.pop(), Ok(0));
+    }
+
+    #[test]
+    fn test_rejected_throttle() {
+        let (producer, consumer) = SPSCEphemeral::<u32, 2>::new().split();
+        let mut throttled = Throttled::new(producer, 1, 2);
+        drop(consumer);
+
+        // refused pushes leave the budget alone
+        assert_eq!(throttled.try_push(1), Err(ThrottleError::Rejected(1)));
+        assert_eq!(throttled.push(2), Err(2));
+        assert_eq!(throttled.ready_in(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_rate_throttle() {
+        const RATE: u32 = 200;
+        let (producer, mut consumer) = SPSCEphemeral::<u32, 32>::new().split();
+        let mut throttled = Throttled::new(producer, RATE, 1);
+
+        // the first goes right away, every other waits one interval
+        let start = Instant::now();
+        for i in 0..10 {
+            throttled.push(i).unwrap();
+        }
+        assert!(start.elapsed() >= Duration::from_secs(1) / RATE * 9);
+        assert_eq!(consumer.pop(), Ok(0));
+    }
+}
@@ -0,0 +1,260 @@
+use core::{
+    cmp::Ordering as CmpOrdering,
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use std::{
+    collections::BinaryHeap,
+    sync::{Mutex, MutexGuard, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Longest nap of a waiting `pop_next`, bounds how late it notices
+/// an item pushed with an earlier deadline than the one it waits for
+const TICK: Duration = Duration::from_millis(1);
+/// `next` while nothing is queued
+const NONE: u64 = u64::MAX;
+
+/// Queued item, the heap pops the smallest deadline first and
+/// items sharing one in push order
+struct Entry<T> {
+    at: Instant,
+    seq: u64,
+    val: T,
+}
+
+impl<T> Entry<T> {
+    fn key(&self) -> (Instant, u64) {
+        (self.at, self.seq)
+    }
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        // `BinaryHeap` is a max-heap
+        other.key().cmp(&self.key())
+    }
+}
+
+struct Heap<T> {
+    entries: BinaryHeap<Entry<T>>,
+    seq: u64,
+}
+
+/// Delayed delivery, items only become poppable once their deadline
+/// passed and come out earliest deadline first, e.g. the retries or
+/// timeouts of a pipeline stage
+///
+/// A binary heap behind a lock plus the earliest deadline in an
+/// atomic, so polling with `pop_expired` while nothing is due never
+/// takes the lock. Share it behind an `Arc`, any thread may push or pop
+pub struct DelayQueue<T> {
+    heap: Mutex<Heap<T>>,
+    origin: Instant,
+    next: AtomicU64, // earliest deadline in nanos past `origin`, `NONE` if empty
+}
+
+impl<T> DelayQueue<T> {
+    pub fn new() -> Self {
+        Self {
+            heap: Mutex::new(Heap {
+                entries: BinaryHeap::new(),
+                seq: 0,
+            }),
+            origin: Instant::now(),
+            next: AtomicU64::new(NONE),
+        }
+    }
+
+    /// Queues `val` to become poppable once `delay` passed
+    pub fn push_after(&self, val: T, delay: Duration) {
+        self.push_at(val, Instant::now() + delay);
+    }
+
+    /// Queues `val` to become poppable at `deadline`,
+    /// right away if that passed already
+    pub fn push_at(&self, val: T, deadline: Instant) {
+        let mut heap = self.lock();
+        let seq = heap.seq;
+        heap.seq += 1;
+        heap.entries.push(Entry {
+            at: deadline,
+            seq,
+            val,
+        });
+        self.publish(&heap);
+    }
+
+    /// The earliest item whose deadline passed, `None` if nothing is due yet
+    pub fn pop_expired(&self) -> Option<T> {
+        // guard: nothing due, skip the lock
+        if self.nanos(Instant::now()) < self.next.load(Ordering::Acquire) {
+            return None;
+        }
+        self.pop_due(Instant::now()).ok()
+    }
+
+    /// Waits until the earliest item is due and pops it,
+    /// forever if nothing is ever pushed
+    pub fn pop_next(&self) -> T {
+        loop {
+            match self.pop_due(Instant::now()) {
+                Ok(val) => return val,
+                Err(wait) => thread::park_timeout(wait.min(TICK)),
+            }
+        }
+    }
+
+    /// `pop_next` giving up with `None` once `timeout` passed
+    pub fn pop_next_timeout(&self, timeout: Duration) -> Option<T> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let now = Instant::now();
+            match self.pop_due(now) {
+                Ok(val) => return Some(val),
+                Err(_) if now >= deadline => return None,
+                Err(wait) => thread::park_timeout(wait.min(TICK).min(deadline - now)),
+            }
+        }
+    }
+
+    /// When the earliest queued item becomes due
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.lock().entries.peek().map(|entry| entry.at)
+    }
+
+    /// Queued items, due or not
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Pops the earliest item if it's due by `now`,
+    /// otherwise how long until it is
+    fn pop_due(&self, now: Instant) -> Result<T, Duration> {
+        let mut heap = self.lock();
+        let wait = match heap.entries.peek() {
+            Some(entry) if entry.at <= now => None,
+            Some(entry) => Some(entry.at - now),
+            None => Some(TICK),
+        };
+        if let Some(wait) = wait {
+            return Err(wait);
+        }
+
+        let entry = heap.entries.pop().expect("peeked above");
+        self.publish(&heap);
+        Ok(entry.val)
+    }
+
+    /// Mirrors the heap's earliest deadline into `next`, under the lock
+    fn publish(&self, heap: &Heap<T>) {
+        let next = heap
+            .entries
+            .peek()
+            .map_or(NONE, |entry| self.nanos(entry.at));
+        self.next.store(next, Ordering::Release);
+    }
+
+    fn nanos(&self, at: Instant) -> u64 {
+        // deadlines before `origin` saturate to 0, due right away
+        let nanos = at.saturating_duration_since(self.origin).as_nanos();
+        nanos.min(u128::from(NONE - 1)) as u64
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Heap<T>> {
+        self.heap.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T> Default for DelayQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for DelayQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DelayQueue")
+            .field("len", &self.len())
+            .field("next_deadline", &self.next_deadline())
+            .finish()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_deadline_timed() {
+        let queue = DelayQueue::new();
+        let now = Instant::now();
+
+        queue.push_at("late", now + Duration::from_secs(60));
+        queue.push_at("second", now - Duration::from_millis(1));
+        queue.push_at("first", now - Duration::from_millis(2));
+        queue.push_at("third", now - Duration::from_millis(1));
+
+        // earliest deadline first, ties in push order
+        assert_eq!(queue.pop_expired(), Some("first"));
+        assert_eq!(queue.pop_expired(), Some("second"));
+        assert_eq!(queue.pop_expired(), Some("third"));
+        assert_eq!(queue.pop_expired(), None);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.next_deadline(), Some(now + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_wait_timed() {
+        let queue = DelayQueue::new();
+        let delay = Duration::from_millis(20);
+
+        let start = Instant::now();
+        queue.push_after(1, delay);
+        assert_eq!(queue.pop_next_timeout(Duration::from_millis(1)), None);
+        assert_eq!(queue.pop_next(), 1);
+        assert!(start.elapsed() >= delay);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_threaded_timed() {
+        const ITEMS: u32 = if cfg!(miri) { 20 } else { 500 };
+        let queue = Arc::new(DelayQueue::new());
+
+        // pushes land while the consumer waits, latest deadline first
+        let push_t = {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                for i in 0..ITEMS {
+                    queue.push_after(i, Duration::from_micros(u64::from(ITEMS - i)));
+                }
+            })
+        };
+
+        let mut seen: Vec<_> = (0..ITEMS).map(|_| queue.pop_next()).collect();
+        push_t.join().unwrap();
+        seen.sort_unstable();
+        assert_eq!(seen, (0..ITEMS).collect::<Vec<_>>());
+    }
+}
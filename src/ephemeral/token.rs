@@ -0,0 +1,205 @@
+use core::{
+    error::Error,
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use std::cell::Cell;
+
+use super::spsc::{pop, push, Ring};
+
+/// Thread key of a side nobody is bound to
+const UNBOUND: usize = 0;
+
+/// Threads each side of a ring got bound to through a token, kept
+/// in the ring's state so tokens made apart still see each other
+pub(crate) struct Owners {
+    producer: AtomicUsize,
+    consumer: AtomicUsize,
+}
+
+impl Owners {
+    pub(crate) const fn new() -> Self {
+        Self {
+            producer: AtomicUsize::new(UNBOUND),
+            consumer: AtomicUsize::new(UNBOUND),
+        }
+    }
+}
+
+/// Binds `owner` to the calling thread if nobody has it yet
+fn bind(owner: &AtomicUsize) -> Result<(), WrongThread> {
+    let me = thread_key();
+    match owner.compare_exchange(UNBOUND, me, Ordering::AcqRel, Ordering::Acquire) {
+        Ok(_) => Ok(()),
+        Err(bound) if bound == me => Ok(()),
+        Err(_) => Err(WrongThread),
+    }
+}
+
+/// Hands `owner` back, only from the thread holding it
+fn unbind(owner: &AtomicUsize) -> Result<(), WrongThread> {
+    let me = thread_key();
+    match owner.compare_exchange(me, UNBOUND, Ordering::AcqRel, Ordering::Acquire) {
+        Ok(_) | Err(UNBOUND) => Ok(()),
+        Err(_) => Err(WrongThread),
+    }
+}
+
+/// Tells live threads apart by the address of a thread local. An
+/// exited thread's key may come back for a new one, fine for
+/// catching two threads at it at once
+fn thread_key() -> usize {
+    thread_local! {
+        static KEY: Cell<u8> = const { Cell::new(0) };
+    }
+
+    KEY.with(|key| key as *const Cell<u8> as usize)
+}
+
+/// The side of the ring is bound to another thread
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WrongThread;
+
+impl fmt::Display for WrongThread {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("ring side bound to another thread")
+    }
+}
+
+impl Error for WrongThread {}
+
+/// Pushing side of a shared ring, for pushing through `&ring` where
+/// `split` doesn't fit, e.g. a `static` ring
+///
+/// The first push binds the side to the calling thread, pushing from
+/// any other thread after that, through this token or another, is
+/// the two-producer race the ring can't take and panics in debug
+/// builds. Release builds skip the check, `bind` does it either way
+pub struct ProducerToken<'a, R: Ring> {
+    ring: &'a R,
+}
+
+impl<'a, R: Ring> ProducerToken<'a, R> {
+    pub fn new(ring: &'a R) -> Self {
+        Self { ring }
+    }
+
+    /// Hands `val` back when full
+    pub fn push(&self, val: R::Item) -> Result<(), R::Item> {
+        #[cfg(debug_assertions)]
+        if self.bind().is_err() {
+            panic!("pushing from a second thread onto a single-producer ring");
+        }
+        push(self.ring, val)
+    }
+
+    /// Binds the side to the calling thread, `Err` when another has it
+    pub fn bind(&self) -> Result<(), WrongThread> {
+        bind(&self.ring.state().owners.producer)
+    }
+
+    /// Frees the side for another thread, once this one is done
+    /// pushing. `Err` when another thread has it
+    pub fn unbind(&self) -> Result<(), WrongThread> {
+        unbind(&self.ring.state().owners.producer)
+    }
+}
+
+/// Popping side of a shared ring, see `ProducerToken`
+pub struct ConsumerToken<'a, R: Ring> {
+    ring: &'a R,
+}
+
+impl<'a, R: Ring> ConsumerToken<'a, R> {
+    pub fn new(ring: &'a R) -> Self {
+        Self { ring }
+    }
+
+    /// `None` when empty
+    pub fn pop(&self) -> Option<R::Item> {
+        #[cfg(debug_assertions)]
+        if self.bind().is_err() {
+            panic!("popping from a second thread off a single-consumer ring");
+        }
+        pop(self.ring)
+    }
+
+    /// Binds the side to the calling thread, `Err` when another has it
+    pub fn bind(&self) -> Result<(), WrongThread> {
+        bind(&self.ring.state().owners.consumer)
+    }
+
+    /// Frees the side for another thread, see `ProducerToken::unbind`
+    pub fn unbind(&self) -> Result<(), WrongThread> {
+        unbind(&self.ring.state().owners.consumer)
+    }
+}
+
+impl<R: Ring> fmt::Debug for ProducerToken<'_, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProducerToken").finish_non_exhaustive()
+    }
+}
+
+impl<R: Ring> fmt::Debug for ConsumerToken<'_, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConsumerToken").finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use crate::ephemeral::spsc::SPSCEphemeral;
+    use std::thread;
+
+    #[test]
+    fn test_bound_on_first_use() {
+        let ring = SPSCEphemeral::<u32, 4>::new();
+        let (tx, rx) = (ring.producer_token(), ring.consumer_token());
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                tx.push(1).unwrap();
+                // a second token on the same thread is the same producer
+                ring.producer_token().push(2).unwrap();
+            })
+            .join()
+            .unwrap();
+            assert_eq!(tx.bind(), Err(WrongThread));
+
+            assert_eq!(rx.pop(), Some(1));
+            s.spawn(|| assert_eq!(rx.bind(), Err(WrongThread)));
+        });
+    }
+
+    #[test]
+    fn test_unbind_token() {
+        let ring = SPSCEphemeral::<u32, 4>::new();
+        let tx = ring.producer_token();
+
+        tx.push(1).unwrap();
+        thread::scope(|s| {
+            s.spawn(|| assert_eq!(tx.unbind(), Err(WrongThread)));
+        });
+        tx.unbind().unwrap();
+        thread::scope(|s| {
+            s.spawn(|| tx.push(2).unwrap());
+        });
+        assert_eq!(ring.consumer_token().pop(), Some(1));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_second_producer_panics() {
+        let ring = SPSCEphemeral::<u32, 4>::new();
+        let tx = ring.producer_token();
+
+        tx.push(1).unwrap();
+        thread::scope(|s| {
+            let racing = s.spawn(|| tx.push(2));
+            assert!(racing.join().is_err());
+        });
+        assert_eq!(ring.len(), 1);
+    }
+}
@@ -0,0 +1,134 @@
+// `AsyncConsumer` already is a `tokio_stream::Stream`, tokio-stream
+// re-exports the `futures_core` trait it implements, so only the
+// pumps to and from `tokio::sync::mpsc` live here
+
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use super::{
+    asynchronous::{AsyncConsumer, AsyncProducer},
+    spsc::{Consumer, Producer, Ring},
+};
+
+/// Forwards every item of `consumer` into `tx` on a tokio task, so
+/// sync producer threads can feed async code. Ends once the ring's
+/// producer is gone and the ring drained, or once `tx` is closed,
+/// which drops the consumer and disconnects the producer in turn
+///
+/// Must be called from within a tokio runtime
+pub fn spawn_pump_to<R>(consumer: Consumer<R>, tx: mpsc::Sender<R::Item>) -> JoinHandle<()>
+where
+    R: Ring + Send + Sync + 'static,
+    R::Item: Send + 'static,
+{
+    let mut consumer = AsyncConsumer::new(consumer);
+    tokio::spawn(async move {
+        while let Some(val) = consumer.pop().await {
+            if tx.send(val).await.is_err() {
+                return;
+            }
+        }
+    })
+}
+
+/// Forwards everything `rx` receives into `producer` on a tokio task,
+/// so async code can feed sync consumer threads. Ends once every
+/// sender of `rx` is gone, or once the ring's consumer is, which
+/// closes `rx` in turn
+///
+/// Must be called from within a tokio runtime
+pub fn spawn_pump_from<R>(mut rx: mpsc::Receiver<R::Item>, producer: Producer<R>) -> JoinHandle<()>
+where
+    R: Ring + Send + Sync + 'static,
+    R::Item: Send + 'static,
+{
+    let mut producer = AsyncProducer::new(producer);
+    tokio::spawn(async move {
+        while let Some(val) = rx.recv().await {
+            if producer.push(val).await.is_err() {
+                return;
+            }
+        }
+    })
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use crate::ephemeral::{dynamic::DynBuffer, spsc::SPSCEphemeral};
+    use std::thread;
+    use tokio::runtime::{Builder, Runtime};
+    use tokio_stream::StreamExt;
+
+    const ITEMS: usize = if cfg!(miri) { 100 } else { 10000 };
+
+    fn runtime() -> Runtime {
+        Builder::new_current_thread().build().unwrap()
+    }
+
+    #[test]
+    fn test_stream_tokio() {
+        let (mut producer, consumer) = SPSCEphemeral::<usize, 8>::new().split();
+
+        let produce_t = thread::spawn(move || {
+            for i in 0..ITEMS {
+                producer.push_blocking(i).unwrap();
+            }
+        });
+
+        let stream = AsyncConsumer::new(consumer);
+        let sum = runtime().block_on(stream.fold(0, |acc, i| acc + i));
+        produce_t.join().unwrap();
+        assert_eq!(sum, (0..ITEMS).sum());
+    }
+
+    #[test]
+    fn test_pump_tokio() {
+        let rt = runtime();
+        let (mut producer, consumer) = SPSCEphemeral::<usize, 8>::new().split();
+        let (back_producer, mut back_consumer) = DynBuffer::with_capacity(8).split();
+
+        // sync thread -> ring -> tokio channel -> ring -> sync thread
+        let (tx, rx) = mpsc::channel(4);
+        let (to, from) = rt.block_on(async {
+            (
+                spawn_pump_to(consumer, tx),
+                spawn_pump_from(rx, back_producer),
+            )
+        });
+
+        let pumps_t = thread::spawn(move || {
+            rt.block_on(async {
+                to.await.unwrap();
+                from.await.unwrap();
+            })
+        });
+        let produce_t = thread::spawn(move || {
+            for i in 0..ITEMS {
+                producer.push_blocking(i).unwrap();
+            }
+        });
+
+        // the ring closes once the producer is gone and everything was pumped
+        let mut seen = Vec::new();
+        while let Ok(val) = back_consumer.pop_blocking() {
+            seen.push(val);
+        }
+        produce_t.join().unwrap();
+        pumps_t.join().unwrap();
+        assert_eq!(seen, (0..ITEMS).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_closed_pump_tokio() {
+        let rt = runtime();
+        let (mut producer, consumer) = SPSCEphemeral::<usize, 4>::new().split();
+
+        let (tx, rx) = mpsc::channel(1);
+        drop(rx);
+        producer.push(1).unwrap();
+        rt.block_on(async { spawn_pump_to(consumer, tx).await.unwrap() });
+
+        // the pump gave up and took the consumer with it
+        assert!(producer.is_disconnected());
+    }
+}
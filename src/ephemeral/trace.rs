@@ -0,0 +1,245 @@
+use tracing::{span::EnteredSpan, trace, trace_span};
+
+/// Name a queue's `tracing` events carry as their `queue` field, set
+/// with the queue's `with_name`. Events are all at trace level under
+/// this module's target, a `brainstorm=trace` filter picks them up
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Label {
+    name: &'static str,
+}
+
+impl Label {
+    pub const fn new(name: &'static str) -> Self {
+        Self { name }
+    }
+
+    /// `count` items went in, leaving `pending` queued
+    pub fn pushed(&self, count: usize, pending: usize) {
+        // a batch push may have found no room at all
+        if count > 0 {
+            trace!(queue = self.name, count, pending, "push");
+        }
+    }
+
+    /// `pushed` for the unbounded queues, which keep no count of
+    /// what's pending
+    pub fn pushed_unbounded(&self, count: usize) {
+        if count > 0 {
+            trace!(queue = self.name, count, "push");
+        }
+    }
+
+    pub fn popped(&self, count: usize) {
+        if count > 0 {
+            trace!(queue = self.name, count, "pop");
+        }
+    }
+
+    pub fn full(&self) {
+        trace!(queue = self.name, "full");
+    }
+
+    pub fn empty(&self) {
+        trace!(queue = self.name, "empty");
+    }
+
+    /// A thread asleep on `side` of the queue was woken
+    #[cfg(feature = "notify")]
+    pub fn woke(&self, side: &'static str) {
+        trace!(queue = self.name, side, "wake");
+    }
+
+    /// Span around a blocking call, the `park` events of the waits
+    /// inside it land in there, and with it the queue's name
+    pub fn blocking(&self, call: &'static str) -> EnteredSpan {
+        trace_span!("blocking", queue = self.name, call).entered()
+    }
+}
+
+/// The calling thread is about to sleep until woken or a nap is over
+#[cfg(feature = "std")]
+pub(crate) fn park() {
+    trace!("park");
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    #[cfg(feature = "std")]
+    mod record {
+        use std::{
+            fmt::{self, Write},
+            string::String,
+            sync::{
+                atomic::{AtomicU64, Ordering},
+                Mutex,
+            },
+            vec::Vec,
+        };
+        use tracing::{
+            field::{Field, Visit},
+            span, Event, Metadata, Subscriber,
+        };
+
+        /// Every span, enter, exit and event as a line of text
+        #[derive(Default)]
+        pub struct Lines {
+            pub lines: Mutex<Vec<String>>,
+            ids: AtomicU64,
+        }
+
+        struct Fields<'a>(&'a mut String);
+
+        impl Visit for Fields<'_> {
+            fn record_debug(&mut self, field: &Field, val: &dyn fmt::Debug) {
+                match field.name() {
+                    "message" => write!(self.0, "{val:?}"),
+                    name => write!(self.0, " {name}={val:?}"),
+                }
+                .unwrap();
+            }
+
+            fn record_str(&mut self, field: &Field, val: &str) {
+                self.record_debug(field, &format_args!("{val}"));
+            }
+        }
+
+        impl Subscriber for Lines {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+                let mut line = String::from(span.metadata().name());
+                span.record(&mut Fields(&mut line));
+                self.lines.lock().unwrap().push(line);
+                span::Id::from_u64(self.ids.fetch_add(1, Ordering::Relaxed) + 1)
+            }
+
+            fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                let mut line = String::new();
+                event.record(&mut Fields(&mut line));
+                self.lines.lock().unwrap().push(line);
+            }
+
+            fn enter(&self, _: &span::Id) {
+                self.lines.lock().unwrap().push("enter".into());
+            }
+
+            fn exit(&self, _: &span::Id) {
+                self.lines.lock().unwrap().push("exit".into());
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_spsc_trace() {
+        use crate::ephemeral::spsc::SPSCEphemeral;
+        use record::Lines;
+        use std::sync::Arc;
+
+        let lines = Arc::new(Lines::default());
+        tracing::subscriber::with_default(lines.clone(), || {
+            let src = SPSCEphemeral::<u32, 2>::new().with_name("jobs");
+            let (mut producer, mut consumer) = src.split();
+            assert_eq!(producer.push_slice(&[1, 2, 3]), 2);
+            assert_eq!(producer.push(3), Err(3));
+            assert_eq!(consumer.drain().count(), 2);
+            assert!(consumer.pop().is_err());
+        });
+        assert_eq!(
+            *lines.lines.lock().unwrap(),
+            [
+                "push queue=jobs count=2 pending=2",
+                "full queue=jobs",
+                "pop queue=jobs count=1",
+                "pop queue=jobs count=1",
+                "empty queue=jobs",
+                "empty queue=jobs",
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_overwrite_trace() {
+        use crate::ephemeral::overwrite::OverwriteBuffer;
+        use record::Lines;
+        use std::sync::Arc;
+
+        let lines = Arc::new(Lines::default());
+        tracing::subscriber::with_default(lines.clone(), || {
+            let src = OverwriteBuffer::<u32, 2>::new().with_name("latest");
+            let (mut producer, mut consumer) = src.split();
+            producer.push_overwrite(1);
+            producer.push_overwrite(2);
+            assert_eq!(producer.push_overwrite(3), Some(1));
+            assert_eq!(consumer.pop(), Some(2));
+        });
+        assert_eq!(
+            *lines.lines.lock().unwrap(),
+            [
+                "push queue=latest count=1 pending=1",
+                "push queue=latest count=1 pending=2",
+                // evicting makes room, but only because it was full
+                "full queue=latest",
+                "push queue=latest count=1 pending=2",
+                "pop queue=latest count=1",
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_unbounded_trace() {
+        use crate::ephemeral::linked::LinkedMPSC;
+        use record::Lines;
+        use std::sync::Arc;
+
+        let lines = Arc::new(Lines::default());
+        tracing::subscriber::with_default(lines.clone(), || {
+            let (mut producer, mut consumer) = LinkedMPSC::new().with_name("log").split();
+            producer.push(1);
+            assert_eq!(consumer.pop(), Some(1));
+            assert_eq!(consumer.pop(), None);
+        });
+        // nothing counts what's pending, so the push goes without
+        assert_eq!(
+            *lines.lines.lock().unwrap(),
+            [
+                "push queue=log count=1",
+                "pop queue=log count=1",
+                "empty queue=log",
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_blocking_trace() {
+        use crate::ephemeral::mpmc::MPMCEphemeral;
+        use record::Lines;
+        use std::{sync::Arc, time::Duration};
+
+        let lines = Arc::new(Lines::default());
+        tracing::subscriber::with_default(lines.clone(), || {
+            let src = MPMCEphemeral::<u32, 2>::new().with_name("idle");
+            assert_eq!(src.pop_timeout(Duration::from_millis(5)), None);
+        });
+        let lines = lines.lines.lock().unwrap();
+        assert_eq!(
+            lines[..2],
+            ["blocking queue=idle call=pop_timeout", "enter"]
+        );
+        assert_eq!(lines.last().unwrap(), "exit");
+        // the pop parked at least once, inside the span
+        assert!(lines.contains(&"park".into()));
+        assert!(lines[2..lines.len() - 1]
+            .iter()
+            .all(|line| line == "park" || line == "empty queue=idle"));
+    }
+}
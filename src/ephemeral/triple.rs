@@ -0,0 +1,204 @@
+use core::sync::atomic::Ordering;
+
+use crate::sync::{Arc, AtomicU8, UnsafeCell};
+
+/// index of the buffer parked in the middle
+const INDEX: u8 = 0b011;
+/// set by the writer on publish, cleared once the reader swapped it out
+const FRESH: u8 = 0b100;
+
+/// Single-writer/single-reader snapshot cell over three copies of `T`.
+/// The writer fills its own copy and swaps it into the middle, the
+/// reader swaps the middle out whenever it holds something newer.
+/// Neither side ever waits, the reader just sees the latest complete
+/// value and skips whatever was overwritten in between
+pub struct TripleBuffer<T> {
+    bufs: [UnsafeCell<T>; 3],
+    back: AtomicU8, // INDEX | FRESH
+}
+
+impl<T: Clone> TripleBuffer<T> {
+    /// Every copy starts out as `initial`, which the reader sees
+    /// until the first publish
+    pub fn new(initial: T) -> Self {
+        Self {
+            bufs: [
+                UnsafeCell::new(initial.clone()),
+                UnsafeCell::new(initial.clone()),
+                UnsafeCell::new(initial),
+            ],
+            back: AtomicU8::new(1),
+        }
+    }
+}
+
+impl<T> TripleBuffer<T> {
+    /// Hands copy 0 to the writer and copy 2 to the reader
+    pub fn split(self) -> (Writer<T>, Reader<T>) {
+        let bufr = Arc::new(self);
+        let writer = Writer {
+            bufr: bufr.clone(),
+            idx: 0,
+        };
+        (writer, Reader { bufr, idx: 2 })
+    }
+}
+
+impl<T: Clone + Default> Default for TripleBuffer<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+// each copy is only ever touched by the side holding its index
+unsafe impl<T: Send> Sync for TripleBuffer<T> {}
+
+/// Write half of a split `TripleBuffer`
+pub struct Writer<T> {
+    bufr: Arc<TripleBuffer<T>>,
+    idx: u8, // copy owned by the writer
+}
+
+impl<T> Writer<T> {
+    /// The copy the next `publish` hands over, it holds whatever
+    /// was written into it two publishes ago
+    pub fn input(&mut self) -> &mut T {
+        let cell = &self.bufr.bufs[self.idx as usize];
+        unsafe { cell.with_mut(|val| &mut *val) }
+    }
+
+    /// Makes the input copy the latest snapshot
+    pub fn publish(&mut self) {
+        // release the input to the reader, acquire the copy it gave back
+        let prev = self.bufr.back.swap(self.idx | FRESH, Ordering::AcqRel);
+        self.idx = prev & INDEX;
+    }
+
+    /// Overwrites the input copy with `val` and publishes it
+    pub fn write(&mut self, val: T) {
+        *self.input() = val;
+        self.publish();
+    }
+
+    /// Whether the reader has yet to pick up the last publish
+    pub fn is_pending(&self) -> bool {
+        self.bufr.back.load(Ordering::Relaxed) & FRESH != 0
+    }
+}
+
+/// Read half of a split `TripleBuffer`
+pub struct Reader<T> {
+    bufr: Arc<TripleBuffer<T>>,
+    idx: u8, // copy owned by the reader
+}
+
+impl<T> Reader<T> {
+    /// Latest snapshot, swapping in a newer one if the writer published
+    pub fn read(&mut self) -> &T {
+        self.update();
+        self.output()
+    }
+
+    /// Snapshot from the last `read`/`update`, without checking for a newer one
+    pub fn output(&self) -> &T {
+        let cell = &self.bufr.bufs[self.idx as usize];
+        unsafe { cell.with(|val| &*val) }
+    }
+
+    /// Swaps in the latest publish if there is one, `false` if
+    /// the current snapshot is still the latest
+    pub fn update(&mut self) -> bool {
+        // guard: nothing new since the last swap
+        if !self.is_updated() {
+            return false;
+        }
+
+        let prev = self.bufr.back.swap(self.idx, Ordering::AcqRel);
+        self.idx = prev & INDEX;
+        true
+    }
+
+    /// Whether the writer published since the last swap
+    pub fn is_updated(&self) -> bool {
+        self.bufr.back.load(Ordering::Relaxed) & FRESH != 0
+    }
+}
+
+// replaces the auto impl, `output` hands out `&T` from `&self`
+unsafe impl<T: Send + Sync> Sync for Reader<T> {}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_latest_triple() {
+        let (mut writer, mut reader) = TripleBuffer::new(0).split();
+
+        assert!(!reader.is_updated());
+        assert_eq!(*reader.read(), 0);
+
+        writer.write(1);
+        writer.write(2);
+        assert!(writer.is_pending() && reader.is_updated());
+        // 1 was overwritten before the reader got to it
+        assert_eq!(*reader.read(), 2);
+        assert!(!writer.is_pending());
+        assert!(!reader.update());
+        assert_eq!(*reader.output(), 2);
+
+        // the input still holds the value published two rounds ago
+        *writer.input() += 10;
+        writer.publish();
+        assert_eq!(*reader.read(), 11);
+    }
+
+    #[test]
+    fn test_threaded_triple() {
+        const ITEMS: u64 = if cfg!(miri) { 200 } else { 100_000 };
+        let (mut writer, mut reader) = TripleBuffer::new([0u64; 8]).split();
+
+        let write_t = thread::spawn(move || {
+            for i in 1..=ITEMS {
+                *writer.input() = [i; 8];
+                writer.publish();
+            }
+        });
+
+        let mut last = 0;
+        while last < ITEMS {
+            let snapshot = *reader.read();
+            // never torn, never older than what was seen before
+            assert!(snapshot.iter().all(|&v| v == snapshot[0]));
+            assert!(snapshot[0] >= last);
+            last = snapshot[0];
+            thread::yield_now();
+        }
+        write_t.join().unwrap();
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_test {
+    use super::*;
+    use loom::thread;
+
+    #[test]
+    fn test_loom_snapshot_triple() {
+        loom::model(|| {
+            let (mut writer, mut reader) = TripleBuffer::new(0).split();
+
+            let write_t = thread::spawn(move || {
+                writer.write(1);
+                writer.write(2);
+            });
+
+            let first = *reader.read();
+            let second = *reader.read();
+            assert!(first <= second);
+            write_t.join().unwrap();
+            assert_eq!(*reader.read(), 2);
+        });
+    }
+}
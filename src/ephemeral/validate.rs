@@ -0,0 +1,169 @@
+use alloc::boxed::Box;
+use core::{
+    fmt, ptr, slice,
+    sync::atomic::{AtomicPtr, AtomicU64, AtomicUsize, Ordering},
+};
+
+/// Stamp of a slot nothing was ever written to
+const NEVER: u64 = 0;
+
+/// Position stamps of a ring's slots, allocated on first use so
+/// `RingState::new` stays const and every `Ring` gets checked,
+/// including the ones written outside this crate
+///
+/// A slot holding position `pos` is stamped `2 pos + 1`, once
+/// popped `2 pos + 2`, so any access that doesn't match the position
+/// the ring expects (a double pop, an unread slot overwritten, a read
+/// racing a write) is caught at the slot
+pub(crate) struct Stamps {
+    ptr: AtomicPtr<AtomicU64>,
+    len: AtomicUsize,
+}
+
+impl Stamps {
+    pub(crate) const fn new() -> Self {
+        Self {
+            ptr: AtomicPtr::new(ptr::null_mut()),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    /// Checks the slot of `pos` is free before a write
+    pub(crate) fn writing(&self, size: usize, pos: u64) {
+        let stamp = self.stamp(size, pos);
+        let found = stamp.load(Ordering::Acquire);
+        let expected = match pos.checked_sub(size as u64) {
+            Some(prev) => taken(prev),
+            None => NEVER,
+        };
+        if found != expected {
+            report("pushing", size, pos, found);
+        }
+    }
+
+    /// Marks the slot of `pos` as holding it, after the write
+    pub(crate) fn written(&self, size: usize, pos: u64) {
+        self.stamp(size, pos).store(full(pos), Ordering::Release);
+    }
+
+    /// Checks the slot of `pos` still holds it, before and after a read
+    pub(crate) fn reading(&self, size: usize, pos: u64) {
+        let found = self.stamp(size, pos).load(Ordering::Acquire);
+        if found != full(pos) {
+            report("popping", size, pos, found);
+        }
+    }
+
+    /// Marks the slot of `pos` as popped, after the read
+    pub(crate) fn read(&self, size: usize, pos: u64) {
+        self.stamp(size, pos).store(taken(pos), Ordering::Release);
+    }
+
+    fn stamp(&self, size: usize, pos: u64) -> &AtomicU64 {
+        &self.slots(size)[pos as usize & (size - 1)]
+    }
+
+    fn slots(&self, size: usize) -> &[AtomicU64] {
+        let mut stamps = self.ptr.load(Ordering::Acquire);
+        if stamps.is_null() {
+            let fresh: Box<[AtomicU64]> = (0..size).map(|_| AtomicU64::new(NEVER)).collect();
+            let fresh = Box::into_raw(fresh).cast::<AtomicU64>();
+            match self.ptr.compare_exchange(
+                ptr::null_mut(),
+                fresh,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    self.len.store(size, Ordering::Relaxed);
+                    stamps = fresh;
+                }
+                // the other side got there first
+                Err(current) => {
+                    drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(fresh, size)) });
+                    stamps = current;
+                }
+            }
+        }
+        // `Ring` promises the arena size never changes
+        unsafe { slice::from_raw_parts(stamps, size) }
+    }
+}
+
+impl Drop for Stamps {
+    fn drop(&mut self) {
+        let stamps = *self.ptr.get_mut();
+        if !stamps.is_null() {
+            let len = *self.len.get_mut();
+            drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(stamps, len)) });
+        }
+    }
+}
+
+fn full(pos: u64) -> u64 {
+    pos.wrapping_mul(2).wrapping_add(1)
+}
+
+fn taken(pos: u64) -> u64 {
+    pos.wrapping_mul(2).wrapping_add(2)
+}
+
+/// What a stamp says about its slot
+struct Found(u64);
+
+impl fmt::Display for Found {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            NEVER => f.write_str("was never written"),
+            stamp if stamp % 2 == 1 => write!(f, "holds position {}", stamp / 2),
+            stamp => write!(f, "was already popped at position {}", stamp / 2 - 1),
+        }
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn report(op: &str, size: usize, pos: u64, found: u64) -> ! {
+    panic!(
+        "ring slot corrupted: {op} position {pos} (slot {} of {size}), but the slot {}",
+        pos as usize & (size - 1),
+        Found(found)
+    );
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_laps_validate() {
+        let stamps = Stamps::new();
+        for pos in 0..16 {
+            stamps.writing(4, pos);
+            stamps.written(4, pos);
+            stamps.reading(4, pos);
+            stamps.read(4, pos);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "popping position 0 (slot 0 of 4), but the slot was already popped")]
+    fn test_double_pop_validate() {
+        let stamps = Stamps::new();
+        stamps.writing(4, 0);
+        stamps.written(4, 0);
+        stamps.reading(4, 0);
+        stamps.read(4, 0);
+        stamps.reading(4, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "pushing position 4 (slot 0 of 4), but the slot holds position 0")]
+    fn test_overwrite_validate() {
+        let stamps = Stamps::new();
+        stamps.writing(4, 0);
+        stamps.written(4, 0);
+        // a lap later without position 0 ever being popped
+        stamps.writing(4, 4);
+    }
+}
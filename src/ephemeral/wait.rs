@@ -0,0 +1,445 @@
+#[cfg(feature = "notify")]
+use crate::util::Notify;
+use core::{error::Error, fmt, hint};
+
+use crate::sync::yield_now;
+#[cfg(feature = "std")]
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How a blocking call passes time while the ring is full/empty
+pub trait WaitStrategy {
+    /// Called every time an attempt failed,
+    /// `round` counts the failed attempts so far
+    fn wait(&mut self, round: u32);
+}
+
+/// Busy spins, lowest latency but burns the core
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Spin;
+
+impl WaitStrategy for Spin {
+    fn wait(&mut self, _round: u32) {
+        hint::spin_loop();
+    }
+}
+
+/// Spins for a while, then yields the time slice
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+pub struct SpinYield {
+    pub spins: u32,
+}
+
+#[cfg(feature = "std")]
+impl Default for SpinYield {
+    fn default() -> Self {
+        Self { spins: 64 }
+    }
+}
+
+#[cfg(feature = "std")]
+impl WaitStrategy for SpinYield {
+    fn wait(&mut self, round: u32) {
+        if round < self.spins {
+            hint::spin_loop();
+        } else {
+            thread::yield_now();
+        }
+    }
+}
+
+/// Spins for a while, then parks the thread for short naps,
+/// the nap wakes itself so no unpark from the other side is needed
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+pub struct SpinPark {
+    pub spins: u32,
+    pub nap: Duration,
+}
+
+#[cfg(feature = "std")]
+impl Default for SpinPark {
+    fn default() -> Self {
+        Self {
+            spins: 64,
+            nap: Duration::from_micros(50),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl WaitStrategy for SpinPark {
+    fn wait(&mut self, round: u32) {
+        if round < self.spins {
+            hint::spin_loop();
+        } else {
+            #[cfg(feature = "tracing")]
+            super::trace::park();
+            thread::park_timeout(self.nap);
+        }
+    }
+}
+
+/// `SpinPark` that learns how long to spin from how long its waits
+/// take, for one handle whose waits look alike. A wait over while
+/// still spinning pulls the budget towards twice its length, one over
+/// within the first nap doubles it since a little more spinning would
+/// have caught it, a longer one halves it
+///
+/// A wait is only known to be over once the next one starts,
+/// `round` 0 tells, so the budget trails by one wait. Every round
+/// reads the clock, which costs about as much as a spin
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+pub struct AdaptiveWait {
+    spin_for: Duration,
+    nap: Duration,
+    started: Option<Instant>, // first failed attempt of the latest wait
+    waited: Duration,         // from there to its latest failed attempt
+}
+
+#[cfg(feature = "std")]
+impl AdaptiveWait {
+    const MIN_SPIN: Duration = Duration::from_nanos(250);
+    const MAX_SPIN: Duration = Duration::from_millis(1);
+
+    pub const fn new(nap: Duration) -> Self {
+        Self {
+            spin_for: Duration::from_micros(2),
+            nap,
+            started: None,
+            waited: Duration::ZERO,
+        }
+    }
+
+    /// How long a wait spins before napping, as learned so far
+    pub fn spin_for(&self) -> Duration {
+        self.spin_for
+    }
+
+    pub fn nap(&self) -> Duration {
+        self.nap
+    }
+
+    /// Adjusts the budget to the wait that just ended
+    fn learn(&mut self) {
+        let (waited, spin_for) = (self.waited, self.spin_for);
+        self.spin_for = if waited < spin_for {
+            // average in, so one quick wait doesn't throw it off
+            (spin_for * 3 + waited * 2) / 4
+        } else if waited < spin_for + self.nap {
+            spin_for * 2
+        } else {
+            spin_for / 2
+        }
+        .clamp(Self::MIN_SPIN, Self::MAX_SPIN);
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for AdaptiveWait {
+    fn default() -> Self {
+        Self::new(Duration::from_micros(50))
+    }
+}
+
+#[cfg(feature = "std")]
+impl WaitStrategy for AdaptiveWait {
+    fn wait(&mut self, round: u32) {
+        let now = Instant::now();
+        if round == 0 {
+            if self.started.is_some() {
+                self.learn();
+            }
+            self.started = Some(now);
+        }
+        let started = *self.started.get_or_insert(now);
+        self.waited = now - started;
+
+        if self.waited < self.spin_for {
+            hint::spin_loop();
+        } else {
+            #[cfg(feature = "tracing")]
+            super::trace::park();
+            thread::park_timeout(self.nap);
+        }
+    }
+}
+
+/// Exponential backoff for hand-written retry loops, spins 1, 2, 4, ..
+/// times per call, then yields the time slice, and once yielding
+/// didn't help either parks for short naps if built `with_park`
+///
+/// Without `std` yielding and parking are a plain spin
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Backoff {
+    step: u32,
+    #[cfg(feature = "std")]
+    nap: Option<Duration>,
+}
+
+impl Backoff {
+    /// 2^6 spins before the first yield
+    const SPIN_LIMIT: u32 = 6;
+    /// yields before the first nap
+    const YIELD_LIMIT: u32 = 10;
+
+    pub const fn new() -> Self {
+        Self {
+            step: 0,
+            #[cfg(feature = "std")]
+            nap: None,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    /// Parks for `nap` at a time once spinning and yielding ran out,
+    /// the nap wakes itself so no unpark from the other side is needed
+    pub const fn with_park(nap: Duration) -> Self {
+        Self {
+            step: 0,
+            nap: Some(nap),
+        }
+    }
+
+    /// Waits a little longer than the last call did
+    pub fn snooze(&mut self) {
+        // loom only switches threads on its own yield
+        if cfg!(loom) {
+            yield_now();
+            return;
+        }
+
+        if self.step <= Self::SPIN_LIMIT {
+            for _ in 0..1 << self.step {
+                hint::spin_loop();
+            }
+        } else if self.step <= Self::YIELD_LIMIT {
+            yield_now();
+        } else {
+            self.nap();
+        }
+        self.step = (self.step + 1).min(Self::YIELD_LIMIT + 1);
+    }
+
+    #[cfg(feature = "std")]
+    fn nap(&self) {
+        match self.nap {
+            Some(nap) => {
+                #[cfg(feature = "tracing")]
+                super::trace::park();
+                thread::park_timeout(nap)
+            }
+            None => yield_now(),
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn nap(&self) {
+        yield_now();
+    }
+
+    /// Still in the spinning stage, past it the wait gives up the core
+    pub fn is_spinning(&self) -> bool {
+        self.step <= Self::SPIN_LIMIT
+    }
+
+    /// Back to the shortest spin, e.g. after the loop made progress
+    pub fn reset(&mut self) {
+        self.step = 0;
+    }
+}
+
+impl WaitStrategy for Backoff {
+    fn wait(&mut self, _round: u32) {
+        self.snooze();
+    }
+}
+
+/// Deadline passed before the push went through, hands the value back
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timeout<T>(pub T);
+
+impl<T> fmt::Display for Timeout<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("timed out waiting for room")
+    }
+}
+
+impl<T: fmt::Debug> Error for Timeout<T> {}
+
+/// Calls `attempt` until it succeeds, passing time with `wait` in between
+pub(crate) fn retry<T, W: WaitStrategy>(wait: &mut W, mut attempt: impl FnMut() -> Option<T>) -> T {
+    let mut round = 0;
+    loop {
+        if let Some(val) = attempt() {
+            return val;
+        }
+        wait.wait(round);
+        round = round.saturating_add(1);
+    }
+}
+
+/// Calls `attempt` until it succeeds, spinning first and then
+/// sleeping on `notify` until the other side moves
+#[cfg(feature = "notify")]
+pub(crate) fn retry_notified<T>(notify: &Notify, mut attempt: impl FnMut() -> Option<T>) -> T {
+    let spins = SpinYield::default().spins;
+    let mut round = 0;
+    loop {
+        if let Some(val) = attempt() {
+            return val;
+        }
+
+        if round < spins {
+            hint::spin_loop();
+            round += 1;
+            continue;
+        }
+
+        notify.prepare();
+        // whatever lands from here on either shows up now or wakes us
+        if let Some(val) = attempt() {
+            notify.cancel();
+            return val;
+        }
+        #[cfg(feature = "tracing")]
+        super::trace::park();
+        notify.sleep();
+    }
+}
+
+/// Calls `attempt` until it succeeds or `timeout` passes,
+/// spinning first and then parking no later than the deadline
+#[cfg(feature = "std")]
+pub(crate) fn retry_until<T>(
+    timeout: Duration,
+    mut attempt: impl FnMut() -> Option<T>,
+) -> Option<T> {
+    let deadline = Instant::now() + timeout;
+    let wait = SpinPark::default();
+    let mut round = 0;
+
+    loop {
+        if let Some(val) = attempt() {
+            return Some(val);
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return None;
+        }
+
+        if round < wait.spins {
+            hint::spin_loop();
+        } else {
+            #[cfg(feature = "tracing")]
+            super::trace::park();
+            thread::park_timeout(wait.nap.min(deadline - now));
+        }
+        round = round.saturating_add(1);
+    }
+}
+
+/// `retry_until` for pushes, which hand the value back on failure
+#[cfg(feature = "std")]
+pub(crate) fn push_until<T>(
+    val: T,
+    timeout: Duration,
+    mut push: impl FnMut(T) -> Result<(), T>,
+) -> Result<(), Timeout<T>> {
+    let mut pending = Some(val);
+    let pushed = retry_until(timeout, || match push(pending.take()?) {
+        Ok(()) => Some(()),
+        Err(val) => {
+            pending = Some(val);
+            None
+        }
+    });
+
+    match (pushed, pending) {
+        (None, Some(val)) => Err(Timeout(val)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stages_backoff() {
+        let mut backoff = Backoff::new();
+        for _ in 0..=Backoff::SPIN_LIMIT {
+            assert!(backoff.is_spinning());
+            backoff.snooze();
+        }
+        assert!(!backoff.is_spinning());
+
+        // yields and naps from here on, the step stops growing
+        for _ in 0..100 {
+            backoff.snooze();
+        }
+        assert_eq!(backoff.step, Backoff::YIELD_LIMIT + 1);
+
+        backoff.reset();
+        assert!(backoff.is_spinning());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_learns_adaptive() {
+        fn after(wait: &mut AdaptiveWait, waited: Duration) -> Duration {
+            wait.waited = waited;
+            wait.learn();
+            wait.spin_for()
+        }
+        let us = Duration::from_micros;
+        let mut wait = AdaptiveWait::new(us(50));
+        assert_eq!(wait.nap(), us(50));
+
+        // the next wait starting is what ends the last one
+        (0..3).for_each(|round| wait.wait(round));
+        assert_eq!(wait.spin_for(), us(2));
+        // as if it had gone quicker than the clock can tell
+        wait.waited = Duration::ZERO;
+        wait.wait(0);
+        assert_eq!(wait.spin_for(), us(2) * 3 / 4);
+
+        // quick waits shrink the budget towards what they need
+        for _ in 0..40 {
+            after(&mut wait, Duration::ZERO);
+        }
+        assert_eq!(wait.spin_for(), AdaptiveWait::MIN_SPIN);
+
+        // over within the first nap, so spin longer
+        let spin_for = wait.spin_for();
+        assert_eq!(after(&mut wait, spin_for + us(1)), spin_for * 2);
+        // long waits aren't worth spinning for
+        assert_eq!(after(&mut wait, us(1000)), spin_for);
+        for _ in 0..20 {
+            let spin_for = wait.spin_for();
+            after(&mut wait, spin_for + us(1));
+        }
+        assert_eq!(wait.spin_for(), AdaptiveWait::MAX_SPIN);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_park_backoff() {
+        let nap = Duration::from_millis(5);
+        let mut backoff = Backoff::with_park(nap);
+        for _ in 0..=Backoff::YIELD_LIMIT {
+            backoff.snooze();
+        }
+
+        let start = Instant::now();
+        backoff.snooze();
+        // parked rather than spun or yielded, and woke by itself
+        let elapsed = start.elapsed();
+        assert!(elapsed >= nap && elapsed < Duration::from_secs(5));
+    }
+}
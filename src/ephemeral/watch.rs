@@ -0,0 +1,121 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        PoisonError, RwLock,
+    },
+    time::Duration,
+};
+
+use super::wait::{retry, Backoff, WaitStrategy};
+
+/// Latest-value cell, every `set` replaces what readers see.
+/// Readers keep the version they last saw and ask whether
+/// anything newer landed, so any number of them can watch
+///
+/// The value sits behind a lock since an arbitrary `Clone`
+/// can't be copied out tear-free, the version check is lock-free
+pub struct Watch<T> {
+    value: RwLock<T>,
+    version: AtomicU64,
+}
+
+impl<T: Clone> Watch<T> {
+    /// Starts out at version 0
+    pub const fn new(val: T) -> Self {
+        Self {
+            value: RwLock::new(val),
+            version: AtomicU64::new(0),
+        }
+    }
+
+    /// Replaces the value and bumps the version
+    pub fn set(&self, val: T) {
+        let mut value = self.value.write().unwrap_or_else(PoisonError::into_inner);
+        *value = val;
+        self.version.fetch_add(1, Ordering::Release);
+    }
+
+    /// Newest value and the version it was set at
+    pub fn get(&self) -> (T, u64) {
+        let value = self.value.read().unwrap_or_else(PoisonError::into_inner);
+        (value.clone(), self.version.load(Ordering::Acquire))
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    /// Whether anything was set after version `seen`
+    pub fn changed(&self, seen: u64) -> bool {
+        self.version() != seen
+    }
+
+    /// Waits with a parking `Backoff` until something newer than `seen`
+    /// is set, updates are rare so waiting readers end up napping
+    pub fn wait_for_change(&self, seen: u64) -> (T, u64) {
+        let mut wait = Backoff::with_park(Duration::from_micros(50));
+        self.wait_for_change_with(seen, &mut wait)
+    }
+
+    pub fn wait_for_change_with<W: WaitStrategy>(&self, seen: u64, wait: &mut W) -> (T, u64) {
+        retry(wait, || self.changed(seen).then(|| self.get()))
+    }
+}
+
+impl<T: Clone + Default> Default for Watch<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_seq_watch() {
+        let watch = Watch::new(String::from("a"));
+
+        let (val, seen) = watch.get();
+        assert_eq!((val.as_str(), seen), ("a", 0));
+        assert!(!watch.changed(seen));
+
+        watch.set(String::from("b"));
+        watch.set(String::from("c"));
+        assert!(watch.changed(seen));
+
+        // only the newest value is kept
+        let (val, seen) = watch.get();
+        assert_eq!((val.as_str(), seen), ("c", 2));
+        assert!(!watch.changed(seen));
+    }
+
+    #[test]
+    fn test_threaded_watch() {
+        let watch = Arc::new(Watch::new(0));
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let watch = watch.clone();
+                thread::spawn(move || {
+                    let (mut last, mut seen) = watch.get();
+                    while last < 100 {
+                        let (val, version) = watch.wait_for_change(seen);
+                        // values only ever move forward
+                        assert!(val > last && version > seen);
+                        (last, seen) = (val, version);
+                    }
+                })
+            })
+            .collect();
+
+        for i in 1..=100 {
+            watch.set(i);
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    }
+}
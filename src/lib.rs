@@ -0,0 +1,22 @@
+//! Lock-free queues and handoff cells built around preallocated arenas
+//!
+//! Without the default `std` feature the crate is `no_std` and needs
+//! `alloc` and pointer-sized CAS, the blocking waits that yield, park
+//! or time out go away along with `actor`, `broadcast`, `channel`,
+//! `duplex`, `eventbus`, `executor`, `pipeline`, `scope`, `signal`,
+//! `stack`, `std_mpsc`, `throttle`, `timed`, `token` and `watch`
+
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+extern crate alloc;
+
+#[macro_use]
+pub mod sync;
+mod util;
+
+pub mod ephemeral;
+
+pub use ephemeral::slot::EphemeralSlot;
+pub use ephemeral::spsc;
+#[cfg(feature = "std")]
+pub use ephemeral::std_mpsc::{channel, sync_channel};
@@ -0,0 +1,97 @@
+//! Small synchronization primitives that aren't queues, `SeqLock` and the
+//! `AtomicCell` built on it, the `SpinLock`/`TicketLock` pair, `Semaphore`
+//! and the latches pipeline stages coordinate with, all usable without `std`
+//!
+//! Also holds the primitives the rings are built on internally,
+//! swapped for loom's model-checked versions under `--cfg loom`
+
+#[cfg(loom)]
+pub(crate) use loom::{
+    cell::UnsafeCell,
+    sync::{
+        atomic::{fence, AtomicBool, AtomicU16, AtomicU32, AtomicU64, AtomicU8, AtomicUsize},
+        Arc,
+    },
+    thread::yield_now,
+};
+
+#[cfg(not(loom))]
+pub(crate) use alloc::sync::Arc;
+#[cfg(not(loom))]
+pub(crate) use core::sync::atomic::{
+    fence, AtomicBool, AtomicU16, AtomicU32, AtomicU8, AtomicUsize,
+};
+
+#[cfg(all(not(loom), target_has_atomic = "64"))]
+pub(crate) use core::sync::atomic::AtomicU64;
+// a lock-based stand-in where only 32-bit atomics exist
+#[cfg(all(not(loom), not(target_has_atomic = "64")))]
+pub(crate) use portable_atomic::AtomicU64;
+
+// gives the other side a chance to run, a plain spin without `std`
+#[cfg(all(not(loom), not(feature = "std")))]
+pub(crate) use core::hint::spin_loop as yield_now;
+#[cfg(all(not(loom), feature = "std"))]
+pub(crate) use std::thread::yield_now;
+
+// in a private module, so the rings' `slot` can hand it out
+// without it being nameable from outside the crate
+#[cfg(not(loom))]
+mod shim {
+    /// `core::cell::UnsafeCell` behind loom's closure API,
+    /// so the same accesses compile against either
+    #[derive(Debug)]
+    #[repr(transparent)]
+    pub struct UnsafeCell<T>(core::cell::UnsafeCell<T>);
+
+    impl<T> UnsafeCell<T> {
+        pub const fn new(val: T) -> Self {
+            Self(core::cell::UnsafeCell::new(val))
+        }
+
+        pub fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+            f(self.0.get())
+        }
+
+        pub fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+            f(self.0.get())
+        }
+    }
+}
+
+#[cfg(not(loom))]
+pub(crate) use shim::UnsafeCell;
+
+/// `const fn` except under loom, whose primitives
+/// can't be built in a const context
+macro_rules! const_fn {
+    ($(#[$attr:meta])* $vis:vis fn $($rest:tt)*) => {
+        #[cfg(not(loom))]
+        $(#[$attr])* $vis const fn $($rest)*
+        #[cfg(loom)]
+        $(#[$attr])* $vis fn $($rest)*
+    };
+}
+
+// how the blocking primitives below wait, parking under `std`
+// since a permit or latch may stay closed for a long while
+pub(crate) fn long_wait() -> crate::ephemeral::wait::Backoff {
+    #[cfg(feature = "std")]
+    return crate::ephemeral::wait::Backoff::with_park(core::time::Duration::from_micros(50));
+    #[cfg(not(feature = "std"))]
+    crate::ephemeral::wait::Backoff::new()
+}
+
+mod cell;
+mod latch;
+mod lock;
+mod semaphore;
+mod seqlock;
+
+pub use cell::{AtomicCell, NoPadding};
+pub use latch::{CountDownLatch, Latch};
+pub use lock::{SpinLock, SpinLockGuard, TicketLock, TicketLockGuard};
+#[cfg(feature = "async")]
+pub use semaphore::Acquire;
+pub use semaphore::{Permit, Semaphore};
+pub use seqlock::SeqLock;
@@ -0,0 +1,380 @@
+use core::fmt;
+#[cfg(not(loom))]
+use core::{mem, sync::atomic::Ordering};
+
+use super::SeqLock;
+
+/// The lock-free atomic a value of the same size is stored as
+#[cfg(not(loom))]
+trait Atom {
+    type Bits: Copy + Eq;
+
+    fn compare_exchange(
+        &self,
+        current: Self::Bits,
+        new: Self::Bits,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<Self::Bits, Self::Bits>;
+}
+
+#[cfg(not(loom))]
+macro_rules! atom {
+    ($($cfg:meta => $atomic:ident($bits:ty)),* $(,)?) => {$(
+        #[cfg($cfg)]
+        impl Atom for core::sync::atomic::$atomic {
+            type Bits = $bits;
+
+            fn compare_exchange(
+                &self,
+                current: $bits,
+                new: $bits,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$bits, $bits> {
+                self.compare_exchange(current, new, success, failure)
+            }
+        }
+    )*};
+}
+
+#[cfg(not(loom))]
+atom! {
+    target_has_atomic = "8" => AtomicU8(u8),
+    target_has_atomic = "16" => AtomicU16(u16),
+    target_has_atomic = "32" => AtomicU32(u32),
+    target_has_atomic = "64" => AtomicU64(u64),
+}
+
+/// `Copy` types without padding, every byte of a value is part of a
+/// field. Needed by `AtomicCell`, which may store a value as the
+/// integer of its size and would read the padding bytes as such
+///
+/// # Safety
+/// The type must have no padding bytes, not even in enum variants or
+/// behind `repr(align)`, e.g. `#[repr(C)] struct S(u16, u16)` but not
+/// `#[repr(C)] struct S(u8, u16)`
+pub unsafe trait NoPadding: Copy {}
+
+macro_rules! no_padding {
+    ($($ty:ty),* $(,)?) => {$(
+        unsafe impl NoPadding for $ty {}
+    )*};
+}
+
+no_padding! {
+    (), bool, char, f32, f64,
+    u8, u16, u32, u64, u128, usize,
+    i8, i16, i32, i64, i128, isize,
+}
+
+macro_rules! no_padding_nonzero {
+    ($($ty:ident),* $(,)?) => {$(
+        unsafe impl NoPadding for core::num::$ty {}
+        // the niche is the zero, same size as the integer
+        unsafe impl NoPadding for Option<core::num::$ty> {}
+    )*};
+}
+
+no_padding_nonzero! {
+    NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128, NonZeroUsize,
+    NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroIsize,
+}
+
+unsafe impl<T: ?Sized> NoPadding for *const T {}
+unsafe impl<T: ?Sized> NoPadding for *mut T {}
+// elements are laid out back to back, a multiple of their alignment
+unsafe impl<T: NoPadding, const N: usize> NoPadding for [T; N] {}
+
+/// Whether a `T` fits `A` exactly, alignment included
+#[cfg(not(loom))]
+const fn fits<T, A>() -> bool {
+    mem::size_of::<T>() == mem::size_of::<A>() && mem::align_of::<T>() >= mem::align_of::<A>()
+}
+
+/// Runs `$inline` with `$A` the atomic `T` fits in, `$locked` if none
+/// does. Picked at compile time per `T`, so one cell never mixes both
+#[cfg(not(loom))]
+macro_rules! dispatch {
+    ($A:ident => $inline:expr, $locked:expr) => {
+        'dispatch: {
+            #[cfg(all(not(loom), target_has_atomic = "8"))]
+            if fits::<T, core::sync::atomic::AtomicU8>() {
+                type $A = core::sync::atomic::AtomicU8;
+                break 'dispatch $inline;
+            }
+            #[cfg(all(not(loom), target_has_atomic = "16"))]
+            if fits::<T, core::sync::atomic::AtomicU16>() {
+                type $A = core::sync::atomic::AtomicU16;
+                break 'dispatch $inline;
+            }
+            #[cfg(all(not(loom), target_has_atomic = "32"))]
+            if fits::<T, core::sync::atomic::AtomicU32>() {
+                type $A = core::sync::atomic::AtomicU32;
+                break 'dispatch $inline;
+            }
+            #[cfg(all(not(loom), target_has_atomic = "64"))]
+            if fits::<T, core::sync::atomic::AtomicU64>() {
+                type $A = core::sync::atomic::AtomicU64;
+                break 'dispatch $inline;
+            }
+            $locked
+        }
+    };
+}
+
+#[cfg(loom)]
+macro_rules! dispatch {
+    ($A:ident => $inline:expr, $locked:expr) => {
+        $locked
+    };
+}
+
+/// Shared `Copy` value with atomic `load`/`store`/`swap`/
+/// `compare_exchange`, for state that's one value rather than a queue
+///
+/// A `T` the size of a lock-free integer (1, 2, 4 or 8 bytes, aligned
+/// at least as strictly) is read and written through that atomic
+/// directly, anything else sits behind a `SeqLock` so loads still
+/// never block writers. `is_lock_free` tells which one a `T` gets.
+/// Under loom every `T` takes the `SeqLock` route
+///
+/// `T` must be `NoPadding`, the atomic route reads a value's bytes as
+/// an integer. A padded `T` goes straight in a `SeqLock` instead,
+/// the route it'd take here anyway minus `compare_exchange`
+pub struct AtomicCell<T> {
+    lock: SeqLock<T>,
+}
+
+impl<T: NoPadding> AtomicCell<T> {
+    const_fn! {
+        pub fn new(val: T) -> Self {
+            Self {
+                lock: SeqLock::new(val),
+            }
+        }
+    }
+
+    /// Whether `T` is stored as a plain atomic, without the `SeqLock`
+    pub const fn is_lock_free() -> bool {
+        dispatch!(_A => true, false)
+    }
+
+    pub fn load(&self) -> T {
+        dispatch!(A => unsafe { from_bits::<T, A>(self.atom::<A>().load(Ordering::Acquire)) }, self.lock.read())
+    }
+
+    pub fn store(&self, val: T) {
+        dispatch!(
+            A => unsafe { self.atom::<A>().store(to_bits::<T, A>(val), Ordering::Release) },
+            self.lock.write(val)
+        )
+    }
+
+    /// Stores `val`, returning the value it replaced
+    pub fn swap(&self, val: T) -> T {
+        dispatch!(
+            A => unsafe { from_bits::<T, A>(self.atom::<A>().swap(to_bits::<T, A>(val), Ordering::AcqRel)) },
+            self.lock.update(|_| Some(val))
+        )
+    }
+
+    pub fn into_inner(self) -> T {
+        self.load()
+    }
+
+    /// Reinterprets the value as the atomic `T` fits, only sound
+    /// when `dispatch!` picked `A` for it
+    #[cfg(not(loom))]
+    unsafe fn atom<A>(&self) -> &A {
+        unsafe { &*self.lock.as_ptr().cast::<A>() }
+    }
+}
+
+impl<T: NoPadding + Eq> AtomicCell<T> {
+    /// Stores `new` if the value is still `current`, `Ok` with the value
+    /// it replaced on success, `Err` with the one in the way otherwise
+    pub fn compare_exchange(&self, current: T, new: T) -> Result<T, T> {
+        dispatch!(
+            A => unsafe { self.compare_exchange_atomic::<A>(current, new) },
+            {
+                let prev = self.lock.update(|val| (val == current).then_some(new));
+                if prev == current {
+                    Ok(prev)
+                } else {
+                    Err(prev)
+                }
+            }
+        )
+    }
+
+    #[cfg(not(loom))]
+    unsafe fn compare_exchange_atomic<A: Atom>(&self, current: T, new: T) -> Result<T, T> {
+        let atom = unsafe { self.atom::<A>() };
+        let new = unsafe { to_bits::<T, A>(new) };
+        let mut expected = unsafe { to_bits::<T, A>(current) };
+        loop {
+            match atom.compare_exchange(expected, new, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(bits) => return Ok(unsafe { from_bits::<T, A>(bits) }),
+                Err(bits) => {
+                    let prev = unsafe { from_bits::<T, A>(bits) };
+                    if prev != current {
+                        return Err(prev);
+                    }
+                    // equal without being the same bits, retry with these
+                    expected = bits;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(loom))]
+unsafe fn to_bits<T, A: Atom>(val: T) -> A::Bits {
+    unsafe { mem::transmute_copy(&val) }
+}
+
+#[cfg(not(loom))]
+unsafe fn from_bits<T, A: Atom>(bits: A::Bits) -> T {
+    unsafe { mem::transmute_copy(&bits) }
+}
+
+impl<T: NoPadding + Default> Default for AtomicCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: NoPadding> From<T> for AtomicCell<T> {
+    fn from(val: T) -> Self {
+        Self::new(val)
+    }
+}
+
+impl<T: NoPadding + fmt::Debug> fmt::Debug for AtomicCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AtomicCell").field(&self.load()).finish()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_inline_cell() {
+        static FLAG: AtomicCell<Option<core::num::NonZeroU32>> = AtomicCell::new(None);
+        let one = core::num::NonZeroU32::new(1);
+
+        assert!(AtomicCell::<Option<core::num::NonZeroU32>>::is_lock_free());
+        assert_eq!(FLAG.swap(one), None);
+        assert_eq!(FLAG.compare_exchange(None, None), Err(one));
+        assert_eq!(FLAG.compare_exchange(one, None), Ok(one));
+        assert_eq!(FLAG.load(), None);
+
+        // bit patterns that compare equal still exchange
+        let cell = AtomicCell::new(Zero(-5));
+        assert_eq!(cell.compare_exchange(Zero(5), Zero(7)).map(|z| z.0), Ok(-5));
+        assert_eq!(cell.load().0, 7);
+    }
+
+    #[test]
+    fn test_locked_cell() {
+        let cell = AtomicCell::new([1u64; 3]);
+
+        assert!(!AtomicCell::<[u64; 3]>::is_lock_free());
+        assert!(!AtomicCell::<[u8; 3]>::is_lock_free());
+        cell.store([2; 3]);
+        assert_eq!(cell.swap([3; 3]), [2; 3]);
+        assert_eq!(cell.compare_exchange([2; 3], [4; 3]), Err([3; 3]));
+        assert_eq!(cell.compare_exchange([3; 3], [4; 3]), Ok([3; 3]));
+        assert_eq!(cell.into_inner(), [4; 3]);
+    }
+
+    #[test]
+    fn test_padded_cell() {
+        // the size and alignment of a `u32`, with a byte of padding
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        #[repr(C, align(4))]
+        struct Padded(u8, u16);
+
+        assert_eq!(mem::size_of::<Padded>(), mem::size_of::<u32>());
+        // not `NoPadding`, so a `SeqLock` and never read as an integer
+        let lock = SeqLock::new(Padded(1, 2));
+        lock.write(Padded(3, 4));
+        assert_eq!(lock.read(), Padded(3, 4));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    // the `SeqLock` copy races the write by design
+    #[cfg_attr(miri, ignore)]
+    fn test_threaded_cell() {
+        use std::thread;
+
+        const ITEMS: u64 = 10000;
+        let small = AtomicCell::new(0u64);
+        let large = AtomicCell::new([0u64; 2]);
+
+        // compare_exchange loops, no increment lost on either route
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..ITEMS {
+                        let mut cur = small.load();
+                        while let Err(prev) = small.compare_exchange(cur, cur + 1) {
+                            cur = prev;
+                        }
+                        let mut cur = large.load();
+                        while let Err(prev) = large.compare_exchange(cur, [cur[0] + 1, cur[1] + 2])
+                        {
+                            cur = prev;
+                        }
+                    }
+                });
+            }
+        });
+        assert_eq!(small.load(), 4 * ITEMS);
+        assert_eq!(large.load(), [4 * ITEMS, 8 * ITEMS]);
+    }
+
+    /// Equal whatever the sign, like `0.0` and `-0.0` are
+    #[derive(Clone, Copy, Debug)]
+    struct Zero(i32);
+
+    impl PartialEq for Zero {
+        fn eq(&self, other: &Self) -> bool {
+            self.0.unsigned_abs() == other.0.unsigned_abs()
+        }
+    }
+
+    impl Eq for Zero {}
+
+    unsafe impl NoPadding for Zero {}
+}
+
+#[cfg(all(test, loom))]
+mod loom_test {
+    use super::*;
+    use crate::sync::Arc;
+    use loom::thread;
+
+    #[test]
+    fn test_loom_swap_cell() {
+        loom::model(|| {
+            let cell = Arc::new(AtomicCell::new([0u64; 2]));
+
+            let other = cell.clone();
+            let swap_t = thread::spawn(move || other.swap([1, 1]));
+
+            let prev = cell.compare_exchange([0, 0], [2, 2]);
+            let swapped = swap_t.join().unwrap();
+            // either the swap saw the exchange or the exchange lost to it
+            match prev {
+                Ok(_) => assert_eq!(swapped, [2, 2]),
+                Err(prev) => assert_eq!((prev, swapped), ([1, 1], [0, 0])),
+            }
+            assert_eq!(cell.load(), [1, 1]);
+        });
+    }
+}
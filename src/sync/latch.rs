@@ -0,0 +1,196 @@
+#[cfg(feature = "std")]
+use core::time::Duration;
+use core::{fmt, sync::atomic::Ordering};
+
+use super::{long_wait, AtomicUsize};
+#[cfg(feature = "std")]
+use crate::ephemeral::wait::retry_until;
+
+/// Opens once `count_down` was called as many times as it started
+/// with, `wait` blocks until then. It never closes again, so it
+/// marks one-off events like every pipeline stage being done
+pub struct CountDownLatch {
+    count: AtomicUsize,
+}
+
+impl CountDownLatch {
+    const_fn! {
+        pub fn new(count: usize) -> Self {
+            Self {
+                count: AtomicUsize::new(count),
+            }
+        }
+    }
+
+    /// Everything before it happens before any `wait` that returns
+    /// after the last count, a no-op once open
+    pub fn count_down(&self) {
+        let _ = self
+            .count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1));
+    }
+
+    /// Counts still missing, stale as soon as it's returned
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.count.load(Ordering::Acquire) == 0
+    }
+
+    /// Waits with `Backoff` until the count reached zero
+    pub fn wait(&self) {
+        let mut backoff = long_wait();
+        while !self.is_open() {
+            backoff.snooze();
+        }
+    }
+
+    #[cfg(feature = "std")]
+    /// `wait` giving up once `timeout` passed, `false` if still closed
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        retry_until(timeout, || self.is_open().then_some(())).is_some()
+    }
+}
+
+impl fmt::Debug for CountDownLatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CountDownLatch")
+            .field("count", &self.count())
+            .finish()
+    }
+}
+
+/// One-shot gate, a `CountDownLatch` of one that `open` opens
+pub struct Latch(CountDownLatch);
+
+impl Latch {
+    const_fn! {
+        pub fn new() -> Self {
+            Self(CountDownLatch::new(1))
+        }
+    }
+
+    pub fn open(&self) {
+        self.0.count_down();
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.0.is_open()
+    }
+
+    /// Waits with `Backoff` until the latch is open
+    pub fn wait(&self) {
+        self.0.wait();
+    }
+
+    #[cfg(feature = "std")]
+    /// `wait` giving up once `timeout` passed, `false` if still closed
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        self.0.wait_timeout(timeout)
+    }
+}
+
+impl Default for Latch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Latch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Latch")
+            .field("open", &self.is_open())
+            .finish()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_count_latch() {
+        static LATCH: CountDownLatch = CountDownLatch::new(2);
+
+        LATCH.count_down();
+        assert!(!LATCH.is_open());
+        LATCH.count_down();
+        LATCH.count_down();
+        assert!(LATCH.is_open() && LATCH.count() == 0);
+        LATCH.wait();
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_timeout_latch() {
+        let gate = Latch::new();
+        assert!(!gate.wait_timeout(Duration::from_millis(1)));
+        gate.open();
+        assert!(gate.wait_timeout(Duration::ZERO));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_pipeline_latch() {
+        use crate::ephemeral::spsc::SPSCEphemeral;
+        use std::thread;
+
+        const STAGES: usize = 4;
+        let start = Latch::new();
+        let done = CountDownLatch::new(STAGES);
+        let (mut producer, mut consumer) = SPSCEphemeral::<usize, 8>::new().split();
+
+        thread::scope(|s| {
+            // workers only start once everything is wired up
+            for _ in 0..STAGES {
+                s.spawn(|| {
+                    start.wait();
+                    done.count_down();
+                });
+            }
+
+            s.spawn(|| {
+                done.wait();
+                producer.push(STAGES).unwrap();
+            });
+
+            assert_eq!(done.count(), STAGES);
+            start.open();
+            assert_eq!(consumer.pop_blocking(), Ok(STAGES));
+        });
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_test {
+    use super::*;
+    use crate::sync::Arc;
+    use loom::thread;
+
+    #[test]
+    fn test_loom_latch() {
+        loom::model(|| {
+            let latch = Arc::new(CountDownLatch::new(2));
+            let written = Arc::new(AtomicUsize::new(0));
+
+            let count_ts: Vec<_> = (0..2)
+                .map(|_| {
+                    let (latch, written) = (latch.clone(), written.clone());
+                    thread::spawn(move || {
+                        written.fetch_add(1, Ordering::Relaxed);
+                        latch.count_down();
+                    })
+                })
+                .collect();
+
+            // both writes happen before the latch opens
+            latch.wait();
+            assert_eq!(written.load(Ordering::Relaxed), 2);
+            for count_t in count_ts {
+                count_t.join().unwrap();
+            }
+        });
+    }
+}
@@ -0,0 +1,307 @@
+use core::{
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::atomic::Ordering,
+};
+
+use super::{AtomicBool, AtomicUsize, UnsafeCell};
+use crate::ephemeral::wait::Backoff;
+
+/// Test-and-test-and-set lock, waiters spin on a plain load and
+/// only retry the swap once the holder let go. Cheapest when held
+/// briefly, but makes no promise about who gets it next
+pub struct SpinLock<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+impl<T> SpinLock<T> {
+    const_fn! {
+        pub fn new(val: T) -> Self {
+            Self {
+                locked: AtomicBool::new(false),
+                value: UnsafeCell::new(val),
+            }
+        }
+    }
+
+    pub fn lock(&self) -> SpinLockGuard<'_, T> {
+        let mut backoff = Backoff::new();
+        loop {
+            if let Some(guard) = self.try_lock() {
+                return guard;
+            }
+            while self.locked.load(Ordering::Relaxed) {
+                backoff.snooze();
+            }
+        }
+    }
+
+    /// `None` while someone else holds the lock
+    pub fn try_lock(&self) -> Option<SpinLockGuard<'_, T>> {
+        self.locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| SpinLockGuard { lock: self })
+    }
+
+    /// Held right now, stale as soon as it's returned
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::Relaxed)
+    }
+
+    /// No locking needed, `&mut self` rules out any guard
+    pub fn get_mut(&mut self) -> &mut T {
+        unsafe { self.value.with_mut(|val| &mut *val) }
+    }
+}
+
+impl<T: Default> Default for SpinLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for SpinLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpinLock")
+            .field("locked", &self.is_locked())
+            .finish_non_exhaustive()
+    }
+}
+
+unsafe impl<T: Send> Sync for SpinLock<T> {}
+
+/// Unlocks the `SpinLock` on drop
+pub struct SpinLockGuard<'a, T> {
+    lock: &'a SpinLock<T>,
+}
+
+impl<T> Deref for SpinLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { self.lock.value.with(|val| &*val) }
+    }
+}
+
+impl<T> DerefMut for SpinLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { self.lock.value.with_mut(|val| &mut *val) }
+    }
+}
+
+impl<T> Drop for SpinLockGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Ordering::Release);
+    }
+}
+
+// sharing the guard shares `&T`
+unsafe impl<T: Sync> Sync for SpinLockGuard<'_, T> {}
+
+/// Fair lock handing out tickets, waiters get the lock strictly in
+/// the order they asked for it. Nobody starves, but a descheduled
+/// waiter holds up everyone queued behind it
+pub struct TicketLock<T> {
+    next: AtomicUsize,    // ticket the next caller draws
+    serving: AtomicUsize, // ticket currently allowed in
+    value: UnsafeCell<T>,
+}
+
+impl<T> TicketLock<T> {
+    const_fn! {
+        pub fn new(val: T) -> Self {
+            Self {
+                next: AtomicUsize::new(0),
+                serving: AtomicUsize::new(0),
+                value: UnsafeCell::new(val),
+            }
+        }
+    }
+
+    pub fn lock(&self) -> TicketLockGuard<'_, T> {
+        let ticket = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut backoff = Backoff::new();
+        while self.serving.load(Ordering::Acquire) != ticket {
+            backoff.snooze();
+        }
+        TicketLockGuard { lock: self }
+    }
+
+    /// `None` while someone else holds the lock or is queued for it
+    pub fn try_lock(&self) -> Option<TicketLockGuard<'_, T>> {
+        // pairs with the unlock's release, as in `lock`
+        let serving = self.serving.load(Ordering::Acquire);
+        self.next
+            .compare_exchange(
+                serving,
+                serving.wrapping_add(1),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .ok()
+            .map(|_| TicketLockGuard { lock: self })
+    }
+
+    /// Held or queued for right now, stale as soon as it's returned
+    pub fn is_locked(&self) -> bool {
+        self.next.load(Ordering::Relaxed) != self.serving.load(Ordering::Relaxed)
+    }
+
+    /// No locking needed, `&mut self` rules out any guard
+    pub fn get_mut(&mut self) -> &mut T {
+        unsafe { self.value.with_mut(|val| &mut *val) }
+    }
+}
+
+impl<T: Default> Default for TicketLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for TicketLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TicketLock")
+            .field("locked", &self.is_locked())
+            .finish_non_exhaustive()
+    }
+}
+
+unsafe impl<T: Send> Sync for TicketLock<T> {}
+
+/// Lets the next ticket in on drop
+pub struct TicketLockGuard<'a, T> {
+    lock: &'a TicketLock<T>,
+}
+
+impl<T> Deref for TicketLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { self.lock.value.with(|val| &*val) }
+    }
+}
+
+impl<T> DerefMut for TicketLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { self.lock.value.with_mut(|val| &mut *val) }
+    }
+}
+
+impl<T> Drop for TicketLockGuard<'_, T> {
+    fn drop(&mut self) {
+        // only the holder ever moves `serving`
+        self.lock.serving.fetch_add(1, Ordering::Release);
+    }
+}
+
+// sharing the guard shares `&T`
+unsafe impl<T: Sync> Sync for TicketLockGuard<'_, T> {}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use std::thread;
+
+    const THREADS: usize = 4;
+    const ITEMS: usize = if cfg!(miri) { 100 } else { 10000 };
+
+    /// Bumps a counter behind `lock` from several threads
+    fn hammer<L: Sync>(lock: &L, bump: impl Fn(&L) + Sync, items: usize) {
+        thread::scope(|s| {
+            for _ in 0..THREADS {
+                s.spawn(|| {
+                    for _ in 0..items {
+                        bump(lock);
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_guard_lock() {
+        static SPIN: SpinLock<i32> = SpinLock::new(0);
+        static TICKET: TicketLock<i32> = TicketLock::new(0);
+
+        let mut guard = SPIN.lock();
+        *guard += 1;
+        assert!(SPIN.is_locked() && SPIN.try_lock().is_none());
+        drop(guard);
+        assert_eq!(*SPIN.try_lock().unwrap(), 1);
+
+        let mut guard = TICKET.lock();
+        *guard += 2;
+        assert!(TICKET.is_locked() && TICKET.try_lock().is_none());
+        drop(guard);
+        assert_eq!(*TICKET.try_lock().unwrap(), 2);
+        assert!(!TICKET.is_locked());
+    }
+
+    #[test]
+    fn test_contended_lock() {
+        let spin = SpinLock::new(0);
+        hammer(&spin, |lock| *lock.lock() += 1, ITEMS);
+        assert_eq!(*spin.lock(), THREADS * ITEMS);
+
+        let mut ticket = TicketLock::new(0);
+        hammer(&ticket, |lock| *lock.lock() += 1, ITEMS);
+        assert_eq!(*ticket.get_mut(), THREADS * ITEMS);
+    }
+
+    #[test]
+    fn test_fifo_lock() {
+        let lock = TicketLock::new(Vec::new());
+        let held = lock.lock();
+
+        // queue the waiters one by one, each draws the next ticket
+        let lock = &lock;
+        thread::scope(|s| {
+            for i in 0..THREADS {
+                s.spawn(move || lock.lock().push(i));
+                while lock.next.load(Ordering::Relaxed) != i + 2 {
+                    thread::yield_now();
+                }
+            }
+            drop(held);
+        });
+        assert_eq!(*lock.lock(), (0..THREADS).collect::<Vec<_>>());
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_test {
+    use super::*;
+    use crate::sync::Arc;
+    use loom::thread;
+
+    #[test]
+    fn test_loom_spin_lock() {
+        loom::model(|| {
+            let lock = Arc::new(SpinLock::new(0));
+
+            let other = lock.clone();
+            let bump_t = thread::spawn(move || *other.lock() += 1);
+            *lock.lock() += 1;
+            bump_t.join().unwrap();
+
+            assert_eq!(*lock.lock(), 2);
+        });
+    }
+
+    #[test]
+    fn test_loom_ticket_lock() {
+        loom::model(|| {
+            let lock = Arc::new(TicketLock::new(0));
+
+            let other = lock.clone();
+            let bump_t = thread::spawn(move || *other.lock() += 1);
+            *lock.lock() += 1;
+            bump_t.join().unwrap();
+
+            assert_eq!(*lock.lock(), 2);
+        });
+    }
+}
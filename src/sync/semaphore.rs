@@ -0,0 +1,251 @@
+use core::{fmt, sync::atomic::Ordering};
+
+#[cfg(feature = "async")]
+use alloc::vec::Vec;
+#[cfg(feature = "async")]
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+#[cfg(feature = "async")]
+use super::SpinLock;
+use super::{long_wait, AtomicUsize};
+
+/// Counting semaphore bounded by the permits it started with,
+/// e.g. to cap how many pipeline stages run at once. Permits
+/// come back on drop, or by hand through `release`
+pub struct Semaphore {
+    permits: AtomicUsize,
+    max: usize,
+    // every task waiting in `acquire_async`, all woken on release
+    #[cfg(feature = "async")]
+    wakers: SpinLock<Vec<Waker>>,
+}
+
+impl Semaphore {
+    const_fn! {
+        pub fn new(permits: usize) -> Self {
+            Self {
+                permits: AtomicUsize::new(permits),
+                max: permits,
+                #[cfg(feature = "async")]
+                wakers: SpinLock::new(Vec::new()),
+            }
+        }
+    }
+
+    /// Waits with `Backoff` until a permit is free
+    pub fn acquire(&self) -> Permit<'_> {
+        let mut backoff = long_wait();
+        loop {
+            if let Some(permit) = self.try_acquire() {
+                return permit;
+            }
+            while self.permits.load(Ordering::Relaxed) == 0 {
+                backoff.snooze();
+            }
+        }
+    }
+
+    /// `None` while every permit is taken
+    pub fn try_acquire(&self) -> Option<Permit<'_>> {
+        self.permits
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |n| n.checked_sub(1))
+            .ok()
+            .map(|_| Permit { sem: self })
+    }
+
+    #[cfg(feature = "async")]
+    /// Resolves once a permit is free
+    pub fn acquire_async(&self) -> Acquire<'_> {
+        Acquire { sem: self }
+    }
+
+    /// Hands back `n` permits taken by `Permit::forget`
+    ///
+    /// Panics if that would leave more permits than the semaphore started with
+    pub fn release(&self, n: usize) {
+        let released = self
+            .permits
+            .fetch_update(Ordering::Release, Ordering::Relaxed, |p| {
+                p.checked_add(n).filter(|&p| p <= self.max)
+            });
+        assert!(
+            released.is_ok(),
+            "released more permits than the semaphore holds"
+        );
+
+        #[cfg(feature = "async")]
+        for waker in self.wakers.lock().drain(..) {
+            waker.wake();
+        }
+    }
+
+    /// Free right now, stale as soon as it's returned
+    pub fn available_permits(&self) -> usize {
+        self.permits.load(Ordering::Relaxed)
+    }
+
+    pub fn max_permits(&self) -> usize {
+        self.max
+    }
+}
+
+impl fmt::Debug for Semaphore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Semaphore")
+            .field("permits", &self.available_permits())
+            .field("max", &self.max)
+            .finish_non_exhaustive()
+    }
+}
+
+/// One permit of a `Semaphore`, handed back on drop
+#[must_use = "the permit is released right away if unused"]
+pub struct Permit<'a> {
+    sem: &'a Semaphore,
+}
+
+impl Permit<'_> {
+    /// Keeps the permit taken past the guard, `Semaphore::release` returns it
+    pub fn forget(self) {
+        core::mem::forget(self);
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.sem.release(1);
+    }
+}
+
+/// Future returned by `Semaphore::acquire_async`
+#[cfg(feature = "async")]
+pub struct Acquire<'a> {
+    sem: &'a Semaphore,
+}
+
+#[cfg(feature = "async")]
+impl<'a> Future for Acquire<'a> {
+    type Output = Permit<'a>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Permit<'a>> {
+        let sem = self.sem;
+        if let Some(permit) = sem.try_acquire() {
+            return Poll::Ready(permit);
+        }
+
+        // register first, then retry so a release in between isn't missed
+        let mut wakers = sem.wakers.lock();
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        drop(wakers);
+
+        match sem.try_acquire() {
+            Some(permit) => Poll::Ready(permit),
+            None => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use std::thread;
+
+    const THREADS: usize = 8;
+    const ITEMS: usize = if cfg!(miri) { 50 } else { 2000 };
+
+    #[test]
+    fn test_permits_semaphore() {
+        static SEM: Semaphore = Semaphore::new(2);
+
+        let a = SEM.acquire();
+        let b = SEM.try_acquire().unwrap();
+        assert!(SEM.try_acquire().is_none());
+        drop(a);
+        assert_eq!(SEM.available_permits(), 1);
+
+        b.forget();
+        assert_eq!(SEM.available_permits(), 1);
+        SEM.release(1);
+        assert_eq!(SEM.available_permits(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "released more permits")]
+    fn test_bounded_semaphore() {
+        let sem = Semaphore::new(1);
+        sem.release(1);
+    }
+
+    #[test]
+    fn test_contended_semaphore() {
+        const PERMITS: usize = 3;
+        let sem = Semaphore::new(PERMITS);
+        let inside = AtomicUsize::new(0);
+
+        thread::scope(|s| {
+            for _ in 0..THREADS {
+                s.spawn(|| {
+                    for _ in 0..ITEMS {
+                        let _permit = sem.acquire();
+                        let now = inside.fetch_add(1, Ordering::SeqCst) + 1;
+                        assert!(now <= PERMITS);
+                        inside.fetch_sub(1, Ordering::SeqCst);
+                    }
+                });
+            }
+        });
+        assert_eq!(sem.available_permits(), PERMITS);
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_async_semaphore() {
+        use futures::executor::block_on;
+
+        let sem = Semaphore::new(1);
+        let held = sem.acquire();
+
+        thread::scope(|s| {
+            let waiter = s.spawn(|| block_on(async { sem.acquire_async().await.forget() }));
+            thread::sleep(std::time::Duration::from_millis(10));
+            drop(held);
+            waiter.join().unwrap();
+        });
+        assert_eq!(sem.available_permits(), 0);
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_test {
+    use super::*;
+    use crate::sync::Arc;
+    use loom::thread;
+
+    #[test]
+    fn test_loom_semaphore() {
+        loom::model(|| {
+            let sem = Arc::new(Semaphore::new(1));
+            let inside = Arc::new(AtomicUsize::new(0));
+
+            let enter = {
+                let (sem, inside) = (sem.clone(), inside.clone());
+                move || {
+                    let _permit = sem.acquire();
+                    assert_eq!(inside.fetch_add(1, Ordering::SeqCst), 0);
+                    inside.fetch_sub(1, Ordering::SeqCst);
+                }
+            };
+            let other_t = thread::spawn(enter.clone());
+            enter();
+            other_t.join().unwrap();
+
+            assert_eq!(sem.available_permits(), 1);
+        });
+    }
+}
@@ -0,0 +1,261 @@
+use core::sync::atomic::Ordering;
+
+use super::{fence, AtomicUsize};
+use crate::ephemeral::wait::Backoff;
+
+/// Holds the value, racing reads are thrown away by the caller
+#[cfg(not(loom))]
+struct Data<T>(core::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T: Copy> Data<T> {
+    const fn new(val: T) -> Self {
+        Self(core::cell::UnsafeCell::new(val))
+    }
+
+    /// May overlap a write, the copy is only trusted
+    /// when the sequence didn't move around it
+    fn read(&self) -> T {
+        unsafe { self.0.get().read_volatile() }
+    }
+
+    fn write(&self, val: T) {
+        unsafe { self.0.get().write_volatile(val) }
+    }
+}
+
+/// loom rejects the racy copy outright, so the value is kept in
+/// word-sized atomics whose interleavings it can explore instead
+#[cfg(loom)]
+struct Data<T> {
+    words: alloc::boxed::Box<[loom::sync::atomic::AtomicU64]>,
+    _marker: core::marker::PhantomData<T>,
+}
+
+#[cfg(loom)]
+impl<T: Copy> Data<T> {
+    fn new(val: T) -> Self {
+        let words = (0..core::mem::size_of::<T>().div_ceil(8))
+            .map(|_| loom::sync::atomic::AtomicU64::new(0))
+            .collect();
+        let data = Self {
+            words,
+            _marker: core::marker::PhantomData,
+        };
+        data.write(val);
+        data
+    }
+
+    fn read(&self) -> T {
+        let buf: alloc::vec::Vec<u64> = self
+            .words
+            .iter()
+            .map(|word| word.load(Ordering::Relaxed))
+            .collect();
+        unsafe { buf.as_ptr().cast::<T>().read_unaligned() }
+    }
+
+    fn write(&self, val: T) {
+        let mut buf = alloc::vec![0u64; self.words.len()];
+        unsafe { buf.as_mut_ptr().cast::<T>().write_unaligned(val) };
+        for (word, val) in self.words.iter().zip(buf) {
+            word.store(val, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Sequence lock for small `Copy` values that are read far more
+/// often than written. Readers never block the writer, they copy
+/// the value and retry if a write overlapped the copy, telling by
+/// an odd or moved sequence number. Writers take turns on the
+/// sequence so several of them are fine too
+pub struct SeqLock<T> {
+    seq: AtomicUsize, // odd while a write is in progress
+    data: Data<T>,
+}
+
+impl<T: Copy> SeqLock<T> {
+    const_fn! {
+        pub fn new(val: T) -> Self {
+            Self {
+                seq: AtomicUsize::new(0),
+                data: Data::new(val),
+            }
+        }
+    }
+
+    /// Consistent copy of the latest value, spins while a write is in progress
+    pub fn read(&self) -> T {
+        let mut backoff = Backoff::new();
+        loop {
+            let before = self.seq.load(Ordering::Acquire);
+            if before & 1 == 0 {
+                let val = self.data.read();
+                // keeps the copy from sinking below the second load
+                fence(Ordering::Acquire);
+                if self.seq.load(Ordering::Relaxed) == before {
+                    return val;
+                }
+            }
+            backoff.snooze();
+        }
+    }
+
+    /// Replaces the value, waiting out a write already in progress
+    pub fn write(&self, val: T) {
+        let seq = self.lock();
+        self.data.write(val);
+        self.seq.store(seq.wrapping_add(2), Ordering::Release);
+    }
+
+    /// Hands the current value to `f` with writers shut out and stores
+    /// what it returns, `None` leaves the value and the version alone.
+    /// Returns the value `f` was given
+    pub(crate) fn update(&self, f: impl FnOnce(T) -> Option<T>) -> T {
+        let seq = self.lock();
+        // no other writer runs, so this copy can't tear
+        let old = self.data.read();
+        match f(old) {
+            Some(val) => {
+                self.data.write(val);
+                self.seq.store(seq.wrapping_add(2), Ordering::Release);
+            }
+            // readers that overlapped saw nothing change
+            None => self.seq.store(seq, Ordering::Release),
+        }
+        old
+    }
+
+    /// The value itself, for callers never mixing it with `read`/`write`
+    #[cfg(not(loom))]
+    pub(crate) fn as_ptr(&self) -> *mut T {
+        self.data.0.get()
+    }
+
+    /// Moves the sequence to odd, waiting out a write already in progress
+    fn lock(&self) -> usize {
+        let mut backoff = Backoff::new();
+        let seq = loop {
+            let seq = self.seq.load(Ordering::Relaxed);
+            if seq & 1 == 0
+                && self
+                    .seq
+                    .compare_exchange_weak(seq, seq + 1, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                break seq;
+            }
+            backoff.snooze();
+        };
+
+        // keeps the write from rising above the odd sequence
+        fence(Ordering::Release);
+        seq
+    }
+
+    /// Number of completed writes
+    pub fn version(&self) -> usize {
+        self.seq.load(Ordering::Acquire) / 2
+    }
+}
+
+impl<T: Copy + Default> Default for SeqLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+unsafe impl<T: Copy + Send> Sync for SeqLock<T> {}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_static_seqlock() {
+        static POS: SeqLock<(i32, i32)> = SeqLock::new((0, 0));
+
+        assert_eq!(POS.read(), (0, 0));
+        POS.write((3, -4));
+        POS.write((5, 12));
+        assert_eq!(POS.read(), (5, 12));
+        assert_eq!(POS.version(), 2);
+    }
+
+    #[test]
+    // the optimistic copy races the write by design
+    #[cfg_attr(miri, ignore)]
+    fn test_torn_seqlock() {
+        const ITEMS: u64 = 100_000;
+        let lock = SeqLock::new([0u64; 8]);
+
+        thread::scope(|s| {
+            for _ in 0..2 {
+                s.spawn(|| {
+                    for i in 1..=ITEMS {
+                        lock.write([i; 8]);
+                    }
+                });
+            }
+
+            let mut reads = 0;
+            while lock.version() < 2 * ITEMS as usize {
+                let val = lock.read();
+                assert!(val.iter().all(|&v| v == val[0]));
+                reads += 1;
+            }
+            assert!(reads > 0);
+        });
+        assert_eq!(lock.read(), [ITEMS; 8]);
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_test {
+    use super::*;
+    use crate::sync::Arc;
+    use loom::thread;
+
+    #[test]
+    fn test_loom_torn_seqlock() {
+        loom::model(|| {
+            let lock = Arc::new(SeqLock::new((0u64, 0u64)));
+
+            let writer = lock.clone();
+            let write_t = thread::spawn(move || {
+                writer.write((1, 1));
+                writer.write((2, 2));
+            });
+
+            let (a, b) = lock.read();
+            assert_eq!(a, b);
+            write_t.join().unwrap();
+            assert_eq!(lock.read(), (2, 2));
+        });
+    }
+
+    #[test]
+    fn test_loom_writers_seqlock() {
+        let mut model = loom::model::Builder::new();
+        model.preemption_bound = Some(2);
+
+        model.check(|| {
+            let lock = Arc::new(SeqLock::new((0u64, 0u64)));
+
+            let write_ts: Vec<_> = (1..=2)
+                .map(|val| {
+                    let writer = lock.clone();
+                    thread::spawn(move || writer.write((val, val)))
+                })
+                .collect();
+
+            let (a, b) = lock.read();
+            assert_eq!(a, b);
+            for write_t in write_ts {
+                write_t.join().unwrap();
+            }
+            assert_eq!(lock.version(), 2);
+        });
+    }
+}
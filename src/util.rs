@@ -0,0 +1,429 @@
+use core::ops::{Deref, DerefMut};
+#[cfg(any(feature = "async", feature = "notify"))]
+use core::sync::atomic::Ordering;
+#[cfg(feature = "notify")]
+use core::sync::atomic::{fence, AtomicU32};
+#[cfg(feature = "async")]
+use core::{cell::UnsafeCell, sync::atomic::AtomicUsize, task::Waker};
+#[cfg(all(feature = "notify", not(target_os = "linux")))]
+use std::{
+    sync::Mutex,
+    thread::{self, Thread},
+};
+
+/// Pads and aligns a value to a cache line, so two hot atomics
+/// never share a line and ping-pong between producer and consumer.
+/// x86_64 and aarch64 prefetch lines in pairs, hence 128 there
+#[cfg_attr(any(target_arch = "x86_64", target_arch = "aarch64"), repr(align(128)))]
+#[cfg_attr(
+    not(any(target_arch = "x86_64", target_arch = "aarch64")),
+    repr(align(64))
+)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CachePadded<T> {
+    value: T,
+}
+
+impl<T> CachePadded<T> {
+    pub const fn new(value: T) -> Self {
+        Self { value }
+    }
+}
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for CachePadded<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+/// Single waker slot that one side registers into and the other
+/// side takes out of, coordinated through a small state word
+/// instead of a lock
+#[cfg(feature = "async")]
+pub struct AtomicWaker {
+    state: AtomicUsize,
+    waker: UnsafeCell<Option<Waker>>,
+}
+
+#[cfg(feature = "async")]
+impl AtomicWaker {
+    const WAITING: usize = 0;
+    const REGISTERING: usize = 0b01;
+    const WAKING: usize = 0b10;
+
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicUsize::new(Self::WAITING),
+            waker: UnsafeCell::new(None),
+        }
+    }
+
+    /// Stores `waker` to be woken by the next `wake`, a wake racing
+    /// with the registration wakes it right away instead of being lost
+    pub fn register(&self, waker: &Waker) {
+        match self.state.compare_exchange(
+            Self::WAITING,
+            Self::REGISTERING,
+            Ordering::Acquire,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => {
+                let slot = unsafe { &mut *self.waker.get() };
+                match slot {
+                    Some(old) if old.will_wake(waker) => {}
+                    _ => *slot = Some(waker.clone()),
+                }
+
+                let raced = self
+                    .state
+                    .compare_exchange(
+                        Self::REGISTERING,
+                        Self::WAITING,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    )
+                    .is_err();
+
+                // guard: a wake came in while we held the slot
+                if raced {
+                    let waker = slot.take();
+                    self.state.swap(Self::WAITING, Ordering::AcqRel);
+                    if let Some(waker) = waker {
+                        waker.wake();
+                    }
+                }
+            }
+            // mid-wake, the caller must poll again
+            Err(Self::WAKING) => waker.wake_by_ref(),
+            // concurrent register, not allowed for a single owner
+            Err(_) => {}
+        }
+    }
+
+    pub fn wake(&self) {
+        if let Some(waker) = self.take() {
+            waker.wake();
+        }
+    }
+
+    pub fn take(&self) -> Option<Waker> {
+        match self.state.fetch_or(Self::WAKING, Ordering::AcqRel) {
+            Self::WAITING => {
+                let waker = unsafe { (*self.waker.get()).take() };
+                self.state.fetch_and(!Self::WAKING, Ordering::Release);
+                waker
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Default for AtomicWaker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "async")]
+unsafe impl Send for AtomicWaker {}
+#[cfg(feature = "async")]
+unsafe impl Sync for AtomicWaker {}
+
+/// Lets one side of a ring sleep until the other side moves. Waking
+/// is a fence and a load while nobody sleeps, the futex (or unpark
+/// off Linux) is only issued once a sleeper announced itself
+#[cfg(feature = "notify")]
+pub struct Notify {
+    state: AtomicU32,
+    #[cfg(not(target_os = "linux"))]
+    thread: Mutex<Option<Thread>>,
+}
+
+#[cfg(feature = "notify")]
+impl Notify {
+    const IDLE: u32 = 0;
+    const SLEEPING: u32 = 1;
+
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU32::new(Self::IDLE),
+            #[cfg(not(target_os = "linux"))]
+            thread: Mutex::new(None),
+        }
+    }
+
+    /// Announces the caller is about to sleep, it must check its
+    /// condition once more before calling `sleep` or `cancel`
+    pub fn prepare(&self) {
+        #[cfg(not(target_os = "linux"))]
+        {
+            *self.thread.lock().unwrap() = Some(thread::current());
+        }
+        self.state.store(Self::SLEEPING, Ordering::Relaxed);
+        // pairs with the fence in `wake`, either the recheck sees
+        // the other side's progress or `wake` sees the announcement
+        fence(Ordering::SeqCst);
+    }
+
+    /// The recheck after `prepare` went through, no sleep needed
+    pub fn cancel(&self) {
+        self.state.store(Self::IDLE, Ordering::Relaxed);
+    }
+
+    /// Blocks until a `wake` since `prepare`, returns right away
+    /// if one already came in
+    pub fn sleep(&self) {
+        while self.state.load(Ordering::Acquire) == Self::SLEEPING {
+            #[cfg(target_os = "linux")]
+            futex::wait(&self.state, Self::SLEEPING);
+            #[cfg(not(target_os = "linux"))]
+            thread::park();
+        }
+    }
+
+    /// Called after publishing progress, tells whether someone had
+    /// announced a sleep
+    pub fn wake(&self) -> bool {
+        fence(Ordering::SeqCst);
+
+        // guard: nobody asleep, the common case
+        if self.state.load(Ordering::Relaxed) == Self::IDLE {
+            return false;
+        }
+        if self.state.swap(Self::IDLE, Ordering::Release) != Self::SLEEPING {
+            return false;
+        }
+        #[cfg(target_os = "linux")]
+        futex::wake(&self.state);
+        #[cfg(not(target_os = "linux"))]
+        if let Some(sleeper) = self.thread.lock().unwrap().as_ref() {
+            sleeper.unpark();
+        }
+        true
+    }
+}
+
+#[cfg(feature = "notify")]
+impl Default for Notify {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(feature = "notify", target_os = "linux"))]
+mod futex {
+    use core::{ptr, sync::atomic::AtomicU32};
+
+    /// Sleeps while `word` still holds `expected`, may return spuriously
+    pub fn wait(word: &AtomicU32, expected: u32) {
+        unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                word.as_ptr(),
+                libc::FUTEX_WAIT | libc::FUTEX_PRIVATE_FLAG,
+                expected,
+                ptr::null::<libc::timespec>(),
+            );
+        }
+    }
+
+    /// Wakes the one thread sleeping on `word`, if any
+    pub fn wake(word: &AtomicU32) {
+        unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                word.as_ptr(),
+                libc::FUTEX_WAKE | libc::FUTEX_PRIVATE_FLAG,
+                1,
+            );
+        }
+    }
+}
+
+/// Anonymous page mappings for arenas that pick their own pages,
+/// Linux only
+#[cfg(all(any(feature = "numa", feature = "huge-pages"), target_os = "linux"))]
+pub mod pages {
+    use core::ptr::{self, NonNull};
+    use std::io;
+
+    // from linux/mempolicy.h, not all of libc's targets export them
+    #[cfg(feature = "numa")]
+    const MPOL_PREFERRED: libc::c_int = 1;
+    /// default huge page size on x86_64, and aarch64 with 4K pages
+    #[cfg(feature = "huge-pages")]
+    pub const HUGE: usize = 2 << 20;
+
+    /// Mapping unmapped on drop, zeroed and page aligned
+    pub struct Pages {
+        ptr: NonNull<u8>,
+        size: usize,
+    }
+
+    impl Pages {
+        /// Maps at least `size` bytes
+        pub fn new(size: usize) -> io::Result<Self> {
+            // a zero length mapping is an error
+            let size = size.max(1);
+            let ptr = map(size, 0)?;
+            Ok(Self { ptr, size })
+        }
+
+        /// Maps `size` bytes rounded up to huge pages, reserved ones
+        /// if the hugetlb pool has room, otherwise plain pages aligned
+        /// for the kernel to back with transparent huge pages
+        #[cfg(feature = "huge-pages")]
+        pub fn huge(size: usize) -> io::Result<Self> {
+            let size = size.max(1).next_multiple_of(HUGE);
+            if let Ok(ptr) = map(size, libc::MAP_HUGETLB) {
+                return Ok(Self { ptr, size });
+            }
+
+            // one huge page more than needed, then trim both ends so
+            // what's left starts on a huge page boundary
+            let base = map(size + HUGE, 0)?.as_ptr();
+            let head = base.align_offset(HUGE);
+            unsafe {
+                if head > 0 {
+                    libc::munmap(base.cast(), head);
+                }
+                libc::munmap(base.add(head + size).cast(), HUGE - head);
+            }
+            let pages = Self {
+                ptr: unsafe { NonNull::new_unchecked(base.add(head)) },
+                size,
+            };
+            // guard: THP turned off just leaves small pages, no harm
+            unsafe { libc::madvise(pages.as_ptr().cast(), size, libc::MADV_HUGEPAGE) };
+            Ok(pages)
+        }
+
+        /// Has the pages faulted in on `node`, falling back to others
+        /// only once it runs out of memory. Fails if `node` isn't one
+        /// this process may allocate on
+        ///
+        /// Before anything touched them, pages already there stay put
+        #[cfg(feature = "numa")]
+        pub fn bind(&self, node: usize) -> io::Result<()> {
+            const BITS: usize = libc::c_ulong::BITS as usize;
+
+            let mut mask = vec![0 as libc::c_ulong; node / BITS + 1];
+            mask[node / BITS] |= 1 << (node % BITS);
+            // the kernel reads one bit less than `maxnode` says
+            let res = unsafe {
+                libc::syscall(
+                    libc::SYS_mbind,
+                    self.ptr.as_ptr(),
+                    self.size,
+                    MPOL_PREFERRED,
+                    mask.as_ptr(),
+                    mask.len() * BITS + 1,
+                    0,
+                )
+            };
+            if res != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        pub fn as_ptr(&self) -> *mut u8 {
+            self.ptr.as_ptr()
+        }
+    }
+
+    fn map(size: usize, flags: libc::c_int) -> io::Result<NonNull<u8>> {
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(unsafe { NonNull::new_unchecked(ptr.cast()) })
+    }
+
+    impl Drop for Pages {
+        fn drop(&mut self) {
+            unsafe { libc::munmap(self.ptr.as_ptr().cast(), self.size) };
+        }
+    }
+
+    unsafe impl Send for Pages {}
+    unsafe impl Sync for Pages {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::mem;
+
+    #[test]
+    fn test_layout_padded() {
+        assert!(mem::align_of::<CachePadded<u8>>() >= 64);
+        assert_eq!(
+            mem::size_of::<CachePadded<u8>>(),
+            mem::align_of::<CachePadded<u8>>()
+        );
+
+        let mut padded = CachePadded::new(1);
+        *padded += 1;
+        assert_eq!(*padded, 2);
+    }
+
+    #[test]
+    #[cfg(feature = "notify")]
+    fn test_wake_before_sleep_notify() {
+        let notify = Notify::new();
+        notify.prepare();
+        assert!(notify.wake());
+        // the wake came in after the announcement, no sleep
+        notify.sleep();
+
+        // a wake with nobody asleep is dropped
+        assert!(!notify.wake());
+        notify.prepare();
+        notify.cancel();
+    }
+
+    #[test]
+    #[cfg(feature = "notify")]
+    fn test_threaded_notify() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::{thread, time::Duration};
+
+        let notify = Notify::new();
+        let ready = AtomicBool::new(false);
+        thread::scope(|s| {
+            s.spawn(|| {
+                while !ready.load(Ordering::Acquire) {
+                    notify.prepare();
+                    if ready.load(Ordering::Acquire) {
+                        notify.cancel();
+                        break;
+                    }
+                    notify.sleep();
+                }
+            });
+
+            thread::sleep(Duration::from_millis(20));
+            ready.store(true, Ordering::Release);
+            notify.wake();
+        });
+    }
+}
@@ -0,0 +1,33 @@
+// non-Send payloads must not cross threads through any queue or cell
+//
+// the diagnostics walk the queues' fields and name paths by the crates
+// in scope, both of which features change, so the .stderr files are
+// for the default features and the test only runs with just those
+#[test]
+#[cfg(all(
+    feature = "std",
+    not(any(
+        feature = "async",
+        feature = "affinity",
+        feature = "futures",
+        feature = "ipc",
+        feature = "notify",
+        feature = "numa",
+        feature = "huge-pages",
+        feature = "persistent",
+        feature = "stats",
+        feature = "latency",
+        feature = "metrics",
+        feature = "serde",
+        feature = "debug-validate",
+        feature = "strict-ordering",
+        feature = "testing",
+        feature = "tokio",
+        feature = "tracing",
+    ))
+))]
+#[cfg_attr(miri, ignore)]
+fn test_compile_fail() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,653 @@
+// every queue driven by random operations against a `VecDeque` model,
+// plus randomized multithreaded schedules checking what comes out
+#![cfg(not(loom))]
+
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use brainstorm::ephemeral::{
+    deque::Worker,
+    dynamic::DynBuffer,
+    isr::IsrQueue,
+    linked::LinkedMPSC,
+    mpmc::{BoundedMpmc, MPMCEphemeral},
+    mpsc::{self, MPSCEphemeral},
+    overwrite::{self, OverwriteBuffer},
+    padded::PaddedBuffer,
+    priority::{self, PriorityBuffer},
+    scq::SCQEphemeral,
+    segment::SegQueue,
+    spmc::{self, SPMCEphemeral},
+    spsc::{Consumer, Producer, Ring, SPSCEphemeral},
+};
+use proptest::prelude::*;
+
+const CAP: usize = 4;
+const CASES: u32 = if cfg!(miri) { 4 } else { 256 };
+
+#[derive(Clone, Debug)]
+enum Op {
+    Push(u32),
+    Pop,
+    PushBatch(Vec<u32>),
+    PopBatch(usize),
+}
+
+fn ops() -> impl Strategy<Value = Vec<Op>> {
+    let op = prop_oneof![
+        3 => any::<u32>().prop_map(Op::Push),
+        3 => Just(Op::Pop),
+        1 => prop::collection::vec(any::<u32>(), 0..2 * CAP).prop_map(Op::PushBatch),
+        1 => (0..2 * CAP).prop_map(Op::PopBatch),
+    ];
+    prop::collection::vec(op, 0..64)
+}
+
+/// One queue as the model sees it, single-threaded
+trait Queue {
+    fn push(&mut self, val: u32) -> Result<(), u32>;
+    fn pop(&mut self) -> Option<u32>;
+
+    /// Pushes a prefix of `vals`, stopping at the first refusal
+    fn push_batch(&mut self, vals: &[u32]) -> usize {
+        vals.iter()
+            .take_while(|&&val| self.push(val).is_ok())
+            .count()
+    }
+
+    fn pop_batch(&mut self, max: usize) -> Vec<u32> {
+        std::iter::from_fn(|| self.pop()).take(max).collect()
+    }
+}
+
+/// Which end things come out of
+#[derive(Clone, Copy)]
+enum Order {
+    Fifo,
+    #[cfg(feature = "std")]
+    Lifo,
+    /// FIFO whose pushes evict the oldest item instead of failing
+    Overwrite,
+    /// LIFO pops, FIFO batches through a stealer
+    Deque,
+}
+
+struct Model {
+    items: VecDeque<u32>,
+    cap: Option<usize>,
+    order: Order,
+}
+
+impl Model {
+    fn new(cap: Option<usize>, order: Order) -> Self {
+        Self {
+            items: VecDeque::new(),
+            cap,
+            order,
+        }
+    }
+
+    fn push(&mut self, val: u32) -> Result<(), u32> {
+        if self.cap == Some(self.items.len()) {
+            match self.order {
+                Order::Overwrite => drop(self.items.pop_front()),
+                _ => return Err(val),
+            }
+        }
+        self.items.push_back(val);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        match self.order {
+            Order::Fifo | Order::Overwrite => self.items.pop_front(),
+            #[cfg(feature = "std")]
+            Order::Lifo => self.items.pop_back(),
+            Order::Deque => self.items.pop_back(),
+        }
+    }
+
+    fn pop_batch(&mut self, max: usize) -> Vec<u32> {
+        match self.order {
+            Order::Deque => std::iter::from_fn(|| self.items.pop_front())
+                .take(max)
+                .collect(),
+            _ => std::iter::from_fn(|| self.pop()).take(max).collect(),
+        }
+    }
+}
+
+fn run(mut queue: impl Queue, mut model: Model, ops: Vec<Op>) -> Result<(), TestCaseError> {
+    for op in ops {
+        match op {
+            Op::Push(val) => prop_assert_eq!(queue.push(val), model.push(val)),
+            Op::Pop => prop_assert_eq!(queue.pop(), model.pop()),
+            Op::PushBatch(vals) => {
+                let expected = vals.iter().take_while(|&&val| model.push(val).is_ok());
+                prop_assert_eq!(queue.push_batch(&vals), expected.count());
+            }
+            Op::PopBatch(max) => prop_assert_eq!(queue.pop_batch(max), model.pop_batch(max)),
+        }
+    }
+    // nothing was lost or made up along the way
+    let rest = model.pop_batch(usize::MAX);
+    prop_assert_eq!(queue.pop_batch(usize::MAX), rest);
+    Ok(())
+}
+
+struct Spsc<R: Ring>(Producer<R>, Consumer<R>);
+
+impl<R: Ring<Item = u32>> Queue for Spsc<R> {
+    fn push(&mut self, val: u32) -> Result<(), u32> {
+        self.0.push(val)
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        self.1.pop().ok()
+    }
+
+    fn push_batch(&mut self, vals: &[u32]) -> usize {
+        self.0.push_slice(vals)
+    }
+
+    fn pop_batch(&mut self, max: usize) -> Vec<u32> {
+        let mut out = Vec::new();
+        self.1.pop_batch(&mut out, max);
+        out
+    }
+}
+
+impl Queue for (mpsc::Producer<u32, CAP>, mpsc::Consumer<u32, CAP>) {
+    fn push(&mut self, val: u32) -> Result<(), u32> {
+        self.0.push(val)
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        self.1.pop()
+    }
+
+    fn pop_batch(&mut self, max: usize) -> Vec<u32> {
+        self.1.drain_up_to(max).collect()
+    }
+}
+
+impl Queue for (spmc::Producer<u32, CAP>, spmc::Consumer<u32, CAP>) {
+    fn push(&mut self, val: u32) -> Result<(), u32> {
+        self.0.push(val)
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        self.1.pop()
+    }
+}
+
+impl Queue for MPMCEphemeral<u32, CAP> {
+    fn push(&mut self, val: u32) -> Result<(), u32> {
+        MPMCEphemeral::push(self, val)
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        MPMCEphemeral::pop(self)
+    }
+}
+
+impl Queue for SCQEphemeral<u32, CAP> {
+    fn push(&mut self, val: u32) -> Result<(), u32> {
+        SCQEphemeral::push(self, val)
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        SCQEphemeral::pop(self)
+    }
+}
+
+impl Queue for IsrQueue<u32, CAP> {
+    fn push(&mut self, val: u32) -> Result<(), u32> {
+        // `&mut self`, nothing else can push or pop
+        unsafe { self.push_from_isr(val) }
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        unsafe { IsrQueue::pop(self) }
+    }
+}
+
+// a single lane, so everything comes out in push order
+impl Queue
+    for (
+        priority::Producer<u32, 1, CAP>,
+        priority::Consumer<u32, 1, CAP>,
+    )
+{
+    fn push(&mut self, val: u32) -> Result<(), u32> {
+        self.0.push(0, val)
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        self.1.pop()
+    }
+}
+
+impl Queue for (overwrite::Producer<u32, CAP>, overwrite::Consumer<u32, CAP>) {
+    fn push(&mut self, val: u32) -> Result<(), u32> {
+        self.0.push_overwrite(val);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        self.1.pop()
+    }
+}
+
+struct Linked(
+    brainstorm::ephemeral::linked::Producer<u32>,
+    brainstorm::ephemeral::linked::Consumer<u32>,
+);
+
+impl Queue for Linked {
+    fn push(&mut self, val: u32) -> Result<(), u32> {
+        self.0.push(val);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        self.1.pop()
+    }
+}
+
+impl Queue for SegQueue<u32> {
+    fn push(&mut self, val: u32) -> Result<(), u32> {
+        SegQueue::push(self, val);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        SegQueue::pop(self)
+    }
+}
+
+struct Deque(Worker<u32>, brainstorm::ephemeral::deque::Stealer<u32>);
+
+impl Queue for Deque {
+    fn push(&mut self, val: u32) -> Result<(), u32> {
+        self.0.push(val);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        self.0.pop()
+    }
+
+    fn pop_batch(&mut self, max: usize) -> Vec<u32> {
+        std::iter::from_fn(|| self.1.steal()).take(max).collect()
+    }
+}
+
+#[cfg(feature = "std")]
+mod std_queues {
+    use super::*;
+    use brainstorm::ephemeral::{channel, stack::EphemeralStack, std_mpsc};
+
+    impl Queue for EphemeralStack<u32> {
+        fn push(&mut self, val: u32) -> Result<(), u32> {
+            EphemeralStack::push(self, val);
+            Ok(())
+        }
+
+        fn pop(&mut self) -> Option<u32> {
+            EphemeralStack::pop(self)
+        }
+    }
+
+    impl<Q: channel::Flavor<Item = u32>> Queue for (channel::Sender<Q>, channel::Receiver<Q>) {
+        fn push(&mut self, val: u32) -> Result<(), u32> {
+            self.0
+                .try_send(val)
+                .map_err(channel::TrySendError::into_inner)
+        }
+
+        fn pop(&mut self) -> Option<u32> {
+            self.1.try_recv().ok()
+        }
+    }
+
+    impl Queue for (std_mpsc::SyncSender<u32>, std_mpsc::Receiver<u32>) {
+        fn push(&mut self, val: u32) -> Result<(), u32> {
+            self.0
+                .try_send(val)
+                .map_err(std_mpsc::TrySendError::into_inner)
+        }
+
+        fn pop(&mut self) -> Option<u32> {
+            self.1.try_recv().ok()
+        }
+    }
+
+    impl Queue for (std_mpsc::Sender<u32>, std_mpsc::Receiver<u32>) {
+        fn push(&mut self, val: u32) -> Result<(), u32> {
+            self.0.send(val).map_err(|err| err.0)
+        }
+
+        fn pop(&mut self) -> Option<u32> {
+            self.1.try_recv().ok()
+        }
+
+        fn pop_batch(&mut self, max: usize) -> Vec<u32> {
+            self.1.try_iter().take(max).collect()
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(CASES))]
+
+        #[test]
+        fn test_model_stack(ops in ops()) {
+            run(EphemeralStack::new(), Model::new(None, Order::Lifo), ops)?;
+        }
+
+        #[test]
+        fn test_model_channel(ops in ops()) {
+            run(channel::bounded::<u32, CAP>(), Model::new(Some(CAP), Order::Fifo), ops.clone())?;
+            run(channel::bounded_spsc::<u32, CAP>(), Model::new(Some(CAP), Order::Fifo), ops)?;
+        }
+
+        #[test]
+        fn test_model_std_mpsc(ops in ops()) {
+            run(std_mpsc::sync_channel(CAP), Model::new(Some(CAP), Order::Fifo), ops.clone())?;
+            run(std_mpsc::channel(), Model::new(None, Order::Fifo), ops)?;
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(CASES))]
+
+    #[test]
+    fn test_model_spsc(ops in ops()) {
+        let fifo = || Model::new(Some(CAP), Order::Fifo);
+        let (p, c) = SPSCEphemeral::<u32, CAP>::new().split();
+        run(Spsc(p, c), fifo(), ops.clone())?;
+        let (p, c) = DynBuffer::with_capacity(CAP).split();
+        run(Spsc(p, c), fifo(), ops.clone())?;
+        let (p, c) = PaddedBuffer::<u32, CAP>::new().split();
+        run(Spsc(p, c), fifo(), ops)?;
+    }
+
+    #[test]
+    fn test_model_seq(ops in ops()) {
+        let fifo = || Model::new(Some(CAP), Order::Fifo);
+        run(MPSCEphemeral::<u32, CAP>::new().split(), fifo(), ops.clone())?;
+        run(SPMCEphemeral::<u32, CAP>::new().split(), fifo(), ops.clone())?;
+        run(MPMCEphemeral::<u32, CAP>::new(), fifo(), ops.clone())?;
+        run(SCQEphemeral::<u32, CAP>::new(), fifo(), ops.clone())?;
+        run(IsrQueue::<u32, CAP>::new(), fifo(), ops.clone())?;
+        run(PriorityBuffer::<u32, 1, CAP>::new().split(), fifo(), ops)?;
+    }
+
+    #[test]
+    fn test_model_overwrite(ops in ops()) {
+        let model = Model::new(Some(CAP), Order::Overwrite);
+        run(OverwriteBuffer::<u32, CAP>::new().split(), model, ops)?;
+    }
+
+    #[test]
+    fn test_model_unbounded(ops in ops()) {
+        let (p, c) = LinkedMPSC::new().split();
+        run(Linked(p, c), Model::new(None, Order::Fifo), ops.clone())?;
+        run(SegQueue::new(), Model::new(None, Order::Fifo), ops.clone())?;
+
+        let worker = Worker::new();
+        let stealer = worker.stealer();
+        run(Deque(worker, stealer), Model::new(None, Order::Deque), ops)?;
+    }
+}
+
+/// xorshift, enough to shake up the schedule without a dependency
+struct Chaos(u64);
+
+impl Chaos {
+    /// Yields or spins a little now and then
+    fn step(&mut self) {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        match self.0 % 8 {
+            0 => thread::yield_now(),
+            1 => (0..self.0 % 64).for_each(|_| std::hint::spin_loop()),
+            _ => {}
+        }
+    }
+}
+
+type PushFn = Box<dyn FnMut(u32) -> bool + Send>;
+type PopFn = Box<dyn FnMut() -> Option<u32> + Send>;
+
+/// Runs every producer and consumer on its own thread with a shaken
+/// schedule, each producer pushing `items` values tagged with its
+/// index. Everything pushed comes out exactly once, and with `fifo`
+/// every consumer sees each producer's values in push order
+fn chaos(seed: u64, items: u32, producers: Vec<PushFn>, consumers: Vec<PopFn>, fifo: bool) {
+    let total = producers.len() * items as usize;
+    let popped = AtomicUsize::new(0);
+
+    let mut seen: Vec<Vec<u32>> = thread::scope(|s| {
+        for (p, mut push) in producers.into_iter().enumerate() {
+            let mut chaos = Chaos(seed | 1 ^ (p as u64) << 32);
+            s.spawn(move || {
+                for i in 0..items {
+                    let val = (p as u32) << 16 | i;
+                    while !push(val) {
+                        chaos.step();
+                    }
+                    chaos.step();
+                }
+            });
+        }
+
+        let consumers: Vec<_> = consumers
+            .into_iter()
+            .enumerate()
+            .map(|(c, mut pop)| {
+                let mut chaos = Chaos(seed.rotate_left(17) | 1 ^ (c as u64) << 40);
+                let popped = &popped;
+                s.spawn(move || {
+                    let mut seen = Vec::new();
+                    while popped.load(Ordering::Relaxed) < total {
+                        if let Some(val) = pop() {
+                            popped.fetch_add(1, Ordering::Relaxed);
+                            seen.push(val);
+                        }
+                        chaos.step();
+                    }
+                    seen
+                })
+            })
+            .collect();
+        consumers.into_iter().map(|c| c.join().unwrap()).collect()
+    });
+
+    if fifo {
+        for seen in &seen {
+            let mut last = vec![None; total];
+            for &val in seen {
+                let (p, i) = ((val >> 16) as usize, val & 0xffff);
+                assert!(last[p] < Some(i), "producer {p} overtaken at {i}");
+                last[p] = Some(i);
+            }
+        }
+    }
+    let mut all: Vec<_> = seen.drain(..).flatten().collect();
+    all.sort_unstable();
+    let expected: Vec<u32> = (0..total as u32 / items)
+        .flat_map(|p| (0..items).map(move |i| p << 16 | i))
+        .collect();
+    assert_eq!(all, expected);
+}
+
+const ITEMS: u32 = if cfg!(miri) { 20 } else { 500 };
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(if cfg!(miri) { 1 } else { 16 }))]
+
+    #[test]
+    fn test_chaos_spsc(seed in any::<u64>()) {
+        let (mut p, mut c) = SPSCEphemeral::<u32, CAP>::new().split();
+        chaos(
+            seed,
+            ITEMS,
+            vec![Box::new(move |val| p.push(val).is_ok())],
+            vec![Box::new(move || c.pop().ok())],
+            true,
+        );
+    }
+
+    #[test]
+    fn test_chaos_mpsc(seed in any::<u64>(), producers in 1..4usize) {
+        let (p, mut c) = MPSCEphemeral::<u32, CAP>::new().split();
+        let pushers = (0..producers)
+            .map(|_| {
+                let p = p.clone();
+                Box::new(move |val| p.push(val).is_ok()) as PushFn
+            })
+            .collect();
+        chaos(seed, ITEMS, pushers, vec![Box::new(move || c.pop())], true);
+    }
+
+    #[test]
+    fn test_chaos_spmc(seed in any::<u64>(), consumers in 1..4usize) {
+        let (mut p, c) = SPMCEphemeral::<u32, CAP>::new().split();
+        let poppers = (0..consumers)
+            .map(|_| {
+                let c = c.clone();
+                Box::new(move || c.pop()) as PopFn
+            })
+            .collect();
+        chaos(seed, ITEMS, vec![Box::new(move |val| p.push(val).is_ok())], poppers, true);
+    }
+
+    #[test]
+    fn test_chaos_mpmc(seed in any::<u64>(), producers in 1..4usize, consumers in 1..4usize) {
+        fn bounded<Q: BoundedMpmc<Item = u32> + Send + 'static>(
+            seed: u64,
+            queue: Q,
+            producers: usize,
+            consumers: usize,
+        ) {
+            let queue = std::sync::Arc::new(queue);
+            let push = |q: &std::sync::Arc<Q>| {
+                let q = q.clone();
+                Box::new(move |val| q.push(val).is_ok()) as PushFn
+            };
+            let pop = |q: &std::sync::Arc<Q>| {
+                let q = q.clone();
+                Box::new(move || q.pop()) as PopFn
+            };
+            chaos(
+                seed,
+                ITEMS,
+                (0..producers).map(|_| push(&queue)).collect(),
+                (0..consumers).map(|_| pop(&queue)).collect(),
+                true,
+            );
+        }
+        bounded(seed, MPMCEphemeral::<u32, CAP>::new(), producers, consumers);
+        bounded(seed, SCQEphemeral::<u32, CAP>::new(), producers, consumers);
+
+        let seg = std::sync::Arc::new(SegQueue::new());
+
+        let pushers = (0..producers)
+            .map(|_| {
+                let q = seg.clone();
+                Box::new(move |val| {
+                    q.push(val);
+                    true
+                }) as PushFn
+            })
+            .collect();
+        let poppers = (0..consumers)
+            .map(|_| {
+                let q = seg.clone();
+                Box::new(move || q.pop()) as PopFn
+            })
+            .collect();
+        chaos(seed, ITEMS, pushers, poppers, true);
+    }
+
+    #[test]
+    fn test_chaos_linked(seed in any::<u64>(), producers in 1..4usize) {
+        let (p, mut c) = LinkedMPSC::new().split();
+        let pushers = (0..producers)
+            .map(|_| {
+                let mut p = p.clone();
+                Box::new(move |val| {
+                    p.push(val);
+                    true
+                }) as PushFn
+            })
+            .collect();
+        drop(p);
+        chaos(seed, ITEMS, pushers, vec![Box::new(move || c.pop())], true);
+    }
+
+    #[test]
+    fn test_chaos_deque(seed in any::<u64>(), stealers in 1..4usize) {
+        let mut worker = Worker::new();
+        let poppers = (0..stealers)
+            .map(|_| {
+                let stealer = worker.stealer();
+                Box::new(move || stealer.steal()) as PopFn
+            })
+            .collect();
+        // the owner only pushes, its own pops would break the order
+        let push = Box::new(move |val| {
+            worker.push(val);
+            true
+        });
+        chaos(seed, ITEMS, vec![push], poppers, false);
+    }
+}
+
+#[cfg(feature = "std")]
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(if cfg!(miri) { 1 } else { 16 }))]
+
+    #[test]
+    fn test_chaos_stack(seed in any::<u64>(), producers in 1..4usize, consumers in 1..4usize) {
+        use brainstorm::ephemeral::stack::EphemeralStack;
+
+        let stack = std::sync::Arc::new(EphemeralStack::new());
+        let pushers = (0..producers)
+            .map(|_| {
+                let stack = stack.clone();
+                Box::new(move |val| {
+                    stack.push(val);
+                    true
+                }) as PushFn
+            })
+            .collect();
+        let poppers = (0..consumers)
+            .map(|_| {
+                let stack = stack.clone();
+                Box::new(move || stack.pop()) as PopFn
+            })
+            .collect();
+        chaos(seed, ITEMS, pushers, poppers, false);
+    }
+
+    #[test]
+    fn test_chaos_channel(seed in any::<u64>(), producers in 1..4usize) {
+        use brainstorm::ephemeral::channel;
+
+        let (tx, rx) = channel::bounded::<u32, CAP>();
+        let pushers = (0..producers)
+            .map(|_| {
+                let tx = tx.clone();
+                Box::new(move |val| tx.try_send(val).is_ok()) as PushFn
+            })
+            .collect();
+        chaos(seed, ITEMS, pushers, vec![Box::new(move || rx.try_recv().ok())], true);
+    }
+}
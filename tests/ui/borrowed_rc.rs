@@ -0,0 +1,11 @@
+use std::{mem::MaybeUninit, rc::Rc, thread};
+
+use brainstorm::ephemeral::borrowed::RingRef;
+
+fn main() {
+    let mut storage = [const { MaybeUninit::<Rc<i32>>::uninit() }; 4];
+    let (_producer, consumer) = RingRef::from_slice(&mut storage).split();
+    thread::scope(|s| {
+        s.spawn(move || drop(consumer));
+    });
+}
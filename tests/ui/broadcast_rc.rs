@@ -0,0 +1,8 @@
+use std::{rc::Rc, thread};
+
+use brainstorm::ephemeral::broadcast::{BroadcastEphemeral, Policy};
+
+fn main() {
+    let (_producer, subscriber) = BroadcastEphemeral::<Rc<i32>, 4>::new(Policy::Lag).split();
+    thread::spawn(move || drop(subscriber));
+}
@@ -0,0 +1,8 @@
+use std::{rc::Rc, thread};
+
+use brainstorm::ephemeral::deque::Worker;
+
+fn main() {
+    let stealer = Worker::<Rc<i32>>::new().stealer();
+    thread::spawn(move || drop(stealer));
+}
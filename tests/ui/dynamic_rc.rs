@@ -0,0 +1,8 @@
+use std::{rc::Rc, thread};
+
+use brainstorm::ephemeral::dynamic::DynBuffer;
+
+fn main() {
+    let (_producer, consumer) = DynBuffer::<Rc<i32>>::with_capacity(4).split();
+    thread::spawn(move || drop(consumer));
+}
@@ -0,0 +1,10 @@
+use std::rc::Rc;
+
+use brainstorm::ephemeral::isr::IsrQueue;
+
+// a `static` is shared with whatever interrupts the main loop
+static QUEUE: IsrQueue<Rc<i32>, 4> = IsrQueue::new();
+
+fn main() {
+    let _ = QUEUE.len();
+}
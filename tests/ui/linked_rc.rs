@@ -0,0 +1,8 @@
+use std::{rc::Rc, thread};
+
+use brainstorm::ephemeral::linked::LinkedMPSC;
+
+fn main() {
+    let (producer, _consumer) = LinkedMPSC::<Rc<i32>>::new().split();
+    thread::spawn(move || drop(producer));
+}
@@ -0,0 +1,9 @@
+use std::{rc::Rc, sync::Arc, thread};
+
+use brainstorm::ephemeral::mpmc::MPMCEphemeral;
+
+fn main() {
+    let src = Arc::new(MPMCEphemeral::<Rc<i32>, 4>::new());
+    let other = src.clone();
+    thread::spawn(move || other.pop());
+}
@@ -0,0 +1,8 @@
+use std::{rc::Rc, thread};
+
+use brainstorm::ephemeral::mpsc::MPSCEphemeral;
+
+fn main() {
+    let (producer, _consumer) = MPSCEphemeral::<Rc<i32>, 4>::new().split();
+    thread::spawn(move || drop(producer));
+}
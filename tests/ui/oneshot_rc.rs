@@ -0,0 +1,8 @@
+use std::{rc::Rc, thread};
+
+use brainstorm::ephemeral::oneshot;
+
+fn main() {
+    let (sender, _receiver) = oneshot::channel::<Rc<i32>>();
+    thread::spawn(move || drop(sender));
+}
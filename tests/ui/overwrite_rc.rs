@@ -0,0 +1,8 @@
+use std::{rc::Rc, thread};
+
+use brainstorm::ephemeral::overwrite::OverwriteBuffer;
+
+fn main() {
+    let (producer, _consumer) = OverwriteBuffer::<Rc<i32>, 4>::new().split();
+    thread::spawn(move || drop(producer));
+}
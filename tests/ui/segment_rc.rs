@@ -0,0 +1,8 @@
+use std::{rc::Rc, thread};
+
+use brainstorm::ephemeral::segment::SegQueue;
+
+fn main() {
+    let queue = SegQueue::<Rc<i32>>::new();
+    thread::spawn(move || drop(queue));
+}
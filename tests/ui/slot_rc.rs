@@ -0,0 +1,8 @@
+use std::{rc::Rc, thread};
+
+use brainstorm::EphemeralSlot;
+
+fn main() {
+    let slot = EphemeralSlot::<Rc<i32>>::new();
+    thread::spawn(move || drop(slot));
+}
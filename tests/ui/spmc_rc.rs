@@ -0,0 +1,8 @@
+use std::{rc::Rc, thread};
+
+use brainstorm::ephemeral::spmc::SPMCEphemeral;
+
+fn main() {
+    let (_producer, consumer) = SPMCEphemeral::<Rc<i32>, 4>::new().split();
+    thread::spawn(move || drop(consumer));
+}
@@ -0,0 +1,11 @@
+use std::{cell::Cell, thread};
+
+use brainstorm::ephemeral::spsc::SPSCEphemeral;
+
+// `peek` borrows the item from `&self`, sharing needs `T: Sync`
+fn main() {
+    let (_producer, consumer) = SPSCEphemeral::<Cell<i32>, 4>::new().split();
+    thread::scope(|s| {
+        s.spawn(|| consumer.peek().map(Cell::get));
+    });
+}
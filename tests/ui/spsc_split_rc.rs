@@ -0,0 +1,8 @@
+use std::{rc::Rc, thread};
+
+use brainstorm::ephemeral::spsc::SPSCEphemeral;
+
+fn main() {
+    let (mut producer, _consumer) = SPSCEphemeral::<Rc<i32>, 4>::new().split();
+    thread::spawn(move || producer.push(Rc::new(1)));
+}
@@ -0,0 +1,9 @@
+use std::{rc::Rc, sync::Arc, thread};
+
+use brainstorm::ephemeral::spsc::SPSCEphemeral;
+
+fn main() {
+    let src = Arc::new(SPSCEphemeral::<Rc<i32>, 4>::new());
+    let other = src.clone();
+    thread::spawn(move || other.push(Rc::new(1)));
+}
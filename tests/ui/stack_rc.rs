@@ -0,0 +1,8 @@
+use std::{rc::Rc, thread};
+
+use brainstorm::ephemeral::stack::EphemeralStack;
+
+fn main() {
+    let stack = EphemeralStack::<Rc<i32>>::new();
+    thread::spawn(move || drop(stack));
+}
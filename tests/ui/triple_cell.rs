@@ -0,0 +1,11 @@
+use std::{cell::Cell, thread};
+
+use brainstorm::ephemeral::triple::TripleBuffer;
+
+// `output` borrows the snapshot from `&self`, sharing needs `T: Sync`
+fn main() {
+    let (_writer, reader) = TripleBuffer::new(Cell::new(0)).split();
+    thread::scope(|s| {
+        s.spawn(|| reader.output().set(1));
+    });
+}
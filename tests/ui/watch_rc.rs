@@ -0,0 +1,8 @@
+use std::{rc::Rc, thread};
+
+use brainstorm::ephemeral::watch::Watch;
+
+fn main() {
+    let watch = Watch::new(Rc::new(1));
+    thread::spawn(move || drop(watch));
+}